@@ -0,0 +1,111 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use gloomscript::builtin::host::HostObject;
+use gloomscript::engine::{Engine, EngineError};
+use gloomscript::obj::func::{Param, ReturnType};
+use gloomscript::obj::types::{DataType, RefType};
+use gloomscript::vm::value::Value;
+
+// 宿主类包装的Rust值 the rust value wrapped by host class
+struct Counter {
+    count: i64,
+}
+
+fn main() -> Result<(), EngineError> {
+    let mut engine = Engine::new(true);
+
+    // 宿主函数 host function
+    engine.register_fn(
+        "hostAdd",
+        vec![
+            Param::new(Rc::new(String::from("a")), DataType::Ref(RefType::Int)),
+            Param::new(Rc::new(String::from("b")), DataType::Ref(RefType::Int)),
+        ],
+        ReturnType::Have(DataType::Ref(RefType::Int)),
+        |_, args| {
+            Result::Ok(Value::Int(
+                args.vec[0].assert_int()? + args.vec[1].assert_int()?,
+            ))
+        },
+    )?;
+
+    // 宿主类 host class
+    let counter = engine.register_class("Counter")?;
+    let class = counter.clone();
+    engine.register_method(
+        &counter,
+        "new",
+        Vec::new(),
+        ReturnType::Have(counter.data_type()),
+        false,
+        move |_, _| Result::Ok(Value::Ref(HostObject::new(&class, Counter { count: 0 }))),
+    )?;
+    engine.register_method(
+        &counter,
+        "incr",
+        vec![Param::new(
+            Rc::new(String::from("step")),
+            DataType::Ref(RefType::Int),
+        )],
+        ReturnType::Have(DataType::Ref(RefType::Int)),
+        true,
+        |_, args| {
+            let obj = args.vec[0].as_ref().downcast::<HostObject>();
+            let mut counter = obj.borrow_mut::<Counter>();
+            counter.count += args.vec[1].assert_int()?;
+            Result::Ok(Value::Int(counter.count))
+        },
+    )?;
+
+    // 编译失败后引擎可以再次编译 the engine could compile again after failure
+    if let Err(err) = engine.compile_str(String::from("let x int = \"x\""), "broken.gs") {
+        eprintln!("{}", err);
+    }
+
+    let src = r#"
+        func greet(String name) String {
+            println(name)
+            name
+        }
+        func count(int n) int {
+            let counter = Counter.new()
+            let result = 0
+            for i in (0,n) {
+                result = counter.incr(hostAdd(i, 1))
+            }
+            result
+        }
+        func check(int n) int {
+            if n < 0 {
+                throw "n can't be negative"
+            }
+            n
+        }
+        println("script loaded")
+    "#;
+    if let Err(err) = engine.compile_str(src.to_string(), "embed.gs") {
+        eprintln!("{}", err);
+        return Result::Ok(());
+    }
+    for warning in engine.warnings() {
+        eprintln!("{}", warning);
+    }
+    let greeting = engine.call("greet", vec![Value::from("Rust")]).unwrap();
+    println!("{}", String::try_from(greeting).unwrap());
+    let count = engine.call("count", vec![Value::from(10)]).unwrap();
+    println!("{}", i64::try_from(count).unwrap());
+    // 运行时错误带有调用栈信息 runtime error carries the traceback
+    if let Err(err) = engine.call("check", vec![Value::from(-1)]) {
+        println!("{}", err);
+    }
+    // 参数的类型在调用前检查 the types of args are checked before calling
+    if let Err(err) = engine.call("check", vec![Value::from("1")]) {
+        println!("{}", err);
+    }
+    // 编译之后不能再注册 registering is not allowed after compiling
+    if let Err(err) = engine.register_class("Late") {
+        println!("{}", err);
+    }
+    Result::Ok(())
+}
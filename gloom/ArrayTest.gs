@@ -0,0 +1,67 @@
+let nums = [3, 1, 2]
+println(nums)
+nums.push(5)
+println(nums.len())
+println(nums.get(0) + 100)
+nums.sort()
+println(nums)
+let doubled = nums.map(func(int x) -> int { x * 2 })
+println(doubled)
+println(nums.filter(func(int x) -> bool { x > 1 }))
+println(nums.reduce(0, func(int acc, int x) -> int { acc + x }))
+println(nums.indexOf(5))
+println(nums.contains(7))
+nums.reverse()
+println(nums)
+println(nums.slice(1, 3))
+println(nums.pop())
+nums.insert(0, 9)
+println(nums.remove(1))
+nums.sortBy(func(int a, int b) -> int { b - a })
+println(nums)
+let words = ["pear", "apple", "fig"]
+words.sort()
+println(words)
+println(words.map(func(String s) -> String { s.append("!") }))
+println(words.isEmpty())
+try {
+    println(nums.get(10))
+} catch err {
+    println(err)
+}
+// 空数组映射的结果按声明的返回类型存储 the result of mapping empty array is stored by the declared return type
+let empty Array<int> = []
+let mapped = empty.map(func(int x) -> int { x * 2 })
+mapped.push(3)
+println(mapped)
+try {
+    nums.sortBy(func(int a, int b) -> int {
+        nums.push(0)
+        a - b
+    })
+} catch err {
+    println(err)
+}
+
+/*
+[3, 1, 2]
+4
+103
+[1, 2, 3, 5]
+[2, 4, 6, 10]
+[2, 3, 5]
+11
+3
+false
+[5, 3, 2, 1]
+[3, 2]
+1
+5
+[9, 3, 2]
+["apple", "fig", "pear"]
+["apple!", "fig!", "pear!"]
+false
+Error("index 10 out of bounds for array of length 3")
+[3]
+Error("the array is modified by the compare function during sortBy")
+*/
@@ -0,0 +1,88 @@
+async func add(int a, int b) int {
+    await yieldNow()
+    return a + b
+}
+
+// 在脚本顶层等待时运行事件循环直到任务结束 await at top level runs the event loop until the task finishes
+println(await add(1, 2)) // 3
+
+async func worker(Array<String> log, String name, int times) int {
+    let i = 0
+    while (i < times) {
+        log.push(name.repeat(i + 1))
+        await yieldNow()
+        i += 1
+    }
+    return times
+}
+
+// 派生的任务轮流执行 spawned tasks run in turn
+let log Array<String> = []
+let a = spawn worker(log, "a", 3)
+let b = spawn worker(log, "b", 2)
+println(await a + await b) // 5
+println(log) // [a, b, aa, bb, aaa]
+
+// 异步函数中的await挂起当前任务 await in async function suspends the current task
+async func delayed(Array<int> order, int millis) {
+    await sleep(millis)
+    order.push(millis)
+}
+async func all(Array<int> order) {
+    let tasks = [spawn delayed(order, 30), spawn delayed(order, 10), spawn delayed(order, 20)]
+    for task in tasks {
+        await task
+    }
+}
+let order Array<int> = []
+await all(order)
+println(order) // [10, 20, 30]
+
+// 任务的结果可以被多次等待 the result of task could be awaited many times
+let sum = add(20, 22)
+println(sum.isDone()) // false
+println(await sum) // 42
+println(await sum) // 42
+println(sum.isDone()) // true
+
+// 任务中的错误在等待处抛出 the error of task is raised at await
+async func fail(String msg) int {
+    await sleep(1)
+    throw msg
+}
+async func safe() String {
+    try {
+        await fail("boom")
+        return "unreachable"
+    } catch e {
+        return e.message()
+    }
+}
+println(await safe()) // "boom"
+
+// 匿名异步函数和类的异步方法 nameless async function and async method of class
+class Counter {
+    pub int count
+    pub async func tick(self, int n) int {
+        let i = 0
+        while (i < n) {
+            self.count += 1
+            await yieldNow()
+            i += 1
+        }
+        return self.count
+    }
+}
+let counter = Counter{ count : 0 }
+let double = async func(Counter counter, int n) int {
+    return await counter.tick(n) * 2
+}
+println(await double(counter, 4)) // 8
+
+// 派生后未被等待的任务在脚本结束后执行 spawned tasks not awaited run after the script finished
+spawn delayed(order, 5)
+spawn async func() {
+    await sleep(10)
+    println(order)
+}()
+println("script end") // "script end"
@@ -0,0 +1,74 @@
+import Channel
+
+// 缓冲区满时发送者挂起 等待接收者取走消息 the sender suspends when the buffer is full, waiting for the receiver to take messages
+async func producer(Channel<int> ch, Array<String> log, int count) {
+    let i = 0
+    while (i < count) {
+        await ch.send(i)
+        log.push("sent")
+        i += 1
+    }
+    ch.close()
+}
+// 迭代通道直到它被关闭 iterate the channel until it is closed
+async func consumer(Channel<int> ch, Array<String> log) int {
+    let sum = 0
+    for n in ch {
+        log.push("recv")
+        sum += n
+    }
+    return sum
+}
+let ch Channel<int> = Channel.bounded(2)
+let log Array<String> = []
+spawn producer(ch, log, 5)
+println(await consumer(ch, log)) // 10
+println(log) // ["sent", "sent", "recv", "recv", "sent", "sent", "recv", "recv", "sent", "recv"]
+println(ch.isClosed()) // true
+
+// 关闭后仍可以取出剩余的消息 之后得到空的可选值
+// remaining messages could still be taken after closed, then get an empty option
+let names Channel<String> = Channel.unbounded()
+await names.send("a")
+await names.send("b")
+names.close()
+println(names.len()) // 2
+println((await names.recv()).get()) // "a"
+println((await names.recv()).get()) // "b"
+println((await names.recv()).isNone()) // true
+
+// 向关闭的通道发送是运行时错误 sending to closed channel is runtime error
+try {
+    await names.send("c")
+} catch e {
+    println("send failed")
+}
+
+// 接收者先等待 多个工作者分担消息 receivers wait first, workers share the messages
+async func worker(Channel<int> jobs, Channel<String> results, String name) {
+    for job in jobs {
+        await results.send(name.repeat(job))
+    }
+}
+async func dispatch(Channel<int> jobs, int count) {
+    let i = 1
+    while (i <= count) {
+        await jobs.send(i)
+        i += 1
+    }
+    jobs.close()
+}
+let jobs Channel<int> = Channel.bounded(1)
+let results Channel<String> = Channel.unbounded()
+let workers = [spawn worker(jobs, results, "x"), spawn worker(jobs, results, "y")]
+await dispatch(jobs, 4)
+for w in workers {
+    await w
+}
+results.close()
+let total = 0
+// 在脚本顶层迭代时运行事件循环 the event loop runs while iterating at top level
+for result in results {
+    total += result.len()
+}
+println(total) // 10
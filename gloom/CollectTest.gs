@@ -0,0 +1,18 @@
+// 收集字节码把多个值合并为一个值 栈的深度减少 n - 1 而不是 n
+// collect bytecodes merge n values into one value, the depth of stack decreases by n - 1 instead of n
+// `gloomscript disasm gloom/CollectTest.gs` 中 grid 的 stack_size 应为 3 the stack_size of grid in disasm should be 3
+func grid() Array<Array<int>> {
+    let g = [[1, 2], [3, 4]]
+    return g
+}
+func pairs() ((int, int), (int, int)) {
+    let p = ((1, 2), (3, 4))
+    return p
+}
+func nested(int n) Array<Array<int>> {
+    let arr = [[n, n + 1], [n + 2, n + 3], [n + 4]]
+    return arr
+}
+println(grid()) // [[1, 2], [3, 4]]
+println(pairs()) // ((1, 2), (3, 4))
+println(nested(1)) // [[1, 2], [3, 4], [5]]
@@ -0,0 +1,72 @@
+enum Color {
+    Red, Green, Blue
+}
+
+enum Shape {
+    Circle(num)
+    Square(num)
+    Empty
+
+    pub func area(self) num {
+        match (self) {
+            Shape.Circle(r) => 3.0 * r * r,
+            Shape.Square(side) => side * side,
+            Shape.Empty => 0.0
+        }
+    }
+}
+
+enum MaybeInt {
+    Some(int)
+    None
+}
+
+let red = Color.Red
+println(red) // Color.Red
+println([red == Color.Red, red == Color.Blue]) // [true,false]
+
+let circle = Shape.Circle(2.0)
+println(circle) // Shape.Circle(2.00000)
+println(circle.area()) // 12.0
+println(Shape.Square(1.5).area()) // 2.25
+println(Shape.Empty.area()) // 0.0
+
+func find(Array<int> arr, int target) MaybeInt {
+    let idx = 0
+    for item in arr {
+        if (item == target) {
+            return MaybeInt.Some(idx)
+        }
+        idx ++
+    }
+    MaybeInt.None
+}
+
+let arr = [3,1,4,1,5]
+for target in [4,9] {
+    let result = find(arr,target)
+    match (result) {
+        MaybeInt.Some(i) => println(i),
+        MaybeInt.None => println("not found")
+    }
+}
+// 2 "not found"
+
+println([MaybeInt.Some(1) == MaybeInt.Some(1), MaybeInt.Some(1) == MaybeInt.Some(2)]) // [true,false]
+
+let desc = match (Color.Green) {
+    Color.Red => "warm",
+    _ => "cold"
+}
+println(desc) // "cold"
+
+// 关联值按结构比较 associated values are compared by structure
+enum Label {
+    Text(String)
+    Pair((String, int))
+    Wrap(MaybeInt)
+}
+let name = "a"
+println([Label.Text(name) == Label.Text("a"), Label.Text("a") == Label.Text("b")]) // [true, false]
+println([Label.Pair(("x", 1)) == Label.Pair(("x", 1)), Label.Pair(("x", 1)) == Label.Pair(("x", 2))]) // [true, false]
+println([Label.Wrap(MaybeInt.Some(1)) == Label.Wrap(MaybeInt.Some(1)), Label.Wrap(MaybeInt.None) == Label.Wrap(MaybeInt.Some(1))]) // [true, false]
@@ -0,0 +1,81 @@
+class Node {
+    pub String name
+    pub Array<Node> links
+    pub func link(self, Node other) {
+        self.links.push(other)
+    }
+    func drop(self) {
+        println([self.name, "Drop"])
+    }
+}
+
+class Cell {
+    pub Array<Cell> links
+}
+
+class Button {
+    pub String name
+    pub Array<Func> handlers
+    func drop(self) {
+        println([self.name, "Drop"])
+    }
+}
+
+func makeNode(String name) Node {
+    let links Array<Node> = []
+    Node{ name : name, links : links }
+}
+
+// 两个对象互相引用 two objects reference each other
+func pair() {
+    let a = makeNode("a")
+    let b = makeNode("b")
+    a.link(b)
+    b.link(a)
+}
+
+// 闭包捕获了持有它的对象 the closure captures the object holding it
+func button() {
+    let handlers Array<Func> = []
+    let button = Button{ name : "button", handlers : handlers }
+    button.handlers.push(func(){
+        println(button.name)
+    })
+}
+
+// 数组持有它的父对象 the array holds its parent
+func parent() {
+    let root = makeNode("root")
+    root.link(root)
+}
+
+pair()
+button()
+parent()
+println("before gc")
+println(gc())
+
+// 仍被引用的环不会被回收 the cycle still referenced won't be collected
+let kept = makeNode("kept")
+kept.link(kept)
+println(gc())
+println(kept.links.len())
+
+// 分配的对象达到阈值时自动回收 collect automatically when allocated objects reach the threshold
+for i in (0,5000,1) {
+    let links Array<Cell> = []
+    let cell = Cell{ links : links }
+    cell.links.push(cell)
+}
+println(gc())
+/*
+"before gc"
+["b", "Drop"]
+["a", "Drop"]
+["button", "Drop"]
+["root", "Drop"]
+9
+0
+1
+16
+*/
@@ -0,0 +1,76 @@
+func countTo(int n) Generator<int> {
+    let i = 0
+    while (i < n) {
+        yield i
+        i += 1
+    }
+}
+
+for i in countTo(3) {
+    println(i) // 0 1 2
+}
+
+// 无限序列只计算需要的部分 infinite sequence only computes the needed part
+func fibonacci() Generator<int> {
+    let a = 0
+    let b = 1
+    while (true) {
+        yield a
+        let next = a + b
+        a = b
+        b = next
+    }
+}
+
+let fib = fibonacci()
+let first Array<int> = []
+for i in (0, 10) {
+    first.push(fib.next().get())
+}
+println(first) // [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]
+
+func words(String text) Generator<String> {
+    for word in text.split(" ") {
+        if (!word.isEmpty()) {
+            yield word.toUpper()
+        }
+    }
+}
+
+let gen = words("lazy  gloom script")
+println(gen) // Generator(words suspended)
+for word in gen {
+    println(word)
+}
+println(gen.next().isNone()) // true
+
+// 闭包生成器和提前返回 closure generator and early return
+let limit = 2
+let firstEvens = func(Array<int> nums) Generator<int> {
+    let found = 0
+    for n in nums {
+        if (found == limit) {
+            return
+        }
+        if (n % 2 == 0) {
+            found += 1
+            yield n
+        }
+    }
+}
+for n in firstEvens([1, 2, 3, 4, 5, 6]) {
+    println(n) // 2 4
+}
+
+// 生成器中的错误传播到调用者 the error in generator propagates to the caller
+func failing() Generator<int> {
+    yield 1
+    throw "generator failed"
+}
+try {
+    for n in failing() {
+        println(n)
+    }
+} catch err {
+    println(err)
+}
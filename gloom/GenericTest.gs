@@ -0,0 +1,65 @@
+class Holder<T>{
+    pub T value
+    pub func get(self) -> T{
+        self.value
+    }
+    pub func set(self, T value){
+        self.value = value
+    }
+}
+
+let holder = Holder{ value : 1 }
+holder.set(holder.get() + 41)
+println(holder.get() + 1)
+println(holder.value * 2)
+
+let text = Holder<String>{ value : "gloom" }
+println(text.get())
+
+interface Comparable<T>{
+    func compareTo(self, T other) -> int
+}
+
+class Item impl Comparable<Item>{
+    pub String name
+    pub int weight
+    pub func compareTo(self, Item other) -> int{
+        self.weight - other.weight
+    }
+}
+
+func max<T : Comparable<T>>(T a, T b) -> T{
+    if a.compareTo(b) >= 0 {
+        a
+    } else {
+        b
+    }
+}
+
+func first<T>(T a, T b) -> T{
+    a
+}
+
+println(max(Item{ name : "apple", weight : 3 }, Item{ name : "melon", weight : 7 }).name)
+println(first(1, 2) + 10)
+
+// 空的字面量字段使用给出的类型实参 empty literal field uses the given type argument
+class Stack<T>{
+    pub Array<T> items
+    pub func push(self, T item){
+        self.items.push(item)
+    }
+}
+let stack = Stack<int>{ items : [] }
+stack.push(1)
+stack.push(2)
+println(stack.items)
+
+/*
+43
+84
+"gloom"
+"melon"
+11
+[1, 2]
+*/
@@ -0,0 +1,61 @@
+let nums = [10, 20, 30, 40]
+println(nums[1] + 1)
+nums[2] = 33
+nums[0] += 5
+nums[3]++
+println(nums)
+println(nums[1..3])
+let text = "héllo"
+println(text[1])
+println(text[1..4])
+let pair = (1, "one")
+println(pair[1])
+let ages = {"tom" : 18}
+ages["amy"] = 20
+ages["tom"] += 1
+println(ages["tom"] + ages["amy"])
+let queue Queue<int> = [1, 2, 3]
+queue[0] = 7
+println(queue[0] * 2)
+println(queue[1..3])
+let grid = [[1, 2], [3, 4]]
+grid[1][0] = 9
+println(grid[1][0])
+println(grid)
+for i in (0, 2) {
+    println(nums[i])
+}
+try {
+    println(nums[10])
+} catch err {
+    println(err)
+}
+try {
+    println(ages["nobody"])
+} catch err {
+    println(err)
+}
+try {
+    println(text[2..9])
+} catch err {
+    println(err)
+}
+
+/*
+21
+[15, 20, 33, 41]
+[20, 33]
+'é'
+"éll"
+"one"
+39
+14
+[2, 3]
+9
+[[1, 2], [9, 4]]
+15
+20
+Error("index 10 out of bounds for array of length 4")
+Error("key "nobody" not found in map")
+Error("slice 2..9 out of bounds for string of length 5")
+*/
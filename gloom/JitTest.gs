@@ -0,0 +1,73 @@
+// 被频繁调用的函数由JIT编译 结果与解释执行相同 --no-jit 下的输出应完全一致
+// hot functions are compiled by JIT, results are the same as interpreting, the output should be identical with --no-jit
+func big(int n) int {
+    9223372036854775800 + n
+}
+func small(int n) int {
+    (-9223372036854775800) - n
+}
+func times(int a, int b) int {
+    a * b
+}
+func negate(int n) int {
+    -n
+}
+func next(int n) int {
+    let m = n
+    m ++
+    m
+}
+func fib(int n) int {
+    let a = 0
+    let b = 1
+    let i = 0
+    while (i < n) {
+        let c = a + b
+        a = b
+        b = c
+        i ++
+    }
+    a
+}
+
+// 调用次数超过编译的阈值 call more times than the threshold of compiling
+let failed = 0
+let i = 0
+while (i < 100) {
+    try {
+        big(i % 10)
+    } catch e {
+        failed += 1
+    }
+    small(i % 8)
+    times(i, i)
+    negate(i)
+    next(i)
+    fib(i % 20)
+    i += 1
+}
+println(failed) // 20
+println(big(7)) // 9223372036854775807
+
+// 编译后溢出仍然是可以被捕获的运行时错误 overflow is still a catchable runtime error after compiled
+try {
+    small(100)
+} catch e {
+    println(e) // Error("attempt to subtract with overflow")
+}
+try {
+    times(4611686018427387904, 2)
+} catch e {
+    println(e) // Error("attempt to multiply with overflow")
+}
+try {
+    negate((-9223372036854775807) - 1)
+} catch e {
+    println(e) // Error("attempt to negate with overflow")
+}
+try {
+    next(9223372036854775807)
+} catch e {
+    println(e) // Error("attempt to add with overflow")
+}
+println([times(-3, 7), negate(5), next(41), fib(90)]) // [-21, -5, 42, 2880067194370816120]
@@ -0,0 +1,71 @@
+let ages = {"tom" : 18, "amy" : 20}
+ages.put("bob", 30)
+println(ages)
+println(ages.get("amy") + 1)
+println(ages.getOr("nobody", 0))
+println(ages.containsKey("tom"))
+println(ages.remove("tom"))
+println(ages.len())
+println(ages.keys())
+println(ages.values())
+for entry in ages {
+    println(entry)
+}
+let empty Map<String, int> = {}
+println(empty.isEmpty())
+let points = {(0, 0) : "origin", (1, 2) : "p"}
+println(points.get((1, 2)))
+let chars = {'a', 'b', 'a'}
+println(chars)
+println(chars.add('c'))
+println(chars.contains('b'))
+let other = {'b', 'z'}
+println(chars.union(other))
+println(chars.intersect(other))
+println(chars.difference(other))
+for ch in chars {
+    println(ch)
+}
+try {
+    ages.get("nobody")
+} catch err {
+    println(err)
+}
+// 泛型参数收到不可哈希的值时是可捕获的运行时错误
+// unhashable value received by generic parameter is a catchable runtime error
+func pair<T>(T a, T b) -> Set<T> {
+    {a, b}
+}
+println(pair(1, 1).len())
+try {
+    pair([1], [2])
+} catch err {
+    println(err)
+}
+
+/*
+{"tom" : 18, "amy" : 20, "bob" : 30}
+21
+0
+true
+true
+2
+["bob", "amy"]
+[30, 20]
+("bob", 30)
+("amy", 20)
+true
+"p"
+{'a', 'b'}
+true
+true
+{'a', 'b', 'c', 'z'}
+{'b'}
+{'a', 'c'}
+'a'
+'b'
+'c'
+Error("key "nobody" not found in map")
+1
+Error("[1] is not hashable")
+*/
@@ -0,0 +1,52 @@
+func describe(int n) -> String {
+    match (n) {
+        0 => "zero",
+        1 => "one",
+        -1 => "minus one",
+        _ => "many"
+    }
+}
+
+for i in (-1,3){
+    println(describe(i))
+}
+// "minus one" "zero" "one" "many"
+
+let c = 'b'
+let code = match (c) {
+    'a' => 1,
+    'b' => {
+        let base = 10
+        base * 2
+    }
+    _ => 0
+}
+println(code) // 20
+
+let name = "gloom"
+match (name) {
+    "rust" => println("crab"),
+    "gloom" => println("script"),
+    _ => {}
+}
+// "script"
+
+let flag = match (code > 10) {
+    true => "big",
+    false => "small"
+}
+println(flag) // "big"
+
+// 嵌套 nested
+for i in (0,4){
+    let text = match (i) {
+        0 => "even",
+        2 => match (i * 2) {
+            4 => "four",
+            _ => "other"
+        },
+        _ => "odd"
+    }
+    println(text)
+}
+// "even" "odd" "four" "odd"
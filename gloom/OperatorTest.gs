@@ -0,0 +1,84 @@
+println(17 % 5)
+println(7.5 % 2)
+println(2 ** 10)
+println(2 ** 3 ** 2)
+println(2.0 ** 0.5)
+println(12 & 10)
+println(12 | 10)
+println(12 ^ 10)
+println(~5)
+println(1 << 10)
+println(-16 >> 2)
+println(1 + 2 * 3 % 4)
+println(true & false | true)
+let hash = 5381
+for ch in "gloom".chars() {
+    hash = ((hash << 5) + hash + ch as int) & 65535
+}
+println(hash)
+let x = 10
+x *= 3
+x /= 4
+x %= 5
+println(x)
+let y = 2.5
+y *= 2
+y /= 0.5
+println(y)
+let bits Array<int> = []
+for i in (0, 8) {
+    bits.push((170 >> i) & 1)
+}
+println(bits)
+try {
+    println(1 % 0)
+} catch err {
+    println(err)
+}
+try {
+    println(1 << 64)
+} catch err {
+    println(err)
+}
+try {
+    println(2 ** 64)
+} catch err {
+    println(err)
+}
+println(1 ** 10000000000)
+// 优先级 precedence
+println(1 + 2 << 3)
+println(10 >> 1 + 1)
+let a = 1
+let b = 2
+println(a | b == 3)
+println(6 & 3 ^ 1 | 8)
+println(1 < 2 && 3 > 2 || false)
+/*
+2
+1.50000
+1024
+512
+1.41421
+8
+14
+6
+-6
+1024
+-4
+3
+true
+64483
+2
+10.00000
+[0, 1, 0, 1, 0, 1, 0, 1]
+Error("attempt to calculate the remainder with a divisor of zero")
+Error("1 << 64, shift amount out of range 0..64")
+Error("attempt to power with overflow")
+1
+24
+2
+true
+11
+true
+*/
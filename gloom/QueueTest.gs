@@ -0,0 +1,43 @@
+let queue Queue<int> = [1, 2, 3]
+queue.pushBack(4)
+queue.pushFront(0)
+println(queue)
+println(queue.len())
+println(queue.peek() + queue.peekBack())
+println(queue.popFront())
+println(queue.popBack())
+for num in queue {
+    println(num * 10)
+}
+let names Queue<String> = []
+names.pushBack("tom")
+names.pushBack("amy")
+names.pushFront("bob")
+println(names.toArray())
+names.forEach(func(String name){
+    println(name)
+})
+names.clear()
+println(names.isEmpty())
+try {
+    names.popFront()
+} catch err {
+    println(err)
+}
+
+/*
+[0, 1, 2, 3, 4]
+5
+4
+0
+4
+10
+20
+30
+["bob", "tom", "amy"]
+"bob"
+"tom"
+"amy"
+true
+Error("pop from empty queue")
+*/
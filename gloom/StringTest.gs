@@ -0,0 +1,75 @@
+let text = "  Hello, 世界!  ".trim()
+println(text)
+println(text.len())
+println(text.charLen())
+println(text.charAt(7))
+println(text.substring(7, 9))
+println(text.find("世界"))
+println(text.contains("Hello"))
+println(text.startsWith("Hello"))
+println(text.endsWith("?"))
+println(text.toUpper())
+println(text.toLower())
+println("ab".repeat(3))
+println("a-b-c".replace("-", "+"))
+let words = "one two  three".split("")
+println(words)
+println(", ".join(words))
+println("x,y".split(","))
+println("42".parseInt().get() + 1)
+println("4.5".parseNum().getOr(0.0))
+println("gloom".parseInt().isNone())
+let parsed Option<int> = "12".parseInt()
+println(parsed)
+println("x".parseNum())
+println("apple".compareTo("banana"))
+println("gloom".equals("gloom"))
+for ch in "héllo".substring(1, 3).chars() {
+    println(ch)
+}
+try {
+    text.charAt(100)
+} catch err {
+    println(err)
+}
+try {
+    "ab".repeat(4611686018427387904)
+} catch err {
+    println(err)
+}
+try {
+    "a".repeat(4611686018427387904)
+} catch err {
+    println(err)
+}
+
+/*
+"Hello, 世界!"
+14
+10
+'世'
+"世界"
+7
+true
+true
+false
+"HELLO, 世界!"
+"hello, 世界!"
+"ababab"
+"a+b+c"
+["one", "two", "three"]
+"one, two, three"
+["x", "y"]
+43
+4.50000
+true
+Some(12)
+None
+-1
+true
+'é'
+'l'
+Error("index 100 out of bounds for string of length 10")
+Error("repeat a string of length 2 by 4611686018427387904 times, the result is too long")
+Error("repeat a string of length 1 by 4611686018427387904 times, the result is too long")
+*/
@@ -0,0 +1,59 @@
+import Timer
+
+// 定时器的回调函数按期限的先后执行 callbacks of timers are called in the order of deadlines
+let log Array<String> = []
+Timer.setTimeout(func() {
+    log.push("slow")
+}, 30)
+Timer.setTimeout(func() {
+    log.push("fast")
+}, 10)
+let cancelled = Timer.setTimeout(func() {
+    log.push("never")
+}, 20)
+println(cancelled.isActive()) // true
+cancelled.cancel()
+println(cancelled.isActive()) // false
+
+// 间隔定时器重复执行直到被取消 interval timer repeats until cancelled
+let ticks Array<int> = [0]
+let interval = Timer.setInterval(func() {
+    ticks[0] += 1
+}, 5)
+
+// 单调时钟 等待时事件循环执行到期的定时器 monotonic clock, the event loop runs due timers while awaiting
+let start = Timer.now()
+await Timer.sleep(50)
+println((Timer.now() - start) >= 50) // true
+println(log) // ["fast", "slow"]
+println(interval.isActive()) // true
+interval.cancel()
+println(ticks[0] >= 5) // true
+
+// 回调函数可以派生任务 callbacks could spawn tasks
+async func report(Array<String> log) {
+    await Timer.sleep(1)
+    log.push("task")
+}
+Timer.setTimeout(func() {
+    spawn report(log)
+}, 1)
+await Timer.sleep(20)
+println(log) // ["fast", "slow", "task"]
+
+// 在回调函数中取消自己 cancel itself in the callback
+let counts Array<int> = [0]
+let holder Array<Timer> = []
+holder.push(Timer.setInterval(func() {
+    counts[0] += 1
+    if counts[0] == 3 {
+        holder[0].cancel()
+    }
+}, 1))
+await Timer.sleep(30)
+println(counts[0]) // 3
+println(holder[0].isActive()) // false
+
+Timer.setTimeout(func() {
+    println("script end") // "script end"
+}, 5)
@@ -0,0 +1,45 @@
+// 调用栈记录每次调用所在的行 而不是所在语句开始的行
+// the traceback records the line of every call, rather than the start of the statement
+
+func inner(int n) int {
+    10 / n
+}
+
+func middle(int n) int {
+    let result = n +
+        inner(n - n)
+    result
+}
+
+class Caller {
+    pub func call(self, int n) int {
+        let sum = [n,
+            middle(n)]
+        sum[0]
+    }
+}
+
+func outer() {
+    let caller = Caller {}
+    let fn = func(int n) int {
+        caller.call(n)
+    }
+    println(
+        fn(1))
+}
+
+try {
+    outer()
+} catch err {
+    println(err.traceback())
+}
+
+/*
+"Traceback (most recent call last):
+  File "gloom/TracebackTest.gs", line 28, in outer
+  File "gloom/TracebackTest.gs", line 25, in <nameless>
+  File "gloom/TracebackTest.gs", line 17, in call
+  File "gloom/TracebackTest.gs", line 10, in middle
+  File "gloom/TracebackTest.gs", line 5, in inner
+runtime error : attempt to divide by zero"
+*/
@@ -0,0 +1,63 @@
+func check(int age) int {
+    if (age < 0) {
+        throw "age can't be negative"
+    }
+    age
+}
+
+func divide(int a, int b) int {
+    a / b
+}
+
+try {
+    println(check(18)) // 18
+    println(check(-1))
+    println("unreachable")
+} catch err {
+    println(err) // Error("age can't be negative")
+}
+
+// Rust层面的错误同样可以被捕获 errors raised by the runtime can also be caught
+try {
+    divide(1, 0)
+} catch err {
+    println(err) // Error("attempt to divide by zero")
+}
+
+// 嵌套的 try-catch nested try-catch
+let count = 0
+for i in (0,5) {
+    try {
+        let arr = [i]
+        try {
+            if (i / 2 * 2 == i) {
+                throw "even"
+            }
+            count += i
+        } catch _ {
+            throw "rethrow"
+        }
+    } catch msg {
+        println(msg)
+    }
+}
+println(count) // 4
+
+// 捕获的错误带有信息和调用栈 再次抛出时保留原来的调用栈
+// the caught error carries the message and the traceback, the original traceback is kept when thrown again
+try {
+    try {
+        divide(1, 0)
+    } catch err {
+        throw err
+    }
+} catch err {
+    println(err.message()) // "attempt to divide by zero"
+    println(err.traceback())
+    // "Traceback (most recent call last):
+    //   File "gloom/TryTest.gs", line 9, in divide
+    // runtime error : attempt to divide by zero"
+}
+
+throw "uncaught"
+println("unreachable")
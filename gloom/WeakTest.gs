@@ -0,0 +1,60 @@
+class Parent {
+    pub String name
+    pub Array<Child> children
+    pub func addChild(self, String name) Child {
+        let child = Child{ name : name, parent : Weak.of(self) }
+        self.children.push(child)
+        child
+    }
+    func drop(self) {
+        println([self.name, "Drop"])
+    }
+}
+
+class Child {
+    pub String name
+    pub Weak<Parent> parent
+    pub func parentName(self) String {
+        let parent = self.parent.upgrade()
+        if parent.isSome() {
+            parent.get().name
+        } else {
+            "none"
+        }
+    }
+    func drop(self) {
+        // 父对象正在被回收时 弱引用无法升级 weak reference can't be upgraded while parent is being finalized
+        println([self.name, "Drop"])
+        println(self.parent.isAlive())
+    }
+}
+
+let kept = func() Child {
+    let children Array<Child> = []
+    let parent = Parent{ name : "root", children : children }
+    let first = parent.addChild("first")
+    parent.addChild("second")
+    println(first.parentName())
+    println(parent.children.len())
+    first
+}()
+println(kept.parentName())
+println(kept.parent.upgrade())
+
+let text = "hello"
+let weakText = Weak.of(text)
+println(weakText.upgrade().get())
+println(weakText)
+/*
+"root"
+2
+["root", "Drop"]
+["second", "Drop"]
+false
+"none"
+None
+"hello"
+Weak("hello")
+["first", "Drop"]
+false
+*/
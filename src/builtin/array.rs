@@ -1,18 +1,22 @@
-use crate::builtin::classes::BuiltinClass;
-use hashbrown::HashMap;
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::function::call_back;
+use crate::obj::func::GloomFuncObj;
 use std::any::Any;
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 
 use crate::builtin::iter::GloomListIter;
+use crate::builtin::string::GloomString;
 use crate::frontend::status::GloomStatus;
-use crate::obj::func::{GloomFunc, Param, ReturnType};
+use crate::obj::func::{GloomFunc, ReturnType};
 use crate::obj::object::{GloomObjRef, Object, ObjectType};
 use crate::obj::refcount::RefCount;
-use crate::obj::types::{DataType, RefType};
+use crate::obj::types::{BasicType, DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
 use crate::vm::machine::GloomVM;
-use crate::vm::value::Value;
+use crate::vm::value::{GloomArgs, Value};
 
 pub struct GloomArray(pub RefCell<RawArray>);
 
@@ -79,13 +83,35 @@ impl Object for GloomArray {
         todo!()
     }
 
-    fn method(&self, _: u16, _: &GloomStatus) -> RefCount<GloomFunc> {
-        todo!()
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::ARRAY_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
     }
 
     fn field(&self, _: u16, _: u8) -> Value {
         panic!()
     }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(array) = self.0.try_borrow() {
+            if let RawArray::RefVec(vec) = &*array {
+                vec.iter().for_each(f);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        if let RawArray::RefVec(vec) = &mut *self.0.borrow_mut() {
+            vec.clear();
+        }
+    }
 }
 
 impl Debug for RawArray {
@@ -100,15 +126,618 @@ impl Debug for RawArray {
     }
 }
 
+impl RawArray {
+    // 按元素的类型选择特化的存储 元素类型不一致时装箱存储
+    // choose the specialized storage by the type of elements, box them if the types are different
+    pub fn from_values(values: Vec<Value>) -> Result<RawArray, GloomError> {
+        let same_as_first = |value: &Value| {
+            matches!(
+                (values.first(), value),
+                (Some(Value::Int(_)), Value::Int(_))
+                    | (Some(Value::Num(_)), Value::Num(_))
+                    | (Some(Value::Char(_)), Value::Char(_))
+                    | (Some(Value::Bool(_)), Value::Bool(_))
+            )
+        };
+        if !values.iter().all(same_as_first) {
+            return Result::Ok(RawArray::RefVec(
+                values
+                    .into_iter()
+                    .map(Value::assert_into_ref)
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        Result::Ok(match values.first() {
+            Some(Value::Int(_)) => RawArray::IntVec(
+                values
+                    .iter()
+                    .map(Value::assert_int)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Some(Value::Num(_)) => RawArray::NumVec(
+                values
+                    .iter()
+                    .map(Value::assert_num)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Some(Value::Char(_)) => RawArray::CharVec(
+                values
+                    .iter()
+                    .map(Value::assert_char)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Some(Value::Bool(_)) => RawArray::BoolVec(
+                values
+                    .iter()
+                    .map(Value::assert_bool)
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => RawArray::RefVec(Vec::new()),
+        })
+    }
+
+    // 与自身存储类型相同的空数组 empty array with the same storage type of self
+    pub fn empty_of(basic_type: BasicType) -> RawArray {
+        match basic_type {
+            BasicType::Int => RawArray::IntVec(Vec::new()),
+            BasicType::Num => RawArray::NumVec(Vec::new()),
+            BasicType::Char => RawArray::CharVec(Vec::new()),
+            BasicType::Bool => RawArray::BoolVec(Vec::new()),
+            BasicType::Ref => RawArray::RefVec(Vec::new()),
+        }
+    }
+
+    pub fn empty_like(&self) -> RawArray {
+        match self {
+            RawArray::IntVec(_) => RawArray::IntVec(Vec::new()),
+            RawArray::NumVec(_) => RawArray::NumVec(Vec::new()),
+            RawArray::CharVec(_) => RawArray::CharVec(Vec::new()),
+            RawArray::BoolVec(_) => RawArray::BoolVec(Vec::new()),
+            RawArray::RefVec(_) => RawArray::RefVec(Vec::new()),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            RawArray::IntVec(vec) => vec.len(),
+            RawArray::NumVec(vec) => vec.len(),
+            RawArray::CharVec(vec) => vec.len(),
+            RawArray::BoolVec(vec) => vec.len(),
+            RawArray::RefVec(vec) => vec.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn values(&self) -> Vec<Value> {
+        match self {
+            RawArray::IntVec(vec) => vec.iter().map(|i| Value::Int(*i)).collect(),
+            RawArray::NumVec(vec) => vec.iter().map(|f| Value::Num(*f)).collect(),
+            RawArray::CharVec(vec) => vec.iter().map(|c| Value::Char(*c)).collect(),
+            RawArray::BoolVec(vec) => vec.iter().map(|b| Value::Bool(*b)).collect(),
+            RawArray::RefVec(vec) => vec.iter().map(|rf| Value::Ref(rf.clone())).collect(),
+        }
+    }
+
+    // 检查下标越界 越界时产生运行时错误 check the index, runtime error if out of bounds
+    #[inline]
+    pub fn check_index(&self, index: i64, len: usize) -> Result<usize, GloomError> {
+        if index < 0 || index as usize >= len {
+            return Result::Err(GloomError::new(format!(
+                "index {} out of bounds for array of length {}",
+                index,
+                self.len()
+            )));
+        }
+        Result::Ok(index as usize)
+    }
+
+    pub fn get(&self, index: i64) -> Result<Value, GloomError> {
+        let index = self.check_index(index, self.len())?;
+        Result::Ok(match self {
+            RawArray::IntVec(vec) => Value::Int(vec[index]),
+            RawArray::NumVec(vec) => Value::Num(vec[index]),
+            RawArray::CharVec(vec) => Value::Char(vec[index]),
+            RawArray::BoolVec(vec) => Value::Bool(vec[index]),
+            RawArray::RefVec(vec) => Value::Ref(vec[index].clone()),
+        })
+    }
+
+    // 被擦除的类型参数传入的值可能是装箱的 value passed by erased type parameter may be boxed
+    pub fn set(&mut self, vm: &GloomVM, index: i64, value: Value) -> Result<(), GloomError> {
+        let index = self.check_index(index, self.len())?;
+        match self {
+            RawArray::IntVec(vec) => vec[index] = value.assert_int_form_num_liked()?,
+            RawArray::NumVec(vec) => vec[index] = value.assert_num_include_int()?,
+            RawArray::CharVec(vec) => vec[index] = value.assert_char_include_int()?,
+            RawArray::BoolVec(vec) => vec[index] = value.assert_bool()?,
+            RawArray::RefVec(vec) => {
+                let old = std::mem::replace(&mut vec[index], value.assert_into_ref()?);
+                vm.drop_object(&old);
+            }
+        }
+        Result::Ok(())
+    }
+
+    pub fn insert(&mut self, index: i64, value: Value) -> Result<(), GloomError> {
+        // 可以插入到末尾 could insert at the end
+        let index = self.check_index(index, self.len() + 1)?;
+        match self {
+            RawArray::IntVec(vec) => vec.insert(index, value.assert_int_form_num_liked()?),
+            RawArray::NumVec(vec) => vec.insert(index, value.assert_num_include_int()?),
+            RawArray::CharVec(vec) => vec.insert(index, value.assert_char_include_int()?),
+            RawArray::BoolVec(vec) => vec.insert(index, value.assert_bool()?),
+            RawArray::RefVec(vec) => vec.insert(index, value.assert_into_ref()?),
+        }
+        Result::Ok(())
+    }
+
+    #[inline]
+    pub fn push(&mut self, value: Value) -> Result<(), GloomError> {
+        self.insert(self.len() as i64, value)
+    }
+
+    pub fn remove(&mut self, index: i64) -> Result<Value, GloomError> {
+        let index = self.check_index(index, self.len())?;
+        Result::Ok(match self {
+            RawArray::IntVec(vec) => Value::Int(vec.remove(index)),
+            RawArray::NumVec(vec) => Value::Num(vec.remove(index)),
+            RawArray::CharVec(vec) => Value::Char(vec.remove(index)),
+            RawArray::BoolVec(vec) => Value::Bool(vec.remove(index)),
+            RawArray::RefVec(vec) => Value::Ref(vec.remove(index)),
+        })
+    }
+
+    pub fn pop(&mut self) -> Result<Value, GloomError> {
+        if self.is_empty() {
+            return Result::Err(GloomError::new(String::from("pop from empty array")));
+        }
+        self.remove(self.len() as i64 - 1)
+    }
+
+    pub fn clear(&mut self, vm: &GloomVM) {
+        match self {
+            RawArray::IntVec(vec) => vec.clear(),
+            RawArray::NumVec(vec) => vec.clear(),
+            RawArray::CharVec(vec) => vec.clear(),
+            RawArray::BoolVec(vec) => vec.clear(),
+            RawArray::RefVec(vec) => {
+                for rf in vec.drain(..) {
+                    vm.drop_object(&rf);
+                }
+            }
+        }
+    }
+
+    pub fn index_of(&self, value: &Value) -> Result<i64, GloomError> {
+        let index = match self {
+            RawArray::IntVec(vec) => {
+                let int = value.assert_int_form_num_liked()?;
+                vec.iter().position(|i| *i == int)
+            }
+            RawArray::NumVec(vec) => {
+                let num = value.assert_num_include_int()?;
+                vec.iter().position(|f| *f == num)
+            }
+            RawArray::CharVec(vec) => {
+                let ch = value.assert_char_include_int()?;
+                vec.iter().position(|c| *c == ch)
+            }
+            RawArray::BoolVec(vec) => {
+                let bl = value.assert_bool()?;
+                vec.iter().position(|b| *b == bl)
+            }
+            RawArray::RefVec(vec) => {
+                let mut found = Option::None;
+                for (index, rf) in vec.iter().enumerate() {
+                    if RawArray::ref_equals(rf, value.clone())? {
+                        found = Option::Some(index);
+                        break;
+                    }
+                }
+                found
+            }
+        };
+        Result::Ok(match index {
+            Some(index) => index as i64,
+            None => -1,
+        })
+    }
+
+    // 字符串按内容比较 string is compared by content
+    fn ref_equals(rf: &GloomObjRef, value: Value) -> Result<bool, GloomError> {
+        if let (ObjectType::String, Value::Ref(other)) = (rf.obj_type(), &value) {
+            if let ObjectType::String = other.obj_type() {
+                return Result::Ok(
+                    rf.downcast::<GloomString>().0.borrow().as_str()
+                        == other.downcast::<GloomString>().0.borrow().as_str(),
+                );
+            }
+        }
+        Value::Ref(rf.clone()).equals(value)
+    }
+
+    pub fn slice(&self, from: i64, to: i64) -> Result<RawArray, GloomError> {
+        let len = self.len();
+        if from < 0 || to < from || to as usize > len {
+            return Result::Err(GloomError::new(format!(
+                "slice {}..{} out of bounds for array of length {}",
+                from, to, len
+            )));
+        }
+        let (from, to) = (from as usize, to as usize);
+        Result::Ok(match self {
+            RawArray::IntVec(vec) => RawArray::IntVec(vec[from..to].to_vec()),
+            RawArray::NumVec(vec) => RawArray::NumVec(vec[from..to].to_vec()),
+            RawArray::CharVec(vec) => RawArray::CharVec(vec[from..to].to_vec()),
+            RawArray::BoolVec(vec) => RawArray::BoolVec(vec[from..to].to_vec()),
+            RawArray::RefVec(vec) => RawArray::RefVec(vec[from..to].to_vec()),
+        })
+    }
+
+    pub fn reverse(&mut self) {
+        match self {
+            RawArray::IntVec(vec) => vec.reverse(),
+            RawArray::NumVec(vec) => vec.reverse(),
+            RawArray::CharVec(vec) => vec.reverse(),
+            RawArray::BoolVec(vec) => vec.reverse(),
+            RawArray::RefVec(vec) => vec.reverse(),
+        }
+    }
+
+    // 按自然顺序排序 只有基本类型和字符串有自然顺序
+    // sort by natural order, only basic types and string have natural order
+    pub fn sort(&mut self) -> Result<(), GloomError> {
+        match self {
+            RawArray::IntVec(vec) => vec.sort_unstable(),
+            RawArray::NumVec(vec) => vec.sort_by(|a, b| a.total_cmp(b)),
+            RawArray::CharVec(vec) => vec.sort_unstable(),
+            RawArray::BoolVec(vec) => vec.sort_unstable(),
+            RawArray::RefVec(vec) => {
+                if let Some(rf) = vec
+                    .iter()
+                    .find(|rf| !matches!(rf.obj_type(), ObjectType::String))
+                {
+                    return Result::Err(GloomError::new(format!(
+                        "{:?} has no natural order to sort",
+                        rf
+                    )));
+                }
+                vec.sort_by(|a, b| {
+                    a.downcast::<GloomString>()
+                        .0
+                        .borrow()
+                        .cmp(&*b.downcast::<GloomString>().0.borrow())
+                });
+            }
+        }
+        Result::Ok(())
+    }
+}
+
 impl BuiltinClass {
     pub fn gloom_array_class() -> BuiltinClass {
-        let map = HashMap::new();
-        let funcs = Vec::new();
+        // 数组的元素类型T是第一个类型参数 element type T of array is the first type parameter
+        let elem_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let result_param = GenericParam {
+            name: Rc::new(String::from("R")),
+            bound: Option::None,
+        };
+        let elem = elem_param.as_data_type(0);
+        let result = result_param.as_data_type(1);
+        let array_of =
+            |data_type: &DataType| DataType::Ref(RefType::Array(Box::new(data_type.clone())));
+        let func_of = Methods::func_type;
+        let param = Methods::param;
+        let mut methods = Methods::new(array_of(&elem), vec![elem_param]);
+        methods.add(
+            "len",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(array_of_args(&args).0.borrow().len() as i64))),
+        );
+        methods.add(
+            "isEmpty",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(array_of_args(&args).0.borrow().is_empty()))),
+        );
+        methods.add(
+            "get",
+            vec![param("index", DataType::Int)],
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let index = args.vec[1].assert_int()?;
+                let value = array_of_args(&args).0.borrow().get(index);
+                value
+            }),
+        );
+        methods.add(
+            "set",
+            vec![param("index", DataType::Int), param("elem", elem.clone())],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, mut args| {
+                let value = args.vec.pop().unwrap();
+                let index = args.vec[1].assert_int()?;
+                array_of_args(&args).0.borrow_mut().set(vm, index, value)?;
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "push",
+            vec![param("elem", elem.clone())],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let value = args.vec.pop().unwrap();
+                array_of_args(&args).0.borrow_mut().push(value)?;
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "pop",
+            Vec::with_capacity(0),
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| array_of_args(&args).0.borrow_mut().pop()),
+        );
+        methods.add(
+            "insert",
+            vec![param("index", DataType::Int), param("elem", elem.clone())],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let value = args.vec.pop().unwrap();
+                let index = args.vec[1].assert_int()?;
+                array_of_args(&args).0.borrow_mut().insert(index, value)?;
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "remove",
+            vec![param("index", DataType::Int)],
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let index = args.vec[1].assert_int()?;
+                let value = array_of_args(&args).0.borrow_mut().remove(index);
+                value
+            }),
+        );
+        methods.add(
+            "clear",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                array_of_args(&args).0.borrow_mut().clear(vm);
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "indexOf",
+            vec![param("elem", elem.clone())],
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let index = array_of_args(&args).0.borrow().index_of(&args.vec[1])?;
+                Result::Ok(Value::Int(index))
+            }),
+        );
+        methods.add(
+            "contains",
+            vec![param("elem", elem.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let index = array_of_args(&args).0.borrow().index_of(&args.vec[1])?;
+                Result::Ok(Value::Bool(index >= 0))
+            }),
+        );
+        methods.add(
+            "slice",
+            vec![param("from", DataType::Int), param("to", DataType::Int)],
+            ReturnType::Have(array_of(&elem)),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let (from, to) = (args.vec[1].assert_int()?, args.vec[2].assert_int()?);
+                let slice = array_of_args(&args).0.borrow().slice(from, to)?;
+                Result::Ok(Value::Ref(GloomArray::new(slice)))
+            }),
+        );
+        methods.add(
+            "reverse",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                array_of_args(&args).0.borrow_mut().reverse();
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "sort",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                array_of_args(&args).0.borrow_mut().sort()?;
+                Result::Ok(Value::None)
+            }),
+        );
+        // 回调时不持有数组的借用 因为回调的函数可能修改数组 排序期间数组被修改是运行时错误
+        // don't hold the borrow of array when calling back, because the called function may modify the array,
+        // modifying the array during sorting is a runtime error
+        methods.add(
+            "sortBy",
+            vec![param(
+                "compare",
+                func_of(
+                    vec![elem.clone(), elem.clone()],
+                    ReturnType::Have(DataType::Int),
+                ),
+            )],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let array = array_of_args(&args);
+                let func = args.vec[1].as_ref();
+                let mut values = array.0.borrow().values();
+                let before = values.clone();
+                // 归并排序 比较函数的第一个错误会中断排序
+                // merge sort, the first error of compare function interrupts the sort
+                let mut error = Option::None;
+                values.sort_by(|a, b| {
+                    if error.is_some() {
+                        return Ordering::Equal;
+                    }
+                    match call_back(vm, func, vec![a.clone(), b.clone()])
+                        .and_then(|order| order.assert_int())
+                    {
+                        Ok(order) => order.cmp(&0),
+                        Err(err) => {
+                            error = Option::Some(err);
+                            Ordering::Equal
+                        }
+                    }
+                });
+                if let Some(error) = error {
+                    return Result::Err(error);
+                }
+                let mut raw = array.0.borrow_mut();
+                if !same_values(&before, &raw.values()) {
+                    return Result::Err(GloomError::new(String::from(
+                        "the array is modified by the compare function during sortBy",
+                    )));
+                }
+                let mut sorted = raw.empty_like();
+                for value in values {
+                    sorted.push(value)?;
+                }
+                *raw = sorted;
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "map",
+            vec![param(
+                "mapper",
+                func_of(vec![elem.clone()], ReturnType::Have(result.clone())),
+            )],
+            ReturnType::Have(array_of(&result)),
+            vec![result_param.clone()],
+            Rc::new(|vm, args| {
+                let func = args.vec[1].as_ref();
+                let values = array_of_args(&args).0.borrow().values();
+                let mapped: Vec<Value> = values
+                    .into_iter()
+                    .map(|value| call_back(vm, func, vec![value]))
+                    .collect::<Result<_, _>>()?;
+                // 结果数组的存储形式取决于函数声明的返回类型 而非返回的值 因此空数组也是正确的
+                // the storage of result array depends on the declared return type of function rather than
+                // the returned values, so that the empty array is also correct
+                let basic_type = match &func
+                    .downcast::<GloomFuncObj>()
+                    .func
+                    .inner()
+                    .info
+                    .return_type
+                {
+                    ReturnType::Have(DataType::Ref(RefType::Any | RefType::TypeParam(_)))
+                    | ReturnType::Void => Option::None,
+                    ReturnType::Have(data_type) => Option::Some(data_type.as_basic()),
+                };
+                let array = match basic_type {
+                    Some(basic_type) => {
+                        let mut array = RawArray::empty_of(basic_type);
+                        for value in mapped {
+                            array.push(value)?;
+                        }
+                        array
+                    }
+                    None => RawArray::from_values(mapped)?,
+                };
+                Result::Ok(Value::Ref(GloomArray::new(array)))
+            }),
+        );
+        methods.add(
+            "filter",
+            vec![param(
+                "predicate",
+                func_of(vec![elem.clone()], ReturnType::Have(DataType::Bool)),
+            )],
+            ReturnType::Have(array_of(&elem)),
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let array = array_of_args(&args);
+                let func = args.vec[1].as_ref();
+                let values = array.0.borrow().values();
+                let mut filtered = array.0.borrow().empty_like();
+                for value in values {
+                    if call_back(vm, func, vec![value.clone()])?.assert_bool()? {
+                        filtered.push(value)?;
+                    }
+                }
+                Result::Ok(Value::Ref(GloomArray::new(filtered)))
+            }),
+        );
+        methods.add(
+            "reduce",
+            vec![
+                param("init", result.clone()),
+                param(
+                    "reducer",
+                    func_of(
+                        vec![result.clone(), elem.clone()],
+                        ReturnType::Have(result.clone()),
+                    ),
+                ),
+            ],
+            ReturnType::Have(result.clone()),
+            vec![result_param.clone()],
+            Rc::new(|vm, args| {
+                let func = args.vec[2].as_ref();
+                let values = array_of_args(&args).0.borrow().values();
+                let mut acc = args.vec[1].clone();
+                for value in values {
+                    acc = call_back(vm, func, vec![acc, value])?;
+                }
+                Result::Ok(acc)
+            }),
+        );
+        methods.add(
+            "forEach",
+            vec![param(
+                "action",
+                func_of(vec![elem.clone()], ReturnType::Void),
+            )],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let func = args.vec[1].as_ref();
+                let values = array_of_args(&args).0.borrow().values();
+                for value in values {
+                    call_back(vm, func, vec![value])?;
+                }
+                Result::Ok(Value::None)
+            }),
+        );
 
         BuiltinClass {
             name: "Array".to_string(),
-            map,
-            funcs,
+            map: methods.map,
+            funcs: methods.funcs,
             get_ref_type_fn: Box::new(|option| match option {
                 None => Result::Ok(RefType::Array(Box::new(DataType::Ref(RefType::Any)))),
                 Some(mut vec) => {
@@ -125,3 +754,21 @@ impl BuiltinClass {
         }
     }
 }
+
+#[inline]
+fn array_of_args(args: &GloomArgs) -> &GloomArray {
+    args.vec[0].as_ref().downcast::<GloomArray>()
+}
+
+// 按值比较基本类型 按地址比较引用 compare basic types by value, compare references by address
+fn same_values(a: &[Value], b: &[Value]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b.iter()).all(|pair| match pair {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Num(a), Value::Num(b)) => a.to_bits() == b.to_bits(),
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Ref(a), Value::Ref(b)) => a.addr_eqs(b),
+            _ => false,
+        })
+}
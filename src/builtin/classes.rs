@@ -2,10 +2,12 @@ use std::fmt::{Debug, Formatter};
 
 use hashbrown::HashMap;
 
+use std::rc::Rc;
+
 use crate::frontend::status::TypeIndex;
-use crate::obj::func::GloomFunc;
+use crate::obj::func::{BuiltinFn, GloomFunc, Param, ReturnType};
 use crate::obj::refcount::RefCount;
-use crate::obj::types::{BuiltinType, DataType, RefType};
+use crate::obj::types::{BuiltinType, DataType, GenericParam, RefType};
 
 pub struct BuiltinClass {
     pub name: String,
@@ -23,6 +25,16 @@ impl BuiltinClass {
         vec.push(RefCount::new(Self::gloom_string_class()));
         vec.push(RefCount::new(Self::gloom_func_class()));
         vec.push(RefCount::new(Self::gloom_array_class()));
+        vec.push(RefCount::new(Self::gloom_map_class()));
+        vec.push(RefCount::new(Self::gloom_set_class()));
+        vec.push(RefCount::new(Self::gloom_queue_class()));
+        vec.push(RefCount::new(Self::gloom_option_class()));
+        vec.push(RefCount::new(Self::gloom_weak_class()));
+        vec.push(RefCount::new(Self::gloom_generator_class()));
+        vec.push(RefCount::new(Self::gloom_task_class()));
+        vec.push(RefCount::new(Self::gloom_timer_class()));
+        vec.push(RefCount::new(Self::gloom_channel_class()));
+        vec.push(RefCount::new(Self::gloom_error_class()));
         vec
     }
     pub fn class_map() -> HashMap<String, TypeIndex> {
@@ -30,17 +42,55 @@ impl BuiltinClass {
         map.insert(String::from("String"), TypeIndex::builtin(0));
         map.insert(String::from("Func"), TypeIndex::builtin(1));
         map.insert(String::from("Array"), TypeIndex::builtin(2));
+        map.insert(String::from("Map"), TypeIndex::builtin(3));
+        map.insert(String::from("Set"), TypeIndex::builtin(4));
+        map.insert(String::from("Queue"), TypeIndex::builtin(5));
+        map.insert(String::from("Option"), TypeIndex::builtin(6));
+        map.insert(String::from("Weak"), TypeIndex::builtin(7));
+        map.insert(String::from("Generator"), TypeIndex::builtin(8));
+        map.insert(String::from("Task"), TypeIndex::builtin(9));
+        map.insert(String::from("Timer"), TypeIndex::builtin(10));
+        map.insert(String::from("Channel"), TypeIndex::builtin(11));
+        map.insert(String::from("Error"), TypeIndex::builtin(12));
         map
     }
     pub fn builtin_type_map() -> HashMap<BuiltinType, u16> {
         let mut map = HashMap::new();
         map.insert(BuiltinType::String, 0);
         map.insert(BuiltinType::Func, 1);
+        map.insert(BuiltinType::Array, 2);
+        map.insert(BuiltinType::Map, 3);
+        map.insert(BuiltinType::Set, 4);
+        map.insert(BuiltinType::Queue, 5);
+        map.insert(BuiltinType::Option, 6);
+        map.insert(BuiltinType::Weak, 7);
+        map.insert(BuiltinType::Generator, 8);
+        map.insert(BuiltinType::Task, 9);
+        map.insert(BuiltinType::Error, 12);
+        map
+    }
+    // 标准库提供的类型 导入对应的库之后才能使用
+    // types provided by std library, could only be used after the library is imported
+    pub fn std_type_map() -> HashMap<BuiltinType, u16> {
+        let mut map = HashMap::new();
+        map.insert(BuiltinType::Timer, 10);
+        map.insert(BuiltinType::Channel, 11);
         map
     }
 
     pub const STRING_INDEX: usize = 0;
     pub const FUNC_INDEX: usize = 1;
+    pub const ARRAY_INDEX: usize = 2;
+    pub const MAP_INDEX: usize = 3;
+    pub const SET_INDEX: usize = 4;
+    pub const QUEUE_INDEX: usize = 5;
+    pub const OPTION_INDEX: usize = 6;
+    pub const WEAK_INDEX: usize = 7;
+    pub const GENERATOR_INDEX: usize = 8;
+    pub const TASK_INDEX: usize = 9;
+    pub const TIMER_INDEX: usize = 10;
+    pub const CHANNEL_INDEX: usize = 11;
+    pub const ERROR_INDEX: usize = 12;
 
     pub fn none_generic_fn(
         ref_type: RefType,
@@ -56,6 +106,93 @@ impl BuiltinClass {
     }
 }
 
+// 内置类的方法表 类的类型参数在前 方法自己的在后
+// method table of builtin class, type parameters of class are in front of the method's own
+pub struct Methods {
+    pub map: HashMap<String, u16>,
+    pub funcs: Vec<RefCount<GloomFunc>>,
+    self_param: Param,
+    generics: Vec<GenericParam>,
+}
+
+impl Methods {
+    pub fn new(self_type: DataType, generics: Vec<GenericParam>) -> Methods {
+        Methods {
+            map: HashMap::new(),
+            funcs: Vec::new(),
+            self_param: Param::new(Rc::new(String::from("self")), self_type),
+            generics,
+        }
+    }
+
+    #[inline]
+    pub fn param(name: &str, data_type: DataType) -> Param {
+        Param::new(Rc::new(String::from(name)), data_type)
+    }
+
+    #[inline]
+    pub fn func_type(params: Vec<DataType>, return_type: ReturnType) -> DataType {
+        DataType::Ref(RefType::Func(Box::new((params, return_type, false))))
+    }
+
+    pub fn add(
+        &mut self,
+        name: &str,
+        mut params: Vec<Param>,
+        return_type: ReturnType,
+        own_generics: Vec<GenericParam>,
+        body: BuiltinFn,
+    ) {
+        params.insert(0, self.self_param.clone());
+        let mut func =
+            GloomFunc::new_builtin_fn(Rc::new(String::from(name)), params, return_type, true, body);
+        func.info.generics = self.generics.clone();
+        func.info.generics.extend(own_generics);
+        self.map.insert(String::from(name), self.funcs.len() as u16);
+        self.funcs.push(RefCount::new(func));
+    }
+
+    // 通过类型调用的函数 类的类型参数由实参推断 function called by type, type parameters of class are inferred by args
+    pub fn add_static(
+        &mut self,
+        name: &str,
+        params: Vec<Param>,
+        return_type: ReturnType,
+        body: BuiltinFn,
+    ) {
+        let mut func = GloomFunc::new_builtin_fn(
+            Rc::new(String::from(name)),
+            params,
+            return_type,
+            false,
+            body,
+        );
+        func.info.generics = self.generics.clone();
+        self.map.insert(String::from(name), self.funcs.len() as u16);
+        self.funcs.push(RefCount::new(func));
+    }
+
+    // 不使用类的类型参数的静态函数 无需推断类型实参
+    // static function not using type parameters of class, no type argument needs to be inferred
+    pub fn add_plain_static(
+        &mut self,
+        name: &str,
+        params: Vec<Param>,
+        return_type: ReturnType,
+        body: BuiltinFn,
+    ) {
+        let func = GloomFunc::new_builtin_fn(
+            Rc::new(String::from(name)),
+            params,
+            return_type,
+            false,
+            body,
+        );
+        self.map.insert(String::from(name), self.funcs.len() as u16);
+        self.funcs.push(RefCount::new(func));
+    }
+}
+
 impl Debug for BuiltinClass {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
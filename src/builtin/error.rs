@@ -0,0 +1,108 @@
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::string::GloomString;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 被 catch 捕获的错误 不可变 再次抛出时保留原来的调用栈
+// error caught by catch, immutable, the original traceback is kept when thrown again
+pub struct GloomErrorObj(pub GloomError);
+
+impl GloomErrorObj {
+    #[inline]
+    pub fn new(error: GloomError) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomErrorObj(error)))
+    }
+}
+
+impl Debug for GloomErrorObj {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error(\"{}\")", self.0.msg)
+    }
+}
+
+impl Object for GloomErrorObj {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Error
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, _: &GloomVM, _: &GloomObjRef) {}
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::ERROR_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_error_class() -> BuiltinClass {
+        let mut methods = Methods::new(DataType::Ref(RefType::Error), Vec::with_capacity(0));
+        methods.add(
+            "message",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::String)),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let msg = error_of_args(&args).0.msg.clone();
+                Result::Ok(Value::Ref(GloomString::new(msg)))
+            }),
+        );
+        // 与未被捕获时打印的调用栈相同 same as the traceback printed when uncaught
+        methods.add(
+            "traceback",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::String)),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let traceback = error_of_args(&args).0.traceback();
+                Result::Ok(Value::Ref(GloomString::new(traceback)))
+            }),
+        );
+        BuiltinClass {
+            name: "Error".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: BuiltinClass::none_generic_fn(RefType::Error),
+        }
+    }
+}
+
+#[inline]
+fn error_of_args(args: &GloomArgs) -> &GloomErrorObj {
+    args.vec[0].as_ref().downcast::<GloomErrorObj>()
+}
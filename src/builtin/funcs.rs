@@ -1,5 +1,6 @@
 use std::rc::Rc;
 use std::io::prelude::*;
+use std::time::Duration;
 
 use hashbrown::HashMap;
 use crate::builtin::string::GloomString;
@@ -7,7 +8,9 @@ use crate::builtin::string::GloomString;
 use crate::obj::class::IsPub;
 use crate::obj::func::{GloomFunc, Param, ReturnType};
 use crate::obj::refcount::RefCount;
+use crate::obj::task::GloomTask;
 use crate::obj::types::{DataType, RefType};
+use crate::vm::error::GloomError;
 use crate::vm::value::Value;
 
 pub struct BuiltInFuncs;
@@ -27,7 +30,7 @@ impl BuiltInFuncs {
                     print!("{:?}", obj);
                 }
                 print!("\r\n");
-                Value::None
+                Result::Ok(Value::None)
             }),
         ))
     }
@@ -43,7 +46,7 @@ impl BuiltInFuncs {
                     print!("{:?}", obj);
                 }
                 std::io::stdout().flush().unwrap();
-                Value::None
+                Result::Ok(Value::None)
             }),
         ))
     }
@@ -55,17 +58,61 @@ impl BuiltInFuncs {
             false,
             Rc::new(|_, _| {
                 let mut buf = String::new();
-                std::io::stdin().read_line(&mut buf).unwrap();
-                Value::Ref(GloomString::new(buf.trim_end().to_string()))
+                if let Err(error) = std::io::stdin().read_line(&mut buf) {
+                    return Result::Err(GloomError::new(format!("failed to read input, {}", error)));
+                }
+                Result::Ok(Value::Ref(GloomString::new(buf.trim_end().to_string())))
+            }),
+        ))
+    }
+    // 立即回收循环引用 返回回收的对象数量 collect reference cycles immediately, return the number of collected objects
+    fn func_gc() -> RefCount<GloomFunc>{
+        RefCount::new(GloomFunc::new_builtin_fn(
+            Rc::new(String::from("gc")),
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            false,
+            Rc::new(|vm, _| {
+                Result::Ok(Value::Int(vm.collect_cycles() as i64))
             }),
         ))
     }
+    // 挂起当前任务指定的毫秒数 suspend the current task for the given milliseconds
+    fn func_sleep() -> RefCount<GloomFunc>{
+        let params = vec![Param::new(Rc::new(String::from("millis")), DataType::Int)];
+        RefCount::new(GloomFunc::new_builtin_fn(
+            Rc::new(String::from("sleep")),
+            params,
+            ReturnType::Have(DataType::Ref(RefType::Task(Box::new(DataType::Ref(RefType::None))))),
+            false,
+            Rc::new(|_, args| {
+                let millis = args.vec[0].assert_int()?;
+                if millis < 0 {
+                    return Result::Err(GloomError::new(format!("sleep for negative time {}ms", millis)));
+                }
+                Result::Ok(Value::Ref(GloomTask::sleep(Duration::from_millis(millis as u64))))
+            }),
+        ))
+    }
+    // 让其它就绪的任务先执行 let other ready tasks run first
+    fn func_yield_now() -> RefCount<GloomFunc>{
+        RefCount::new(GloomFunc::new_builtin_fn(
+            Rc::new(String::from("yieldNow")),
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Task(Box::new(DataType::Ref(RefType::None))))),
+            false,
+            Rc::new(|_, _| Result::Ok(Value::Ref(GloomTask::yield_now()))),
+        ))
+    }
     pub fn func_list() -> Vec<RefCount<GloomFunc>> {
         let empty_name = Rc::new(String::from(""));
         vec![
         Self::func_println(empty_name.clone()),
         Self::func_print(empty_name.clone()),
-        Self::func_input()
+        Self::func_input(),
+        Self::func_gc(),
+        Self::func_sleep(),
+        Self::func_yield_now()
         ]
     }
     pub fn func_map() -> HashMap<String, (u16, IsBuiltIn, IsPub, u16)> {
@@ -73,6 +120,9 @@ impl BuiltInFuncs {
         map.insert(String::from("println"), (0, true, true, 0));
         map.insert(String::from("print"  ), (1, true, true, 0));
         map.insert(String::from("input"  ), (2, true, true, 0));
+        map.insert(String::from("gc"     ), (3, true, true, 0));
+        map.insert(String::from("sleep"  ), (4, true, true, 0));
+        map.insert(String::from("yieldNow"), (5, true, true, 0));
         map
     }
 }
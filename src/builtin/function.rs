@@ -4,9 +4,19 @@ use hashbrown::HashMap;
 
 use crate::builtin::classes::BuiltinClass;
 use crate::obj::func::{GloomFunc, GloomFuncObj, Param, ReturnType};
+use crate::obj::object::GloomObjRef;
 use crate::obj::refcount::RefCount;
 use crate::obj::types::{DataType, RefType};
-use crate::vm::value::Value;
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 内置函数回调脚本中的函数 错误由内置函数继续传播
+// builtin function calls back the function of script, the error is propagated by the builtin function
+#[inline]
+pub fn call_back(vm: &GloomVM, func: &GloomObjRef, args: Vec<Value>) -> Result<Value, GloomError> {
+    vm.call(func.downcast::<GloomFuncObj>(), GloomArgs::new(args))
+}
 
 impl BuiltinClass {
     pub fn gloom_func_class() -> BuiltinClass {
@@ -26,9 +36,9 @@ impl BuiltinClass {
             ReturnType::Void,
             true,
             Rc::new(|_, mut args| {
-                let obj_ref = args.vec.pop().unwrap().assert_into_ref();
+                let obj_ref = args.vec.pop().unwrap().assert_into_ref()?;
                 println!("{:?}", obj_ref.downcast::<GloomFuncObj>().func.inner().body);
-                Value::None
+                Result::Ok(Value::None)
             }),
         )));
         map.insert(String::from("printBody"), 0);
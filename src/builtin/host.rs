@@ -0,0 +1,107 @@
+use std::any::Any;
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::GloomFunc;
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, RefType};
+use crate::vm::machine::GloomVM;
+use crate::vm::value::Value;
+
+// 宿主程序注册的类 用于声明方法的参数类型和创建实例
+// class registered by host program, used to declare the param types of methods and create instances
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostClass {
+    pub index: u16,
+    pub name: Rc<String>,
+}
+
+impl HostClass {
+    #[inline]
+    pub fn data_type(&self) -> DataType {
+        DataType::Ref(RefType::Host(self.index, self.name.clone()))
+    }
+}
+
+// 宿主类的实例 包装一个Rust值 instance of host class, wraps a rust value
+pub struct HostObject {
+    pub class: HostClass,
+    pub value: RefCell<Box<dyn Any>>,
+}
+
+impl Debug for HostObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{:p}", self.class.name, self)
+    }
+}
+
+impl Object for HostObject {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Host
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn drop_by_vm(&self, _: &GloomVM, _: &GloomObjRef) {}
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(self.class.index as usize)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+}
+
+impl HostObject {
+    #[inline]
+    pub fn new<T: Any>(class: &HostClass, value: T) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(HostObject {
+            class: class.clone(),
+            value: RefCell::new(Box::new(value)),
+        }))
+    }
+
+    // 类型不匹配时panic 会被转换为运行时错误 panic if type mismatched, which will be converted to runtime error
+    pub fn borrow<T: Any>(&self) -> Ref<'_, T> {
+        Ref::map(self.value.borrow(), |value| {
+            match value.downcast_ref::<T>() {
+                Some(value) => value,
+                None => panic!("{} doesn't hold the expected rust type", self.class.name),
+            }
+        })
+    }
+
+    pub fn borrow_mut<T: Any>(&self) -> RefMut<'_, T> {
+        let name = &self.class.name;
+        RefMut::map(self.value.borrow_mut(), |value| {
+            match value.downcast_mut::<T>() {
+                Some(value) => value,
+                None => panic!("{} doesn't hold the expected rust type", name),
+            }
+        })
+    }
+}
@@ -0,0 +1,431 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use hashbrown::HashMap;
+
+use crate::builtin::array::{GloomArray, RawArray};
+use crate::builtin::boxed::{GloomBool, GloomChar, GloomInt};
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::function::call_back;
+use crate::builtin::iter::GloomListIter;
+use crate::builtin::string::GloomString;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::tuple::GloomTuple;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 键的哈希形式 装箱的值与基本类型的值相同 字符串按内容比较
+// hash form of key, boxed value is the same as value of basic type, string is compared by content
+// 泛型参数可能在运行时传入不可哈希的值 此时抛出运行时错误
+// generic parameter may receive unhashable value at runtime, raise a runtime error then
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum HashKey {
+    Int(i64),
+    Char(char),
+    Bool(bool),
+    Str(String),
+    Tuple(Vec<HashKey>),
+}
+
+impl HashKey {
+    pub fn from_value(value: &Value) -> Result<HashKey, GloomError> {
+        Result::Ok(match value {
+            Value::Int(int) => HashKey::Int(*int),
+            Value::Char(ch) => HashKey::Char(*ch),
+            Value::Bool(bl) => HashKey::Bool(*bl),
+            Value::Ref(rf) => match rf.obj_type() {
+                ObjectType::Int => HashKey::Int(rf.downcast::<GloomInt>().0.get()),
+                ObjectType::Char => HashKey::Char(rf.downcast::<GloomChar>().0.get()),
+                ObjectType::Bool => HashKey::Bool(rf.downcast::<GloomBool>().0.get()),
+                ObjectType::String => HashKey::Str(rf.downcast::<GloomString>().0.borrow().clone()),
+                ObjectType::Tuple => HashKey::Tuple(
+                    rf.downcast::<GloomTuple>()
+                        .values()
+                        .iter()
+                        .map(HashKey::from_value)
+                        .collect::<Result<Vec<HashKey>, GloomError>>()?,
+                ),
+                _ => return Result::Err(not_hashable(value)),
+            },
+            _ => return Result::Err(not_hashable(value)),
+        })
+    }
+}
+
+#[inline]
+fn not_hashable(value: &Value) -> GloomError {
+    GloomError::new(format!("{:?} is not hashable", value))
+}
+
+// 保持插入顺序的哈希表 删除时用最后一个元素填补空位
+// hash table keeping insertion order, fill the hole by the last entry when remove
+#[derive(Default)]
+pub struct RawMap {
+    pub entries: Vec<(Value, Value)>,
+    indexes: HashMap<HashKey, usize>,
+}
+
+impl RawMap {
+    #[inline]
+    pub fn new() -> RawMap {
+        RawMap::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, key: &Value) -> Result<Option<Value>, GloomError> {
+        Result::Ok(
+            self.indexes
+                .get(&HashKey::from_value(key)?)
+                .map(|index| self.entries[*index].1.clone()),
+        )
+    }
+
+    #[inline]
+    pub fn contains(&self, key: &Value) -> Result<bool, GloomError> {
+        Result::Ok(self.indexes.contains_key(&HashKey::from_value(key)?))
+    }
+
+    // 返回被替换的值 return the replaced value
+    pub fn insert(&mut self, key: Value, value: Value) -> Result<Option<Value>, GloomError> {
+        let hash_key = HashKey::from_value(&key)?;
+        Result::Ok(match self.indexes.get(&hash_key) {
+            Some(index) => Option::Some(std::mem::replace(&mut self.entries[*index].1, value)),
+            None => {
+                self.indexes.insert(hash_key, self.entries.len());
+                self.entries.push((key, value));
+                Option::None
+            }
+        })
+    }
+
+    pub fn remove(&mut self, key: &Value) -> Result<Option<(Value, Value)>, GloomError> {
+        let index = match self.indexes.remove(&HashKey::from_value(key)?) {
+            Some(index) => index,
+            None => return Result::Ok(Option::None),
+        };
+        let entry = self.entries.swap_remove(index);
+        if let Some((moved_key, _)) = self.entries.get(index) {
+            self.indexes.insert(HashKey::from_value(moved_key)?, index);
+        }
+        Result::Ok(Option::Some(entry))
+    }
+
+    pub fn clear(&mut self, vm: &GloomVM) {
+        self.indexes.clear();
+        for (key, value) in self.entries.drain(..) {
+            drop_value(vm, &key);
+            drop_value(vm, &value);
+        }
+    }
+}
+
+#[inline]
+pub fn drop_value(vm: &GloomVM, value: &Value) {
+    if let Value::Ref(rf) = value {
+        vm.drop_object(rf);
+    }
+}
+
+pub struct GloomMap(pub RefCell<RawMap>);
+
+impl GloomMap {
+    #[inline]
+    pub fn new(map: RawMap) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomMap(RefCell::new(map))))
+    }
+}
+
+impl Debug for GloomMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .borrow()
+            .entries
+            .iter()
+            .map(|(key, value)| format!("{:?} : {:?}", key, value))
+            .collect();
+        write!(f, "{{{}}}", entries.join(", "))
+    }
+}
+
+impl Object for GloomMap {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Map
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        for (key, value) in self.0.borrow().entries.iter() {
+            drop_value(vm, key);
+            drop_value(vm, value);
+        }
+    }
+
+    fn iter(&self, rf: &GloomObjRef) -> GloomObjRef {
+        GloomListIter::new(rf.clone())
+    }
+
+    // 以元组的形式遍历键值对 iterate key-value pairs as tuples
+    fn at(&self, index: &mut usize) -> Option<Value> {
+        let option = self
+            .0
+            .borrow()
+            .entries
+            .get(*index)
+            .map(|(key, value)| Value::Ref(GloomTuple::new(vec![key.clone(), value.clone()])));
+        *index += 1;
+        option
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::MAP_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(map) = self.0.try_borrow() {
+            for (key, value) in map.entries.iter() {
+                if let Value::Ref(rf) = key {
+                    f(rf);
+                }
+                if let Value::Ref(rf) = value {
+                    f(rf);
+                }
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        self.0.replace(RawMap::new());
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_map_class() -> BuiltinClass {
+        // 键和值的类型K和V是前两个类型参数 key type K and value type V are the first two type parameters
+        let key_param = GenericParam {
+            name: Rc::new(String::from("K")),
+            bound: Option::None,
+        };
+        let value_param = GenericParam {
+            name: Rc::new(String::from("V")),
+            bound: Option::None,
+        };
+        let key = key_param.as_data_type(0);
+        let value = value_param.as_data_type(1);
+        let param = Methods::param;
+        let array_of =
+            |data_type: &DataType| DataType::Ref(RefType::Array(Box::new(data_type.clone())));
+        let mut methods = Methods::new(
+            DataType::Ref(RefType::Map(Box::new((key.clone(), value.clone())))),
+            vec![key_param, value_param],
+        );
+        methods.add(
+            "len",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(map_of_args(&args).0.borrow().len() as i64))),
+        );
+        methods.add(
+            "isEmpty",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(map_of_args(&args).0.borrow().is_empty()))),
+        );
+        methods.add(
+            "get",
+            vec![param("key", key.clone())],
+            ReturnType::Have(value.clone()),
+            Vec::with_capacity(0),
+            Rc::new(
+                |_, args| match map_of_args(&args).0.borrow().get(&args.vec[1])? {
+                    Some(value) => Result::Ok(value),
+                    None => Result::Err(GloomError::new(format!(
+                        "key {:?} not found in map",
+                        args.vec[1]
+                    ))),
+                },
+            ),
+        );
+        methods.add(
+            "getOr",
+            vec![param("key", key.clone()), param("default", value.clone())],
+            ReturnType::Have(value.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let default = args.vec.pop().unwrap();
+                let option = map_of_args(&args).0.borrow().get(&args.vec[1])?;
+                Result::Ok(option.unwrap_or(default))
+            }),
+        );
+        methods.add(
+            "put",
+            vec![param("key", key.clone()), param("value", value.clone())],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, mut args| {
+                let value = args.vec.pop().unwrap();
+                let key = args.vec.pop().unwrap();
+                let old = map_of_args(&args).0.borrow_mut().insert(key, value)?;
+                if let Some(old) = old {
+                    drop_value(vm, &old);
+                }
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "containsKey",
+            vec![param("key", key.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                Result::Ok(Value::Bool(
+                    map_of_args(&args).0.borrow().contains(&args.vec[1])?,
+                ))
+            }),
+        );
+        methods.add(
+            "remove",
+            vec![param("key", key.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let option = map_of_args(&args).0.borrow_mut().remove(&args.vec[1])?;
+                match option {
+                    Some((key, value)) => {
+                        drop_value(vm, &key);
+                        drop_value(vm, &value);
+                        Result::Ok(Value::Bool(true))
+                    }
+                    None => Result::Ok(Value::Bool(false)),
+                }
+            }),
+        );
+        methods.add(
+            "clear",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                map_of_args(&args).0.borrow_mut().clear(vm);
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "keys",
+            Vec::with_capacity(0),
+            ReturnType::Have(array_of(&key)),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let keys = map_of_args(&args)
+                    .0
+                    .borrow()
+                    .entries
+                    .iter()
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                Result::Ok(Value::Ref(GloomArray::new(RawArray::from_values(keys)?)))
+            }),
+        );
+        methods.add(
+            "values",
+            Vec::with_capacity(0),
+            ReturnType::Have(array_of(&value)),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let values = map_of_args(&args)
+                    .0
+                    .borrow()
+                    .entries
+                    .iter()
+                    .map(|(_, value)| value.clone())
+                    .collect();
+                Result::Ok(Value::Ref(GloomArray::new(RawArray::from_values(values)?)))
+            }),
+        );
+        methods.add(
+            "forEach",
+            vec![param(
+                "action",
+                Methods::func_type(vec![key, value], ReturnType::Void),
+            )],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let func = args.vec[1].as_ref();
+                // 回调时不持有借用 don't hold the borrow when calling back
+                let entries = map_of_args(&args).0.borrow().entries.clone();
+                for (key, value) in entries {
+                    call_back(vm, func, vec![key, value])?;
+                }
+                Result::Ok(Value::None)
+            }),
+        );
+        BuiltinClass {
+            name: "Map".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Map(Box::new((
+                    DataType::Ref(RefType::Any),
+                    DataType::Ref(RefType::Any),
+                )))),
+                Some(mut vec) => {
+                    if vec.len() != 2 {
+                        return Result::Err(format!(
+                            "type Map<K,V> need two generic types, found {:?}",
+                            vec
+                        ));
+                    }
+                    let value = vec.pop().unwrap();
+                    let key = vec.pop().unwrap();
+                    if !key.is_hashable() {
+                        return Result::Err(format!("key type {} of Map is not hashable", key));
+                    }
+                    Result::Ok(RefType::Map(Box::new((key, value))))
+                }
+            }),
+        }
+    }
+}
+
+#[inline]
+fn map_of_args(args: &GloomArgs) -> &GloomMap {
+    args.vec[0].as_ref().downcast::<GloomMap>()
+}
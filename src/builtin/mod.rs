@@ -1,9 +1,14 @@
 pub mod array;
 pub mod boxed;
 pub mod classes;
+pub mod error;
 pub mod funcs;
 pub mod function;
+pub mod host;
 pub mod iter;
+pub mod map;
 pub mod obj;
+pub mod option;
 pub mod queue;
+pub mod set;
 pub mod string;
@@ -0,0 +1,156 @@
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::map::drop_value;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 可能没有值的结果 不可变 result which may have no value, immutable
+pub struct GloomOption(pub Option<Value>);
+
+impl GloomOption {
+    #[inline]
+    pub fn new(option: Option<Value>) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomOption(option)))
+    }
+}
+
+impl Debug for GloomOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(value) => write!(f, "Some({:?})", value),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl Object for GloomOption {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Option
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        if let Some(value) = &self.0 {
+            drop_value(vm, value);
+        }
+    }
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::OPTION_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Some(Value::Ref(rf)) = &self.0 {
+            f(rf);
+        }
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_option_class() -> BuiltinClass {
+        // 值的类型T是第一个类型参数 value type T is the first type parameter
+        let value_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let value = value_param.as_data_type(0);
+        let mut methods = Methods::new(
+            DataType::Ref(RefType::Option(Box::new(value.clone()))),
+            vec![value_param],
+        );
+        methods.add(
+            "isSome",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(option_of_args(&args).0.is_some()))),
+        );
+        methods.add(
+            "isNone",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(option_of_args(&args).0.is_none()))),
+        );
+        methods.add(
+            "get",
+            Vec::with_capacity(0),
+            ReturnType::Have(value.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| match &option_of_args(&args).0 {
+                Some(value) => Result::Ok(value.clone()),
+                None => Result::Err(GloomError::new(String::from("get value from none option"))),
+            }),
+        );
+        methods.add(
+            "getOr",
+            vec![Methods::param("default", value.clone())],
+            ReturnType::Have(value),
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let default = args.vec.pop().unwrap();
+                match &option_of_args(&args).0 {
+                    Some(value) => Result::Ok(value.clone()),
+                    None => Result::Ok(default),
+                }
+            }),
+        );
+        BuiltinClass {
+            name: "Option".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Option(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Option<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    Result::Ok(RefType::Option(Box::new(vec.pop().unwrap())))
+                }
+            }),
+        }
+    }
+}
+
+#[inline]
+fn option_of_args(args: &GloomArgs) -> &GloomOption {
+    args.vec[0].as_ref().downcast::<GloomOption>()
+}
@@ -4,15 +4,20 @@ use std::collections::VecDeque;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 
+use crate::builtin::array::{GloomArray, RawArray};
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::function::call_back;
 use crate::builtin::iter::GloomListIter;
 use crate::frontend::status::GloomStatus;
-use crate::obj::func::GloomFunc;
+use crate::obj::func::{GloomFunc, ReturnType};
 use crate::obj::object::{GloomObjRef, Object, ObjectType};
 use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
 use crate::vm::machine::GloomVM;
-use crate::vm::value::Value;
+use crate::vm::value::{GloomArgs, Value};
 
-pub struct GloomQueue(RefCell<RawQueue>);
+pub struct GloomQueue(pub RefCell<RawQueue>);
 
 pub enum RawQueue {
     IntQue(VecDeque<i64>),
@@ -41,7 +46,7 @@ impl GloomQueue {
 
 impl Debug for GloomQueue {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.0)
+        write!(f, "{:?}", self.0.borrow())
     }
 }
 
@@ -76,12 +81,34 @@ impl Object for GloomQueue {
     }
 
     fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
-        todo!()
+        status
+            .builtin_classes
+            .get(BuiltinClass::QUEUE_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
     }
 
     fn field(&self, _: u16, _: u8) -> Value {
         panic!()
     }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(queue) = self.0.try_borrow() {
+            if let RawQueue::RefQue(queue) = &*queue {
+                queue.iter().for_each(f);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        if let RawQueue::RefQue(queue) = &mut *self.0.borrow_mut() {
+            queue.clear();
+        }
+    }
 }
 
 impl Debug for RawQueue {
@@ -95,3 +122,291 @@ impl Debug for RawQueue {
         }
     }
 }
+
+impl RawQueue {
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            RawQueue::IntQue(que) => que.len(),
+            RawQueue::NumQue(que) => que.len(),
+            RawQueue::CharQue(que) => que.len(),
+            RawQueue::BoolQue(que) => que.len(),
+            RawQueue::RefQue(que) => que.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn values(&self) -> Vec<Value> {
+        match self {
+            RawQueue::IntQue(que) => que.iter().map(|i| Value::Int(*i)).collect(),
+            RawQueue::NumQue(que) => que.iter().map(|f| Value::Num(*f)).collect(),
+            RawQueue::CharQue(que) => que.iter().map(|c| Value::Char(*c)).collect(),
+            RawQueue::BoolQue(que) => que.iter().map(|b| Value::Bool(*b)).collect(),
+            RawQueue::RefQue(que) => que.iter().map(|rf| Value::Ref(rf.clone())).collect(),
+        }
+    }
+
+    // 元素可能是装箱的值 element may be a boxed value
+    pub fn push_front(&mut self, value: Value) -> Result<(), GloomError> {
+        match self {
+            RawQueue::IntQue(que) => que.push_front(value.assert_int_form_num_liked()?),
+            RawQueue::NumQue(que) => que.push_front(value.assert_num_include_int()?),
+            RawQueue::CharQue(que) => que.push_front(value.assert_char_include_int()?),
+            RawQueue::BoolQue(que) => que.push_front(value.assert_bool()?),
+            RawQueue::RefQue(que) => que.push_front(value.assert_into_ref()?),
+        }
+        Result::Ok(())
+    }
+
+    pub fn push_back(&mut self, value: Value) -> Result<(), GloomError> {
+        match self {
+            RawQueue::IntQue(que) => que.push_back(value.assert_int_form_num_liked()?),
+            RawQueue::NumQue(que) => que.push_back(value.assert_num_include_int()?),
+            RawQueue::CharQue(que) => que.push_back(value.assert_char_include_int()?),
+            RawQueue::BoolQue(que) => que.push_back(value.assert_bool()?),
+            RawQueue::RefQue(que) => que.push_back(value.assert_into_ref()?),
+        }
+        Result::Ok(())
+    }
+
+    pub fn pop_front(&mut self) -> Result<Value, GloomError> {
+        let option = match self {
+            RawQueue::IntQue(que) => que.pop_front().map(Value::Int),
+            RawQueue::NumQue(que) => que.pop_front().map(Value::Num),
+            RawQueue::CharQue(que) => que.pop_front().map(Value::Char),
+            RawQueue::BoolQue(que) => que.pop_front().map(Value::Bool),
+            RawQueue::RefQue(que) => que.pop_front().map(Value::Ref),
+        };
+        option.ok_or_else(|| GloomError::new(String::from("pop from empty queue")))
+    }
+
+    pub fn pop_back(&mut self) -> Result<Value, GloomError> {
+        let option = match self {
+            RawQueue::IntQue(que) => que.pop_back().map(Value::Int),
+            RawQueue::NumQue(que) => que.pop_back().map(Value::Num),
+            RawQueue::CharQue(que) => que.pop_back().map(Value::Char),
+            RawQueue::BoolQue(que) => que.pop_back().map(Value::Bool),
+            RawQueue::RefQue(que) => que.pop_back().map(Value::Ref),
+        };
+        option.ok_or_else(|| GloomError::new(String::from("pop from empty queue")))
+    }
+
+    pub fn peek_front(&self) -> Result<Value, GloomError> {
+        let option = match self {
+            RawQueue::IntQue(que) => que.front().map(|i| Value::Int(*i)),
+            RawQueue::NumQue(que) => que.front().map(|f| Value::Num(*f)),
+            RawQueue::CharQue(que) => que.front().map(|c| Value::Char(*c)),
+            RawQueue::BoolQue(que) => que.front().map(|b| Value::Bool(*b)),
+            RawQueue::RefQue(que) => que.front().map(|rf| Value::Ref(rf.clone())),
+        };
+        option.ok_or_else(|| GloomError::new(String::from("peek on empty queue")))
+    }
+
+    pub fn peek_back(&self) -> Result<Value, GloomError> {
+        let option = match self {
+            RawQueue::IntQue(que) => que.back().map(|i| Value::Int(*i)),
+            RawQueue::NumQue(que) => que.back().map(|f| Value::Num(*f)),
+            RawQueue::CharQue(que) => que.back().map(|c| Value::Char(*c)),
+            RawQueue::BoolQue(que) => que.back().map(|b| Value::Bool(*b)),
+            RawQueue::RefQue(que) => que.back().map(|rf| Value::Ref(rf.clone())),
+        };
+        option.ok_or_else(|| GloomError::new(String::from("peek on empty queue")))
+    }
+
+    // 调用者检查下标 the caller checks the index
+    pub fn set(&mut self, vm: &GloomVM, index: usize, value: Value) -> Result<(), GloomError> {
+        match self {
+            RawQueue::IntQue(que) => que[index] = value.assert_int_form_num_liked()?,
+            RawQueue::NumQue(que) => que[index] = value.assert_num_include_int()?,
+            RawQueue::CharQue(que) => que[index] = value.assert_char_include_int()?,
+            RawQueue::BoolQue(que) => que[index] = value.assert_bool()?,
+            RawQueue::RefQue(que) => {
+                let old = std::mem::replace(&mut que[index], value.assert_into_ref()?);
+                vm.drop_object(&old);
+            }
+        }
+        Result::Ok(())
+    }
+
+    // 调用者检查范围 the caller checks the range
+    pub fn slice(&self, from: usize, to: usize) -> RawQueue {
+        match self {
+            RawQueue::IntQue(que) => RawQueue::IntQue(que.range(from..to).copied().collect()),
+            RawQueue::NumQue(que) => RawQueue::NumQue(que.range(from..to).copied().collect()),
+            RawQueue::CharQue(que) => RawQueue::CharQue(que.range(from..to).copied().collect()),
+            RawQueue::BoolQue(que) => RawQueue::BoolQue(que.range(from..to).copied().collect()),
+            RawQueue::RefQue(que) => RawQueue::RefQue(que.range(from..to).cloned().collect()),
+        }
+    }
+
+    pub fn clear(&mut self, vm: &GloomVM) {
+        match self {
+            RawQueue::IntQue(que) => que.clear(),
+            RawQueue::NumQue(que) => que.clear(),
+            RawQueue::CharQue(que) => que.clear(),
+            RawQueue::BoolQue(que) => que.clear(),
+            RawQueue::RefQue(que) => {
+                for rf in que.drain(..) {
+                    vm.drop_object(&rf);
+                }
+            }
+        }
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_queue_class() -> BuiltinClass {
+        // 队列的元素类型T是第一个类型参数 element type T of queue is the first type parameter
+        let elem_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let elem = elem_param.as_data_type(0);
+        let param = Methods::param;
+        let mut methods = Methods::new(
+            DataType::Ref(RefType::Queue(Box::new(elem.clone()))),
+            vec![elem_param],
+        );
+        methods.add(
+            "len",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(queue_of_args(&args).0.borrow().len() as i64))),
+        );
+        methods.add(
+            "isEmpty",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(queue_of_args(&args).0.borrow().is_empty()))),
+        );
+        methods.add(
+            "pushFront",
+            vec![param("elem", elem.clone())],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let elem = args.vec.pop().unwrap();
+                queue_of_args(&args).0.borrow_mut().push_front(elem)?;
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "pushBack",
+            vec![param("elem", elem.clone())],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let elem = args.vec.pop().unwrap();
+                queue_of_args(&args).0.borrow_mut().push_back(elem)?;
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "popFront",
+            Vec::with_capacity(0),
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let value = queue_of_args(&args).0.borrow_mut().pop_front();
+                value
+            }),
+        );
+        methods.add(
+            "popBack",
+            Vec::with_capacity(0),
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let value = queue_of_args(&args).0.borrow_mut().pop_back();
+                value
+            }),
+        );
+        // 查看队首的元素 peek the front element
+        methods.add(
+            "peek",
+            Vec::with_capacity(0),
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let value = queue_of_args(&args).0.borrow().peek_front();
+                value
+            }),
+        );
+        methods.add(
+            "peekBack",
+            Vec::with_capacity(0),
+            ReturnType::Have(elem.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let value = queue_of_args(&args).0.borrow().peek_back();
+                value
+            }),
+        );
+        methods.add(
+            "clear",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                queue_of_args(&args).0.borrow_mut().clear(vm);
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "toArray",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Array(Box::new(elem.clone())))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let values = queue_of_args(&args).0.borrow().values();
+                Result::Ok(Value::Ref(GloomArray::new(RawArray::from_values(values)?)))
+            }),
+        );
+        methods.add(
+            "forEach",
+            vec![param(
+                "action",
+                Methods::func_type(vec![elem], ReturnType::Void),
+            )],
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let func = args.vec[1].as_ref();
+                // 回调时不持有借用 don't hold the borrow when calling back
+                let values = queue_of_args(&args).0.borrow().values();
+                for value in values {
+                    call_back(vm, func, vec![value])?;
+                }
+                Result::Ok(Value::None)
+            }),
+        );
+        BuiltinClass {
+            name: "Queue".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Queue(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Queue<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    Result::Ok(RefType::Queue(Box::new(vec.pop().unwrap())))
+                }
+            }),
+        }
+    }
+}
+
+#[inline]
+fn queue_of_args(args: &GloomArgs) -> &GloomQueue {
+    args.vec[0].as_ref().downcast::<GloomQueue>()
+}
@@ -0,0 +1,286 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::builtin::array::{GloomArray, RawArray};
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::iter::GloomListIter;
+use crate::builtin::map::{drop_value, RawMap};
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 集合是值为空的哈希表 set is a hash table whose values are none
+pub struct GloomSet(pub RefCell<RawMap>);
+
+impl GloomSet {
+    #[inline]
+    pub fn new(set: RawMap) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomSet(RefCell::new(set))))
+    }
+
+    pub fn from_values(values: Vec<Value>) -> Result<GloomObjRef, GloomError> {
+        let mut set = RawMap::new();
+        for value in values {
+            set.insert(value, Value::None)?;
+        }
+        Result::Ok(GloomSet::new(set))
+    }
+
+    #[inline]
+    pub fn values(&self) -> Vec<Value> {
+        self.0
+            .borrow()
+            .entries
+            .iter()
+            .map(|(elem, _)| elem.clone())
+            .collect()
+    }
+}
+
+impl Debug for GloomSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let elems: Vec<String> = self
+            .0
+            .borrow()
+            .entries
+            .iter()
+            .map(|(elem, _)| format!("{:?}", elem))
+            .collect();
+        write!(f, "{{{}}}", elems.join(", "))
+    }
+}
+
+impl Object for GloomSet {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Set
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        for (elem, _) in self.0.borrow().entries.iter() {
+            drop_value(vm, elem);
+        }
+    }
+
+    fn iter(&self, rf: &GloomObjRef) -> GloomObjRef {
+        GloomListIter::new(rf.clone())
+    }
+
+    fn at(&self, index: &mut usize) -> Option<Value> {
+        let option = self
+            .0
+            .borrow()
+            .entries
+            .get(*index)
+            .map(|(elem, _)| elem.clone());
+        *index += 1;
+        option
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::SET_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(set) = self.0.try_borrow() {
+            for (elem, _) in set.entries.iter() {
+                if let Value::Ref(rf) = elem {
+                    f(rf);
+                }
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        self.0.replace(RawMap::new());
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_set_class() -> BuiltinClass {
+        // 集合的元素类型T是第一个类型参数 element type T of set is the first type parameter
+        let elem_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let elem = elem_param.as_data_type(0);
+        let set_type = DataType::Ref(RefType::Set(Box::new(elem.clone())));
+        let param = Methods::param;
+        let mut methods = Methods::new(set_type.clone(), vec![elem_param]);
+        methods.add(
+            "len",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(set_of_args(&args).0.borrow().len() as i64))),
+        );
+        methods.add(
+            "isEmpty",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(set_of_args(&args).0.borrow().is_empty()))),
+        );
+        // 返回是否新加入了元素 return whether the element is newly added
+        methods.add(
+            "add",
+            vec![param("elem", elem.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let elem = args.vec.pop().unwrap();
+                let mut set = set_of_args(&args).0.borrow_mut();
+                if set.contains(&elem)? {
+                    Result::Ok(Value::Bool(false))
+                } else {
+                    set.insert(elem, Value::None)?;
+                    Result::Ok(Value::Bool(true))
+                }
+            }),
+        );
+        methods.add(
+            "remove",
+            vec![param("elem", elem.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let option = set_of_args(&args).0.borrow_mut().remove(&args.vec[1])?;
+                match option {
+                    Some((elem, _)) => {
+                        drop_value(vm, &elem);
+                        Result::Ok(Value::Bool(true))
+                    }
+                    None => Result::Ok(Value::Bool(false)),
+                }
+            }),
+        );
+        methods.add(
+            "contains",
+            vec![param("elem", elem.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                Result::Ok(Value::Bool(
+                    set_of_args(&args).0.borrow().contains(&args.vec[1])?,
+                ))
+            }),
+        );
+        methods.add(
+            "clear",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                set_of_args(&args).0.borrow_mut().clear(vm);
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "toArray",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Array(Box::new(elem)))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let values = set_of_args(&args).values();
+                Result::Ok(Value::Ref(GloomArray::new(RawArray::from_values(values)?)))
+            }),
+        );
+        methods.add(
+            "union",
+            vec![param("other", set_type.clone())],
+            ReturnType::Have(set_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let mut values = set_of_args(&args).values();
+                values.extend(args.vec[1].as_ref().downcast::<GloomSet>().values());
+                Result::Ok(Value::Ref(GloomSet::from_values(values)?))
+            }),
+        );
+        methods.add(
+            "intersect",
+            vec![param("other", set_type.clone())],
+            ReturnType::Have(set_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let other = args.vec[1].as_ref().downcast::<GloomSet>().0.borrow();
+                let mut values = Vec::new();
+                for elem in set_of_args(&args).values() {
+                    if other.contains(&elem)? {
+                        values.push(elem);
+                    }
+                }
+                Result::Ok(Value::Ref(GloomSet::from_values(values)?))
+            }),
+        );
+        methods.add(
+            "difference",
+            vec![param("other", set_type.clone())],
+            ReturnType::Have(set_type),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let other = args.vec[1].as_ref().downcast::<GloomSet>().0.borrow();
+                let mut values = Vec::new();
+                for elem in set_of_args(&args).values() {
+                    if !other.contains(&elem)? {
+                        values.push(elem);
+                    }
+                }
+                Result::Ok(Value::Ref(GloomSet::from_values(values)?))
+            }),
+        );
+        BuiltinClass {
+            name: "Set".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Set(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Set<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    let elem = vec.pop().unwrap();
+                    if !elem.is_hashable() {
+                        return Result::Err(format!(
+                            "element type {} of Set is not hashable",
+                            elem
+                        ));
+                    }
+                    Result::Ok(RefType::Set(Box::new(elem)))
+                }
+            }),
+        }
+    }
+}
+
+#[inline]
+fn set_of_args(args: &GloomArgs) -> &GloomSet {
+    args.vec[0].as_ref().downcast::<GloomSet>()
+}
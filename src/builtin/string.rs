@@ -3,17 +3,18 @@ use std::cell::RefCell;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 
-use hashbrown::HashMap;
-
-use crate::builtin::classes::BuiltinClass;
+use crate::builtin::array::{GloomArray, RawArray};
+use crate::builtin::classes::{BuiltinClass, Methods};
 use crate::builtin::iter::GloomListIter;
+use crate::builtin::option::GloomOption;
 use crate::frontend::status::GloomStatus;
-use crate::obj::func::{GloomFunc, Param, ReturnType};
+use crate::obj::func::{GloomFunc, ReturnType};
 use crate::obj::object::{GloomObjRef, Object, ObjectType};
 use crate::obj::refcount::RefCount;
 use crate::obj::types::{DataType, RefType};
+use crate::vm::error::GloomError;
 use crate::vm::machine::GloomVM;
-use crate::vm::value::Value;
+use crate::vm::value::{GloomArgs, Value};
 
 pub struct GloomString(pub RefCell<String>);
 
@@ -124,37 +125,358 @@ impl GloomString {
         }
         (step, char::from_u32(ch).unwrap())
     }
+
+    // 每个字符的起始字节位置 按at()的UTF-8解码遍历
+    // start byte offset of each char, walk through by the UTF-8 decoding of at()
+    pub fn char_offsets(&self) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        let mut index = 0;
+        loop {
+            let offset = index;
+            match self.at(&mut index) {
+                Some(_) => offsets.push(offset),
+                None => break,
+            }
+        }
+        offsets
+    }
+
+    #[inline]
+    pub fn char_len(&self) -> usize {
+        self.char_offsets().len()
+    }
+
+    pub fn char_at(&self, index: i64) -> Result<char, GloomError> {
+        let offsets = self.char_offsets();
+        if index < 0 || index as usize >= offsets.len() {
+            return Result::Err(GloomError::new(format!(
+                "index {} out of bounds for string of length {}",
+                index,
+                offsets.len()
+            )));
+        }
+        let mut offset = offsets[index as usize];
+        self.at(&mut offset).unwrap().assert_char()
+    }
+
+    // 按字符下标截取 [from, to)  substring by char index [from, to)
+    pub fn substring(&self, from: i64, to: i64) -> Result<String, GloomError> {
+        let offsets = self.char_offsets();
+        let len = offsets.len();
+        if from < 0 || to < from || to as usize > len {
+            return Result::Err(GloomError::new(format!(
+                "substring [{}, {}) out of range for string of length {}",
+                from, to, len
+            )));
+        }
+        let string = self.0.borrow();
+        let start = offsets.get(from as usize).copied().unwrap_or(string.len());
+        let end = offsets.get(to as usize).copied().unwrap_or(string.len());
+        Result::Ok(string[start..end].to_string())
+    }
+
+    // 子串第一次出现的字符下标 没有则为-1
+    // char index of the first occurrence of the sub string, -1 if not found
+    pub fn find(&self, sub: &str) -> i64 {
+        let option = self.0.borrow().find(sub);
+        match option {
+            Some(byte_index) => match self.char_offsets().binary_search(&byte_index) {
+                Result::Ok(index) => index as i64,
+                Result::Err(_) => panic!(),
+            },
+            None => -1,
+        }
+    }
 }
 
 impl BuiltinClass {
     pub fn gloom_string_class() -> BuiltinClass {
-        let mut map = HashMap::new();
-        let mut funcs = Vec::new();
-        let empty_string = Rc::new(String::new());
-        funcs.push(RefCount::new(GloomFunc::new_builtin_fn(
-            Rc::new(String::from("append")),
+        let string_type = DataType::Ref(RefType::String);
+        let param = Methods::param;
+        let mut methods = Methods::new(string_type.clone(), Vec::with_capacity(0));
+        methods.add(
+            "append",
+            vec![param("other", string_type.clone())],
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let mut string = str_of_args(&args, 0).clone();
+                string.push_str(str_of_args(&args, 1).as_str());
+                new_string(string)
+            }),
+        );
+        // 字节数 number of bytes
+        methods.add(
+            "len",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(str_of_args(&args, 0).len() as i64))),
+        );
+        // 字符数 number of chars
+        methods.add(
+            "charLen",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(string_of_args(&args, 0).char_len() as i64))),
+        );
+        methods.add(
+            "isEmpty",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(str_of_args(&args, 0).is_empty()))),
+        );
+        methods.add(
+            "charAt",
+            vec![param("index", DataType::Int)],
+            ReturnType::Have(DataType::Char),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let index = args.vec[1].assert_int()?;
+                Result::Ok(Value::Char(string_of_args(&args, 0).char_at(index)?))
+            }),
+        );
+        methods.add(
+            "substring",
+            vec![param("from", DataType::Int), param("to", DataType::Int)],
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let from = args.vec[1].assert_int()?;
+                let to = args.vec[2].assert_int()?;
+                new_string(string_of_args(&args, 0).substring(from, to)?)
+            }),
+        );
+        methods.add(
+            "chars",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Array(Box::new(DataType::Char)))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let chars = str_of_args(&args, 0).chars().collect();
+                Result::Ok(Value::Ref(GloomArray::new(RawArray::CharVec(chars))))
+            }),
+        );
+        methods.add(
+            "split",
+            vec![param("separator", string_type.clone())],
+            ReturnType::Have(DataType::Ref(RefType::Array(Box::new(string_type.clone())))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let string = str_of_args(&args, 0);
+                let separator = str_of_args(&args, 1);
+                // 空的分隔符按空白分割 empty separator splits by whitespace
+                let parts: Vec<&str> = if separator.is_empty() {
+                    string.split_whitespace().collect()
+                } else {
+                    string.split(separator.as_str()).collect()
+                };
+                let parts = parts
+                    .into_iter()
+                    .map(|part| GloomString::new(part.to_string()))
+                    .collect();
+                Result::Ok(Value::Ref(GloomArray::new(RawArray::RefVec(parts))))
+            }),
+        );
+        // 以自身为分隔符连接 join with self as separator
+        methods.add(
+            "join",
+            vec![param(
+                "parts",
+                DataType::Ref(RefType::Array(Box::new(string_type.clone()))),
+            )],
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let parts: Vec<String> = args.vec[1]
+                    .as_ref()
+                    .downcast::<GloomArray>()
+                    .0
+                    .borrow()
+                    .values()
+                    .iter()
+                    .map(|part| part.as_ref().downcast::<GloomString>().0.borrow().clone())
+                    .collect();
+                new_string(parts.join(str_of_args(&args, 0).as_str()))
+            }),
+        );
+        methods.add(
+            "trim",
+            Vec::with_capacity(0),
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| new_string(str_of_args(&args, 0).trim().to_string())),
+        );
+        methods.add(
+            "replace",
             vec![
-                Param::new(empty_string.clone(), DataType::Ref(RefType::String)),
-                Param::new(empty_string.clone(), DataType::Ref(RefType::String)),
+                param("from", string_type.clone()),
+                param("to", string_type.clone()),
             ],
-            ReturnType::Have(DataType::Ref(RefType::String)),
-            true,
-            Rc::new(|_, args| {
-                let mut iter = args.vec.into_iter();
-                let myself = iter.next().unwrap().assert_into_ref();
-                let mut string = myself.downcast::<GloomString>().0.borrow().clone();
-                let other_ref = iter.next().unwrap().assert_into_ref();
-                let other = other_ref.downcast::<GloomString>();
-                string.push_str(other.0.borrow().as_str());
-                Value::Ref(GloomString::new(string))
-            }),
-        )));
-        map.insert(String::from("append"), 0);
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let from = str_of_args(&args, 1);
+                let to = str_of_args(&args, 2);
+                new_string(str_of_args(&args, 0).replace(from.as_str(), to.as_str()))
+            }),
+        );
+        methods.add(
+            "startsWith",
+            vec![param("prefix", string_type.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let prefix = str_of_args(&args, 1);
+                Result::Ok(Value::Bool(
+                    str_of_args(&args, 0).starts_with(prefix.as_str()),
+                ))
+            }),
+        );
+        methods.add(
+            "endsWith",
+            vec![param("suffix", string_type.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let suffix = str_of_args(&args, 1);
+                Result::Ok(Value::Bool(
+                    str_of_args(&args, 0).ends_with(suffix.as_str()),
+                ))
+            }),
+        );
+        methods.add(
+            "find",
+            vec![param("sub", string_type.clone())],
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let sub = str_of_args(&args, 1).clone();
+                Result::Ok(Value::Int(string_of_args(&args, 0).find(sub.as_str())))
+            }),
+        );
+        methods.add(
+            "contains",
+            vec![param("sub", string_type.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let sub = str_of_args(&args, 1);
+                Result::Ok(Value::Bool(str_of_args(&args, 0).contains(sub.as_str())))
+            }),
+        );
+        methods.add(
+            "toUpper",
+            Vec::with_capacity(0),
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| new_string(str_of_args(&args, 0).to_uppercase())),
+        );
+        methods.add(
+            "toLower",
+            Vec::with_capacity(0),
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| new_string(str_of_args(&args, 0).to_lowercase())),
+        );
+        methods.add(
+            "repeat",
+            vec![param("times", DataType::Int)],
+            ReturnType::Have(string_type.clone()),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let times = args.vec[1].assert_int()?;
+                if times < 0 {
+                    return Result::Err(GloomError::new(format!(
+                        "repeat a string by negative times {}",
+                        times
+                    )));
+                }
+                let str = str_of_args(&args, 0);
+                // 过长的结果是运行时错误 而非中止进程 too long result is a runtime error rather than aborting the process
+                let mut result = String::new();
+                let reserved = match str.len().checked_mul(times as usize) {
+                    Some(len) => result.try_reserve_exact(len).is_ok(),
+                    None => false,
+                };
+                if !reserved {
+                    return Result::Err(GloomError::new(format!(
+                        "repeat a string of length {} by {} times, the result is too long",
+                        str.len(),
+                        times
+                    )));
+                }
+                for _ in 0..times {
+                    result.push_str(str.as_str());
+                }
+                new_string(result)
+            }),
+        );
+        methods.add(
+            "parseInt",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Option(Box::new(DataType::Int)))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let option = str_of_args(&args, 0).trim().parse::<i64>().ok();
+                Result::Ok(Value::Ref(GloomOption::new(option.map(Value::Int))))
+            }),
+        );
+        methods.add(
+            "parseNum",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Option(Box::new(DataType::Num)))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let option = str_of_args(&args, 0).trim().parse::<f64>().ok();
+                Result::Ok(Value::Ref(GloomOption::new(option.map(Value::Num))))
+            }),
+        );
+        methods.add(
+            "equals",
+            vec![param("other", string_type.clone())],
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                Result::Ok(Value::Bool(
+                    *str_of_args(&args, 0) == *str_of_args(&args, 1),
+                ))
+            }),
+        );
+        // 按字典序比较 小于、等于、大于分别返回-1、0、1
+        // compare in lexicographical order, return -1, 0, 1 for less, equal, greater
+        methods.add(
+            "compareTo",
+            vec![param("other", string_type)],
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                let ordering = str_of_args(&args, 0).cmp(&*str_of_args(&args, 1));
+                Result::Ok(Value::Int(ordering as i64))
+            }),
+        );
         BuiltinClass {
             name: "String".to_string(),
-            map,
-            funcs,
+            map: methods.map,
+            funcs: methods.funcs,
             get_ref_type_fn: BuiltinClass::none_generic_fn(RefType::String),
         }
     }
 }
+
+#[inline]
+fn string_of_args(args: &GloomArgs, index: usize) -> &GloomString {
+    args.vec[index].as_ref().downcast::<GloomString>()
+}
+
+#[inline]
+fn str_of_args(args: &GloomArgs, index: usize) -> std::cell::Ref<'_, String> {
+    string_of_args(args, index).0.borrow()
+}
+
+#[inline]
+fn new_string(string: String) -> Result<Value, GloomError> {
+    Result::Ok(Value::Ref(GloomString::new(string)))
+}
@@ -46,14 +46,32 @@ pub enum ByteCode {
     WriteFieldBool(u16, u8),
     WriteFieldRef(u16),
 
+    // 读取下标 保留容器和下标 read subscript, keep the container and index
+    ReadIndex,
+    // 读取下标 弹出容器和下标 read subscript, pop the container and index
+    ReadIndexAndPop,
+    // 弹出值、下标和容器 pop value, index and container
+    WriteIndex,
+    // 弹出两个下标和容器 压入切片 pop two indexes and container, push the slice
+    ReadSlice,
+
     DropLocal(u16),
     NotOp,
     NegOp,
+    BitNot,
 
     Plus,
     Sub,
     Mul,
     Div,
+    Mod,
+    Pow,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 
     PlusOne,
     SubOne,
@@ -95,6 +113,9 @@ pub enum ByteCode {
     CollectTuple(u16),
     CollectArray(BasicType, u16),
     CollectQueue(BasicType, u16),
+    // 弹出键值对 pop key-value pairs
+    CollectMap(u16),
+    CollectSet(u16),
 
     // pop three int and push RangeIter
     RangeIter,
@@ -111,12 +132,35 @@ pub enum ByteCode {
     AsRef,
 
     Construct(u16),
+    // pop the meta enum and push the enum value of the tag
+    ConstructEnum(u16),
+    // pop the associated value and the meta enum, push the enum value of the tag
+    ConstructEnumWithVal(u16),
+    // pop the enum value and push its associated value
+    ReadEnumVal,
 
     // pop the condition after jump
     JumpIf(u32),
     JumpIfNot(u32),
     Jump(u32),
     JumpIfNone(u32),
+    // pop the matched value and jump to the label found in the jump table of constant pool
+    JumpTable(u16),
+
+    // push a handler of the try block in the try table of constant pool
+    EnterTry(u16),
+    // pop the error message string and raise a runtime error
+    Throw,
+    // pop the value and suspend the frame of generator, resume from the next bytecode
+    Yield,
+    // replace the task on top with its result, suspend the frame of async func at this bytecode if unfinished
+    Await,
+    // replace the task on top with its result, run the event loop until it finishes
+    AwaitBlocking,
+    // hand the task on top over to the scheduler, keep it on the stack
+    Spawn,
+    // receive from the channel on top like InvokeNext, suspend the frame of async func at this bytecode if it is empty
+    AwaitNext,
 
     Return,
 }
@@ -139,6 +183,7 @@ impl ByteCode {
             | ByteCode::ReadStatic(_)
             | ByteCode::LoadBuiltinType(_)
             | ByteCode::ReadField(_, _)
+            | ByteCode::ReadIndex
             | ByteCode::LoadDirectDefFn(_)
             | ByteCode::LoadNamelessFn(_)
             | ByteCode::ReadLocal(_, _)
@@ -146,6 +191,10 @@ impl ByteCode {
 
             ByteCode::ReadFieldAndPop(_, _) => 0,
 
+            ByteCode::ReadIndexAndPop => -1,
+            ByteCode::WriteIndex => -3,
+            ByteCode::ReadSlice => -2,
+
             ByteCode::WriteLocalInt(_, _)
             | ByteCode::WriteLocalNum(_, _)
             | ByteCode::WriteLocalChar(_, _)
@@ -162,9 +211,16 @@ impl ByteCode {
             | ByteCode::WriteFieldBool(_, _)
             | ByteCode::WriteFieldRef(_) => -1,
 
-            ByteCode::JumpIf(_) | ByteCode::JumpIfNot(_) | ByteCode::JumpIfNone(_) => -1,
+            ByteCode::JumpIf(_)
+            | ByteCode::JumpIfNot(_)
+            | ByteCode::JumpIfNone(_)
+            | ByteCode::JumpTable(_) => -1,
 
-            ByteCode::Jump(_) | ByteCode::DropLocal(_) | ByteCode::NotOp | ByteCode::NegOp => 0,
+            ByteCode::Jump(_)
+            | ByteCode::DropLocal(_)
+            | ByteCode::NotOp
+            | ByteCode::NegOp
+            | ByteCode::BitNot => 0,
 
             ByteCode::CallTopFn { nargs }
             | ByteCode::CallStaticFn { index: _, nargs }
@@ -173,6 +229,11 @@ impl ByteCode {
 
             ByteCode::Return => 0,
 
+            ByteCode::EnterTry(_) => 0,
+            ByteCode::Throw => -1,
+            ByteCode::Yield => -1,
+            ByteCode::Await | ByteCode::AwaitBlocking | ByteCode::Spawn => 0,
+
             ByteCode::AsInt
             | ByteCode::AsNum
             | ByteCode::AsChar
@@ -183,6 +244,13 @@ impl ByteCode {
             | ByteCode::Sub
             | ByteCode::Mul
             | ByteCode::Div
+            | ByteCode::Mod
+            | ByteCode::Pow
+            | ByteCode::BitAnd
+            | ByteCode::BitOr
+            | ByteCode::BitXor
+            | ByteCode::ShiftLeft
+            | ByteCode::ShiftRight
             | ByteCode::GreaterThan
             | ByteCode::LessThan
             | ByteCode::GreaterThanEquals
@@ -196,14 +264,18 @@ impl ByteCode {
 
             ByteCode::CollectTuple(i)
             | ByteCode::CollectArray(_, i)
-            | ByteCode::CollectQueue(_, i) => -(i as i16),
+            | ByteCode::CollectQueue(_, i)
+            | ByteCode::CollectSet(i) => 1 - (i as i16),
+            ByteCode::CollectMap(i) => 1 - 2 * (i as i16),
 
             ByteCode::Construct(_) => 1,
+            ByteCode::ConstructEnum(_) | ByteCode::ReadEnumVal => 0,
+            ByteCode::ConstructEnumWithVal(_) => -1,
 
             // pop source object after invoke iter() fn
             ByteCode::InvokeIter => 0,
 
-            ByteCode::InvokeNext => 1,
+            ByteCode::InvokeNext | ByteCode::AwaitNext => 1,
 
             ByteCode::RangeIter => -2,
 
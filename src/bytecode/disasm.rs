@@ -0,0 +1,370 @@
+use std::fmt::Write;
+
+use hashbrown::HashMap;
+use serde_json::{json, Value as Json};
+
+use crate::builtin::string::GloomString;
+use crate::bytecode::code::ByteCode;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{FuncBody, GloomFunc};
+use crate::vm::constant::ConstantPool;
+
+// 反汇编器 列出所有函数的字节码 并解析常量池、跳转目标和局部变量名
+// disassembler, list the bytecode of all functions, resolve the constant pool, jump targets and local variable names
+pub struct Disassembler<'a> {
+    status: &'a GloomStatus,
+    pool: &'a ConstantPool,
+}
+
+// 反汇编后的一条字节码 a disassembled bytecode
+struct Instruction {
+    pc: usize,
+    line: Option<u16>,
+    label: Option<String>,
+    code: String,
+    comment: Option<String>,
+}
+
+// 反汇编后的一个函数 a disassembled function
+struct Listing {
+    kind: &'static str,
+    name: String,
+    file_index: u16,
+    stack_size: u16,
+    local_size: u16,
+    locals: Vec<(u16, u8, String)>,
+    instructions: Vec<Instruction>,
+    // 位于字节码末尾的标签 label at the end of bytecodes
+    end_label: Option<String>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(status: &'a GloomStatus, pool: &'a ConstantPool) -> Self {
+        Disassembler { status, pool }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for listing in self.listings() {
+            writeln!(text, "{} {}", listing.kind, listing.name).unwrap();
+            writeln!(
+                text,
+                "  file {} , stack_size {} , local_size {}",
+                self.file_name(listing.file_index),
+                listing.stack_size,
+                listing.local_size
+            )
+            .unwrap();
+            if !listing.locals.is_empty() {
+                let locals: Vec<String> = listing
+                    .locals
+                    .iter()
+                    .map(|(slot_idx, sub_idx, name)| {
+                        format!("{} ({}, {})", name, slot_idx, sub_idx)
+                    })
+                    .collect();
+                writeln!(text, "  locals {}", locals.join(" , ")).unwrap();
+            }
+            for instruction in listing.instructions.iter() {
+                if let Some(label) = &instruction.label {
+                    writeln!(text, "{}:", label).unwrap();
+                }
+                let line = match instruction.line {
+                    Some(line) => line.to_string(),
+                    None => String::new(),
+                };
+                let mut code_line =
+                    format!("  {:>5} {:>5}  {}", instruction.pc, line, instruction.code);
+                if let Some(comment) = &instruction.comment {
+                    while code_line.len() < 48 {
+                        code_line.push(' ');
+                    }
+                    code_line.push_str(" // ");
+                    code_line.push_str(comment);
+                }
+                writeln!(text, "{}", code_line).unwrap();
+            }
+            if let Some(label) = &listing.end_label {
+                writeln!(text, "{}:", label).unwrap();
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn json(&self) -> Json {
+        let funcs: Vec<Json> = self
+            .listings()
+            .into_iter()
+            .map(|listing| {
+                let locals: Vec<Json> = listing
+                    .locals
+                    .iter()
+                    .map(|(slot_idx, sub_idx, name)| {
+                        json!({ "name": name, "slot": slot_idx, "sub": sub_idx })
+                    })
+                    .collect();
+                let codes: Vec<Json> = listing
+                    .instructions
+                    .into_iter()
+                    .map(|instruction| {
+                        json!({
+                            "pc": instruction.pc,
+                            "line": instruction.line,
+                            "label": instruction.label,
+                            "code": instruction.code,
+                            "comment": instruction.comment,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "kind": listing.kind,
+                    "name": listing.name,
+                    "file": self.file_name(listing.file_index),
+                    "stack_size": listing.stack_size,
+                    "local_size": listing.local_size,
+                    "locals": locals,
+                    "bytecodes": codes,
+                    "end_label": listing.end_label,
+                })
+            })
+            .collect();
+        json!({ "funcs": funcs })
+    }
+
+    fn listings(&self) -> Vec<Listing> {
+        let status = self.status;
+        let mut listings = Vec::new();
+        for script_body in status.script_bodies.iter() {
+            let func = &script_body.inner().func;
+            let name = self.file_name(func.info.file_index).to_string();
+            listings.push(self.listing("script", name, func));
+        }
+        for func in status.funcs.iter() {
+            let func = func.inner();
+            // 跳过内置函数 skip builtin functions
+            if let FuncBody::ByteCodes(_) = func.body {
+                listings.push(self.listing("func", func.info.name.to_string(), &func));
+            }
+        }
+        for class in status.classes.iter() {
+            let class = class.inner();
+            for func in class.funcs.iter() {
+                let func = func.inner();
+                let name = format!("{}.{}", class.name, func.info.name);
+                listings.push(self.listing("method", name, &func));
+            }
+        }
+        for enum_class in status.enums.iter() {
+            let enum_class = enum_class.inner();
+            for func in enum_class.funcs.iter() {
+                let func = func.inner();
+                let name = format!("{}.{}", enum_class.name, func.info.name);
+                listings.push(self.listing("method", name, &func));
+            }
+        }
+        for (index, func) in self.pool.nameless_fn.iter().enumerate() {
+            let func = func.inner();
+            let name = format!("{} #{}", func.info.name, index);
+            listings.push(self.listing("nameless", name, &func));
+        }
+        listings
+    }
+
+    fn listing(&self, kind: &'static str, name: String, func: &GloomFunc) -> Listing {
+        let bytecodes = match &func.body {
+            FuncBody::ByteCodes(bytecodes) => bytecodes,
+            _ => panic!(),
+        };
+        // 收集所有跳转目标并按位置编号 collect all jump targets and number them by position
+        let mut targets = Vec::new();
+        for bytecode in bytecodes.iter() {
+            match *bytecode {
+                ByteCode::JumpIf(label)
+                | ByteCode::JumpIfNot(label)
+                | ByteCode::Jump(label)
+                | ByteCode::JumpIfNone(label)
+                | ByteCode::JumpIfStaticInit { label, .. } => targets.push(label),
+                ByteCode::JumpTable(idx) => {
+                    let table = &self.pool.jump_table[idx as usize];
+                    targets.extend(table.int_map.values());
+                    targets.extend(table.str_map.values());
+                    targets.push(table.default);
+                }
+                ByteCode::EnterTry(idx) => {
+                    let try_block = &self.pool.try_table[idx as usize];
+                    targets.push(try_block.end);
+                    targets.push(try_block.handler);
+                }
+                _ => {}
+            }
+        }
+        targets.sort_unstable();
+        targets.dedup();
+        let labels: HashMap<u32, String> = targets
+            .iter()
+            .enumerate()
+            .map(|(idx, target)| (*target, format!("L{}", idx)))
+            .collect();
+
+        let instructions = bytecodes
+            .iter()
+            .enumerate()
+            .map(|(pc, bytecode)| {
+                let (code, comment) = self.describe(func, *bytecode, &labels);
+                Instruction {
+                    pc,
+                    line: func.info.line(pc),
+                    label: labels.get(&(pc as u32)).cloned(),
+                    code,
+                    comment,
+                }
+            })
+            .collect();
+        Listing {
+            kind,
+            name,
+            file_index: func.info.file_index,
+            stack_size: func.info.stack_size,
+            local_size: func.info.local_size,
+            locals: func.info.local_names.clone(),
+            instructions,
+            end_label: labels.get(&(bytecodes.len() as u32)).cloned(),
+        }
+    }
+
+    // 字节码的文本及其注释 the text of bytecode and its comment
+    fn describe(
+        &self,
+        func: &GloomFunc,
+        bytecode: ByteCode,
+        labels: &HashMap<u32, String>,
+    ) -> (String, Option<String>) {
+        let status = self.status;
+        let pool = self.pool;
+        let label = |target: u32| labels.get(&target).unwrap().clone();
+        let local = |slot_idx: u16, sub_idx: u8| {
+            func.info
+                .local_names
+                .iter()
+                .find(|(slot, sub, _)| *slot == slot_idx && *sub == sub_idx)
+                .map(|(_, _, name)| name.clone())
+        };
+        match bytecode {
+            ByteCode::JumpIf(target) => (format!("JumpIf({})", label(target)), None),
+            ByteCode::JumpIfNot(target) => (format!("JumpIfNot({})", label(target)), None),
+            ByteCode::Jump(target) => (format!("Jump({})", label(target)), None),
+            ByteCode::JumpIfNone(target) => (format!("JumpIfNone({})", label(target)), None),
+            ByteCode::JumpIfStaticInit {
+                label: target,
+                static_idx,
+            } => (
+                format!(
+                    "JumpIfStaticInit {{ label: {}, static_idx: {} }}",
+                    label(target),
+                    static_idx
+                ),
+                None,
+            ),
+            ByteCode::JumpTable(idx) => {
+                let table = &pool.jump_table[idx as usize];
+                let mut cases: Vec<String> = table
+                    .int_map
+                    .iter()
+                    .map(|(key, target)| format!("{} => {}", key, label(*target)))
+                    .collect();
+                cases.extend(
+                    table
+                        .str_map
+                        .iter()
+                        .map(|(key, target)| format!("{:?} => {}", key, label(*target))),
+                );
+                // 哈希表无序 排序使输出稳定 hash map is unordered, sort to make the output stable
+                cases.sort();
+                cases.push(format!("_ => {}", label(table.default)));
+                (format!("{:?}", bytecode), Some(cases.join(" , ")))
+            }
+            ByteCode::EnterTry(idx) => {
+                let try_block = &pool.try_table[idx as usize];
+                (
+                    format!("{:?}", bytecode),
+                    Some(format!(
+                        "end {} , catch {}",
+                        label(try_block.end),
+                        label(try_block.handler)
+                    )),
+                )
+            }
+            ByteCode::LoadConstString(idx) => {
+                let str = pool.str[idx as usize]
+                    .downcast::<GloomString>()
+                    .0
+                    .borrow()
+                    .clone();
+                (format!("{:?}", bytecode), Some(format!("{:?}", str)))
+            }
+            ByteCode::LoadConstInt(idx) => (
+                format!("{:?}", bytecode),
+                Some(pool.int[idx as usize].to_string()),
+            ),
+            ByteCode::LoadConstNum(idx) => (
+                format!("{:?}", bytecode),
+                Some(pool.num[idx as usize].to_string()),
+            ),
+            ByteCode::LoadClass(idx) | ByteCode::Construct(idx) => (
+                format!("{:?}", bytecode),
+                Some(status.classes[idx as usize].inner().name.to_string()),
+            ),
+            ByteCode::LoadEnum(idx) => (
+                format!("{:?}", bytecode),
+                Some(status.enums[idx as usize].inner().name.to_string()),
+            ),
+            ByteCode::LoadBuiltinType(idx) => (
+                format!("{:?}", bytecode),
+                Some(status.builtin_classes[idx as usize].inner().name.clone()),
+            ),
+            ByteCode::LoadDirectDefFn(idx) => (
+                format!("{:?}", bytecode),
+                Some(status.funcs[idx as usize].inner().info.name.to_string()),
+            ),
+            ByteCode::LoadNamelessFn(idx) => (
+                format!("{:?}", bytecode),
+                Some(format!(
+                    "{} #{}",
+                    pool.nameless_fn[idx as usize].inner().info.name,
+                    idx
+                )),
+            ),
+            ByteCode::CallMethodDyn {
+                interface_idx,
+                fn_idx,
+                ..
+            } => {
+                let interface = status.interfaces[interface_idx as usize].inner();
+                let func_name = interface.funcs[fn_idx as usize].inner().info.name.clone();
+                (
+                    format!("{:?}", bytecode),
+                    Some(format!("{}.{}", interface.name, func_name)),
+                )
+            }
+            ByteCode::ReadLocal(slot_idx, sub_idx)
+            | ByteCode::WriteLocalInt(slot_idx, sub_idx)
+            | ByteCode::WriteLocalNum(slot_idx, sub_idx)
+            | ByteCode::WriteLocalChar(slot_idx, sub_idx)
+            | ByteCode::WriteLocalBool(slot_idx, sub_idx) => {
+                (format!("{:?}", bytecode), local(slot_idx, sub_idx))
+            }
+            ByteCode::WriteLocalRef(slot_idx) | ByteCode::DropLocal(slot_idx) => {
+                (format!("{:?}", bytecode), local(slot_idx, 0))
+            }
+            _ => (format!("{:?}", bytecode), None),
+        }
+    }
+
+    fn file_name(&self, file_index: u16) -> &str {
+        match self.status.paths.get(file_index as usize) {
+            Some(path) => path.as_str(),
+            None => "<unknown>",
+        }
+    }
+}
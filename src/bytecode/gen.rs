@@ -4,13 +4,13 @@ use std::ops::Deref;
 use crate::builtin::string::GloomString;
 use crate::bytecode::code::ByteCode;
 use crate::frontend::ast::{
-    Chain, ExprType, Expression, ForIter, FuncExpr, LeftValue, Statement, Var,
+    Chain, ExprType, Expression, ForIter, FuncExpr, LeftValue, Pattern, Statement, Var,
 };
 use crate::frontend::ops::{BinOp, LeftValueOp};
-use crate::frontend::status::GloomStatus;
+use crate::frontend::status::{DeclCount, GloomStatus};
 use crate::obj::func::{FuncBody, GloomFunc};
 use crate::obj::types::{BasicType, DataType, RefType};
-use crate::vm::constant::ConstantPool;
+use crate::vm::constant::{ConstantPool, JumpTable, TryBlock};
 
 pub struct CodeGenerator {
     constant_pool: ConstantPool,
@@ -44,13 +44,30 @@ impl CodeGenerator {
         }
         self.constant_pool
     }
-    fn generate_func(&mut self, func: &mut GloomFunc) {
+    // 为from之后加载的声明生成代码 generate code for the declarations loaded after from
+    pub fn generate_decls(&mut self, status: &GloomStatus, from: &DeclCount) {
+        for func in status.funcs[from.funcs..].iter() {
+            self.generate_func(&mut func.inner_mut());
+        }
+        for class in status.classes[from.classes..].iter() {
+            for func in class.inner().funcs.iter() {
+                self.generate_func(&mut func.inner_mut());
+            }
+        }
+        for class in status.enums[from.enums..].iter() {
+            for func in class.inner().funcs.iter() {
+                self.generate_func(&mut func.inner_mut());
+            }
+        }
+    }
+    pub fn generate_func(&mut self, func: &mut GloomFunc) {
         match &func.body {
             FuncBody::AST(vec) => {
                 let mut context = GenerateContext::new(func.info.local_size * 4);
                 self.generate_statements(vec, &mut context);
                 // context.push(ByteCode::Return);
                 func.info.stack_size = context.stack_size();
+                func.info.lines = std::mem::take(&mut context.lines);
                 func.body = FuncBody::ByteCodes(context.bytecodes());
             }
             FuncBody::Builtin(_) => {}
@@ -59,6 +76,7 @@ impl CodeGenerator {
     }
     fn generate_statements(&mut self, statements: &Vec<Statement>, context: &mut GenerateContext) {
         for stmt in statements.iter() {
+            context.mark_line(stmt.line());
             match stmt {
                 Statement::Let(let_info) => {
                     let (var, _, expr, _) = let_info.deref();
@@ -74,7 +92,7 @@ impl CodeGenerator {
                     context.push(code);
                 }
                 Statement::Static(static_info) | Statement::PubStatic(static_info) => {
-                    let (var, _, expr) = static_info.deref();
+                    let (var, _, expr, _) = static_info.deref();
                     let index = match var {
                         Var::StaticInt(i)
                         | Var::StaticNum(i)
@@ -110,7 +128,7 @@ impl CodeGenerator {
                     }
                 }
                 Statement::LeftValueOp(op_info) => {
-                    let (left_value, operation) = op_info.deref();
+                    let (left_value, operation, _) = op_info.deref();
                     match left_value {
                         LeftValue::Var(var) => {
                             if let LeftValueOp::Assign(_) = operation {
@@ -132,25 +150,7 @@ impl CodeGenerator {
                                 };
                                 context.push(read_code);
                             };
-                            match operation {
-                                LeftValueOp::Assign(expr) => {
-                                    self.generate_expression(expr, context);
-                                }
-                                LeftValueOp::PlusEq(expr) => {
-                                    self.generate_expression(expr, context);
-                                    context.push(ByteCode::Plus);
-                                }
-                                LeftValueOp::SubEq(expr) => {
-                                    self.generate_expression(expr, context);
-                                    context.push(ByteCode::Sub);
-                                }
-                                LeftValueOp::PlusOne => {
-                                    context.push(ByteCode::PlusOne);
-                                }
-                                LeftValueOp::SubOne => {
-                                    context.push(ByteCode::SubOne);
-                                }
-                            }
+                            self.generate_left_value_op(operation, context);
                             let write_code = match var {
                                 Var::LocalInt(i1, i2) => ByteCode::WriteLocalInt(*i1, *i2),
                                 Var::LocalNum(i1, i2) => ByteCode::WriteLocalNum(*i1, *i2),
@@ -184,25 +184,7 @@ impl CodeGenerator {
                                 // need read field and do some calculation before write field
                                 context.push(ByteCode::ReadField(slot_idx, sub_idx));
                             }
-                            match operation {
-                                LeftValueOp::Assign(expr) => {
-                                    self.generate_expression(expr, context);
-                                }
-                                LeftValueOp::PlusEq(expr) => {
-                                    self.generate_expression(expr, context);
-                                    context.push(ByteCode::Plus);
-                                }
-                                LeftValueOp::SubEq(expr) => {
-                                    self.generate_expression(expr, context);
-                                    context.push(ByteCode::Sub);
-                                }
-                                LeftValueOp::PlusOne => {
-                                    context.push(ByteCode::PlusOne);
-                                }
-                                LeftValueOp::SubOne => {
-                                    context.push(ByteCode::SubOne);
-                                }
-                            };
+                            self.generate_left_value_op(operation, context);
                             let write_field_code = match field_type {
                                 BasicType::Int => ByteCode::WriteFieldInt(slot_idx, sub_idx),
                                 BasicType::Num => ByteCode::WriteFieldNum(slot_idx, sub_idx),
@@ -212,6 +194,24 @@ impl CodeGenerator {
                             };
                             context.push(write_field_code);
                         }
+                        LeftValue::Index(index_info) => {
+                            let (container, index, elem_type) = index_info.deref();
+                            self.generate_expression(container, context);
+                            self.generate_expression(index, context);
+                            if let LeftValueOp::Assign(_) = operation {
+                                // do nothing
+                            } else {
+                                // 元素可能是装箱的值 element may be a boxed value
+                                context.push(ByteCode::ReadIndex);
+                                match elem_type {
+                                    BasicType::Int => context.push(ByteCode::AsInt),
+                                    BasicType::Num => context.push(ByteCode::AsNum),
+                                    _ => {}
+                                }
+                            }
+                            self.generate_left_value_op(operation, context);
+                            context.push(ByteCode::WriteIndex);
+                        }
                     }
                 }
                 Statement::Expr(expr, _) | Statement::Discard(expr, _) => {
@@ -223,6 +223,11 @@ impl CodeGenerator {
                                 context.push(ByteCode::Pop);
                             }
                         }
+                        Expression::Match(match_def) => {
+                            if !match_def.return_void {
+                                context.push(ByteCode::Pop);
+                            }
+                        }
                         _ => context.push(ByteCode::Pop),
                     }
                 }
@@ -290,7 +295,11 @@ impl CodeGenerator {
                     // now stack top have a iter obj
 
                     let start_judge_idx = context.bytecodes.len() as u32;
-                    context.push(ByteCode::InvokeNext);
+                    context.push(if for_loop.suspend {
+                        ByteCode::AwaitNext
+                    } else {
+                        ByteCode::InvokeNext
+                    });
 
                     // if result of next() is none, pop and jump to end, else, just execute
                     let jump_if_none_idx = context.bytecodes.len();
@@ -344,6 +353,57 @@ impl CodeGenerator {
                         }
                     }
                 }
+                Statement::Try(try_catch) => {
+                    // 先占位，因为try块中可能有嵌套的try
+                    // take the place firstly, because there may be nested try in the block
+                    let try_idx = self.constant_pool.try_table.len();
+                    self.constant_pool.try_table.push(TryBlock::new());
+                    context.push(ByteCode::EnterTry(try_idx as u16));
+                    let start = context.bytecodes.len() as u32;
+                    self.generate_statements(&try_catch.statements, context);
+                    for idx in try_catch.drop_slots.iter() {
+                        context.push(ByteCode::DropLocal(*idx));
+                    }
+                    let end = context.bytecodes.len() as u32;
+                    let jump_end_idx = context.bytecodes.len();
+                    context.push(ByteCode::Jump(Self::INVALID_LABEL));
+
+                    // 发生错误时 虚拟机会把错误信息压入栈顶 然后跳转到这里
+                    // when error occurs, vm will push the error message on the stack top and jump here
+                    let handler = context.bytecodes.len() as u32;
+                    context.pushed_by_vm(1);
+                    // try块中的局部变量可能尚未被析构 the locals of try block may haven't been dropped
+                    for idx in try_catch.drop_slots.iter() {
+                        context.push(ByteCode::DropLocal(*idx));
+                    }
+                    context.push(match try_catch.var {
+                        Some(Var::LocalRef(slot_idx)) => ByteCode::WriteLocalRef(slot_idx),
+                        None => ByteCode::Pop,
+                        _ => panic!(),
+                    });
+                    self.generate_statements(&try_catch.catch_statements, context);
+                    for idx in try_catch.catch_drop_slots.iter() {
+                        context.push(ByteCode::DropLocal(*idx));
+                    }
+
+                    let end_idx = context.bytecodes.len() as u32;
+                    if let ByteCode::Jump(label) = context.bytecodes.get_mut(jump_end_idx).unwrap()
+                    {
+                        *label = end_idx;
+                    } else {
+                        panic!()
+                    }
+                    *self.constant_pool.try_table.get_mut(try_idx).unwrap() =
+                        TryBlock::from(start, end, handler);
+                }
+                Statement::Throw(expr, _) => {
+                    self.generate_expression(expr, context);
+                    context.push(ByteCode::Throw);
+                }
+                Statement::Yield(expr, _) => {
+                    self.generate_expression(expr, context);
+                    context.push(ByteCode::Yield);
+                }
             }
         }
     }
@@ -421,6 +481,19 @@ impl CodeGenerator {
                 };
                 context.push(code);
             }
+            Expression::Map(entries) => {
+                for (key, value) in entries.iter().rev() {
+                    self.generate_expression(value, context);
+                    self.generate_expression(key, context);
+                }
+                context.push(ByteCode::CollectMap(entries.len() as u16));
+            }
+            Expression::Set(elems) => {
+                for expr in elems.iter().rev() {
+                    self.generate_expression(expr, context);
+                }
+                context.push(ByteCode::CollectSet(elems.len() as u16));
+            }
             Expression::NegOp(expr) => {
                 self.generate_expression(expr, context);
                 context.push(ByteCode::NegOp);
@@ -429,6 +502,23 @@ impl CodeGenerator {
                 self.generate_expression(expr, context);
                 context.push(ByteCode::NotOp);
             }
+            Expression::BitNotOp(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::BitNot);
+            }
+            Expression::Await(await_expr) => {
+                let (expr, suspend) = await_expr.deref();
+                self.generate_expression(expr, context);
+                context.push(if *suspend {
+                    ByteCode::Await
+                } else {
+                    ByteCode::AwaitBlocking
+                });
+            }
+            Expression::Spawn(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::Spawn);
+            }
             Expression::BinaryOp(bin_op_vec) => {
                 self.generate_expression(&bin_op_vec.left, context);
                 for (bin_op, expr) in bin_op_vec.vec.iter() {
@@ -438,6 +528,13 @@ impl CodeGenerator {
                         BinOp::Sub => ByteCode::Sub,
                         BinOp::Mul => ByteCode::Mul,
                         BinOp::Div => ByteCode::Div,
+                        BinOp::Mod => ByteCode::Mod,
+                        BinOp::Pow => ByteCode::Pow,
+                        BinOp::BitAnd => ByteCode::BitAnd,
+                        BinOp::BitOr => ByteCode::BitOr,
+                        BinOp::BitXor => ByteCode::BitXor,
+                        BinOp::Shl => ByteCode::ShiftLeft,
+                        BinOp::Shr => ByteCode::ShiftRight,
                         BinOp::Gt => ByteCode::GreaterThan,
                         BinOp::Lt => ByteCode::LessThan,
                         BinOp::GtEq => ByteCode::GreaterThanEquals,
@@ -544,7 +641,91 @@ impl CodeGenerator {
                     DataType::Ref(_) => ByteCode::AsRef,
                 });
             }
-            Expression::Match(m) => panic!("not support now {:#?}", m),
+            Expression::Match(match_def) => {
+                self.generate_expression(&match_def.matched, context);
+                // 需要解构关联值时，被匹配的值先存入局部变量
+                // store the matched value in local if the associated value need to be destructured
+                if let Some(slot_idx) = match_def.matched_slot {
+                    context.push(ByteCode::WriteLocalRef(slot_idx));
+                    context.push(ByteCode::ReadLocal(slot_idx, 0));
+                }
+                // 先占位，因为分支中可能有嵌套的match
+                // take the place firstly, because there may be nested match in branches
+                let table_idx = self.constant_pool.jump_table.len();
+                self.constant_pool.jump_table.push(JumpTable::new());
+                context.push(ByteCode::JumpTable(table_idx as u16));
+                let mut table = JumpTable::new();
+                let mut jump_end_indexes = Vec::with_capacity(match_def.branches.len());
+                let max_idx = match_def.branches.len().max(1) - 1;
+                for (idx, branch) in match_def.branches.iter().enumerate() {
+                    // 分支的起始位置即为跳转表中该模式的label
+                    // the start index of branch is the label of its pattern in jump table
+                    let label = context.bytecodes.len() as u32;
+                    match &branch.pattern {
+                        Pattern::Int(i) => {
+                            table.int_map.entry(*i).or_insert(label);
+                        }
+                        Pattern::Char(ch) => {
+                            table.int_map.entry(*ch as i64).or_insert(label);
+                        }
+                        Pattern::Bool(bl) => {
+                            table.int_map.entry(*bl as i64).or_insert(label);
+                        }
+                        Pattern::Enum { tag, .. } => {
+                            table.int_map.entry(*tag as i64).or_insert(label);
+                        }
+                        Pattern::Str(str) => {
+                            table.str_map.entry(String::clone(str)).or_insert(label);
+                        }
+                        Pattern::Wildcard => {
+                            if table.default == Self::INVALID_LABEL {
+                                table.default = label;
+                            }
+                        }
+                    }
+                    if let (
+                        Pattern::Enum {
+                            bind: Some(var), ..
+                        },
+                        Some(slot_idx),
+                    ) = (&branch.pattern, match_def.matched_slot)
+                    {
+                        context.push(ByteCode::ReadLocal(slot_idx, 0));
+                        context.push(ByteCode::ReadEnumVal);
+                        context.push(match var {
+                            Var::LocalInt(i1, i2) => ByteCode::WriteLocalInt(*i1, *i2),
+                            Var::LocalNum(i1, i2) => ByteCode::WriteLocalNum(*i1, *i2),
+                            Var::LocalChar(i1, i2) => ByteCode::WriteLocalChar(*i1, *i2),
+                            Var::LocalBool(i1, i2) => ByteCode::WriteLocalBool(*i1, *i2),
+                            Var::LocalRef(i) => ByteCode::WriteLocalRef(*i),
+                            _ => panic!(),
+                        });
+                    }
+                    self.generate_statements(&branch.statements, context);
+                    for slot_idx in branch.drop_vec.iter() {
+                        context.push(ByteCode::DropLocal(*slot_idx));
+                    }
+                    if idx < max_idx {
+                        jump_end_indexes.push(context.bytecodes.len());
+                        context.push(ByteCode::Jump(Self::INVALID_LABEL));
+                    }
+                }
+                // 没有'_'分支时，未匹配的值直接跳转到末尾
+                // if there is no '_' branch, the unmatched value jump to the end directly
+                let end_idx = context.bytecodes.len() as u32;
+                if table.default == Self::INVALID_LABEL {
+                    table.default = end_idx;
+                }
+                for idx in jump_end_indexes {
+                    if let ByteCode::Jump(label) = context.bytecodes.get_mut(idx).unwrap() {
+                        *label = end_idx;
+                    }
+                }
+                if let Some(slot_idx) = match_def.matched_slot {
+                    context.push(ByteCode::DropLocal(slot_idx));
+                }
+                *self.constant_pool.jump_table.get_mut(table_idx).unwrap() = table;
+            }
         }
     }
     #[inline]
@@ -559,6 +740,7 @@ impl CodeGenerator {
                 need_self,
                 args,
                 is_dyn,
+                line,
             } => {
                 for arg_expr in args.iter() {
                     self.generate_expression(arg_expr, context);
@@ -583,15 +765,70 @@ impl CodeGenerator {
                         nargs: args.len() as u16,
                     }
                 };
-                context.push(call_code);
+                context.push_call(call_code, *line);
             }
-            Chain::Call(args) => {
+            Chain::Call(args, line) => {
                 for arg_expr in args.iter() {
                     self.generate_expression(arg_expr, context);
                 }
-                context.push(ByteCode::CallTopFn {
-                    nargs: args.len() as u16,
-                })
+                context.push_call(
+                    ByteCode::CallTopFn {
+                        nargs: args.len() as u16,
+                    },
+                    *line,
+                )
+            }
+            Chain::EnumValue(tag, val) => match val {
+                Some(val) => {
+                    self.generate_expression(val, context);
+                    context.push(ByteCode::ConstructEnumWithVal(*tag));
+                }
+                None => context.push(ByteCode::ConstructEnum(*tag)),
+            },
+            Chain::Index(index, _) => {
+                self.generate_expression(index, context);
+                context.push(ByteCode::ReadIndexAndPop);
+            }
+            Chain::Slice(range) => {
+                let (from, to) = range.deref();
+                self.generate_expression(from, context);
+                self.generate_expression(to, context);
+                context.push(ByteCode::ReadSlice);
+            }
+        }
+    }
+
+    // 计算左值的新值 calculate the new value of left value
+    fn generate_left_value_op(&mut self, operation: &LeftValueOp, context: &mut GenerateContext) {
+        match operation {
+            LeftValueOp::Assign(expr) => {
+                self.generate_expression(expr, context);
+            }
+            LeftValueOp::PlusEq(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::Plus);
+            }
+            LeftValueOp::SubEq(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::Sub);
+            }
+            LeftValueOp::MulEq(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::Mul);
+            }
+            LeftValueOp::DivEq(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::Div);
+            }
+            LeftValueOp::ModEq(expr) => {
+                self.generate_expression(expr, context);
+                context.push(ByteCode::Mod);
+            }
+            LeftValueOp::PlusOne => {
+                context.push(ByteCode::PlusOne);
+            }
+            LeftValueOp::SubOne => {
+                context.push(ByteCode::SubOne);
             }
         }
     }
@@ -600,12 +837,20 @@ impl CodeGenerator {
             constant_pool: ConstantPool::new(),
         }
     }
+    // 向已有的常量池追加 append to an existing constant pool
+    pub fn with_pool(constant_pool: ConstantPool) -> Self {
+        CodeGenerator { constant_pool }
+    }
+    pub fn into_pool(self) -> ConstantPool {
+        self.constant_pool
+    }
 }
 
 struct GenerateContext {
     bytecodes: Vec<ByteCode>,
     max_stack_size: u16,
     curr_stack_size: i16,
+    lines: Vec<(u32, u16)>,
 }
 
 impl GenerateContext {
@@ -614,6 +859,7 @@ impl GenerateContext {
             bytecodes: Vec::with_capacity(codes as usize),
             max_stack_size: 0,
             curr_stack_size: 0,
+            lines: Vec::new(),
         }
     }
     fn bytecodes(self) -> Vec<ByteCode> {
@@ -623,11 +869,45 @@ impl GenerateContext {
     fn push(&mut self, bytecode: ByteCode) {
         let stack_affect = bytecode.stack_affect();
         self.curr_stack_size += stack_affect;
+        debug_assert!(
+            self.curr_stack_size >= 0,
+            "stack size becomes negative after {:?}",
+            bytecode
+        );
         if self.curr_stack_size as u16 > self.max_stack_size {
             self.max_stack_size = self.curr_stack_size as u16;
         }
         self.bytecodes.push(bytecode);
     }
+    // 记录接下来生成的字节码所在的行 record the line of bytecodes generated next
+    #[inline]
+    fn mark_line(&mut self, line: u16) {
+        let pc = self.bytecodes.len() as u32;
+        match self.lines.last_mut() {
+            Some((last_pc, last_line)) if *last_pc == pc => *last_line = line,
+            Some((_, last_line)) if *last_line == line => {}
+            _ => self.lines.push((pc, line)),
+        }
+    }
+    // 调用栈记录调用所在的行 而不是语句开始的行 之后的字节码仍属于原来的行
+    // the traceback records the line of the call rather than the start of statement, the bytecodes after it still belong to the original line
+    #[inline]
+    fn push_call(&mut self, bytecode: ByteCode, line: u16) {
+        let curr_line = self.lines.last().map(|(_, line)| *line);
+        self.mark_line(line);
+        self.push(bytecode);
+        if let Some(curr_line) = curr_line {
+            self.mark_line(curr_line);
+        }
+    }
+    // 由虚拟机而非字节码压入栈中的值 values pushed on the stack by vm instead of bytecode
+    #[inline]
+    fn pushed_by_vm(&mut self, count: i16) {
+        self.curr_stack_size += count;
+        if self.curr_stack_size as u16 > self.max_stack_size {
+            self.max_stack_size = self.curr_stack_size as u16;
+        }
+    }
     fn stack_size(&self) -> u16 {
         self.max_stack_size
     }
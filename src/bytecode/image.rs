@@ -0,0 +1,1741 @@
+use std::rc::Rc;
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::builtin::string::GloomString;
+use crate::bytecode::code::ByteCode;
+use crate::frontend::index::SlotIndexer;
+use crate::frontend::script::ScriptBody;
+use crate::frontend::status::GloomStatus;
+use crate::obj::class::{GloomClass, InterfaceImpl};
+use crate::obj::func::{Capture, FuncBody, FuncInfo, GloomFunc, Param, ReturnType};
+use crate::obj::gloom_enum::{GloomEnumClass, RelatedType};
+use crate::obj::interface::Interface;
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{BasicType, BuiltinType, DataType, RefType, TypeParam};
+use crate::vm::constant::{ConstantPool, JumpTable, TryBlock};
+use crate::vm::static_table::StaticTable;
+
+// 编译后的字节码镜像 .gsc 文件的格式
+// the format of compiled bytecode image, the .gsc file
+//   magic "GSC\0" | version u16 | paths | declarations | interfaces | classes | enums
+//   | funcs | script bodies | constant pool | static table
+// 所有整数都以小端序存储 类、接口和枚举通过下标引用
+// all integers are stored in little endian, classes interfaces and enums are referenced by index
+
+const MAGIC: &[u8; 4] = b"GSC\0";
+pub const EXTENSION: &str = "gsc";
+// 镜像格式改变时需要增加版本号 increase the version when the format of image changed
+pub const IMAGE_VERSION: u16 = 1;
+
+#[derive(Error, Debug)]
+pub enum ImageError {
+    #[error("not a compiled GloomScript file")]
+    NotImage,
+
+    #[error("unsupported image version {found}, expect version {expect}, please compile the script again")]
+    MismatchedVersion { expect: u16, found: u16 },
+
+    #[error("the image is compiled with {found} builtin functions, but there are {expect}, please compile the script again")]
+    MismatchedBuiltin { expect: usize, found: usize },
+
+    #[error("unexpected end of image")]
+    UnexpectedEnd,
+
+    #[error("invalid {what} tag {tag} in image")]
+    InvalidTag { what: &'static str, tag: u8 },
+
+    #[error("invalid {what} index {index} in image")]
+    InvalidIndex { what: &'static str, index: u16 },
+
+    #[error("invalid jump label {label} in image")]
+    InvalidLabel { label: u32 },
+
+    #[error("invalid utf-8 string in image")]
+    InvalidString,
+}
+
+pub fn is_image(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn write_image(status: &GloomStatus, pool: &ConstantPool, statics: &StaticTable) -> Vec<u8> {
+    let mut writer = ImageWriter {
+        bytes: Vec::with_capacity(1024),
+        status,
+    };
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.u16(IMAGE_VERSION);
+    writer.len(status.paths.len());
+    for path in status.paths.iter() {
+        writer.str(path);
+    }
+    // 先写入声明 以便读取时先创建空的类型 write declarations first, so that empty types can be created first when reading
+    writer.len(status.interfaces.len());
+    for interface in status.interfaces.iter() {
+        writer.str(interface.inner().name.as_str());
+    }
+    writer.len(status.classes.len());
+    for class in status.classes.iter() {
+        let class = class.inner();
+        writer.str(class.name.as_str());
+        writer.u16(class.file_index);
+    }
+    writer.len(status.enums.len());
+    for enum_class in status.enums.iter() {
+        let enum_class = enum_class.inner();
+        writer.str(enum_class.name.as_str());
+        writer.u16(enum_class.file_index);
+    }
+    for interface in status.interfaces.iter() {
+        writer.interface(&interface.inner());
+    }
+    for class in status.classes.iter() {
+        writer.class(&class.inner());
+    }
+    for enum_class in status.enums.iter() {
+        writer.enum_class(&enum_class.inner());
+    }
+    // 内置函数位于函数表的开头 不需要写入 builtin functions are at the head of function table, no need to write
+    let builtin_len = status
+        .funcs
+        .iter()
+        .take_while(|func| matches!(func.inner().body, FuncBody::Builtin(_)))
+        .count();
+    writer.len(builtin_len);
+    writer.len(status.funcs.len() - builtin_len);
+    for func in status.funcs[builtin_len..].iter() {
+        writer.func(&func.inner());
+    }
+    writer.len(status.script_bodies.len());
+    for script_body in status.script_bodies.iter() {
+        let script_body = script_body.inner();
+        writer.u16(script_body.file_index);
+        writer.func(&script_body.func);
+    }
+    writer.pool(pool);
+    writer.u16(statics.len);
+    writer.u16_vec(&statics.drop_vec);
+    writer.bytes
+}
+
+pub fn read_image(bytes: &[u8]) -> Result<(GloomStatus, ConstantPool, StaticTable), ImageError> {
+    if !is_image(bytes) {
+        return Result::Err(ImageError::NotImage);
+    }
+    let mut reader = ImageReader {
+        bytes,
+        pos: MAGIC.len(),
+        status: GloomStatus::new(),
+    };
+    let version = reader.u16()?;
+    if version != IMAGE_VERSION {
+        return Result::Err(ImageError::MismatchedVersion {
+            expect: IMAGE_VERSION,
+            found: version,
+        });
+    }
+    for _ in 0..reader.len()? {
+        let path = reader.str()?;
+        reader.status.paths.push(path);
+    }
+    // 空的类型 empty types
+    for index in 0..reader.len()? {
+        let name = Rc::new(reader.str()?);
+        let interface = RefCount::new(Interface::new(name, index as u16));
+        reader.status.interfaces.push(interface);
+    }
+    for index in 0..reader.len()? {
+        let name = Rc::new(reader.str()?);
+        let file_index = reader.u16()?;
+        let class = RefCount::new(GloomClass::new(name, file_index, index as u16));
+        reader.status.classes.push(class);
+    }
+    for _ in 0..reader.len()? {
+        let name = Rc::new(reader.str()?);
+        let file_index = reader.u16()?;
+        let enum_class = RefCount::new(GloomEnumClass::new(name, file_index));
+        reader.status.enums.push(enum_class);
+    }
+    for index in 0..reader.status.interfaces.len() {
+        reader.interface(index)?;
+    }
+    for index in 0..reader.status.classes.len() {
+        reader.class(index)?;
+    }
+    for index in 0..reader.status.enums.len() {
+        reader.enum_class(index)?;
+    }
+    let builtin_len = reader.len()?;
+    if builtin_len != reader.status.funcs.len() {
+        return Result::Err(ImageError::MismatchedBuiltin {
+            expect: reader.status.funcs.len(),
+            found: builtin_len,
+        });
+    }
+    for _ in 0..reader.len()? {
+        let func = reader.func()?;
+        reader.status.funcs.push(RefCount::new(func));
+    }
+    for _ in 0..reader.len()? {
+        let file_index = reader.u16()?;
+        let func = reader.func()?;
+        reader
+            .status
+            .script_bodies
+            .push(RefCount::new(ScriptBody::new(func, file_index)));
+    }
+    let pool = reader.pool()?;
+    let static_len = reader.u16()?;
+    let static_drop_vec = reader.u16_vec()?;
+    ImageVerifier::new(&reader.status, &pool, static_len).verify(&static_drop_vec)?;
+    Result::Ok((
+        reader.status,
+        pool,
+        StaticTable::new(static_len, static_drop_vec),
+    ))
+}
+
+struct ImageWriter<'a> {
+    bytes: Vec<u8>,
+    status: &'a GloomStatus,
+}
+
+impl<'a> ImageWriter<'a> {
+    #[inline]
+    fn u8(&mut self, val: u8) {
+        self.bytes.push(val);
+    }
+    #[inline]
+    fn u16(&mut self, val: u16) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+    #[inline]
+    fn u32(&mut self, val: u32) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+    #[inline]
+    fn i64(&mut self, val: i64) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+    #[inline]
+    fn f64(&mut self, val: f64) {
+        self.bytes.extend_from_slice(&val.to_le_bytes());
+    }
+    #[inline]
+    fn bool(&mut self, val: bool) {
+        self.bytes.push(val as u8);
+    }
+    #[inline]
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+    fn str(&mut self, str: &str) {
+        self.len(str.len());
+        self.bytes.extend_from_slice(str.as_bytes());
+    }
+    fn u16_vec(&mut self, vec: &[u16]) {
+        self.len(vec.len());
+        for val in vec.iter() {
+            self.u16(*val);
+        }
+    }
+
+    fn enum_index(&self, enum_class: &RefCount<GloomEnumClass>) -> u16 {
+        self.status
+            .enums
+            .iter()
+            .position(|e| e.eq(enum_class))
+            .unwrap() as u16
+    }
+
+    fn data_type(&mut self, data_type: &DataType) {
+        match data_type {
+            DataType::Int => self.u8(0),
+            DataType::Num => self.u8(1),
+            DataType::Char => self.u8(2),
+            DataType::Bool => self.u8(3),
+            DataType::Ref(ref_type) => {
+                self.u8(4);
+                self.ref_type(ref_type);
+            }
+        }
+    }
+    fn ref_type(&mut self, ref_type: &RefType) {
+        match ref_type {
+            RefType::Any => self.u8(0),
+            RefType::None => self.u8(1),
+            RefType::MySelf => self.u8(2),
+            RefType::Class(class) => {
+                self.u8(3);
+                self.u16(class.inner().class_index);
+            }
+            RefType::Enum(enum_class) => {
+                self.u8(4);
+                self.u16(self.enum_index(enum_class));
+            }
+            RefType::Interface(interface) => {
+                self.u8(5);
+                self.u16(interface.inner().interface_index);
+            }
+            RefType::MetaClass(class) => {
+                self.u8(6);
+                self.u16(class.inner().class_index);
+            }
+            RefType::MetaEnum(enum_class) => {
+                self.u8(7);
+                self.u16(self.enum_index(enum_class));
+            }
+            RefType::MetaInterface(interface) => {
+                self.u8(8);
+                self.u16(interface.inner().interface_index);
+            }
+            RefType::MataBuiltinType(builtin_type) => {
+                self.u8(9);
+                self.u8(match builtin_type {
+                    BuiltinType::Int => 0,
+                    BuiltinType::Num => 1,
+                    BuiltinType::Char => 2,
+                    BuiltinType::Bool => 3,
+                    BuiltinType::String => 4,
+                    BuiltinType::Func => 5,
+                    BuiltinType::Weak => 6,
+                    BuiltinType::Array => 7,
+                    BuiltinType::Queue => 8,
+                    BuiltinType::Map => 9,
+                    BuiltinType::Set => 10,
+                    BuiltinType::Option => 11,
+                    BuiltinType::Generator => 12,
+                    BuiltinType::Task => 13,
+                    BuiltinType::Timer => 14,
+                    BuiltinType::Channel => 15,
+                    BuiltinType::Error => 16,
+                    // 宿主类只存在于嵌入的引擎中 host classes only exist in embedded engine
+                    BuiltinType::Host(_) => panic!("host type can't be written into image"),
+                });
+            }
+            RefType::Tuple(types) => {
+                self.u8(10);
+                self.len(types.len());
+                for data_type in types.iter() {
+                    self.data_type(data_type);
+                }
+            }
+            RefType::Func(func_type) => {
+                self.u8(11);
+                let (params, return_type, any_ok) = func_type.as_ref();
+                self.len(params.len());
+                for data_type in params.iter() {
+                    self.data_type(data_type);
+                }
+                self.return_type(return_type);
+                self.bool(*any_ok);
+            }
+            RefType::Weak(data_type) => {
+                self.u8(12);
+                self.data_type(data_type);
+            }
+            RefType::Array(data_type) => {
+                self.u8(13);
+                self.data_type(data_type);
+            }
+            RefType::Queue(data_type) => {
+                self.u8(14);
+                self.data_type(data_type);
+            }
+            RefType::Int => self.u8(15),
+            RefType::Num => self.u8(16),
+            RefType::Char => self.u8(17),
+            RefType::Bool => self.u8(18),
+            RefType::String => self.u8(19),
+            RefType::TypeParam(param) => {
+                self.u8(20);
+                self.u16(param.index);
+                self.str(param.name.as_str());
+                self.bool(param.bound.is_some());
+                if let Some(interface) = &param.bound {
+                    self.u16(interface.inner().interface_index);
+                }
+            }
+            RefType::Generic(generic) => {
+                self.u8(21);
+                self.ref_type(&generic.0);
+                self.len(generic.1.len());
+                for data_type in generic.1.iter() {
+                    self.data_type(data_type);
+                }
+            }
+            RefType::Map(map_type) => {
+                self.u8(22);
+                self.data_type(&map_type.0);
+                self.data_type(&map_type.1);
+            }
+            RefType::Set(data_type) => {
+                self.u8(23);
+                self.data_type(data_type);
+            }
+            RefType::Option(data_type) => {
+                self.u8(24);
+                self.data_type(data_type);
+            }
+            RefType::Generator(data_type) => {
+                self.u8(25);
+                self.data_type(data_type);
+            }
+            RefType::Task(data_type) => {
+                self.u8(26);
+                self.data_type(data_type);
+            }
+            RefType::Timer => self.u8(27),
+            RefType::Channel(data_type) => {
+                self.u8(28);
+                self.data_type(data_type);
+            }
+            RefType::Error => self.u8(29),
+            RefType::Host(_, name) => panic!("host type {} can't be written into image", name),
+        }
+    }
+    fn return_type(&mut self, return_type: &ReturnType) {
+        match return_type {
+            ReturnType::Void => self.u8(0),
+            ReturnType::Have(data_type) => {
+                self.u8(1);
+                self.data_type(data_type);
+            }
+        }
+    }
+    fn basic_type(&mut self, basic_type: BasicType) {
+        self.u8(match basic_type {
+            BasicType::Int => 0,
+            BasicType::Num => 1,
+            BasicType::Char => 2,
+            BasicType::Bool => 3,
+            BasicType::Ref => 4,
+        });
+    }
+
+    fn func(&mut self, func: &GloomFunc) {
+        let info = &func.info;
+        self.str(info.name.as_str());
+        self.len(info.params.len());
+        for param in info.params.iter() {
+            self.str(param.name.as_str());
+            self.data_type(&param.data_type);
+            self.u16(param.index.0);
+            self.u8(param.index.1);
+        }
+        self.return_type(&info.return_type);
+        self.len(info.captures.len());
+        for capture in info.captures.iter() {
+            self.u16(capture.from_slot_idx);
+            self.u8(capture.from_sub_idx);
+            self.u16(capture.to_slot_idx);
+            self.u8(capture.to_sub_idx);
+            self.basic_type(capture.basic_type);
+        }
+        self.u16_vec(&info.drop_slots);
+        self.bool(info.need_self);
+        self.bool(info.is_generator);
+        self.bool(info.is_async);
+        self.u16(info.file_index);
+        self.u16(info.local_size);
+        self.u16(info.stack_size);
+        self.len(info.lines.len());
+        for (pc, line) in info.lines.iter() {
+            self.u32(*pc);
+            self.u16(*line);
+        }
+        self.len(info.local_names.len());
+        for (slot_idx, sub_idx, name) in info.local_names.iter() {
+            self.u16(*slot_idx);
+            self.u8(*sub_idx);
+            self.str(name.as_str());
+        }
+        match &func.body {
+            // 接口中的抽象函数 abstract function in interface
+            FuncBody::None => self.u8(0),
+            FuncBody::ByteCodes(bytecodes) => {
+                self.u8(1);
+                self.len(bytecodes.len());
+                for bytecode in bytecodes.iter() {
+                    self.bytecode(*bytecode);
+                }
+            }
+            // 内置函数不会出现在用户定义的类型中 AST会在生成代码后被替换
+            // builtin functions never appear in user-defined types, AST is replaced after code generation
+            FuncBody::Builtin(_) | FuncBody::AST(_) | FuncBody::Jit(_) => panic!(),
+        }
+    }
+
+    fn interface(&mut self, interface: &Interface) {
+        self.len(interface.parents.len());
+        for parent in interface.parents.iter() {
+            self.u16(parent.inner().interface_index);
+        }
+        self.len(interface.funcs.len());
+        for func in interface.funcs.iter() {
+            self.func(&func.inner());
+        }
+        self.len(interface.map.len());
+        for (name, index) in interface.map.iter() {
+            self.str(name.as_str());
+            self.u16(*index);
+        }
+    }
+
+    fn class(&mut self, class: &GloomClass) {
+        match &class.parent {
+            None => self.bool(false),
+            Some(parent) => {
+                self.bool(true);
+                self.u16(parent.inner().class_index);
+            }
+        }
+        self.len(class.impls.len());
+        for interface_impl in class.impls.iter() {
+            self.u16(interface_impl.interface.inner().interface_index);
+            self.u16_vec(&interface_impl.fn_table);
+        }
+        self.len(class.map.len());
+        for (name, (slot_idx, sub_idx, is_pub, is_fn)) in class.map.iter() {
+            self.str(name.as_str());
+            self.u16(*slot_idx);
+            self.u8(*sub_idx);
+            self.bool(*is_pub);
+            self.bool(*is_fn);
+        }
+        let field_types = class.field_indexer.types();
+        self.len(field_types.len());
+        for data_type in field_types.iter() {
+            self.data_type(data_type);
+        }
+        self.len(class.funcs.len());
+        for func in class.funcs.iter() {
+            self.func(&func.inner());
+        }
+        self.u16(class.field_count);
+        self.u16(class.fn_drop_idx);
+        self.bool(class.is_filled);
+    }
+
+    fn enum_class(&mut self, enum_class: &GloomEnumClass) {
+        self.len(enum_class.types.len());
+        for (related_type, name) in enum_class.types.iter().zip(enum_class.names.iter()) {
+            self.str(name.as_str());
+            match related_type {
+                RelatedType::None => self.u8(0),
+                RelatedType::Have(data_type) => {
+                    self.u8(1);
+                    self.data_type(data_type);
+                }
+            }
+        }
+        self.len(enum_class.func_map.len());
+        for (name, (index, is_pub)) in enum_class.func_map.iter() {
+            self.str(name.as_str());
+            self.u16(*index);
+            self.bool(*is_pub);
+        }
+        self.len(enum_class.funcs.len());
+        for func in enum_class.funcs.iter() {
+            self.func(&func.inner());
+        }
+    }
+
+    fn pool(&mut self, pool: &ConstantPool) {
+        self.len(pool.int.len());
+        for int in pool.int.iter() {
+            self.i64(*int);
+        }
+        self.len(pool.num.len());
+        for num in pool.num.iter() {
+            self.f64(*num);
+        }
+        self.len(pool.str.len());
+        for str in pool.str.iter() {
+            self.str(str.downcast::<GloomString>().0.borrow().as_str());
+        }
+        self.len(pool.nameless_fn.len());
+        for func in pool.nameless_fn.iter() {
+            self.func(&func.inner());
+        }
+        self.len(pool.jump_table.len());
+        for jump_table in pool.jump_table.iter() {
+            self.len(jump_table.int_map.len());
+            for (key, label) in jump_table.int_map.iter() {
+                self.i64(*key);
+                self.u32(*label);
+            }
+            self.len(jump_table.str_map.len());
+            for (key, label) in jump_table.str_map.iter() {
+                self.str(key.as_str());
+                self.u32(*label);
+            }
+            self.u32(jump_table.default);
+        }
+        self.len(pool.try_table.len());
+        for try_block in pool.try_table.iter() {
+            self.u32(try_block.start);
+            self.u32(try_block.end);
+            self.u32(try_block.handler);
+        }
+    }
+
+    fn bytecode(&mut self, bytecode: ByteCode) {
+        match bytecode {
+            ByteCode::Pop => self.u8(0),
+            ByteCode::CopyTop => self.u8(1),
+            ByteCode::LoadConstString(idx) => {
+                self.u8(2);
+                self.u16(idx);
+            }
+            ByteCode::LoadConstInt(idx) => {
+                self.u8(3);
+                self.u16(idx);
+            }
+            ByteCode::LoadDirectInt(int) => {
+                self.u8(4);
+                self.u32(int as u32);
+            }
+            ByteCode::LoadDirectNum(num) => {
+                self.u8(5);
+                self.u32(num.to_bits());
+            }
+            ByteCode::LoadConstNum(idx) => {
+                self.u8(6);
+                self.u16(idx);
+            }
+            ByteCode::LoadDirectChar(ch) => {
+                self.u8(7);
+                self.u32(ch as u32);
+            }
+            ByteCode::LoadDirectBool(bl) => {
+                self.u8(8);
+                self.bool(bl);
+            }
+            ByteCode::LoadClass(idx) => {
+                self.u8(9);
+                self.u16(idx);
+            }
+            ByteCode::LoadEnum(idx) => {
+                self.u8(10);
+                self.u16(idx);
+            }
+            ByteCode::LoadBuiltinType(idx) => {
+                self.u8(11);
+                self.u16(idx);
+            }
+            ByteCode::ReadLocal(slot_idx, sub_idx) => {
+                self.u8(12);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteLocalInt(slot_idx, sub_idx) => {
+                self.u8(13);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteLocalNum(slot_idx, sub_idx) => {
+                self.u8(14);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteLocalChar(slot_idx, sub_idx) => {
+                self.u8(15);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteLocalBool(slot_idx, sub_idx) => {
+                self.u8(16);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteLocalRef(slot_idx) => {
+                self.u8(17);
+                self.u16(slot_idx);
+            }
+            ByteCode::ReadStatic(idx) => {
+                self.u8(18);
+                self.u16(idx);
+            }
+            ByteCode::JumpIfStaticInit { label, static_idx } => {
+                self.u8(19);
+                self.u32(label);
+                self.u16(static_idx);
+            }
+            ByteCode::WriteStaticInt(idx) => {
+                self.u8(20);
+                self.u16(idx);
+            }
+            ByteCode::WriteStaticNum(idx) => {
+                self.u8(21);
+                self.u16(idx);
+            }
+            ByteCode::WriteStaticChar(idx) => {
+                self.u8(22);
+                self.u16(idx);
+            }
+            ByteCode::WriteStaticBool(idx) => {
+                self.u8(23);
+                self.u16(idx);
+            }
+            ByteCode::WriteStaticRef(idx) => {
+                self.u8(24);
+                self.u16(idx);
+            }
+            ByteCode::ReadField(slot_idx, sub_idx) => {
+                self.u8(25);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::ReadFieldAndPop(slot_idx, sub_idx) => {
+                self.u8(26);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteFieldInt(slot_idx, sub_idx) => {
+                self.u8(27);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteFieldNum(slot_idx, sub_idx) => {
+                self.u8(28);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteFieldChar(slot_idx, sub_idx) => {
+                self.u8(29);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteFieldBool(slot_idx, sub_idx) => {
+                self.u8(30);
+                self.u16(slot_idx);
+                self.u8(sub_idx);
+            }
+            ByteCode::WriteFieldRef(slot_idx) => {
+                self.u8(31);
+                self.u16(slot_idx);
+            }
+            ByteCode::DropLocal(slot_idx) => {
+                self.u8(32);
+                self.u16(slot_idx);
+            }
+            ByteCode::NotOp => self.u8(33),
+            ByteCode::NegOp => self.u8(34),
+            ByteCode::Plus => self.u8(35),
+            ByteCode::Sub => self.u8(36),
+            ByteCode::Mul => self.u8(37),
+            ByteCode::Div => self.u8(38),
+            ByteCode::PlusOne => self.u8(39),
+            ByteCode::SubOne => self.u8(40),
+            ByteCode::GreaterThan => self.u8(41),
+            ByteCode::LessThan => self.u8(42),
+            ByteCode::GreaterThanEquals => self.u8(43),
+            ByteCode::LessThanEquals => self.u8(44),
+            ByteCode::Equals => self.u8(45),
+            ByteCode::NotEquals => self.u8(46),
+            ByteCode::LogicAnd => self.u8(47),
+            ByteCode::LogicOr => self.u8(48),
+            ByteCode::LoadDirectDefFn(idx) => {
+                self.u8(49);
+                self.u16(idx);
+            }
+            ByteCode::LoadNamelessFn(idx) => {
+                self.u8(50);
+                self.u16(idx);
+            }
+            ByteCode::CallTopFn { nargs } => {
+                self.u8(51);
+                self.u16(nargs);
+            }
+            ByteCode::CallStaticFn { index, nargs } => {
+                self.u8(52);
+                self.u16(index);
+                self.u16(nargs);
+            }
+            ByteCode::CallMethod { index, nargs } => {
+                self.u8(53);
+                self.u16(index);
+                self.u16(nargs);
+            }
+            ByteCode::CallMethodDyn {
+                interface_idx,
+                fn_idx,
+                nargs,
+            } => {
+                self.u8(54);
+                self.u16(interface_idx);
+                self.u16(fn_idx);
+                self.u16(nargs);
+            }
+            ByteCode::CollectTuple(len) => {
+                self.u8(55);
+                self.u16(len);
+            }
+            ByteCode::CollectArray(basic_type, len) => {
+                self.u8(56);
+                self.basic_type(basic_type);
+                self.u16(len);
+            }
+            ByteCode::CollectQueue(basic_type, len) => {
+                self.u8(57);
+                self.basic_type(basic_type);
+                self.u16(len);
+            }
+            ByteCode::RangeIter => self.u8(58),
+            ByteCode::InvokeIter => self.u8(59),
+            ByteCode::InvokeNext => self.u8(60),
+            ByteCode::AsInt => self.u8(61),
+            ByteCode::AsNum => self.u8(62),
+            ByteCode::AsChar => self.u8(63),
+            ByteCode::AsBool => self.u8(64),
+            ByteCode::AsRef => self.u8(65),
+            ByteCode::Construct(idx) => {
+                self.u8(66);
+                self.u16(idx);
+            }
+            ByteCode::ConstructEnum(tag) => {
+                self.u8(67);
+                self.u16(tag);
+            }
+            ByteCode::ConstructEnumWithVal(tag) => {
+                self.u8(68);
+                self.u16(tag);
+            }
+            ByteCode::ReadEnumVal => self.u8(69),
+            ByteCode::JumpIf(label) => {
+                self.u8(70);
+                self.u32(label);
+            }
+            ByteCode::JumpIfNot(label) => {
+                self.u8(71);
+                self.u32(label);
+            }
+            ByteCode::Jump(label) => {
+                self.u8(72);
+                self.u32(label);
+            }
+            ByteCode::JumpIfNone(label) => {
+                self.u8(73);
+                self.u32(label);
+            }
+            ByteCode::JumpTable(idx) => {
+                self.u8(74);
+                self.u16(idx);
+            }
+            ByteCode::EnterTry(idx) => {
+                self.u8(75);
+                self.u16(idx);
+            }
+            ByteCode::Throw => self.u8(76),
+            ByteCode::Return => self.u8(77),
+            ByteCode::CollectMap(len) => {
+                self.u8(78);
+                self.u16(len);
+            }
+            ByteCode::CollectSet(len) => {
+                self.u8(79);
+                self.u16(len);
+            }
+            ByteCode::ReadIndex => self.u8(80),
+            ByteCode::ReadIndexAndPop => self.u8(81),
+            ByteCode::WriteIndex => self.u8(82),
+            ByteCode::ReadSlice => self.u8(83),
+            ByteCode::Mod => self.u8(84),
+            ByteCode::Pow => self.u8(85),
+            ByteCode::BitAnd => self.u8(86),
+            ByteCode::BitOr => self.u8(87),
+            ByteCode::BitXor => self.u8(88),
+            ByteCode::BitNot => self.u8(89),
+            ByteCode::ShiftLeft => self.u8(90),
+            ByteCode::ShiftRight => self.u8(91),
+            ByteCode::Yield => self.u8(92),
+            ByteCode::Await => self.u8(93),
+            ByteCode::AwaitBlocking => self.u8(94),
+            ByteCode::Spawn => self.u8(95),
+            ByteCode::AwaitNext => self.u8(96),
+        }
+    }
+}
+
+struct ImageReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    status: GloomStatus,
+}
+
+impl<'a> ImageReader<'a> {
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ImageError> {
+        if self.pos + len > self.bytes.len() {
+            return Result::Err(ImageError::UnexpectedEnd);
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Result::Ok(slice)
+    }
+    #[inline]
+    fn u8(&mut self) -> Result<u8, ImageError> {
+        Result::Ok(self.take(1)?[0])
+    }
+    #[inline]
+    fn u16(&mut self) -> Result<u16, ImageError> {
+        let mut buf = [0u8; 2];
+        buf.copy_from_slice(self.take(2)?);
+        Result::Ok(u16::from_le_bytes(buf))
+    }
+    #[inline]
+    fn u32(&mut self) -> Result<u32, ImageError> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Result::Ok(u32::from_le_bytes(buf))
+    }
+    #[inline]
+    fn i64(&mut self) -> Result<i64, ImageError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Result::Ok(i64::from_le_bytes(buf))
+    }
+    #[inline]
+    fn f64(&mut self) -> Result<f64, ImageError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Result::Ok(f64::from_le_bytes(buf))
+    }
+    #[inline]
+    fn bool(&mut self) -> Result<bool, ImageError> {
+        Result::Ok(self.u8()? != 0)
+    }
+    #[inline]
+    fn len(&mut self) -> Result<usize, ImageError> {
+        Result::Ok(self.u32()? as usize)
+    }
+    // 长度来自文件 损坏的文件可能给出巨大的长度 每个元素至少占一个字节 所以容量不超过剩余的字节数
+    // the length comes from file, a corrupted file may give a huge length,
+    // each element takes one byte at least, so the capacity doesn't exceed the remaining bytes
+    #[inline]
+    fn vec<T>(&self, len: usize) -> Vec<T> {
+        Vec::with_capacity(len.min(self.bytes.len() - self.pos))
+    }
+    fn char(&mut self) -> Result<char, ImageError> {
+        char::from_u32(self.u32()?).ok_or(ImageError::InvalidString)
+    }
+    fn str(&mut self) -> Result<String, ImageError> {
+        let len = self.len()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| ImageError::InvalidString)
+    }
+    fn u16_vec(&mut self) -> Result<Vec<u16>, ImageError> {
+        let len = self.len()?;
+        let mut vec = self.vec(len);
+        for _ in 0..len {
+            vec.push(self.u16()?);
+        }
+        Result::Ok(vec)
+    }
+
+    fn class_ref(&mut self) -> Result<RefCount<GloomClass>, ImageError> {
+        let index = self.u16()?;
+        match self.status.classes.get(index as usize) {
+            Some(class) => Result::Ok(class.clone()),
+            None => Result::Err(ImageError::InvalidIndex {
+                what: "class",
+                index,
+            }),
+        }
+    }
+    fn enum_ref(&mut self) -> Result<RefCount<GloomEnumClass>, ImageError> {
+        let index = self.u16()?;
+        match self.status.enums.get(index as usize) {
+            Some(enum_class) => Result::Ok(enum_class.clone()),
+            None => Result::Err(ImageError::InvalidIndex {
+                what: "enum",
+                index,
+            }),
+        }
+    }
+    fn interface_ref(&mut self) -> Result<RefCount<Interface>, ImageError> {
+        let index = self.u16()?;
+        match self.status.interfaces.get(index as usize) {
+            Some(interface) => Result::Ok(interface.clone()),
+            None => Result::Err(ImageError::InvalidIndex {
+                what: "interface",
+                index,
+            }),
+        }
+    }
+
+    fn data_type(&mut self) -> Result<DataType, ImageError> {
+        Result::Ok(match self.u8()? {
+            0 => DataType::Int,
+            1 => DataType::Num,
+            2 => DataType::Char,
+            3 => DataType::Bool,
+            4 => DataType::Ref(self.ref_type()?),
+            tag => {
+                return Result::Err(ImageError::InvalidTag {
+                    what: "data type",
+                    tag,
+                })
+            }
+        })
+    }
+    fn ref_type(&mut self) -> Result<RefType, ImageError> {
+        Result::Ok(match self.u8()? {
+            0 => RefType::Any,
+            1 => RefType::None,
+            2 => RefType::MySelf,
+            3 => RefType::Class(self.class_ref()?),
+            4 => RefType::Enum(self.enum_ref()?),
+            5 => RefType::Interface(self.interface_ref()?),
+            6 => RefType::MetaClass(self.class_ref()?),
+            7 => RefType::MetaEnum(self.enum_ref()?),
+            8 => RefType::MetaInterface(self.interface_ref()?),
+            9 => RefType::MataBuiltinType(match self.u8()? {
+                0 => BuiltinType::Int,
+                1 => BuiltinType::Num,
+                2 => BuiltinType::Char,
+                3 => BuiltinType::Bool,
+                4 => BuiltinType::String,
+                5 => BuiltinType::Func,
+                6 => BuiltinType::Weak,
+                7 => BuiltinType::Array,
+                8 => BuiltinType::Queue,
+                9 => BuiltinType::Map,
+                10 => BuiltinType::Set,
+                11 => BuiltinType::Option,
+                12 => BuiltinType::Generator,
+                13 => BuiltinType::Task,
+                14 => BuiltinType::Timer,
+                15 => BuiltinType::Channel,
+                16 => BuiltinType::Error,
+                tag => {
+                    return Result::Err(ImageError::InvalidTag {
+                        what: "builtin type",
+                        tag,
+                    })
+                }
+            }),
+            10 => {
+                let len = self.len()?;
+                let mut types = self.vec(len);
+                for _ in 0..len {
+                    types.push(self.data_type()?);
+                }
+                RefType::Tuple(Box::new(types))
+            }
+            11 => {
+                let len = self.len()?;
+                let mut params = self.vec(len);
+                for _ in 0..len {
+                    params.push(self.data_type()?);
+                }
+                let return_type = self.return_type()?;
+                let any_ok = self.bool()?;
+                RefType::Func(Box::new((params, return_type, any_ok)))
+            }
+            12 => RefType::Weak(Box::new(self.data_type()?)),
+            13 => RefType::Array(Box::new(self.data_type()?)),
+            14 => RefType::Queue(Box::new(self.data_type()?)),
+            15 => RefType::Int,
+            16 => RefType::Num,
+            17 => RefType::Char,
+            18 => RefType::Bool,
+            19 => RefType::String,
+            20 => {
+                let index = self.u16()?;
+                let name = Rc::new(self.str()?);
+                let bound = if self.bool()? {
+                    Option::Some(self.interface_ref()?)
+                } else {
+                    Option::None
+                };
+                RefType::TypeParam(Box::new(TypeParam { index, name, bound }))
+            }
+            21 => {
+                let base = self.ref_type()?;
+                let len = self.len()?;
+                let mut args = self.vec(len);
+                for _ in 0..len {
+                    args.push(self.data_type()?);
+                }
+                RefType::Generic(Box::new((base, args)))
+            }
+            22 => {
+                let key = self.data_type()?;
+                let value = self.data_type()?;
+                RefType::Map(Box::new((key, value)))
+            }
+            23 => RefType::Set(Box::new(self.data_type()?)),
+            24 => RefType::Option(Box::new(self.data_type()?)),
+            25 => RefType::Generator(Box::new(self.data_type()?)),
+            26 => RefType::Task(Box::new(self.data_type()?)),
+            27 => RefType::Timer,
+            28 => RefType::Channel(Box::new(self.data_type()?)),
+            29 => RefType::Error,
+            tag => {
+                return Result::Err(ImageError::InvalidTag {
+                    what: "reference type",
+                    tag,
+                })
+            }
+        })
+    }
+    fn return_type(&mut self) -> Result<ReturnType, ImageError> {
+        Result::Ok(match self.u8()? {
+            0 => ReturnType::Void,
+            1 => ReturnType::Have(self.data_type()?),
+            tag => {
+                return Result::Err(ImageError::InvalidTag {
+                    what: "return type",
+                    tag,
+                })
+            }
+        })
+    }
+    fn basic_type(&mut self) -> Result<BasicType, ImageError> {
+        Result::Ok(match self.u8()? {
+            0 => BasicType::Int,
+            1 => BasicType::Num,
+            2 => BasicType::Char,
+            3 => BasicType::Bool,
+            4 => BasicType::Ref,
+            tag => {
+                return Result::Err(ImageError::InvalidTag {
+                    what: "basic type",
+                    tag,
+                })
+            }
+        })
+    }
+
+    fn func(&mut self) -> Result<GloomFunc, ImageError> {
+        let name = Rc::new(self.str()?);
+        let param_len = self.len()?;
+        let mut params = self.vec(param_len);
+        for _ in 0..param_len {
+            let name = Rc::new(self.str()?);
+            let data_type = self.data_type()?;
+            let mut param = Param::new(name, data_type);
+            param.index = (self.u16()?, self.u8()?);
+            params.push(param);
+        }
+        let return_type = self.return_type()?;
+        let capture_len = self.len()?;
+        let mut captures = self.vec(capture_len);
+        for _ in 0..capture_len {
+            captures.push(Capture::new(
+                self.u16()?,
+                self.u8()?,
+                self.u16()?,
+                self.u8()?,
+                self.basic_type()?,
+            ));
+        }
+        let drop_slots = self.u16_vec()?;
+        let need_self = self.bool()?;
+        let is_generator = self.bool()?;
+        let is_async = self.bool()?;
+        let file_index = self.u16()?;
+        let local_size = self.u16()?;
+        let stack_size = self.u16()?;
+        let line_len = self.len()?;
+        let mut lines = self.vec(line_len);
+        for _ in 0..line_len {
+            lines.push((self.u32()?, self.u16()?));
+        }
+        let name_len = self.len()?;
+        let mut local_names = self.vec(name_len);
+        for _ in 0..name_len {
+            local_names.push((self.u16()?, self.u8()?, self.str()?));
+        }
+        let body = match self.u8()? {
+            0 => FuncBody::None,
+            1 => {
+                let len = self.len()?;
+                let mut bytecodes = self.vec(len);
+                for _ in 0..len {
+                    bytecodes.push(self.bytecode()?);
+                }
+                FuncBody::ByteCodes(bytecodes)
+            }
+            tag => {
+                return Result::Err(ImageError::InvalidTag {
+                    what: "function body",
+                    tag,
+                })
+            }
+        };
+        Result::Ok(GloomFunc {
+            info: FuncInfo {
+                name,
+                params,
+                return_type,
+                captures,
+                drop_slots,
+                need_self,
+                is_generator,
+                is_async,
+                file_index,
+                local_size,
+                stack_size,
+                lines,
+                local_names,
+                generics: Vec::with_capacity(0),
+            },
+            body,
+        })
+    }
+
+    fn interface(&mut self, index: usize) -> Result<(), ImageError> {
+        let mut parents = Vec::new();
+        for _ in 0..self.len()? {
+            parents.push(self.interface_ref()?);
+        }
+        let mut funcs = Vec::new();
+        for _ in 0..self.len()? {
+            funcs.push(RefCount::new(self.func()?));
+        }
+        let mut map = HashMap::new();
+        for _ in 0..self.len()? {
+            let name = Rc::new(self.str()?);
+            map.insert(name, self.u16()?);
+        }
+        let mut interface = self.status.interfaces[index].inner_mut();
+        interface.parents = parents;
+        interface.funcs = funcs;
+        interface.map = map;
+        Result::Ok(())
+    }
+
+    fn class(&mut self, index: usize) -> Result<(), ImageError> {
+        let parent = if self.bool()? {
+            Option::Some(self.class_ref()?)
+        } else {
+            Option::None
+        };
+        let mut impls = Vec::new();
+        for _ in 0..self.len()? {
+            let interface = self.interface_ref()?;
+            let fn_table = self.u16_vec()?;
+            impls.push(InterfaceImpl {
+                interface,
+                fn_table,
+                args: Vec::with_capacity(0),
+            });
+        }
+        let mut map = HashMap::new();
+        for _ in 0..self.len()? {
+            let name = self.str()?;
+            let slot_idx = self.u16()?;
+            let sub_idx = self.u8()?;
+            let is_pub = self.bool()?;
+            let is_fn = self.bool()?;
+            map.insert(name, (slot_idx, sub_idx, is_pub, is_fn));
+        }
+        let mut field_types = Vec::new();
+        for _ in 0..self.len()? {
+            field_types.push(self.data_type()?);
+        }
+        let mut funcs = Vec::new();
+        for _ in 0..self.len()? {
+            funcs.push(RefCount::new(self.func()?));
+        }
+        let field_count = self.u16()?;
+        let fn_drop_idx = self.u16()?;
+        let is_filled = self.bool()?;
+        let mut class = self.status.classes[index].inner_mut();
+        class.parent = parent;
+        class.impls = impls;
+        class.map = map;
+        class.field_indexer = SlotIndexer::from_types(field_types);
+        class.funcs = funcs;
+        class.field_count = field_count;
+        class.fn_drop_idx = fn_drop_idx;
+        class.is_filled = is_filled;
+        Result::Ok(())
+    }
+
+    fn enum_class(&mut self, index: usize) -> Result<(), ImageError> {
+        let mut values = Vec::new();
+        for _ in 0..self.len()? {
+            let name = self.str()?;
+            let related_type = match self.u8()? {
+                0 => Option::None,
+                1 => Option::Some(self.data_type()?),
+                tag => {
+                    return Result::Err(ImageError::InvalidTag {
+                        what: "related type",
+                        tag,
+                    })
+                }
+            };
+            values.push((name, related_type));
+        }
+        let mut func_map = HashMap::new();
+        for _ in 0..self.len()? {
+            let name = self.str()?;
+            let index = self.u16()?;
+            func_map.insert(name, (index, self.bool()?));
+        }
+        let mut funcs = Vec::new();
+        for _ in 0..self.len()? {
+            funcs.push(RefCount::new(self.func()?));
+        }
+        let mut enum_class = self.status.enums[index].inner_mut();
+        for (name, related_type) in values.into_iter() {
+            enum_class.add_enum_value(name, related_type);
+        }
+        enum_class.func_map = func_map;
+        enum_class.funcs = funcs;
+        Result::Ok(())
+    }
+
+    fn pool(&mut self) -> Result<ConstantPool, ImageError> {
+        let mut pool = ConstantPool::new();
+        for _ in 0..self.len()? {
+            pool.int.push(self.i64()?);
+        }
+        for _ in 0..self.len()? {
+            pool.num.push(self.f64()?);
+        }
+        for _ in 0..self.len()? {
+            pool.str.push(GloomString::new(self.str()?));
+        }
+        for _ in 0..self.len()? {
+            pool.nameless_fn.push(RefCount::new(self.func()?));
+        }
+        for _ in 0..self.len()? {
+            let mut jump_table = JumpTable::new();
+            for _ in 0..self.len()? {
+                let key = self.i64()?;
+                jump_table.int_map.insert(key, self.u32()?);
+            }
+            for _ in 0..self.len()? {
+                let key = self.str()?;
+                jump_table.str_map.insert(key, self.u32()?);
+            }
+            jump_table.default = self.u32()?;
+            pool.jump_table.push(jump_table);
+        }
+        for _ in 0..self.len()? {
+            pool.try_table
+                .push(TryBlock::from(self.u32()?, self.u32()?, self.u32()?));
+        }
+        Result::Ok(pool)
+    }
+
+    fn bytecode(&mut self) -> Result<ByteCode, ImageError> {
+        Result::Ok(match self.u8()? {
+            0 => ByteCode::Pop,
+            1 => ByteCode::CopyTop,
+            2 => ByteCode::LoadConstString(self.u16()?),
+            3 => ByteCode::LoadConstInt(self.u16()?),
+            4 => ByteCode::LoadDirectInt(self.u32()? as i32),
+            5 => ByteCode::LoadDirectNum(f32::from_bits(self.u32()?)),
+            6 => ByteCode::LoadConstNum(self.u16()?),
+            7 => ByteCode::LoadDirectChar(self.char()?),
+            8 => ByteCode::LoadDirectBool(self.bool()?),
+            9 => ByteCode::LoadClass(self.u16()?),
+            10 => ByteCode::LoadEnum(self.u16()?),
+            11 => ByteCode::LoadBuiltinType(self.u16()?),
+            12 => ByteCode::ReadLocal(self.u16()?, self.u8()?),
+            13 => ByteCode::WriteLocalInt(self.u16()?, self.u8()?),
+            14 => ByteCode::WriteLocalNum(self.u16()?, self.u8()?),
+            15 => ByteCode::WriteLocalChar(self.u16()?, self.u8()?),
+            16 => ByteCode::WriteLocalBool(self.u16()?, self.u8()?),
+            17 => ByteCode::WriteLocalRef(self.u16()?),
+            18 => ByteCode::ReadStatic(self.u16()?),
+            19 => ByteCode::JumpIfStaticInit {
+                label: self.u32()?,
+                static_idx: self.u16()?,
+            },
+            20 => ByteCode::WriteStaticInt(self.u16()?),
+            21 => ByteCode::WriteStaticNum(self.u16()?),
+            22 => ByteCode::WriteStaticChar(self.u16()?),
+            23 => ByteCode::WriteStaticBool(self.u16()?),
+            24 => ByteCode::WriteStaticRef(self.u16()?),
+            25 => ByteCode::ReadField(self.u16()?, self.u8()?),
+            26 => ByteCode::ReadFieldAndPop(self.u16()?, self.u8()?),
+            27 => ByteCode::WriteFieldInt(self.u16()?, self.u8()?),
+            28 => ByteCode::WriteFieldNum(self.u16()?, self.u8()?),
+            29 => ByteCode::WriteFieldChar(self.u16()?, self.u8()?),
+            30 => ByteCode::WriteFieldBool(self.u16()?, self.u8()?),
+            31 => ByteCode::WriteFieldRef(self.u16()?),
+            32 => ByteCode::DropLocal(self.u16()?),
+            33 => ByteCode::NotOp,
+            34 => ByteCode::NegOp,
+            35 => ByteCode::Plus,
+            36 => ByteCode::Sub,
+            37 => ByteCode::Mul,
+            38 => ByteCode::Div,
+            39 => ByteCode::PlusOne,
+            40 => ByteCode::SubOne,
+            41 => ByteCode::GreaterThan,
+            42 => ByteCode::LessThan,
+            43 => ByteCode::GreaterThanEquals,
+            44 => ByteCode::LessThanEquals,
+            45 => ByteCode::Equals,
+            46 => ByteCode::NotEquals,
+            47 => ByteCode::LogicAnd,
+            48 => ByteCode::LogicOr,
+            49 => ByteCode::LoadDirectDefFn(self.u16()?),
+            50 => ByteCode::LoadNamelessFn(self.u16()?),
+            51 => ByteCode::CallTopFn { nargs: self.u16()? },
+            52 => ByteCode::CallStaticFn {
+                index: self.u16()?,
+                nargs: self.u16()?,
+            },
+            53 => ByteCode::CallMethod {
+                index: self.u16()?,
+                nargs: self.u16()?,
+            },
+            54 => ByteCode::CallMethodDyn {
+                interface_idx: self.u16()?,
+                fn_idx: self.u16()?,
+                nargs: self.u16()?,
+            },
+            55 => ByteCode::CollectTuple(self.u16()?),
+            56 => ByteCode::CollectArray(self.basic_type()?, self.u16()?),
+            57 => ByteCode::CollectQueue(self.basic_type()?, self.u16()?),
+            58 => ByteCode::RangeIter,
+            59 => ByteCode::InvokeIter,
+            60 => ByteCode::InvokeNext,
+            61 => ByteCode::AsInt,
+            62 => ByteCode::AsNum,
+            63 => ByteCode::AsChar,
+            64 => ByteCode::AsBool,
+            65 => ByteCode::AsRef,
+            66 => ByteCode::Construct(self.u16()?),
+            67 => ByteCode::ConstructEnum(self.u16()?),
+            68 => ByteCode::ConstructEnumWithVal(self.u16()?),
+            69 => ByteCode::ReadEnumVal,
+            70 => ByteCode::JumpIf(self.u32()?),
+            71 => ByteCode::JumpIfNot(self.u32()?),
+            72 => ByteCode::Jump(self.u32()?),
+            73 => ByteCode::JumpIfNone(self.u32()?),
+            74 => ByteCode::JumpTable(self.u16()?),
+            75 => ByteCode::EnterTry(self.u16()?),
+            76 => ByteCode::Throw,
+            77 => ByteCode::Return,
+            78 => ByteCode::CollectMap(self.u16()?),
+            79 => ByteCode::CollectSet(self.u16()?),
+            80 => ByteCode::ReadIndex,
+            81 => ByteCode::ReadIndexAndPop,
+            82 => ByteCode::WriteIndex,
+            83 => ByteCode::ReadSlice,
+            84 => ByteCode::Mod,
+            85 => ByteCode::Pow,
+            86 => ByteCode::BitAnd,
+            87 => ByteCode::BitOr,
+            88 => ByteCode::BitXor,
+            89 => ByteCode::BitNot,
+            90 => ByteCode::ShiftLeft,
+            91 => ByteCode::ShiftRight,
+            92 => ByteCode::Yield,
+            93 => ByteCode::Await,
+            94 => ByteCode::AwaitBlocking,
+            95 => ByteCode::Spawn,
+            96 => ByteCode::AwaitNext,
+            tag => {
+                return Result::Err(ImageError::InvalidTag {
+                    what: "bytecode",
+                    tag,
+                })
+            }
+        })
+    }
+}
+
+// 加载时校验字节码中的下标和跳转位置 损坏的镜像在运行前被拒绝 而不是使虚拟机崩溃
+// verify the indexes and labels in bytecodes when loading, a corrupted image is rejected before running rather than crashing the vm
+struct ImageVerifier<'a> {
+    status: &'a GloomStatus,
+    pool: &'a ConstantPool,
+    // 每个静态变量被写入时的类型 the type that every static variable is written as
+    statics: Vec<Option<BasicType>>,
+    // 所有类中最多的字段槽位数 the max count of field slots among all classes
+    field_len: usize,
+}
+
+impl<'a> ImageVerifier<'a> {
+    fn new(status: &'a GloomStatus, pool: &'a ConstantPool, static_len: u16) -> Self {
+        ImageVerifier {
+            status,
+            pool,
+            statics: vec![Option::None; static_len as usize],
+            field_len: status
+                .classes
+                .iter()
+                .map(|class| class.inner().field_indexer.size() as usize)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    fn verify(&mut self, static_drop_vec: &[u16]) -> Result<(), ImageError> {
+        for slot_idx in static_drop_vec.iter() {
+            Self::put(&mut self.statics, "static", *slot_idx, 0, BasicType::Ref)?;
+        }
+        let status = self.status;
+        for func in status.funcs.iter() {
+            self.func(&func.inner())?;
+        }
+        for script_body in status.script_bodies.iter() {
+            self.func(&script_body.inner().func)?;
+        }
+        for interface in status.interfaces.iter() {
+            for func in interface.inner().funcs.iter() {
+                self.func(&func.inner())?;
+            }
+        }
+        for class in status.classes.iter() {
+            let class = class.inner();
+            for func in class.funcs.iter() {
+                self.func(&func.inner())?;
+            }
+            for fn_table in class.impls.iter().map(|impl_table| &impl_table.fn_table) {
+                for fn_idx in fn_table.iter() {
+                    Self::index("function", *fn_idx, class.funcs.len())?;
+                }
+            }
+            if class.fn_drop_idx < u16::MAX {
+                Self::index("function", class.fn_drop_idx, class.funcs.len())?;
+            }
+        }
+        for enum_class in status.enums.iter() {
+            for func in enum_class.inner().funcs.iter() {
+                self.func(&func.inner())?;
+            }
+        }
+        for func in self.pool.nameless_fn.iter() {
+            self.func(&func.inner())?;
+        }
+        Result::Ok(())
+    }
+
+    fn func(&mut self, func: &GloomFunc) -> Result<(), ImageError> {
+        let bytecodes = match &func.body {
+            FuncBody::ByteCodes(bytecodes) => bytecodes,
+            _ => return Result::Ok(()),
+        };
+        let info = &func.info;
+        let len = bytecodes.len();
+        // 先收集局部变量槽位的类型 再检查读取 collect the types of local slots first, then check the reads
+        let mut locals: Vec<Option<BasicType>> = vec![Option::None; info.local_size as usize];
+        for param in info.params.iter() {
+            let (slot_idx, sub_idx) = param.index;
+            Self::put(
+                &mut locals,
+                "local slot",
+                slot_idx,
+                sub_idx,
+                param.data_type.as_basic(),
+            )?;
+        }
+        for capture in info.captures.iter() {
+            Self::put(
+                &mut locals,
+                "local slot",
+                capture.to_slot_idx,
+                capture.to_sub_idx,
+                capture.basic_type,
+            )?;
+        }
+        for slot_idx in info.drop_slots.iter() {
+            Self::put(&mut locals, "local slot", *slot_idx, 0, BasicType::Ref)?;
+        }
+        for code in bytecodes.iter() {
+            let (slot_idx, sub_idx, basic_type) = match *code {
+                ByteCode::WriteLocalInt(slot_idx, sub_idx) => (slot_idx, sub_idx, BasicType::Int),
+                ByteCode::WriteLocalNum(slot_idx, sub_idx) => (slot_idx, sub_idx, BasicType::Num),
+                ByteCode::WriteLocalChar(slot_idx, sub_idx) => (slot_idx, sub_idx, BasicType::Char),
+                ByteCode::WriteLocalBool(slot_idx, sub_idx) => (slot_idx, sub_idx, BasicType::Bool),
+                ByteCode::WriteLocalRef(slot_idx) | ByteCode::DropLocal(slot_idx) => {
+                    (slot_idx, 0, BasicType::Ref)
+                }
+                _ => continue,
+            };
+            Self::put(&mut locals, "local slot", slot_idx, sub_idx, basic_type)?;
+        }
+        for code in bytecodes.iter() {
+            match *code {
+                ByteCode::LoadConstString(idx) => Self::index("string", idx, self.pool.str.len())?,
+                ByteCode::LoadConstInt(idx) => Self::index("int", idx, self.pool.int.len())?,
+                ByteCode::LoadConstNum(idx) => Self::index("num", idx, self.pool.num.len())?,
+                ByteCode::LoadClass(idx) | ByteCode::Construct(idx) => {
+                    Self::index("class", idx, self.status.classes.len())?
+                }
+                ByteCode::LoadEnum(idx) => Self::index("enum", idx, self.status.enums.len())?,
+                ByteCode::LoadBuiltinType(idx) => {
+                    Self::index("builtin type", idx, self.status.builtin_classes.len())?
+                }
+                ByteCode::ReadLocal(slot_idx, sub_idx) => {
+                    Self::read(&locals, slot_idx, sub_idx)?;
+                }
+                // 字段所属的类在运行时才能确定 the class of field is known only at runtime
+                ByteCode::ReadField(_, sub_idx) | ByteCode::ReadFieldAndPop(_, sub_idx) => {
+                    Self::index("sub slot", sub_idx as u16, Self::sub_len(BasicType::Bool))?
+                }
+                ByteCode::WriteFieldInt(slot_idx, sub_idx) => {
+                    self.field(slot_idx, sub_idx, BasicType::Int)?
+                }
+                ByteCode::WriteFieldNum(slot_idx, sub_idx) => {
+                    self.field(slot_idx, sub_idx, BasicType::Num)?
+                }
+                ByteCode::WriteFieldChar(slot_idx, sub_idx) => {
+                    self.field(slot_idx, sub_idx, BasicType::Char)?
+                }
+                ByteCode::WriteFieldBool(slot_idx, sub_idx) => {
+                    self.field(slot_idx, sub_idx, BasicType::Bool)?
+                }
+                ByteCode::WriteFieldRef(slot_idx) => self.field(slot_idx, 0, BasicType::Ref)?,
+                ByteCode::ReadStatic(static_idx) => {
+                    Self::index("static", static_idx, self.statics.len())?
+                }
+                ByteCode::JumpIfStaticInit { label, static_idx } => {
+                    Self::index("static", static_idx, self.statics.len())?;
+                    Self::label(label, len)?;
+                }
+                ByteCode::WriteStaticInt(static_idx) => {
+                    Self::put(&mut self.statics, "static", static_idx, 0, BasicType::Int)?
+                }
+                ByteCode::WriteStaticNum(static_idx) => {
+                    Self::put(&mut self.statics, "static", static_idx, 0, BasicType::Num)?
+                }
+                ByteCode::WriteStaticChar(static_idx) => {
+                    Self::put(&mut self.statics, "static", static_idx, 0, BasicType::Char)?
+                }
+                ByteCode::WriteStaticBool(static_idx) => {
+                    Self::put(&mut self.statics, "static", static_idx, 0, BasicType::Bool)?
+                }
+                ByteCode::WriteStaticRef(static_idx) => {
+                    Self::put(&mut self.statics, "static", static_idx, 0, BasicType::Ref)?
+                }
+                ByteCode::LoadDirectDefFn(idx) => {
+                    Self::index("function", idx, self.status.funcs.len())?
+                }
+                // 闭包捕获的是当前函数的局部变量 the closure captures the local variables of current function
+                ByteCode::LoadNamelessFn(idx) => {
+                    Self::index("nameless function", idx, self.pool.nameless_fn.len())?;
+                    let nameless_fn = self.pool.nameless_fn[idx as usize].inner();
+                    for capture in nameless_fn.info.captures.iter() {
+                        Self::read(&locals, capture.from_slot_idx, capture.from_sub_idx)?;
+                    }
+                }
+                ByteCode::CallMethodDyn {
+                    interface_idx,
+                    fn_idx,
+                    ..
+                } => {
+                    Self::index("interface", interface_idx, self.status.interfaces.len())?;
+                    let interface = self.status.interfaces[interface_idx as usize].inner();
+                    Self::index("interface function", fn_idx, interface.funcs.len())?;
+                }
+                ByteCode::JumpIf(label)
+                | ByteCode::JumpIfNot(label)
+                | ByteCode::Jump(label)
+                | ByteCode::JumpIfNone(label) => Self::label(label, len)?,
+                ByteCode::JumpTable(idx) => {
+                    Self::index("jump table", idx, self.pool.jump_table.len())?;
+                    let table = &self.pool.jump_table[idx as usize];
+                    for label in table.int_map.values().chain(table.str_map.values()) {
+                        Self::label(*label, len)?;
+                    }
+                    Self::label(table.default, len)?;
+                }
+                ByteCode::EnterTry(idx) => {
+                    Self::index("try block", idx, self.pool.try_table.len())?;
+                    let try_block = &self.pool.try_table[idx as usize];
+                    Self::label(try_block.start, len)?;
+                    Self::label(try_block.end, len)?;
+                    Self::label(try_block.handler, len)?;
+                }
+                _ => {}
+            }
+        }
+        Result::Ok(())
+    }
+
+    #[inline]
+    fn field(&self, slot_idx: u16, sub_idx: u8, basic_type: BasicType) -> Result<(), ImageError> {
+        Self::index("field slot", slot_idx, self.field_len)?;
+        Self::index("sub slot", sub_idx as u16, Self::sub_len(basic_type))
+    }
+
+    #[inline]
+    fn index(what: &'static str, index: u16, len: usize) -> Result<(), ImageError> {
+        if (index as usize) < len {
+            Result::Ok(())
+        } else {
+            Result::Err(ImageError::InvalidIndex { what, index })
+        }
+    }
+
+    // 跳转到末尾表示结束执行 jumping to the end means finishing the execution
+    #[inline]
+    fn label(label: u32, len: usize) -> Result<(), ImageError> {
+        if label as usize <= len {
+            Result::Ok(())
+        } else {
+            Result::Err(ImageError::InvalidLabel { label })
+        }
+    }
+
+    // 一个槽位中能存放的值的个数 the count of values that one slot can hold
+    #[inline]
+    fn sub_len(basic_type: BasicType) -> usize {
+        match basic_type {
+            BasicType::Int | BasicType::Num => 2,
+            BasicType::Char => 4,
+            BasicType::Bool => 16,
+            BasicType::Ref => 1,
+        }
+    }
+
+    // 同一个槽位只能以一种类型写入 a slot can only be written as one type
+    fn put(
+        slots: &mut [Option<BasicType>],
+        what: &'static str,
+        slot_idx: u16,
+        sub_idx: u8,
+        basic_type: BasicType,
+    ) -> Result<(), ImageError> {
+        let slot = match slots.get_mut(slot_idx as usize) {
+            Some(slot) => slot,
+            None => {
+                return Result::Err(ImageError::InvalidIndex {
+                    what,
+                    index: slot_idx,
+                })
+            }
+        };
+        match slot {
+            Option::None => *slot = Option::Some(basic_type),
+            Option::Some(written) if *written as u8 == basic_type as u8 => {}
+            Option::Some(_) => {
+                return Result::Err(ImageError::InvalidIndex {
+                    what,
+                    index: slot_idx,
+                })
+            }
+        }
+        Self::index("sub slot", sub_idx as u16, Self::sub_len(basic_type))
+    }
+
+    // 从未被写入的槽位读取时得到 None reading from a slot that never written gets None
+    fn read(locals: &[Option<BasicType>], slot_idx: u16, sub_idx: u8) -> Result<(), ImageError> {
+        match locals.get(slot_idx as usize) {
+            Some(Option::Some(basic_type)) => {
+                Self::index("sub slot", sub_idx as u16, Self::sub_len(*basic_type))
+            }
+            Some(Option::None) => Result::Ok(()),
+            None => Result::Err(ImageError::InvalidIndex {
+                what: "local slot",
+                index: slot_idx,
+            }),
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod code;
+pub mod disasm;
 pub mod gen;
+pub mod image;
@@ -0,0 +1,395 @@
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::builtin::host::{HostClass, HostObject};
+use crate::bytecode::gen::CodeGenerator;
+use crate::frontend::analysis::Analyzer;
+use crate::frontend::diagnostic::Diagnostic;
+use crate::frontend::error::AnalysisError;
+use crate::frontend::import::Importer;
+use crate::frontend::parse::ParseError;
+use crate::obj::class::GloomClass;
+use crate::obj::func::{BuiltinFn, GloomFunc, Param, ReturnType};
+use crate::obj::gloom_enum::GloomEnum;
+use crate::obj::gloom_object::GloomObject;
+use crate::obj::object::ObjectType;
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 供Rust程序嵌入的解释器 先注册宿主函数和类 再编译脚本 最后调用脚本中的函数
+// interpreter embedded in rust program, register host functions and classes first,
+// then compile the script, call the functions in script at last
+pub struct Engine {
+    importer: RefCount<Importer>,
+    // 编译后被消耗 consumed after compiling
+    analyzer: Option<Analyzer>,
+    // 编译失败时在新的分析器上重放注册 replay the registrations on a new analyzer when compiling failed
+    registrations: Vec<Registration>,
+    vm: Option<GloomVM>,
+    funcs: HashMap<String, u16>,
+    // 编译时产生的警告 由宿主决定如何输出 warnings produced when compiling, the host decides how to output them
+    warnings: Vec<Diagnostic>,
+    enable_jit: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum EngineError {
+    #[error("failed to read {path} : {error}")]
+    Io { path: String, error: io::Error },
+
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+
+    #[error("{}", .0.iter().map(|diagnostic| diagnostic.to_string()).collect::<Vec<String>>().join("\n"))]
+    Analysis(Vec<Diagnostic>),
+
+    #[error("failed to generate code : {0}")]
+    Generate(String),
+
+    #[error("{}", .0.traceback())]
+    Runtime(GloomError),
+
+    #[error("the engine has compiled a script, it could only compile once")]
+    AlreadyCompiled,
+
+    #[error("the engine has compiled a script, host functions and classes must be registered before compiling")]
+    RegisterAfterCompiled,
+
+    #[error("the engine hasn't compiled any script")]
+    NotCompiled,
+
+    #[error("function {0} is not found in script")]
+    FuncNotFound(String),
+
+    #[error("mismatched args num of function {func}, expect {expect}, found {found}")]
+    MismatchedArgsNum {
+        func: String,
+        expect: usize,
+        found: usize,
+    },
+
+    #[error("mismatched type of arg {index} of function {func}, expect {expect}, found {found}")]
+    MismatchedArgType {
+        func: String,
+        index: usize,
+        expect: String,
+        found: String,
+    },
+}
+
+// 宿主注册的函数 类和方法 host registered functions, classes and methods
+enum Registration {
+    Func(HostFunc),
+    Class(String),
+    Method(HostClass, HostFunc),
+}
+
+struct HostFunc {
+    name: Rc<String>,
+    params: Vec<Param>,
+    return_type: ReturnType,
+    need_self: bool,
+    func: BuiltinFn,
+}
+
+impl HostFunc {
+    // 分析器会修改函数 每次注册都创建新的 analyzer modifies the function, create a new one for every registration
+    fn to_func(&self) -> GloomFunc {
+        GloomFunc::new_builtin_fn(
+            self.name.clone(),
+            self.params.clone(),
+            self.return_type.clone(),
+            self.need_self,
+            self.func.clone(),
+        )
+    }
+}
+
+impl Engine {
+    pub fn new(enable_jit: bool) -> Engine {
+        Engine {
+            importer: RefCount::new(Importer::new()),
+            analyzer: Option::Some(Analyzer::new()),
+            registrations: Vec::new(),
+            vm: Option::None,
+            funcs: HashMap::new(),
+            warnings: Vec::new(),
+            enable_jit,
+        }
+    }
+
+    // 注册一个宿主函数 脚本中可以直接调用它 返回的错误会像脚本中的异常一样被抛出
+    // register a host function, scripts can call it directly, the returned error is thrown like an exception in script
+    pub fn register_fn<F>(
+        &mut self,
+        name: &str,
+        params: Vec<Param>,
+        return_type: ReturnType,
+        func: F,
+    ) -> Result<u16, EngineError>
+    where
+        F: Fn(&GloomVM, GloomArgs) -> Result<Value, GloomError> + 'static,
+    {
+        let func = HostFunc {
+            name: Rc::new(name.to_string()),
+            params,
+            return_type,
+            need_self: false,
+            func: Rc::new(func),
+        };
+        let index = self.analyzer_mut()?.register_func(func.to_func());
+        self.registrations.push(Registration::Func(func));
+        Result::Ok(index)
+    }
+
+    // 注册一个宿主类 返回的HostClass用于声明方法和创建实例
+    // register a host class, the returned HostClass is used to declare methods and create instances
+    pub fn register_class(&mut self, name: &str) -> Result<HostClass, EngineError> {
+        let class = self.analyzer_mut()?.register_class(name);
+        self.registrations
+            .push(Registration::Class(name.to_string()));
+        Result::Ok(class)
+    }
+
+    // 实例方法的第一个参数self会被自动添加 the first param self of instance method is added automatically
+    pub fn register_method<F>(
+        &mut self,
+        class: &HostClass,
+        name: &str,
+        mut params: Vec<Param>,
+        return_type: ReturnType,
+        need_self: bool,
+        func: F,
+    ) -> Result<u16, EngineError>
+    where
+        F: Fn(&GloomVM, GloomArgs) -> Result<Value, GloomError> + 'static,
+    {
+        if need_self {
+            params.insert(
+                0,
+                Param::new(Rc::new(String::from("self")), class.data_type()),
+            );
+        }
+        let func = HostFunc {
+            name: Rc::new(name.to_string()),
+            params,
+            return_type,
+            need_self,
+            func: Rc::new(func),
+        };
+        let index = self.analyzer_mut()?.register_method(class, func.to_func());
+        self.registrations
+            .push(Registration::Method(class.clone(), func));
+        Result::Ok(index)
+    }
+
+    pub fn compile_file(&mut self, path: &str) -> Result<(), EngineError> {
+        let src = std::fs::read_to_string(path).map_err(|error| EngineError::Io {
+            path: path.to_string(),
+            error,
+        })?;
+        self.compile_str(src, path)
+    }
+
+    // 编译脚本并执行其中的语句 失败后引擎恢复到编译之前 可以再次编译
+    // compile the script and execute the statements in it,
+    // the engine is restored to the state before compiling after failure, and could compile again
+    pub fn compile_str(&mut self, src: String, name: &str) -> Result<(), EngineError> {
+        if self.analyzer.is_none() {
+            return Result::Err(EngineError::AlreadyCompiled);
+        }
+        let analyzer = self.analyzer.take().unwrap();
+        let result = self.compile(analyzer, src, name);
+        if result.is_err() {
+            self.restore();
+        }
+        result
+    }
+
+    fn compile(
+        &mut self,
+        mut analyzer: Analyzer,
+        src: String,
+        name: &str,
+    ) -> Result<(), EngineError> {
+        let parsed_file = Importer::import_str(src, name.to_string(), self.importer.clone())?;
+        // 分析器中的panic同样作为错误 panics in analyzer are also errors
+        let result = internal(|| analyzer.analysis(parsed_file, false));
+        self.warnings = analyzer.take_warnings();
+        match result {
+            Ok(result) => result.map_err(EngineError::Analysis)?,
+            Err(error) => {
+                let err = AnalysisError::Panicked(error.msg);
+                let diagnostic = Diagnostic::from_analysis(err, name, 0, Option::None);
+                return Result::Err(EngineError::Analysis(vec![diagnostic]));
+            }
+        }
+        self.funcs = analyzer.defined_funcs();
+        let (mut status, static_table) = analyzer.result();
+        let constant_pool = internal(|| CodeGenerator::new().generate(&mut status))
+            .map_err(|error| EngineError::Generate(error.msg))?;
+        let mut vm = GloomVM::new(static_table, constant_pool, status, self.enable_jit);
+        vm.run().map_err(EngineError::Runtime)?;
+        self.vm = Option::Some(vm);
+        Result::Ok(())
+    }
+
+    // 新的分析器按原顺序重放注册 得到的下标与之前相同
+    // the new analyzer replays the registrations in the original order, the indexes are the same as before
+    fn restore(&mut self) {
+        let mut analyzer = Analyzer::new();
+        for registration in self.registrations.iter() {
+            match registration {
+                Registration::Func(func) => {
+                    analyzer.register_func(func.to_func());
+                }
+                Registration::Class(name) => {
+                    analyzer.register_class(name);
+                }
+                Registration::Method(class, func) => {
+                    analyzer.register_method(class, func.to_func());
+                }
+            }
+        }
+        self.importer = RefCount::new(Importer::new());
+        self.analyzer = Option::Some(analyzer);
+        self.funcs.clear();
+    }
+
+    // 调用脚本中直接定义的函数 call the function directly defined in script
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, EngineError> {
+        let vm = match &self.vm {
+            Some(vm) => vm,
+            None => return Result::Err(EngineError::NotCompiled),
+        };
+        let func = match self.funcs.get(name) {
+            Some(index) => vm.status().funcs.get(*index as usize).unwrap().clone(),
+            None => return Result::Err(EngineError::FuncNotFound(name.to_string())),
+        };
+        let expect = func.inner().info.params.len();
+        if expect != args.len() {
+            return Result::Err(EngineError::MismatchedArgsNum {
+                func: name.to_string(),
+                expect,
+                found: args.len(),
+            });
+        }
+        for (index, (param, arg)) in func.inner().info.params.iter().zip(args.iter()).enumerate() {
+            if !value_belongs_to(arg, &param.data_type) {
+                return Result::Err(EngineError::MismatchedArgType {
+                    func: name.to_string(),
+                    index,
+                    expect: param.data_type.to_string(),
+                    found: format!("{:?}", arg),
+                });
+            }
+        }
+        match internal(|| vm.invoke(&func, GloomArgs::new(args))) {
+            Ok(result) => result.map_err(EngineError::Runtime),
+            Err(error) => Result::Err(EngineError::Runtime(error)),
+        }
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    #[inline]
+    fn analyzer_mut(&mut self) -> Result<&mut Analyzer, EngineError> {
+        match &mut self.analyzer {
+            Some(analyzer) => Result::Ok(analyzer),
+            None => Result::Err(EngineError::RegisterAfterCompiled),
+        }
+    }
+}
+
+// 宿主传入的参数没有经过分析器检查 只检查值的外层类型 容器的元素类型不检查
+// the args passed by host are not checked by analyzer, only the outer type of value is checked,
+// the element types of containers are not checked
+fn value_belongs_to(value: &Value, data_type: &DataType) -> bool {
+    let ref_type = data_type.as_ref_type();
+    let rf = match value {
+        Value::Int(_) | Value::Num(_) => {
+            return matches!(ref_type, RefType::Int | RefType::Num | RefType::Any)
+                || is_type_param(&ref_type)
+        }
+        Value::Char(_) => {
+            return matches!(ref_type, RefType::Char | RefType::Any) || is_type_param(&ref_type)
+        }
+        Value::Bool(_) => {
+            return matches!(ref_type, RefType::Bool | RefType::Any) || is_type_param(&ref_type)
+        }
+        Value::Ref(rf) => rf,
+        Value::None => return false,
+    };
+    let obj_type = rf.obj_type();
+    match ref_type {
+        RefType::Any | RefType::TypeParam(_) => true,
+        RefType::Int | RefType::Num => matches!(obj_type, ObjectType::Int | ObjectType::Num),
+        RefType::Char => matches!(obj_type, ObjectType::Char),
+        RefType::Bool => matches!(obj_type, ObjectType::Bool),
+        RefType::String => matches!(obj_type, ObjectType::String),
+        RefType::Class(class) => {
+            matches!(obj_type, ObjectType::Class)
+                && is_derived_from(rf.downcast::<GloomObject>().class.clone(), &class)
+        }
+        RefType::Enum(class) => {
+            matches!(obj_type, ObjectType::Enum) && rf.downcast::<GloomEnum>().class == class
+        }
+        RefType::Interface(_) => matches!(obj_type, ObjectType::Class | ObjectType::Enum),
+        RefType::Host(index, _) => {
+            matches!(obj_type, ObjectType::Host) && rf.downcast::<HostObject>().class.index == index
+        }
+        RefType::Generic(generic) => value_belongs_to(value, &DataType::Ref(generic.0)),
+        RefType::Tuple(_) => matches!(obj_type, ObjectType::Tuple),
+        RefType::Func(_) => matches!(obj_type, ObjectType::Func),
+        RefType::Weak(_) => matches!(obj_type, ObjectType::Weak),
+        RefType::Array(_) => matches!(obj_type, ObjectType::Array),
+        RefType::Queue(_) => matches!(obj_type, ObjectType::Queue),
+        RefType::Map(_) => matches!(obj_type, ObjectType::Map),
+        RefType::Set(_) => matches!(obj_type, ObjectType::Set),
+        RefType::Option(_) => matches!(obj_type, ObjectType::Option),
+        RefType::Generator(_) => matches!(obj_type, ObjectType::Generator),
+        RefType::Task(_) => matches!(obj_type, ObjectType::Task),
+        RefType::Timer => matches!(obj_type, ObjectType::Timer),
+        RefType::Channel(_) => matches!(obj_type, ObjectType::Channel),
+        RefType::Error => matches!(obj_type, ObjectType::Error),
+        RefType::MetaClass(_) => matches!(obj_type, ObjectType::MetaClass),
+        RefType::MetaEnum(_) => matches!(obj_type, ObjectType::MetaEnum),
+        RefType::MetaInterface(_) => matches!(obj_type, ObjectType::Interface),
+        RefType::MataBuiltinType(_) => matches!(obj_type, ObjectType::MetaBuiltinType),
+        RefType::None | RefType::MySelf => false,
+    }
+}
+
+#[inline]
+fn is_type_param(ref_type: &RefType) -> bool {
+    matches!(ref_type, RefType::TypeParam(_))
+}
+
+fn is_derived_from(mut class: RefCount<GloomClass>, expect: &RefCount<GloomClass>) -> bool {
+    loop {
+        if class.eq(expect) {
+            return true;
+        }
+        let parent = class.inner().parent.clone();
+        match parent {
+            Some(parent) => class = parent,
+            None => return false,
+        }
+    }
+}
+
+// 脚本的错误通过Result返回 panic只来自解释器内部的缺陷 它仍由宿主的panic hook打印 然后被转换为内部错误
+// errors of script are returned by Result, panics only come from the bugs inside interpreter,
+// they are still printed by the panic hook of host, and then converted into internal errors
+fn internal<T>(f: impl FnOnce() -> T) -> Result<T, GloomError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(GloomError::internal)
+}
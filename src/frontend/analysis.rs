@@ -1,33 +1,39 @@
 use std::borrow::{Borrow, BorrowMut};
+use std::cmp::Reverse;
 use std::ops::{Add, Deref, DerefMut};
 use std::option::Option::Some;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 
 use hashbrown::hash_map::Entry;
 use hashbrown::HashMap;
 
 use crate::frontend::ast::BlockType;
+use crate::frontend::diagnostic::Diagnostic;
 use crate::frontend::error::AnalysisError;
-use crate::frontend::ops::LeftValueOp;
-use crate::frontend::token::Token;
+use crate::frontend::ops::{BinOp, LeftValueOp};
+use crate::frontend::symbol::{Symbol, SymbolKind};
+use crate::frontend::token::{Span, Token};
 use crate::obj::func::FuncInfo;
+use crate::vm::error::GloomError;
 use crate::vm::static_table::{ListIndexer, StaticTable};
 use crate::{
     builtin::classes::BuiltinClass,
     builtin::funcs::{BuiltInFuncs, IsBuiltIn},
+    builtin::host::HostClass,
     frontend::{
         ast::*,
         index::SlotIndexer,
         ops::BinOpType,
         script::{ParsedFile, ScriptBody},
-        status::{GloomStatus, MetaType, TypeIndex},
+        status::{DeclCount, GloomStatus, MetaType, TypeIndex},
     },
     obj::class::{GloomClass, IsPub},
     obj::func::{Capture, FuncBody, GloomFunc, Param, ReturnType},
-    obj::gloom_enum::GloomEnumClass,
+    obj::gloom_enum::{GloomEnumClass, RelatedType},
     obj::interface::Interface,
     obj::refcount::RefCount,
-    obj::types::{BreakType, BuiltinType, DataType, DeclaredType, RefType},
+    obj::types::{BreakType, BuiltinType, DataType, DeclaredType, GenericParam, RefType},
 };
 
 pub struct Analyzer {
@@ -45,6 +51,18 @@ pub struct Analyzer {
     builtin_map: HashMap<BuiltinType, u16>,
     static_indexer: RefCount<ListIndexer>,
     paths: Vec<String>,
+    // 每个文件中每行第一条语句的范围 以文件索引为键 用于定位错误
+    // the span of the first statement at each line of each file, keyed by file index, used to locate errors
+    statement_spans: HashMap<u16, HashMap<Line, Span>>,
+    // 每个文件中标识符的范围 错误中提到的标识符作为错误的位置
+    // the spans of identifiers in each file, the identifier mentioned in error is the location of error
+    ident_spans: HashMap<u16, Vec<(Rc<String>, Span)>>,
+    // 分析时收集的错误 errors collected when analysing
+    diagnostics: RefCount<Vec<Diagnostic>>,
+    // 只在语言服务器中记录 only recorded in language server
+    symbols: Option<RefCount<Vec<Symbol>>>,
+    // 警告不会中止分析 由调用者决定如何输出 warnings don't abort analysis, the caller decides how to output them
+    warnings: RefCount<Vec<Diagnostic>>,
 }
 
 // 这些字段被存储到Analyzer而非GloomStatus中，这意味着我想要它们在运行前被丢弃。
@@ -52,7 +70,18 @@ pub struct Analyzer {
 type IsLocal = bool;
 
 impl Analyzer {
-    pub fn analysis(&mut self, mut script: ParsedFile, debug: bool) -> Result<(), AnalysisError> {
+    // 分析脚本 返回所有的错误 analyse the script, return all errors
+    pub fn analysis(&mut self, script: ParsedFile, debug: bool) -> Result<(), Vec<Diagnostic>> {
+        let result = self.analysis_script(script, debug);
+        self.diagnose(result)
+    }
+
+    fn analysis_script(
+        &mut self,
+        mut script: ParsedFile,
+        debug: bool,
+    ) -> Result<(), AnalysisError> {
+        let from = self.status.decl_count();
         // load types
         // 加载空的定义类型 load empty declared type : class interface and enum
         self.load_decl(&mut script)?;
@@ -62,15 +91,30 @@ impl Analyzer {
         self.status
             .script_bodies
             .sort_by(|b1, b2| b2.inner().file_index.cmp(&b1.inner().file_index));
+        self.analysis_decls(&from)?;
+        // script executable body
+        for script_body in self.status.script_bodies.iter() {
+            let script_body_rc = script_body.clone();
+            let mut script_body_ref = script_body_rc.inner_mut();
+            self.analysis_func(&mut script_body_ref.func, Option::None, DeclaredType::IsNot);
+        }
+        if debug {
+            println!("{:?}", self.status)
+        }
+        Result::Ok(())
+    }
+
+    // 分析from之后加载的声明 analysis the declarations loaded after from
+    fn analysis_decls(&mut self, from: &DeclCount) -> Result<(), AnalysisError> {
         // analysis interface, nothing need be filled
-        self.analysis_interfaces()?;
+        self.analysis_interfaces(from.interfaces)?;
         // fill fields and functions of class
-        self.fill_classes()?;
+        self.fill_classes(from.classes)?;
         // fill enum value declaration and functions of enum
-        self.fill_enums()?;
+        self.fill_enums(from.enums)?;
         // analysis and check executable code
         // functions in classes
-        for class in self.status.classes.iter() {
+        for class in self.status.classes[from.classes..].iter() {
             let class = class.clone();
             for func in class.inner().funcs.iter() {
                 let func = func.clone();
@@ -79,11 +123,11 @@ impl Analyzer {
                     &mut *func_ref,
                     Option::None,
                     DeclaredType::Class(class.clone()),
-                )?;
+                );
             }
         }
         // function in enums
-        for enum_class in self.status.enums.iter() {
+        for enum_class in self.status.enums[from.enums..].iter() {
             let enum_class = enum_class.clone();
             for func in enum_class.inner().funcs.iter() {
                 let func = func.clone();
@@ -92,23 +136,14 @@ impl Analyzer {
                     &mut *func_ref,
                     Option::None,
                     DeclaredType::Enum(enum_class.clone()),
-                )?;
+                );
             }
         }
         // functions that declared directly
-        for func in self.status.funcs.iter() {
+        for func in self.status.funcs[from.funcs..].iter() {
             let func = func.clone();
             let mut func_ref = func.inner_mut();
-            self.analysis_func(&mut *func_ref, Option::None, DeclaredType::IsNot)?;
-        }
-        // script executable body
-        for script_body in self.status.script_bodies.iter() {
-            let script_body_rc = script_body.clone();
-            let mut script_body_ref = script_body_rc.inner_mut();
-            self.analysis_func(&mut script_body_ref.func, Option::None, DeclaredType::IsNot)?;
-        }
-        if debug {
-            println!("{:?}", self.status)
+            self.analysis_func(&mut *func_ref, Option::None, DeclaredType::IsNot);
         }
         Result::Ok(())
     }
@@ -118,7 +153,7 @@ impl Analyzer {
         func: &mut GloomFunc,
         out_env: Option<&AnalyzeContext>,
         belonged_type: DeclaredType,
-    ) -> Result<(), AnalysisError> {
+    ) {
         let params = &mut func.info.params;
         let func_return_type = &func.info.return_type;
         let mut context = AnalyzeContext::new(
@@ -132,44 +167,57 @@ impl Analyzer {
                 .as_str(),
             out_env,
         );
-        // load param into symbol table and allocate local slot for parameters
-        for param in params.iter_mut() {
-            let param_name = &param.name;
-            let param_type = &param.data_type;
-            let (slot_idx, sub_idx) = context.indexer.put(param_type.clone());
-            param.index = (slot_idx, sub_idx);
-            match context.symbol_table.entry(param_name.deref().clone()) {
-                Entry::Vacant(entry) => {
-                    entry.insert((slot_idx, sub_idx, true));
-                }
-                Entry::Occupied(_) => {
-                    return Result::Err(AnalysisError::VarAlreadyOccupied {
+        context.generics = func.info.generics.clone();
+        // 生成器函数体没有返回值 交出的值是返回的生成器的类型实参
+        // generator body returns nothing, the yielded values are of the type argument of the returned generator
+        if func.info.is_generator {
+            context.func_return_type = ReturnType::Void;
+            context.yield_type = Option::Some(match &func.info.return_type {
+                ReturnType::Have(DataType::Ref(RefType::Generator(elem))) => elem.deref().clone(),
+                found => {
+                    let err = AnalysisError::NotGeneratorReturnType {
                         info: context.info(),
-                        symbol: param_name.deref().clone(),
-                    })
+                        found: found.clone(),
+                    };
+                    self.report(&context, err);
+                    DataType::Ref(RefType::Any)
                 }
-            };
+            });
+        }
+        // 异步函数体的返回值是返回的任务的结果 the return value of async function body is the result of returned task
+        if func.info.is_async {
+            context.func_return_type = func.info.return_type.task_result();
+            context.is_async = true;
+        }
+        // load param into symbol table and allocate local slot for parameters
+        for param in params.iter_mut() {
+            match context.declare_local(param.name.as_str(), param.data_type.clone()) {
+                Ok(index) => param.index = index,
+                Err(err) => self.report(&context, err),
+            }
         }
         // analysis per statement
         let body = &mut func.body;
         if let FuncBody::AST(body) = body {
             context.block_stack.push(BlockType::Func);
-            self.analysis_statements(&mut context, body)?;
+            if let Err(err) = self.analysis_statements(&mut context, body) {
+                self.report(&context, err);
+            }
             context.block_stack.pop();
         }
         func.info.captures = context.captures;
         func.info.local_size = context.indexer.size();
         func.info.drop_slots = context.indexer.basic_drop_vec();
-        Result::Ok(())
+        func.info.local_names = context.local_names;
     }
 
     #[inline]
     fn handle_left_value_op(
         &self,
         context: &mut AnalyzeContext,
-        left_val_tuple: &mut Box<(LeftValue, LeftValueOp)>,
+        left_val_tuple: &mut Box<(LeftValue, LeftValueOp, Line)>,
     ) -> Result<DataType, AnalysisError> {
-        let (left_val, left_val_op) = left_val_tuple.deref_mut();
+        let (left_val, left_val_op, _) = left_val_tuple.deref_mut();
         let left_val_type = match left_val {
             LeftValue::Var(var) => {
                 let var_name_ref = var.name().clone();
@@ -203,7 +251,44 @@ impl Analyzer {
                     },
                 }
             }
-            LeftValue::Chain(_, _) => DataType::Int,
+            LeftValue::Chain(expr, chains) => {
+                // 借用表达式链的分析 analysis as a chain expression
+                let mut chain_box = Box::new((
+                    std::mem::replace(expr, Expression::None),
+                    std::mem::take(chains),
+                ));
+                let result = self.handle_chains(context, &mut chain_box);
+                let (first, analyzed) = *chain_box;
+                *expr = first;
+                *chains = analyzed;
+                let (data_type, erased) = result?;
+                if erased && !data_type.as_basic().is_ref() {
+                    if let LeftValueOp::Assign(_) = left_val_op {
+                    } else {
+                        return Result::Err(AnalysisError::UnsupportedSyntax(format!(
+                            "{} calculate in place on field of generic type {}",
+                            context.info(),
+                            data_type
+                        )));
+                    }
+                }
+                data_type
+            }
+            LeftValue::Index(index_info) => {
+                let (container, index, basic_type) = index_info.deref_mut();
+                let container_type = self.deduce_type(container, context)?;
+                if let DataType::Ref(RefType::String) | DataType::Ref(RefType::Tuple(_)) =
+                    &container_type
+                {
+                    return Result::Err(AnalysisError::ImmutableElement {
+                        info: context.info(),
+                        typ: container_type,
+                    });
+                }
+                let elem_type = self.index_type(context, &container_type, index)?;
+                *basic_type = elem_type.as_basic();
+                elem_type
+            }
         };
         Result::Ok(match left_val_op {
             LeftValueOp::Assign(expr) => {
@@ -253,6 +338,15 @@ impl Analyzer {
                 }
                 left_val_type
             }
+            LeftValueOp::MulEq(expr) => {
+                self.check_calc_inplace(context, Token::MulEq, left_val_type, expr)?
+            }
+            LeftValueOp::DivEq(expr) => {
+                self.check_calc_inplace(context, Token::DivEq, left_val_type, expr)?
+            }
+            LeftValueOp::ModEq(expr) => {
+                self.check_calc_inplace(context, Token::ModEq, left_val_type, expr)?
+            }
             LeftValueOp::PlusOne => {
                 if !left_val_type.is_int_or_num() {
                     return Result::Err(AnalysisError::CalcInplaceLeftMismatchedType {
@@ -276,20 +370,68 @@ impl Analyzer {
         })
     }
 
+    // 整数的左值只能与整数计算 left value of int can only be calculated with int
+    fn check_calc_inplace(
+        &self,
+        context: &mut AnalyzeContext,
+        ops: Token,
+        left_val_type: DataType,
+        expr: &mut Expression,
+    ) -> Result<DataType, AnalysisError> {
+        if !left_val_type.is_int_or_num() {
+            return Result::Err(AnalysisError::CalcInplaceLeftMismatchedType {
+                info: context.info(),
+                ops,
+                found: left_val_type,
+            });
+        }
+        let expr_type = self.deduce_type(expr, context)?;
+        if !expr_type.is_int_or_num() || (left_val_type.is_int() && !expr_type.is_int()) {
+            return Result::Err(AnalysisError::CalcInplaceRightMismatchedType {
+                info: context.info(),
+                ops,
+                found: expr_type,
+            });
+        }
+        Result::Ok(left_val_type)
+    }
+
     fn handle_chains(
         &self,
         context: &mut AnalyzeContext,
         chains: &mut Box<(Expression, Vec<Chain>)>,
-    ) -> Result<DataType, AnalysisError> {
+    ) -> Result<(DataType, bool), AnalysisError> {
         let (expr, chain_vec) = chains.deref_mut();
         let mut expr_type = self.deduce_type(expr, context)?;
         let mut new_type = DataType::Ref(RefType::None);
+        // 结果的原始类型是否是被擦除的类型参数 whether the raw type of result is an erased type parameter
+        let mut erased = false;
         let chains_len = chain_vec.len();
         for (chain_idx, chain) in chain_vec.iter_mut().enumerate() {
+            // 枚举值 enum value, such as Color.Red or Result.Ok(x)
+            if let DataType::Ref(RefType::MetaEnum(class)) = &expr_type {
+                let class = class.clone();
+                if let Option::Some(enum_value) = self.handle_enum_value(context, &class, chain)? {
+                    *chain = enum_value;
+                    expr_type = DataType::Ref(RefType::Enum(class));
+                    continue;
+                }
+            }
+            let member_name = match chain {
+                Chain::Access(VarId::Name(name), _)
+                | Chain::FnCall {
+                    func: VarId::Name(name),
+                    ..
+                } => Option::Some(name.clone()),
+                _ => Option::None,
+            };
+            let mut member_type = Option::None;
+            let (owner_type, type_args) = Self::generic_owner(context, &expr_type);
+            erased = false;
             match chain {
                 Chain::Access(field, basic_type) => {
                     let field_name = field.name();
-                    match &expr_type {
+                    match &owner_type {
                         // find field
                         DataType::Ref(RefType::Class(class)) => {
                             match class.inner().map.get(field_name.as_str()) {
@@ -298,9 +440,12 @@ impl Analyzer {
                                         && (*is_pub || context.belonged_type.equal_class(class))
                                     {
                                         *field = VarId::Index(*slot_idx, *sub_idx);
-                                        new_type =
-                                            class.inner().field_indexer.get_type(*slot_idx).clone();
-                                        *basic_type = new_type.as_basic();
+                                        let class_ref = class.inner();
+                                        let field_type =
+                                            class_ref.field_indexer.get_type(*slot_idx);
+                                        *basic_type = field_type.as_basic();
+                                        erased = field_type.is_type_param();
+                                        new_type = field_type.substitute(&type_args);
                                     } else {
                                         return Result::Err(AnalysisError::UnknownField {
                                             info: context.info(),
@@ -431,9 +576,34 @@ impl Analyzer {
                         }
                     }
                 }
-                Chain::Call(args) => match &expr_type {
+                // Chain::EnumValue 只由 handle_enum_value 生成  only generated by handle_enum_value
+                Chain::EnumValue(_, _) => panic!(),
+                Chain::Index(index, basic_type) => {
+                    let elem_type = self.index_type(context, &expr_type, index)?;
+                    *basic_type = elem_type.as_basic();
+                    // 容器中基本类型的元素可能是装箱的值 element of basic type in container may be a boxed value
+                    erased = true;
+                    new_type = elem_type;
+                }
+                Chain::Slice(range) => {
+                    let (from, to) = range.deref_mut();
+                    new_type = self.slice_type(context, &expr_type, from, to)?;
+                }
+                Chain::Call(args, _) => match &expr_type {
                     DataType::Ref(RefType::Func(func_type)) => {
                         let (param_types, return_type, _) = func_type.deref();
+                        // 直接调用泛型函数时推断类型实参 infer type arguments when calling generic function directly
+                        let (generics, func_name) = match expr {
+                            Expression::Var(var) if chain_idx == 0 => match var.deref().deref() {
+                                Var::DirectFn(index) => {
+                                    let func = self.status.funcs.get(*index as usize).unwrap();
+                                    let func = func.inner();
+                                    (func.info.generics.clone(), func.info.name.to_string())
+                                }
+                                _ => (Vec::with_capacity(0), "".to_string()),
+                            },
+                            _ => (Vec::with_capacity(0), "".to_string()),
+                        };
                         if param_types.len() != args.len() {
                             return Result::Err(AnalysisError::MismatchedArgsNum {
                                 info: context.info(),
@@ -443,16 +613,29 @@ impl Analyzer {
                                 found: args.len(),
                             });
                         }
-                        for (arg_idx, (arg, param_type)) in
-                            args.iter_mut().zip(param_types.iter()).enumerate()
+                        let mut arg_types = Vec::with_capacity(args.len());
+                        for arg in args.iter_mut() {
+                            arg_types.push(self.deduce_type(arg, context)?);
+                        }
+                        let mut type_args = Vec::with_capacity(0);
+                        if !generics.is_empty() {
+                            let mut inferred = vec![Option::None; generics.len()];
+                            for (arg_type, param_type) in arg_types.iter().zip(param_types.iter()) {
+                                param_type.infer(arg_type, &mut inferred);
+                            }
+                            type_args = self
+                                .complete_generics(context, &func_name, &generics, 0, inferred)?;
+                        }
+                        for (arg_idx, (arg_type, param_type)) in
+                            arg_types.into_iter().zip(param_types.iter()).enumerate()
                         {
-                            let arg_type = self.deduce_type(arg, context)?;
-                            if !arg_type.belong_to(param_type) {
+                            let param_type = param_type.substitute(&type_args);
+                            if !arg_type.belong_to(&param_type) {
                                 return Result::Err(AnalysisError::MismatchedArgType {
                                     info: context.info(),
                                     idx: arg_idx,
-                                    func: "".to_string(),
-                                    expect: param_type.clone(),
+                                    func: func_name,
+                                    expect: param_type,
                                     found: arg_type,
                                 });
                             }
@@ -462,12 +645,13 @@ impl Analyzer {
                                 if chain_idx != chains_len - 1 {
                                     return Result::Err(AnalysisError::ChainAfterVoid {
                                         info: context.info(),
-                                        func: "".to_string(),
+                                        func: func_name,
                                     });
                                 }
                             }
                             ReturnType::Have(data_type) => {
-                                new_type = data_type.clone();
+                                erased = data_type.is_type_param();
+                                new_type = data_type.substitute(&type_args);
                             }
                         }
                     }
@@ -483,10 +667,11 @@ impl Analyzer {
                     need_self,
                     args,
                     is_dyn,
+                    ..
                 } => {
                     let func_name = func.name();
                     let function: RefCount<GloomFunc>;
-                    match &expr_type {
+                    match &owner_type {
                         DataType::Ref(ref_type) => {
                             match ref_type {
                                 // caller is object, call member function
@@ -818,6 +1003,14 @@ impl Analyzer {
                                 RefType::Any | RefType::None | RefType::MySelf => {
                                     panic!()
                                 }
+                                // 没有约束的类型参数 type parameter without bound
+                                RefType::TypeParam(_) | RefType::Generic(_) => {
+                                    return Result::Err(AnalysisError::FuncNotFound {
+                                        info: context.info(),
+                                        func: func_name.to_string(),
+                                        typ: ref_type.to_string(),
+                                    })
+                                }
                                 // non-static function
                                 builtin_type => {
                                     let builtin_type = builtin_type.as_built_type();
@@ -899,38 +1092,376 @@ impl Analyzer {
                         }
                     }
                     let function = function.inner();
+                    let generics = &function.info.generics;
+                    // 所属类型的类型实参由调用者给出 函数自己的由实参推断
+                    // type arguments of belonged type are given by caller, the function's own are inferred by args
+                    let outer_count = match &owner_type {
+                        DataType::Ref(RefType::Class(class))
+                        | DataType::Ref(RefType::MetaClass(class)) => class.inner().generics.len(),
+                        DataType::Ref(RefType::Interface(interface)) => {
+                            interface.inner().generics.len()
+                        }
+                        _ => type_args.len(),
+                    };
                     let mut param_iter = function.info.params.iter();
                     if *need_self {
                         let self_type = match param_iter.next() {
-                            Some(param) => &param.data_type,
+                            Some(param) => param.data_type.substitute(&type_args),
                             None => panic!(),
                         };
                         if !expr_type.belong_to(&self_type) {
                             return Result::Err(AnalysisError::MismatchedSelfType {
                                 info: context.info(),
                                 func: function.info.name.to_string(),
-                                expect: self_type.clone(),
+                                expect: self_type,
                                 found: expr_type.clone(),
                             });
                         }
                     }
-                    for (idx, (arg_expr, param)) in args.iter_mut().zip(param_iter).enumerate() {
-                        let arg_type = self.deduce_type(arg_expr, context)?;
-                        if !arg_type.belong_to(&param.data_type) {
+                    let mut inferred = vec![Option::None; generics.len()];
+                    for (arg, type_arg) in inferred.iter_mut().zip(type_args) {
+                        *arg = Option::Some(type_arg);
+                    }
+                    let mut arg_types = Vec::with_capacity(args.len());
+                    for arg_expr in args.iter_mut() {
+                        arg_types.push(self.deduce_type(arg_expr, context)?);
+                    }
+                    let mut type_args = Vec::with_capacity(0);
+                    if !generics.is_empty() {
+                        for (arg_type, param) in arg_types.iter().zip(param_iter.clone()) {
+                            param.data_type.infer(arg_type, &mut inferred);
+                        }
+                        type_args = self.complete_generics(
+                            context,
+                            &func_name,
+                            generics,
+                            outer_count,
+                            inferred,
+                        )?;
+                    }
+                    for (idx, (arg_type, param)) in
+                        arg_types.into_iter().zip(param_iter).enumerate()
+                    {
+                        let param_type = param.data_type.substitute(&type_args);
+                        if !arg_type.belong_to(&param_type) {
                             return Result::Err(AnalysisError::MismatchedArgType {
                                 info: context.info(),
                                 idx,
                                 func: func_name.to_string(),
-                                expect: param.data_type.clone(),
+                                expect: param_type,
                                 found: arg_type,
                             });
                         }
                     }
+                    member_type = Option::Some(function.get_type().substitute(&type_args));
+                    if let ReturnType::Have(return_type) = &function.info.return_type {
+                        erased = return_type.is_type_param();
+                        new_type = return_type.substitute(&type_args);
+                    }
+                    // 只有引用类型的对象可以被弱引用 only object of reference type could be weakly referenced
+                    if let DataType::Ref(RefType::Weak(object)) = &new_type {
+                        if !matches!(object.deref(), DataType::Ref(_)) {
+                            return Result::Err(AnalysisError::WeakOfBasicType {
+                                info: context.info(),
+                                typ: object.deref().clone(),
+                            });
+                        }
+                    }
                 }
             };
+            if let Some(name) = member_name {
+                let data_type = member_type.unwrap_or_else(|| new_type.clone());
+                self.record(context, name, &data_type, SymbolKind::Member(expr_type));
+            }
             expr_type = std::mem::replace(&mut new_type, DataType::Ref(RefType::None));
         }
-        Result::Ok(expr_type)
+        Result::Ok((expr_type, erased))
+    }
+
+    // 下标访问的元素类型 element type of subscript access
+    fn index_type(
+        &self,
+        context: &mut AnalyzeContext,
+        container: &DataType,
+        index: &mut Expression,
+    ) -> Result<DataType, AnalysisError> {
+        let (expect, elem_type) = match container {
+            DataType::Ref(RefType::Array(elem)) | DataType::Ref(RefType::Queue(elem)) => {
+                (DataType::Int, elem.deref().clone())
+            }
+            DataType::Ref(RefType::String) => (DataType::Int, DataType::Char),
+            DataType::Ref(RefType::Map(map_type)) => (map_type.0.clone(), map_type.1.clone()),
+            // 元组的元素类型由字面量下标确定 element type of tuple is determined by the literal index
+            DataType::Ref(RefType::Tuple(types)) => {
+                return match index {
+                    Expression::Int(idx) if *idx >= 0 && (*idx as usize) < types.len() => {
+                        Result::Ok(types[*idx as usize].clone())
+                    }
+                    _ => Result::Err(AnalysisError::BadTupleIndex {
+                        info: context.info(),
+                        typ: container.clone(),
+                    }),
+                };
+            }
+            _ => {
+                return Result::Err(AnalysisError::CannotIndex {
+                    info: context.info(),
+                    typ: container.clone(),
+                })
+            }
+        };
+        let index_type = self.deduce_type(index, context)?;
+        if !index_type.belong_to(&expect) {
+            return Result::Err(AnalysisError::MismatchedIndexType {
+                info: context.info(),
+                typ: container.clone(),
+                expect,
+                found: index_type,
+            });
+        }
+        Result::Ok(elem_type)
+    }
+
+    // 切片的类型与容器相同 type of slice is the same as the container
+    fn slice_type(
+        &self,
+        context: &mut AnalyzeContext,
+        container: &DataType,
+        from: &mut Expression,
+        to: &mut Expression,
+    ) -> Result<DataType, AnalysisError> {
+        match container {
+            DataType::Ref(RefType::Array(_))
+            | DataType::Ref(RefType::Queue(_))
+            | DataType::Ref(RefType::String) => {}
+            _ => {
+                return Result::Err(AnalysisError::CannotSlice {
+                    info: context.info(),
+                    typ: container.clone(),
+                })
+            }
+        }
+        for bound in [from, to] {
+            let bound_type = self.deduce_type(bound, context)?;
+            if !bound_type.belong_to(&DataType::Int) {
+                return Result::Err(AnalysisError::MismatchedIndexType {
+                    info: context.info(),
+                    typ: container.clone(),
+                    expect: DataType::Int,
+                    found: bound_type,
+                });
+            }
+        }
+        Result::Ok(container.clone())
+    }
+
+    // 数组字面量按标记的类型创建数组或队列 空的字面量使用标记的元素类型 返回是否是空的字面量
+    // array literal creates array or queue by the type mark, empty literal uses the element type of mark,
+    // return whether it is an empty literal
+    fn mark_array_literal(expr: &mut Expression, mark: &DataType) -> bool {
+        if let Expression::Array(array) = expr {
+            let (array, basic_type, is_queue) = array.deref_mut();
+            let elem_type = match mark {
+                DataType::Ref(RefType::Array(elem_type)) => elem_type,
+                DataType::Ref(RefType::Queue(elem_type)) => {
+                    *is_queue = true;
+                    elem_type
+                }
+                _ => return false,
+            };
+            if array.is_empty() {
+                *basic_type = elem_type.as_basic();
+                return true;
+            }
+        }
+        false
+    }
+
+    // 容器字面量的元素类型 类型不一致时为Any
+    // element type of container literal, Any if the types are different
+    fn deduce_elem_type(
+        &self,
+        context: &mut AnalyzeContext,
+        exprs: Vec<&mut Expression>,
+    ) -> Result<DataType, AnalysisError> {
+        let mut elem_type = Option::None;
+        for (idx, expr) in exprs.into_iter().enumerate() {
+            let data_type = self.deduce_type(expr, context)?;
+            if data_type.is_none() {
+                return Result::Err(AnalysisError::VoidInArray {
+                    info: context.info(),
+                    no: idx,
+                    elem: format!("{:?}", expr),
+                });
+            }
+            elem_type = match elem_type {
+                Some(elem_type) if elem_type != data_type => {
+                    Option::Some(DataType::Ref(RefType::Any))
+                }
+                Some(elem_type) => Option::Some(elem_type),
+                None => Option::Some(data_type),
+            };
+        }
+        Result::Ok(elem_type.unwrap_or(DataType::Ref(RefType::Any)))
+    }
+
+    // 泛型类型的成员按其原始类型查找 类型参数按其约束查找 返回查找成员的类型和类型实参
+    // members of generic type are found by its raw type, and type parameter is found as its bound,
+    // return the type to find members and the type arguments
+    fn generic_owner(context: &AnalyzeContext, data_type: &DataType) -> (DataType, Vec<DataType>) {
+        match data_type {
+            DataType::Ref(RefType::Generic(generic)) => {
+                (DataType::Ref(generic.0.clone()), generic.1.clone())
+            }
+            DataType::Ref(RefType::TypeParam(param)) => {
+                let bound = context
+                    .generics
+                    .get(param.index as usize)
+                    .filter(|generic| generic.name.eq(&param.name))
+                    .and_then(|generic| generic.bound.clone());
+                match (bound, &param.bound) {
+                    (Some(bound), _) => Self::generic_owner(context, &bound),
+                    (None, Some(interface)) => (
+                        DataType::Ref(RefType::Interface(interface.clone())),
+                        Vec::with_capacity(0),
+                    ),
+                    (None, None) => (data_type.clone(), Vec::with_capacity(0)),
+                }
+            }
+            // 内置容器的元素类型是其方法的第一个类型实参
+            // element type of builtin container is the first type argument of its methods
+            DataType::Ref(RefType::Array(elem))
+            | DataType::Ref(RefType::Queue(elem))
+            | DataType::Ref(RefType::Set(elem))
+            | DataType::Ref(RefType::Option(elem))
+            | DataType::Ref(RefType::Weak(elem))
+            | DataType::Ref(RefType::Generator(elem))
+            | DataType::Ref(RefType::Task(elem))
+            | DataType::Ref(RefType::Channel(elem)) => {
+                (data_type.clone(), vec![elem.deref().clone()])
+            }
+            DataType::Ref(RefType::Map(map_type)) => {
+                let (key, value) = map_type.deref();
+                (data_type.clone(), vec![key.clone(), value.clone()])
+            }
+            _ => (data_type.clone(), Vec::with_capacity(0)),
+        }
+    }
+
+    // 补全类型实参并检查约束 未推断出的外层类型参数保持不变
+    // complete type arguments and check the bounds, the outer type parameters not inferred are kept
+    fn complete_generics(
+        &self,
+        context: &AnalyzeContext,
+        name: &str,
+        generics: &[GenericParam],
+        outer_count: usize,
+        inferred: Vec<Option<DataType>>,
+    ) -> Result<Vec<DataType>, AnalysisError> {
+        let mut args = Vec::with_capacity(generics.len());
+        let mut need_check = Vec::with_capacity(generics.len());
+        for (idx, (param, arg)) in generics.iter().zip(inferred).enumerate() {
+            match arg {
+                Some(arg) => {
+                    args.push(arg);
+                    need_check.push(true);
+                }
+                None if idx < outer_count => {
+                    args.push(param.as_data_type(idx as u16));
+                    need_check.push(false);
+                }
+                None => {
+                    return Result::Err(AnalysisError::CannotInferGeneric {
+                        info: context.info(),
+                        param: param.name.to_string(),
+                        name: name.to_string(),
+                    })
+                }
+            }
+        }
+        for ((param, arg), need_check) in generics.iter().zip(args.iter()).zip(need_check) {
+            if let (Some(bound), true) = (&param.bound, need_check) {
+                let bound = bound.substitute(&args);
+                if !arg.belong_to(&bound) {
+                    return Result::Err(AnalysisError::UnsatisfiedBound {
+                        info: context.info(),
+                        param: param.name.to_string(),
+                        bound,
+                        found: arg.clone(),
+                    });
+                }
+            }
+        }
+        Result::Ok(args)
+    }
+
+    // 若chain指向一个枚举值，则返回替换后的 Chain::EnumValue，否则返回None，作为静态函数处理
+    // if the chain refers to an enum value, return Chain::EnumValue to replace it, otherwise return None and handle it as static function
+    fn handle_enum_value(
+        &self,
+        context: &mut AnalyzeContext,
+        class: &RefCount<GloomEnumClass>,
+        chain: &mut Chain,
+    ) -> Result<Option<Chain>, AnalysisError> {
+        let (name, args) = match chain {
+            Chain::Access(field, _) => (field.name(), Option::None),
+            Chain::FnCall { func, args, .. } => (func.name(), Option::Some(args)),
+            _ => return Result::Ok(Option::None),
+        };
+        let class_ref = class.inner();
+        let tag = match class_ref.enum_map.get(name.as_str()) {
+            Some(tag) => *tag,
+            None => return Result::Ok(Option::None),
+        };
+        let related_type = class_ref.types.get(tag as usize).unwrap();
+        match (related_type, args) {
+            (RelatedType::None, Option::None) => {
+                Result::Ok(Option::Some(Chain::EnumValue(tag, Option::None)))
+            }
+            (RelatedType::Have(data_type), Option::Some(args)) => {
+                if args.len() != 1 {
+                    return Result::Err(AnalysisError::MismatchedEnumValueNum {
+                        info: context.info(),
+                        enum_name: class_ref.name.to_string(),
+                        value: name.to_string(),
+                        expect: 1,
+                        found: args.len(),
+                    });
+                }
+                let mut arg = args.pop().unwrap();
+                let arg_type = self.deduce_type(&mut arg, context)?;
+                if !arg_type.belong_to(data_type) {
+                    return Result::Err(AnalysisError::MismatchedEnumValueType {
+                        info: context.info(),
+                        enum_name: class_ref.name.to_string(),
+                        value: name.to_string(),
+                        expect: data_type.clone(),
+                        found: arg_type,
+                    });
+                }
+                Result::Ok(Option::Some(Chain::EnumValue(
+                    tag,
+                    Option::Some(Box::new(arg)),
+                )))
+            }
+            (RelatedType::Have(data_type), Option::None) => {
+                Result::Err(AnalysisError::MissingEnumValue {
+                    info: context.info(),
+                    enum_name: class_ref.name.to_string(),
+                    value: name.to_string(),
+                    typ: data_type.clone(),
+                })
+            }
+            (RelatedType::None, Option::Some(args)) => {
+                Result::Err(AnalysisError::MismatchedEnumValueNum {
+                    info: context.info(),
+                    enum_name: class_ref.name.to_string(),
+                    value: name.to_string(),
+                    expect: 0,
+                    found: args.len(),
+                })
+            }
+        }
     }
 
     fn deduce_type(
@@ -988,6 +1519,11 @@ impl Analyzer {
                                             var_name.deref().clone(),
                                             (slot_idx, sub_idx, true),
                                         );
+                                        context.local_names.push((
+                                            slot_idx,
+                                            sub_idx,
+                                            var_name.deref().clone(),
+                                        ));
                                         context.captures.push(Capture::new(
                                             *out_slot_idx,
                                             *out_sub_idx,
@@ -1111,9 +1647,26 @@ impl Analyzer {
                         },
                     }
                 }
+                if self.symbols.is_some() {
+                    let kind = self.symbol_kind(var_ref, var_name.as_str());
+                    self.record(context, var_name, &result_type, kind);
+                }
                 result_type
             }
-            Expression::Chain(chains) => self.handle_chains(context, chains)?,
+            Expression::Chain(chains) => {
+                let (data_type, erased) = self.handle_chains(context, chains)?;
+                // 被擦除的类型参数以对象的形式保存基本类型的值 需要拆箱
+                // erased type parameter holds the value of basic type as object, which need to be unboxed
+                if erased && !data_type.as_basic().is_ref() {
+                    let chain = std::mem::replace(expr, Expression::None);
+                    let parsed_type = ParsedType::Single(SingleType {
+                        name: Rc::new(data_type.to_string()),
+                        generic: Option::None,
+                    });
+                    *expr = Expression::Cast(Box::new((chain, parsed_type, data_type.clone())));
+                }
+                data_type
+            }
             Expression::Tuple(tuple) => {
                 let vec = tuple.deref_mut();
                 let mut tuple_types = Vec::with_capacity(vec.len());
@@ -1123,10 +1676,17 @@ impl Analyzer {
                 DataType::Ref(RefType::Tuple(Box::new(tuple_types)))
             }
             Expression::Array(array) => {
-                let (array, basic_type, _) = array.deref_mut();
+                let (array, basic_type, is_queue) = array.deref_mut();
+                let collection_type = |elem_type: DataType| {
+                    if *is_queue {
+                        DataType::Ref(RefType::Queue(Box::new(elem_type)))
+                    } else {
+                        DataType::Ref(RefType::Array(Box::new(elem_type)))
+                    }
+                };
                 if array.len() == 0 {
                     // without any array item
-                    DataType::Ref(RefType::Array(Box::new(DataType::Ref(RefType::Any))))
+                    collection_type(DataType::Ref(RefType::Any))
                 } else {
                     // array with generic type
                     let mut iter = array.iter_mut();
@@ -1153,20 +1713,52 @@ impl Analyzer {
                         }
                     }
                     *basic_type = data_type.as_basic();
-                    DataType::Ref(RefType::Array(Box::new(data_type)))
+                    collection_type(data_type)
+                }
+            }
+            Expression::Map(entries) => {
+                let (keys, values): (Vec<&mut Expression>, Vec<&mut Expression>) =
+                    entries.iter_mut().map(|(key, value)| (key, value)).unzip();
+                let key_type = self.deduce_elem_type(context, keys)?;
+                let value_type = self.deduce_elem_type(context, values)?;
+                if !key_type.is_hashable() {
+                    return Result::Err(AnalysisError::UnhashableType {
+                        info: context.info(),
+                        typ: key_type,
+                    });
                 }
+                DataType::Ref(RefType::Map(Box::new((key_type, value_type))))
+            }
+            Expression::Set(elems) => {
+                let elem_type = self.deduce_elem_type(context, elems.iter_mut().collect())?;
+                if !elem_type.is_hashable() {
+                    return Result::Err(AnalysisError::UnhashableType {
+                        info: context.info(),
+                        typ: elem_type,
+                    });
+                }
+                DataType::Ref(RefType::Set(Box::new(elem_type)))
             }
             Expression::Construct(construction) => {
                 let con_type = &construction.deref_mut().class_type;
                 let class_type = match con_type {
                     ExprType::Parsed(parsed_type) => {
-                        self.get_type(parsed_type, context.file_index)?
+                        self.get_type(parsed_type, context.file_index, &context.generics)?
                     }
                     ExprType::Analyzed(data_type) => data_type.clone(),
                 };
-                construction.class_type = ExprType::Analyzed(class_type.clone());
-                let class_rc = match &class_type {
-                    DataType::Ref(RefType::Class(class)) => class,
+                // 泛型类未给出的类型实参由字段推断 type arguments of generic class not given are inferred by fields
+                let (class_rc, given_args) = match &class_type {
+                    DataType::Ref(RefType::Class(class)) => (class.clone(), Option::None),
+                    DataType::Ref(RefType::Generic(generic)) => match &generic.0 {
+                        RefType::Class(class) => (class.clone(), Option::Some(generic.1.clone())),
+                        _ => {
+                            return Result::Err(AnalysisError::UnexpectedNewClassMark {
+                                info: context.info(),
+                                wrong_type: class_type,
+                            })
+                        }
+                    },
                     _ => {
                         return Result::Err(AnalysisError::UnexpectedNewClassMark {
                             info: context.info(),
@@ -1174,7 +1766,11 @@ impl Analyzer {
                         })
                     }
                 };
+                construction.class_type =
+                    ExprType::Analyzed(DataType::Ref(RefType::Class(class_rc.clone())));
                 let class = class_rc.inner();
+                let kind = SymbolKind::Type(MetaType::Class, class.file_index);
+                self.record(context, class.name.clone(), &class_type, kind);
                 if class.field_count as usize != construction.fields.len() {
                     return Result::Err(AnalysisError::MismatchedNewFieldNum {
                         info: context.info(),
@@ -1182,11 +1778,12 @@ impl Analyzer {
                         expect: class.field_count as usize,
                     });
                 }
+                let mut field_types = Vec::with_capacity(construction.fields.len());
                 for (var, field_basic_type, expr) in construction.fields.iter_mut() {
                     let field_name = var.name();
                     match class.map.get(field_name.as_str()) {
                         Some((slot_idx, sub_idx, is_pub, is_fn)) => {
-                            if *is_pub || context.belonged_type.equal_class(class_rc) {
+                            if *is_pub || context.belonged_type.equal_class(&class_rc) {
                                 if *is_fn {
                                     return Result::Err(AnalysisError::UnknownField {
                                         info: context.info(),
@@ -1194,20 +1791,18 @@ impl Analyzer {
                                         class: format!("{}", class_type),
                                     });
                                 } else {
-                                    let expr_type = self.deduce_type(expr, context)?;
                                     let field_type = class.field_indexer.get_type(*slot_idx);
+                                    let is_empty_literal =
+                                        Self::mark_array_literal(expr, field_type);
+                                    let expr_type = self.deduce_type(expr, context)?;
                                     *field_basic_type = field_type.as_basic();
-                                    if expr_type.belong_to(field_type) {
-                                        *var = VarId::Index(*slot_idx, *sub_idx);
-                                    } else {
-                                        return Result::Err(AnalysisError::MismatchedFieldType {
-                                            info: context.info(),
-                                            name: field_name.deref().clone(),
-                                            expect: field_type.clone(),
-                                            found: expr_type,
-                                            class: class_type.to_string(),
-                                        });
-                                    }
+                                    *var = VarId::Index(*slot_idx, *sub_idx);
+                                    field_types.push((
+                                        field_name,
+                                        field_type.clone(),
+                                        expr_type,
+                                        is_empty_literal,
+                                    ));
                                 }
                             } else {
                                 return Result::Err(AnalysisError::FieldNotPublic {
@@ -1227,13 +1822,91 @@ impl Analyzer {
                         }
                     }
                 }
-                class_type.clone()
+                let mut type_args = Vec::with_capacity(0);
+                if !class.generics.is_empty() {
+                    let mut inferred = match given_args {
+                        Some(args) => args.into_iter().map(Option::Some).collect(),
+                        None => vec![Option::None; class.generics.len()],
+                    };
+                    // 空的字面量不参与推断 empty literals don't take part in inference
+                    for (_, field_type, expr_type, is_empty_literal) in field_types.iter() {
+                        if !*is_empty_literal {
+                            field_type.infer(expr_type, &mut inferred);
+                        }
+                    }
+                    type_args =
+                        self.complete_generics(context, &class.name, &class.generics, 0, inferred)?;
+                }
+                for ((field_name, field_type, expr_type, is_empty_literal), (_, _, expr)) in
+                    field_types.into_iter().zip(construction.fields.iter_mut())
+                {
+                    let field_type = field_type.substitute(&type_args);
+                    // 空的字面量使用代入类型实参后的元素类型
+                    // empty literal uses the element type after substituting type arguments
+                    if is_empty_literal {
+                        Self::mark_array_literal(expr, &field_type);
+                        continue;
+                    }
+                    if !expr_type.belong_to(&field_type) {
+                        return Result::Err(AnalysisError::MismatchedFieldType {
+                            info: context.info(),
+                            name: field_name.deref().clone(),
+                            expect: field_type,
+                            found: expr_type,
+                            class: class_type.to_string(),
+                        });
+                    }
+                }
+                if type_args.is_empty() {
+                    DataType::Ref(RefType::Class(class_rc.clone()))
+                } else {
+                    DataType::Ref(RefType::Generic(Box::new((
+                        RefType::Class(class_rc.clone()),
+                        type_args,
+                    ))))
+                }
             }
             Expression::NegOp(expr) => self.deduce_type(expr.deref_mut(), context)?,
             Expression::NotOp(expr) => self.deduce_type(expr.deref_mut(), context)?,
+            Expression::BitNotOp(expr) => {
+                let data_type = self.deduce_type(expr.deref_mut(), context)?;
+                if !data_type.is_int() {
+                    return Result::Err(AnalysisError::UnaryOpWrongType {
+                        info: context.info(),
+                        op: Token::BitNot,
+                        typ: data_type,
+                    });
+                }
+                data_type
+            }
+            Expression::Await(await_expr) => {
+                let (expr, suspend) = await_expr.deref_mut();
+                *suspend = context.is_async;
+                match self.deduce_type(expr, context)? {
+                    DataType::Ref(RefType::Task(result)) => DataType::clone(&result),
+                    found => {
+                        return Result::Err(AnalysisError::NotTask {
+                            info: context.info(),
+                            keyword: Token::Await,
+                            found,
+                        })
+                    }
+                }
+            }
+            Expression::Spawn(expr) => match self.deduce_type(expr.deref_mut(), context)? {
+                task_type @ DataType::Ref(RefType::Task(_)) => task_type,
+                found => {
+                    return Result::Err(AnalysisError::NotTask {
+                        info: context.info(),
+                        keyword: Token::Spawn,
+                        found,
+                    })
+                }
+            },
             Expression::Cast(cast) => {
                 let (expr, parsed_type, data_type) = cast.deref_mut();
-                let cast_type = self.get_type(parsed_type, context.file_index)?;
+                let cast_type =
+                    self.get_type(parsed_type, context.file_index, &context.generics)?;
                 let real_type = self.deduce_type(expr, context)?;
                 *data_type = cast_type.clone();
                 if (cast_type.is_num_liked() && real_type.is_num_liked())
@@ -1254,27 +1927,47 @@ impl Analyzer {
                 // at this time, the func must be un-analyzed
                 let mut func = match func_expr {
                     FuncExpr::Parsed(func) => {
+                        if !func.generics.is_empty() {
+                            return Result::Err(AnalysisError::UnsupportedSyntax(format!(
+                                "{} nameless function can't have generic parameters",
+                                context.info()
+                            )));
+                        }
                         let mut params = Vec::with_capacity(func.params.len());
                         for (name, parsed_type) in func.params.iter() {
                             params.push(Param::new(
                                 name.clone(),
-                                self.get_type(parsed_type, context.file_index)?,
+                                self.get_type(parsed_type, context.file_index, &context.generics)?,
                             ));
                         }
                         let return_type = match &func.return_type {
-                            Some(parsed_type) => {
-                                ReturnType::Have(self.get_type(parsed_type, context.file_index)?)
-                            }
+                            Some(parsed_type) => ReturnType::Have(self.get_type(
+                                parsed_type,
+                                context.file_index,
+                                &context.generics,
+                            )?),
                             None => ReturnType::Void,
                         };
+                        let return_type = if func.is_async {
+                            return_type.into_task()
+                        } else {
+                            return_type
+                        };
                         let statements = std::mem::replace(&mut func.body, Vec::with_capacity(0));
-                        GloomFunc::new(
+                        let is_generator = func.is_generator;
+                        let is_async = func.is_async;
+                        let mut func = GloomFunc::new(
                             Rc::new(String::from("<nameless>")),
                             context.file_index,
                             params,
                             return_type,
                             statements,
-                        )
+                        );
+                        func.info.is_generator = is_generator;
+                        func.info.is_async = is_async;
+                        // 可以使用外层函数的泛型参数 could use the generic parameters of outer function
+                        func.info.generics = context.generics.clone();
+                        func
                     }
                     FuncExpr::Analysed(_) => panic!(),
                 };
@@ -1282,7 +1975,7 @@ impl Analyzer {
                     &mut func,
                     Option::Some(context),
                     context.belonged_type.clone(),
-                )?;
+                );
                 let func_type = func.get_type();
                 let is_parsed = func_expr.is_parsed();
                 if is_parsed {
@@ -1355,18 +2048,26 @@ impl Analyzer {
                                 });
                             }
                         }
-                        BinOpType::Logic => {
-                            if left_type.is_bool() {
+                        // 位运算只接受int '&' '|' '^'也接受bool
+                        // bitwise operation only accepts int, '&' '|' '^' also accept bool
+                        BinOpType::Bitwise => {
+                            let accept_bool = !matches!(op, BinOp::Shl | BinOp::Shr);
+                            if left_type.is_int() || (accept_bool && left_type.is_bool()) {
                                 let right_type = self.deduce_type(expr, context)?;
-                                if right_type.is_bool() {
-                                    left_type = DataType::Bool;
-                                } else {
+                                if right_type.is_int() != left_type.is_int()
+                                    || right_type.is_bool() != left_type.is_bool()
+                                {
                                     return Result::Err(AnalysisError::BinOpWrongType {
                                         info: context.info(),
                                         op: *op,
                                         typ: right_type,
                                     });
                                 }
+                                left_type = if left_type.is_int() {
+                                    DataType::Int
+                                } else {
+                                    DataType::Bool
+                                };
                             } else {
                                 return Result::Err(AnalysisError::BinOpWrongType {
                                     info: context.info(),
@@ -1375,17 +2076,42 @@ impl Analyzer {
                                 });
                             }
                         }
-                    }
-                }
-                left_type
-            }
+                        BinOpType::Logic => {
+                            if left_type.is_bool() {
+                                let right_type = self.deduce_type(expr, context)?;
+                                if right_type.is_bool() {
+                                    left_type = DataType::Bool;
+                                } else {
+                                    return Result::Err(AnalysisError::BinOpWrongType {
+                                        info: context.info(),
+                                        op: *op,
+                                        typ: right_type,
+                                    });
+                                }
+                            } else {
+                                return Result::Err(AnalysisError::BinOpWrongType {
+                                    info: context.info(),
+                                    op: *op,
+                                    typ: left_type,
+                                });
+                            }
+                        }
+                    }
+                }
+                left_type
+            }
             Expression::IfElse(if_else) => {
                 match self.analysis_if_else(if_else.deref_mut(), context)? {
                     ReturnType::Have(data_type) => data_type,
                     ReturnType::Void => DataType::Ref(RefType::None),
                 }
             }
-            expr => return Result::Err(AnalysisError::UnsupportedSyntax(format!("{:?}", expr))),
+            Expression::Match(match_def) => {
+                match self.analysis_match(match_def.deref_mut(), context)? {
+                    ReturnType::Have(data_type) => data_type,
+                    ReturnType::Void => DataType::Ref(RefType::None),
+                }
+            }
         };
         Result::Ok(data_type)
     }
@@ -1455,16 +2181,8 @@ impl Analyzer {
 
                 let var = &mut for_loop.var;
                 var_name = var.name();
-                let (slot_idx, sub_idx) = context.indexer.put(DataType::Int);
-                match context.symbol_table.entry(var_name.deref().clone()) {
-                    Entry::Vacant(entry) => entry.insert((slot_idx, sub_idx, true)),
-                    Entry::Occupied(_) => {
-                        return Result::Err(AnalysisError::VarAlreadyOccupied {
-                            info: context.info(),
-                            symbol: var_name.to_string(),
-                        })
-                    }
-                };
+                let (slot_idx, sub_idx) =
+                    context.declare_local(var_name.as_str(), DataType::Int)?;
                 *var = Var::LocalInt(slot_idx, sub_idx);
             }
             ForIter::Iter(iter_expr) => {
@@ -1472,7 +2190,20 @@ impl Analyzer {
                 let item_type = match &mut iter_type {
                     DataType::Ref(RefType::Array(item_type)) => DataType::clone(&item_type),
                     DataType::Ref(RefType::Queue(item_type)) => DataType::clone(&item_type),
+                    DataType::Ref(RefType::Set(item_type)) => DataType::clone(item_type),
+                    // 键值对 key-value pair
+                    DataType::Ref(RefType::Map(map_type)) => {
+                        DataType::Ref(RefType::Tuple(Box::new(vec![
+                            map_type.0.clone(),
+                            map_type.1.clone(),
+                        ])))
+                    }
                     DataType::Ref(RefType::String) => DataType::Char,
+                    DataType::Ref(RefType::Generator(item_type)) => DataType::clone(item_type),
+                    DataType::Ref(RefType::Channel(item_type)) => {
+                        for_loop.suspend = context.is_async;
+                        DataType::clone(item_type)
+                    }
                     other_type => {
                         return Result::Err(AnalysisError::CannotIter {
                             info: context.info(),
@@ -1483,16 +2214,7 @@ impl Analyzer {
 
                 var_name = for_loop.var.name();
                 let basic_type = item_type.as_basic();
-                let (slot_idx, sub_idx) = context.indexer.put(item_type);
-                match context.symbol_table.entry(var_name.deref().clone()) {
-                    Entry::Vacant(entry) => entry.insert((slot_idx, sub_idx, true)),
-                    Entry::Occupied(_) => {
-                        return Result::Err(AnalysisError::VarAlreadyOccupied {
-                            info: context.info(),
-                            symbol: var_name.to_string(),
-                        })
-                    }
-                };
+                let (slot_idx, sub_idx) = context.declare_local(var_name.as_str(), item_type)?;
                 for_loop.var = Var::new_local(slot_idx, sub_idx, basic_type);
             }
         }
@@ -1510,6 +2232,41 @@ impl Analyzer {
         Result::Ok(())
     }
 
+    fn analysis_try(
+        &self,
+        try_catch: &mut TryCatch,
+        context: &mut AnalyzeContext,
+    ) -> Result<(), AnalysisError> {
+        context.expr_stack.push((SyntaxType::Try, try_catch.line));
+        context.block_stack.push(BlockType::Try);
+
+        context.indexer.enter_sub_block();
+        self.analysis_statements(context, &mut try_catch.statements)?;
+        try_catch.drop_slots = context.indexer.level_sub_block();
+
+        context.indexer.enter_sub_block();
+        // catch 后的变量是捕获的错误 the variable after catch is the caught error
+        let var_name = match &try_catch.var {
+            Some(var) => {
+                let var_name = var.name();
+                let (slot_idx, _) =
+                    context.declare_local(var_name.as_str(), DataType::Ref(RefType::Error))?;
+                try_catch.var = Option::Some(Var::LocalRef(slot_idx));
+                Option::Some(var_name)
+            }
+            None => Option::None,
+        };
+        self.analysis_statements(context, &mut try_catch.catch_statements)?;
+        if let Some(var_name) = var_name {
+            context.symbol_table.remove(var_name.as_str());
+        }
+        try_catch.catch_drop_slots = context.indexer.level_sub_block();
+
+        context.block_stack.pop();
+        context.expr_stack.pop();
+        Result::Ok(())
+    }
+
     #[inline]
     fn analysis_statements(
         &self,
@@ -1525,74 +2282,30 @@ impl Analyzer {
             BlockType::Func => false,
             BlockType::Loop => true,
             BlockType::IfElse => true,
+            BlockType::Try => true,
         };
         let max_idx = if statements.len() > 0 {
             statements.len() - 1
         } else {
             0
         };
+        let failed_depth = context.failed_vars.len();
         for (idx, statement) in statements.iter_mut().enumerate() {
-            match statement {
-                Statement::Let(let_tuple) => {
-                    let (var, marked_type, expr, line) = let_tuple.deref_mut();
-                    match marked_type {
-                        None => {
-                            // 未标记变量类型 without type mark
-                            let deduced_type = self.deduce_type(expr, context)?;
-                            let basic_type = deduced_type.as_basic();
-                            // 检查变量名是否重复 check if the variable name occupied
-                            let (slot_idx, sub_idx) = context.indexer.put(deduced_type);
-                            match context.symbol_table.entry(var.name().deref().clone()) {
-                                Entry::Vacant(entry) => entry.insert((slot_idx, sub_idx, true)),
-                                Entry::Occupied(_) => {
-                                    return Result::Err(AnalysisError::VarAlreadyOccupied {
-                                        info: context.info(),
-                                        symbol: var.name().deref().clone(),
-                                    })
-                                }
-                            };
-                            if var_is_temp {
-                                temp_var_table.push(var.name().deref().clone());
-                            }
-                            *var = Var::new_local(slot_idx, sub_idx, basic_type);
-                        }
-                        Some(data_type) => {
-                            // 已标记变量类型 with type mark
-                            let data_type = self.get_type(data_type, context.file_index)?;
-                            let basic_type = data_type.as_basic();
-                            let expr_type = self.deduce_type(expr, context)?;
-                            if !expr_type.belong_to(&data_type) {
-                                return Result::Err(AnalysisError::VarDeclMismatchedType {
-                                    info: context.info(),
-                                    var: var.name().deref().clone(),
-                                    expect: data_type,
-                                    found: expr_type,
-                                });
-                            }
-                            let (slot_idx, sub_idx) = context.indexer.put(data_type);
-                            // 检查变量名是否重复 check if the variable name occupied
-                            match context.symbol_table.entry(var.name().deref().clone()) {
-                                Entry::Vacant(entry) => entry.insert((slot_idx, sub_idx, true)),
-                                Entry::Occupied(_) => {
-                                    return Result::Err(AnalysisError::VarAlreadyOccupied {
-                                        info: context.info(),
-                                        symbol: var.name().deref().clone(),
-                                    })
-                                }
-                            };
-                            if var_is_temp {
-                                temp_var_table.push(var.name().deref().clone());
-                            }
-                            *var = Var::new_local(slot_idx, sub_idx, basic_type);
-                        }
-                    }
-                }
-                Statement::LeftValueOp(left) => {
-                    self.handle_left_value_op(context, left)?;
-                }
-                Statement::Expr(expr, _) => {
-                    let expr_type = self.deduce_type(expr, context)?;
-                    if max_idx == idx {
+            context.line = statement.line();
+            let is_last = max_idx == idx;
+            // 出错的语句被跳过 恢复上下文后继续分析下一条语句
+            // the wrong statement is skipped, continue to analyse next statement after restoring the context
+            let depth = context.depth();
+            match self.analysis_statement(
+                context,
+                statement,
+                is_last,
+                curr_block_type,
+                var_is_temp,
+                &mut temp_var_table,
+            ) {
+                Ok(Some(expr_type)) => {
+                    if is_last {
                         last_is_expr = true;
                         let is_void = expr_type.is_none();
                         if !is_void {
@@ -1600,220 +2313,17 @@ impl Analyzer {
                         };
                     }
                 }
-                Statement::Discard(expr, line) => {
-                    self.deduce_type(expr, context)?;
-                    if max_idx == idx {
-                        match curr_block_type {
-                            BlockType::Func => {
-                                if context.func_return_type.is_void() {
-                                } else {
-                                    return Result::Err(AnalysisError::MismatchedReturnType {
-                                        info: context.info(),
-                                        expect: context.func_return_type.clone(),
-                                        found: ReturnType::Void,
-                                    });
-                                }
-                            }
-                            BlockType::IfElse => {
-                                match context.break_stack.last().unwrap() {
-                                    BreakType::Type(data_type) => {
-                                        return Result::Err(
-                                            AnalysisError::MismatchedIfElseResultType {
-                                                info: context.info(),
-                                                expect: ReturnType::Have(data_type.clone()),
-                                                found: ReturnType::Void,
-                                            },
-                                        )
-                                    }
-                                    // nothing to do
-                                    BreakType::Uninit => {}
-                                    BreakType::Void => {}
-                                }
-                            }
-                            BlockType::Loop => {}
-                        }
-                    }
-                }
-                Statement::Break(line) => {
-                    if context
-                        .block_stack
-                        .iter()
-                        .rfind(|block| {
-                            if let BlockType::Loop = block {
-                                true
-                            } else {
-                                false
-                            }
-                        })
-                        .is_none()
-                    {
-                        return Result::Err(AnalysisError::UnexpectBreak {
-                            info: context.info(),
-                            line: *line,
-                        });
-                    }
-                }
-                Statement::Return(expr, line) => {
-                    context.expr_stack.push((SyntaxType::Return, *line));
-                    let data_type = self.deduce_type(expr, context)?;
-                    match &context.func_return_type {
-                        ReturnType::Have(return_type) => {
-                            if !data_type.belong_to(return_type) {
-                                return Result::Err(AnalysisError::MismatchedReturnType {
-                                    info: context.info(),
-                                    expect: ReturnType::Have(return_type.clone()),
-                                    found: ReturnType::Have(data_type.clone()),
-                                });
-                            }
-                        }
-                        ReturnType::Void => {
-                            if !data_type.is_none() {
-                                return Result::Err(AnalysisError::MismatchedReturnType {
-                                    info: context.info(),
-                                    expect: ReturnType::Void,
-                                    found: ReturnType::Have(data_type.clone()),
-                                });
-                            }
-                        }
-                    }
-                    context.expr_stack.pop();
-                }
-                Statement::Continue(line) => {
-                    if context
-                        .block_stack
-                        .iter()
-                        .rfind(|block| {
-                            if let BlockType::Loop = block {
-                                true
-                            } else {
-                                false
-                            }
-                        })
-                        .is_none()
-                    {
-                        return Result::Err(AnalysisError::UnexpectContinue {
-                            info: context.info(),
-                            line: *line,
-                        });
-                    }
-                }
-                Statement::Static(static_tuple) => {
-                    if let BlockType::Func = curr_block_type {
-                    } else {
-                        return Result::Err(AnalysisError::StaticInLoopOfIfElse {
-                            info: context.info(),
-                            line: 0,
-                        });
-                    }
-                    let (var, parsed_type, expr) = static_tuple.deref_mut();
-                    match parsed_type {
-                        Some(parsed_type) => {
-                            let marked_type = self.get_type(parsed_type, context.file_index)?;
-                            let basic_type = marked_type.as_basic();
-                            let expr_type = self.deduce_type(expr, context)?;
-                            if !expr_type.belong_to(&marked_type) {
-                                return Result::Err(AnalysisError::VarDeclMismatchedType {
-                                    info: context.info(),
-                                    var: var.name().deref().clone(),
-                                    expect: marked_type,
-                                    found: expr_type,
-                                });
-                            }
-                            let slot_idx = self.static_indexer.inner_mut().put(marked_type);
-                            match context.symbol_table.entry(var.name().deref().clone()) {
-                                Entry::Vacant(entry) => entry.insert((slot_idx, 0, false)),
-                                Entry::Occupied(_) => {
-                                    return Result::Err(AnalysisError::VarAlreadyOccupied {
-                                        info: context.info(),
-                                        symbol: var.name().deref().clone(),
-                                    })
-                                }
-                            };
-                            *var = Var::new_static(slot_idx, basic_type);
+                Ok(None) => {}
+                Err(err) => {
+                    self.report(context, err);
+                    context.restore(depth);
+                    // 声明失败的变量不再被报告未定义 the variable failed to declare is not reported as undefined later
+                    if let Statement::Let(let_tuple) = statement {
+                        let var_name = let_tuple.0.name();
+                        if !context.symbol_table.contains_key(var_name.as_str()) {
+                            context.failed_vars.push(var_name.to_string());
                         }
-                        None => {
-                            let expr_type = self.deduce_type(expr, context)?;
-                            let basic_type = expr_type.as_basic();
-                            let slot_idx = self.static_indexer.inner_mut().put(expr_type);
-                            match context.symbol_table.entry(var.name().deref().clone()) {
-                                Entry::Vacant(entry) => entry.insert((slot_idx, 0, false)),
-                                Entry::Occupied(_) => {
-                                    return Result::Err(AnalysisError::VarAlreadyOccupied {
-                                        info: context.info(),
-                                        symbol: var.name().deref().clone(),
-                                    })
-                                }
-                            };
-                            *var = Var::new_static(slot_idx, basic_type);
-                        }
-                    };
-                }
-                Statement::PubStatic(static_tuple) => {
-                    if let BlockType::Func = curr_block_type {
-                    } else {
-                        return Result::Err(AnalysisError::StaticInLoopOfIfElse {
-                            info: context.info(),
-                            line: 0,
-                        });
                     }
-                    let (var, parsed_type, expr) = static_tuple.deref_mut();
-                    let pub_static_symbol_table = self.static_map.clone();
-                    match parsed_type {
-                        Some(parsed_type) => {
-                            let marked_type = self.get_type(parsed_type, context.file_index)?;
-                            let basic_type = marked_type.as_basic();
-                            let expr_type = self.deduce_type(expr, context)?;
-                            if !expr_type.belong_to(&marked_type) {
-                                return Result::Err(AnalysisError::VarDeclMismatchedType {
-                                    info: context.info(),
-                                    var: var.name().deref().clone(),
-                                    expect: marked_type,
-                                    found: expr_type,
-                                });
-                            }
-                            let slot_idx = self.static_indexer.inner_mut().put(marked_type);
-                            match pub_static_symbol_table
-                                .inner_mut()
-                                .entry(var.name().deref().clone())
-                            {
-                                Entry::Vacant(entry) => entry.insert(slot_idx),
-                                Entry::Occupied(_) => {
-                                    return Result::Err(AnalysisError::VarAlreadyOccupied {
-                                        info: context.info(),
-                                        symbol: var.name().deref().clone(),
-                                    })
-                                }
-                            };
-                            *var = Var::new_static(slot_idx, basic_type);
-                        }
-                        None => {
-                            let expr_type = self.deduce_type(expr, context)?;
-                            let basic_type = expr_type.as_basic();
-                            let slot_idx = self.static_indexer.inner_mut().put(expr_type);
-                            match pub_static_symbol_table
-                                .inner_mut()
-                                .entry(var.name().deref().clone())
-                            {
-                                Entry::Vacant(entry) => entry.insert(slot_idx),
-                                Entry::Occupied(_) => {
-                                    return Result::Err(AnalysisError::VarAlreadyOccupied {
-                                        info: context.info(),
-                                        symbol: var.name().deref().clone(),
-                                    })
-                                }
-                            };
-                            *var = Var::new_static(slot_idx, basic_type);
-                        }
-                    };
-                }
-                Statement::While(while_loop) => {
-                    self.analysis_while(while_loop.deref_mut(), context)?;
-                }
-                Statement::For(for_loop) => {
-                    self.analysis_for(for_loop.deref_mut(), context)?;
-                }
-                stmt => {
-                    return Result::Err(AnalysisError::UnsupportedSyntax(format!("{:?}", stmt)))
                 }
             }
         }
@@ -1831,10 +2341,14 @@ impl Analyzer {
                     } else if context.func_return_type.is_void() {
                         // nothing to do
                     } else {
-                        panic!()
+                        return Result::Err(AnalysisError::MismatchedReturnType {
+                            info: context.info(),
+                            expect: context.func_return_type.clone(),
+                            found: last_type,
+                        });
                     }
                 }
-                BlockType::Loop => {
+                BlockType::Loop | BlockType::Try => {
                     // nothing to do
                 }
                 BlockType::IfElse => {
@@ -1869,9 +2383,21 @@ impl Analyzer {
                                         panic!()
                                     };
                                 *last_statement = result_statement;
+                            } else {
+                                let expect = ReturnType::Have(already_type.clone());
+                                return Result::Err(AnalysisError::MismatchedIfElseResultType {
+                                    info: context.info(),
+                                    expect,
+                                    found: last_type,
+                                });
                             }
                         }
                         // BreakType::UnInit
+                        // 无返回值的表达式不作为结果，否则其结果不会被弹出
+                        // void expression is not the result, otherwise its value won't be popped
+                        break_type if last_type.is_void() => {
+                            *break_type = BreakType::Type(last_type.data_type().clone());
+                        }
                         break_type => {
                             let result_statement =
                                 if let Statement::Expr(expr, line) = last_statement {
@@ -1893,10 +2419,321 @@ impl Analyzer {
             for var_name in temp_var_table.iter() {
                 context.symbol_table.remove(var_name.as_str());
             }
+            context.failed_vars.truncate(failed_depth);
         }
         Result::Ok(())
     }
 
+    // 分析一条语句 表达式语句返回表达式的类型 analyse a statement, expression statement returns the type of expression
+    fn analysis_statement(
+        &self,
+        context: &mut AnalyzeContext,
+        statement: &mut Statement,
+        is_last: bool,
+        curr_block_type: BlockType,
+        var_is_temp: bool,
+        temp_var_table: &mut Vec<String>,
+    ) -> Result<Option<DataType>, AnalysisError> {
+        match statement {
+            Statement::Let(let_tuple) => {
+                let (var, marked_type, expr, line) = let_tuple.deref_mut();
+                match marked_type {
+                    None => {
+                        // 未标记变量类型 without type mark
+                        let deduced_type = self.deduce_type(expr, context)?;
+                        let basic_type = deduced_type.as_basic();
+                        self.record(context, var.name(), &deduced_type, SymbolKind::Var);
+                        // 检查变量名是否重复 check if the variable name occupied
+                        let (slot_idx, sub_idx) =
+                            context.declare_local(var.name().as_str(), deduced_type)?;
+                        if var_is_temp {
+                            temp_var_table.push(var.name().deref().clone());
+                        }
+                        *var = Var::new_local(slot_idx, sub_idx, basic_type);
+                    }
+                    Some(data_type) => {
+                        // 已标记变量类型 with type mark
+                        let data_type =
+                            self.get_type(data_type, context.file_index, &context.generics)?;
+                        let basic_type = data_type.as_basic();
+                        let is_empty_literal = Self::mark_array_literal(expr, &data_type);
+                        let mut expr_type = self.deduce_type(expr, context)?;
+                        if is_empty_literal {
+                            expr_type = data_type.clone();
+                        }
+                        if !expr_type.belong_to(&data_type) {
+                            return Result::Err(AnalysisError::VarDeclMismatchedType {
+                                info: context.info(),
+                                var: var.name().deref().clone(),
+                                expect: data_type,
+                                found: expr_type,
+                            });
+                        }
+                        self.record(context, var.name(), &data_type, SymbolKind::Var);
+                        // 检查变量名是否重复 check if the variable name occupied
+                        let (slot_idx, sub_idx) =
+                            context.declare_local(var.name().as_str(), data_type)?;
+                        if var_is_temp {
+                            temp_var_table.push(var.name().deref().clone());
+                        }
+                        *var = Var::new_local(slot_idx, sub_idx, basic_type);
+                    }
+                }
+            }
+            Statement::LeftValueOp(left) => {
+                self.handle_left_value_op(context, left)?;
+            }
+            Statement::Expr(expr, _) => {
+                return Result::Ok(Option::Some(self.deduce_type(expr, context)?));
+            }
+            Statement::Discard(expr, line) => {
+                self.deduce_type(expr, context)?;
+                if is_last {
+                    match curr_block_type {
+                        BlockType::Func => {
+                            if context.func_return_type.is_void() {
+                            } else {
+                                return Result::Err(AnalysisError::MismatchedReturnType {
+                                    info: context.info(),
+                                    expect: context.func_return_type.clone(),
+                                    found: ReturnType::Void,
+                                });
+                            }
+                        }
+                        BlockType::IfElse => {
+                            match context.break_stack.last().unwrap() {
+                                BreakType::Type(data_type) => {
+                                    return Result::Err(AnalysisError::MismatchedIfElseResultType {
+                                        info: context.info(),
+                                        expect: ReturnType::Have(data_type.clone()),
+                                        found: ReturnType::Void,
+                                    })
+                                }
+                                // nothing to do
+                                BreakType::Uninit => {}
+                                BreakType::Void => {}
+                            }
+                        }
+                        BlockType::Loop | BlockType::Try => {}
+                    }
+                }
+            }
+            Statement::Break(line) => {
+                if context
+                    .block_stack
+                    .iter()
+                    .rfind(|block| {
+                        if let BlockType::Loop = block {
+                            true
+                        } else {
+                            false
+                        }
+                    })
+                    .is_none()
+                {
+                    return Result::Err(AnalysisError::UnexpectBreak {
+                        info: context.info(),
+                        line: *line,
+                    });
+                }
+            }
+            Statement::Return(expr, line) => {
+                context.expr_stack.push((SyntaxType::Return, *line));
+                let data_type = self.deduce_type(expr, context)?;
+                match &context.func_return_type {
+                    ReturnType::Have(return_type) => {
+                        if !data_type.belong_to(return_type) {
+                            return Result::Err(AnalysisError::MismatchedReturnType {
+                                info: context.info(),
+                                expect: ReturnType::Have(return_type.clone()),
+                                found: ReturnType::Have(data_type.clone()),
+                            });
+                        }
+                    }
+                    ReturnType::Void => {
+                        if !data_type.is_none() {
+                            return Result::Err(AnalysisError::MismatchedReturnType {
+                                info: context.info(),
+                                expect: ReturnType::Void,
+                                found: ReturnType::Have(data_type.clone()),
+                            });
+                        }
+                    }
+                }
+                context.expr_stack.pop();
+            }
+            Statement::Continue(line) => {
+                if context
+                    .block_stack
+                    .iter()
+                    .rfind(|block| {
+                        if let BlockType::Loop = block {
+                            true
+                        } else {
+                            false
+                        }
+                    })
+                    .is_none()
+                {
+                    return Result::Err(AnalysisError::UnexpectContinue {
+                        info: context.info(),
+                        line: *line,
+                    });
+                }
+            }
+            Statement::Static(static_tuple) => {
+                if let BlockType::Func = curr_block_type {
+                } else {
+                    return Result::Err(AnalysisError::StaticInLoopOfIfElse {
+                        info: context.info(),
+                        line: 0,
+                    });
+                }
+                let (var, parsed_type, expr, _) = static_tuple.deref_mut();
+                match parsed_type {
+                    Some(parsed_type) => {
+                        let marked_type =
+                            self.get_type(parsed_type, context.file_index, &context.generics)?;
+                        let basic_type = marked_type.as_basic();
+                        let expr_type = self.deduce_type(expr, context)?;
+                        if !expr_type.belong_to(&marked_type) {
+                            return Result::Err(AnalysisError::VarDeclMismatchedType {
+                                info: context.info(),
+                                var: var.name().deref().clone(),
+                                expect: marked_type,
+                                found: expr_type,
+                            });
+                        }
+                        let slot_idx = self.static_indexer.inner_mut().put(marked_type);
+                        match context.symbol_table.entry(var.name().deref().clone()) {
+                            Entry::Vacant(entry) => entry.insert((slot_idx, 0, false)),
+                            Entry::Occupied(_) => {
+                                return Result::Err(AnalysisError::VarAlreadyOccupied {
+                                    info: context.info(),
+                                    symbol: var.name().deref().clone(),
+                                })
+                            }
+                        };
+                        *var = Var::new_static(slot_idx, basic_type);
+                    }
+                    None => {
+                        let expr_type = self.deduce_type(expr, context)?;
+                        let basic_type = expr_type.as_basic();
+                        let slot_idx = self.static_indexer.inner_mut().put(expr_type);
+                        match context.symbol_table.entry(var.name().deref().clone()) {
+                            Entry::Vacant(entry) => entry.insert((slot_idx, 0, false)),
+                            Entry::Occupied(_) => {
+                                return Result::Err(AnalysisError::VarAlreadyOccupied {
+                                    info: context.info(),
+                                    symbol: var.name().deref().clone(),
+                                })
+                            }
+                        };
+                        *var = Var::new_static(slot_idx, basic_type);
+                    }
+                };
+            }
+            Statement::PubStatic(static_tuple) => {
+                if let BlockType::Func = curr_block_type {
+                } else {
+                    return Result::Err(AnalysisError::StaticInLoopOfIfElse {
+                        info: context.info(),
+                        line: 0,
+                    });
+                }
+                let (var, parsed_type, expr, _) = static_tuple.deref_mut();
+                let pub_static_symbol_table = self.static_map.clone();
+                match parsed_type {
+                    Some(parsed_type) => {
+                        let marked_type =
+                            self.get_type(parsed_type, context.file_index, &context.generics)?;
+                        let basic_type = marked_type.as_basic();
+                        let expr_type = self.deduce_type(expr, context)?;
+                        if !expr_type.belong_to(&marked_type) {
+                            return Result::Err(AnalysisError::VarDeclMismatchedType {
+                                info: context.info(),
+                                var: var.name().deref().clone(),
+                                expect: marked_type,
+                                found: expr_type,
+                            });
+                        }
+                        let slot_idx = self.static_indexer.inner_mut().put(marked_type);
+                        match pub_static_symbol_table
+                            .inner_mut()
+                            .entry(var.name().deref().clone())
+                        {
+                            Entry::Vacant(entry) => entry.insert(slot_idx),
+                            Entry::Occupied(_) => {
+                                return Result::Err(AnalysisError::VarAlreadyOccupied {
+                                    info: context.info(),
+                                    symbol: var.name().deref().clone(),
+                                })
+                            }
+                        };
+                        *var = Var::new_static(slot_idx, basic_type);
+                    }
+                    None => {
+                        let expr_type = self.deduce_type(expr, context)?;
+                        let basic_type = expr_type.as_basic();
+                        let slot_idx = self.static_indexer.inner_mut().put(expr_type);
+                        match pub_static_symbol_table
+                            .inner_mut()
+                            .entry(var.name().deref().clone())
+                        {
+                            Entry::Vacant(entry) => entry.insert(slot_idx),
+                            Entry::Occupied(_) => {
+                                return Result::Err(AnalysisError::VarAlreadyOccupied {
+                                    info: context.info(),
+                                    symbol: var.name().deref().clone(),
+                                })
+                            }
+                        };
+                        *var = Var::new_static(slot_idx, basic_type);
+                    }
+                };
+            }
+            Statement::While(while_loop) => {
+                self.analysis_while(while_loop.deref_mut(), context)?;
+            }
+            Statement::For(for_loop) => {
+                self.analysis_for(for_loop.deref_mut(), context)?;
+            }
+            Statement::Try(try_catch) => {
+                self.analysis_try(try_catch.deref_mut(), context)?;
+            }
+            Statement::Yield(expr, line) => {
+                context.expr_stack.push((SyntaxType::Yield, *line));
+                let data_type = self.deduce_type(expr, context)?;
+                // 解析器保证yield只出现在生成器函数中 parser ensures yield only occurs in generator function
+                let yield_type = context.yield_type.as_ref().unwrap();
+                if !data_type.belong_to(yield_type) {
+                    return Result::Err(AnalysisError::MismatchedYieldType {
+                        info: context.info(),
+                        line: *line,
+                        expect: yield_type.clone(),
+                        found: data_type,
+                    });
+                }
+                context.expr_stack.pop();
+            }
+            Statement::Throw(expr, line) => {
+                context.expr_stack.push((SyntaxType::Throw, *line));
+                let data_type = self.deduce_type(expr, context)?;
+                // 抛出错误信息或者再次抛出捕获的错误 throw the error message or throw the caught error again
+                if !data_type.is_string() && !matches!(data_type, DataType::Ref(RefType::Error)) {
+                    return Result::Err(AnalysisError::ThrowNotString {
+                        info: context.info(),
+                        line: *line,
+                        found: data_type,
+                    });
+                }
+                context.expr_stack.pop();
+            }
+            stmt => return Result::Err(AnalysisError::UnsupportedSyntax(format!("{:?}", stmt))),
+        }
+        Result::Ok(Option::None)
+    }
+
     fn analysis_if_else(
         &self,
         if_else: &mut IfElse,
@@ -1911,60 +2748,272 @@ impl Analyzer {
             if !cond_type.is_bool() {
                 return Result::Err(AnalysisError::IfConditionNotBool {
                     info: context.info(),
-                    no: branch_idx,
-                    line: branch.line,
-                    found: cond_type,
+                    no: branch_idx,
+                    line: branch.line,
+                    found: cond_type,
+                });
+            }
+            context
+                .expr_stack
+                .push((SyntaxType::IfElseBranch, branch.line));
+            context.indexer.enter_sub_block();
+
+            if let Result::Err(err) = self.analysis_statements(context, statements) {
+                return Result::Err(err);
+            }
+
+            // 处理完一个分支的全部语句 handle all the statements of one branch
+            // 清理分支内声明的变量的信息 clear the info of the variables declared in branch
+            branch.drop_vec = context.indexer.level_sub_block();
+            context.expr_stack.pop();
+        }
+        context.block_stack.pop();
+        Result::Ok(match context.break_stack.pop().unwrap() {
+            BreakType::Type(data_type) => {
+                if data_type.is_none() {
+                    if_else.return_void = true;
+                    ReturnType::Void
+                } else {
+                    ReturnType::Have(data_type)
+                }
+            }
+            BreakType::Uninit => {
+                if_else.return_void = true;
+                ReturnType::Void
+            }
+            BreakType::Void => {
+                if_else.return_void = true;
+                ReturnType::Void
+            }
+        })
+    }
+
+    fn analysis_match(
+        &self,
+        match_def: &mut MatchDef,
+        context: &mut AnalyzeContext,
+    ) -> Result<ReturnType, AnalysisError> {
+        // 分支中的语句会改变当前行 statements in branches change the current line
+        let match_line = context.line;
+        let matched_type = self.deduce_type(&mut match_def.matched, context)?;
+        let enum_class = match &matched_type {
+            DataType::Int | DataType::Char | DataType::Bool => Option::None,
+            DataType::Ref(RefType::Int)
+            | DataType::Ref(RefType::Char)
+            | DataType::Ref(RefType::Bool)
+            | DataType::Ref(RefType::String) => Option::None,
+            DataType::Ref(RefType::Enum(class)) => Option::Some(class.clone()),
+            _ => {
+                return Result::Err(AnalysisError::CannotMatch {
+                    info: context.info(),
+                    typ: matched_type,
+                })
+            }
+        };
+        // 检查每个分支的模式 check the pattern of every branch
+        // 用于检查穷尽性的 key used to check exhaustiveness
+        let mut covered: Vec<i64> = Vec::new();
+        let mut covered_str: Vec<Rc<String>> = Vec::new();
+        let mut have_wildcard = false;
+        let mut have_bind = false;
+        for branch in match_def.branches.iter_mut() {
+            let line = branch.line;
+            if have_wildcard {
+                let msg = format!(
+                    "{} line {}, unreachable pattern {:?} after '_'",
+                    context.info(),
+                    line,
+                    branch.pattern
+                );
+                self.warn(context, Diagnostic::UNREACHABLE_PATTERN_CODE, msg, line);
+            }
+            // 模式是否与被匹配值的类型相符，以及该模式是否已被之前的分支覆盖
+            // whether the pattern matches the type of matched value, and whether it's covered by previous branches
+            let (is_match, is_covered) = match &mut branch.pattern {
+                Pattern::Wildcard => {
+                    have_wildcard = true;
+                    (true, false)
+                }
+                Pattern::Int(i) => (matched_type.is_int(), Self::cover(&mut covered, *i)),
+                Pattern::Char(ch) => (
+                    matched_type.eq(&DataType::Char)
+                        || matched_type.eq(&DataType::Ref(RefType::Char)),
+                    Self::cover(&mut covered, *ch as i64),
+                ),
+                Pattern::Bool(bl) => (
+                    matched_type.is_bool(),
+                    Self::cover(&mut covered, *bl as i64),
+                ),
+                Pattern::Str(str) => (
+                    matched_type.eq(&DataType::Ref(RefType::String)),
+                    Self::cover(&mut covered_str, str.clone()),
+                ),
+                Pattern::Enum {
+                    enum_name,
+                    value_name,
+                    tag,
+                    bind,
+                } => match &enum_class {
+                    Some(class) if class.inner().name.eq(enum_name) => {
+                        match class.inner().enum_map.get(value_name.as_str()) {
+                            Some(index) => {
+                                *tag = *index;
+                                if bind.is_some() {
+                                    if let RelatedType::None =
+                                        class.inner().types.get(*index as usize).unwrap()
+                                    {
+                                        return Result::Err(AnalysisError::BindNoneEnumValue {
+                                            info: context.info(),
+                                            line,
+                                            enum_name: enum_name.to_string(),
+                                            value: value_name.to_string(),
+                                        });
+                                    }
+                                    have_bind = true;
+                                }
+                                (true, Self::cover(&mut covered, *index as i64))
+                            }
+                            None => {
+                                return Result::Err(AnalysisError::UnknownEnumValue {
+                                    info: context.info(),
+                                    line,
+                                    enum_name: enum_name.to_string(),
+                                    value: value_name.to_string(),
+                                })
+                            }
+                        }
+                    }
+                    _ => (false, false),
+                },
+            };
+            if !is_match {
+                return Result::Err(AnalysisError::MismatchedPatternType {
+                    info: context.info(),
+                    line,
+                    pattern: format!("{:?}", branch.pattern),
+                    typ: matched_type,
                 });
             }
-            context
-                .expr_stack
-                .push((SyntaxType::IfElseBranch, branch.line));
-            context.indexer.enter_sub_block();
-
-            if let Result::Err(err) = self.analysis_statements(context, statements) {
-                return Result::Err(err);
+            if is_covered {
+                let msg = format!(
+                    "{} line {}, unreachable pattern {:?}, it's already covered by previous branch",
+                    context.info(),
+                    line,
+                    branch.pattern
+                );
+                self.warn(context, Diagnostic::UNREACHABLE_PATTERN_CODE, msg, line);
             }
+        }
+        // 穷尽性检查 exhaustiveness check
+        let is_exhaustive = have_wildcard
+            || match &enum_class {
+                Some(class) => (0..class.inner().types.len() as i64).all(|i| covered.contains(&i)),
+                None => matched_type.is_bool() && covered.contains(&0) && covered.contains(&1),
+            };
 
-            // 处理完一个分支的全部语句 handle all the statements of one branch
-            // 清理分支内声明的变量的信息 clear the info of the variables declared in branch
+        // match的分支与if-else分支一样以最后一个表达式作为结果
+        // the branch of match use the last expression as result, same as if-else branch
+        // 需要解构关联值时，被匹配的值先存入局部变量
+        // store the matched value in a local variable if the associated value need to be destructured
+        if have_bind {
+            let (slot_idx, _) = context.indexer.put(matched_type.clone());
+            match_def.matched_slot = Option::Some(slot_idx);
+        }
+        context.block_stack.push(BlockType::IfElse);
+        context.break_stack.push(BreakType::Uninit);
+        for branch in match_def.branches.iter_mut() {
+            context.expr_stack.push((SyntaxType::Match, branch.line));
+            context.indexer.enter_sub_block();
+            let mut bind_name = Option::None;
+            if let Pattern::Enum {
+                tag,
+                bind: Some(var),
+                ..
+            } = &mut branch.pattern
+            {
+                let related_type = match enum_class
+                    .as_ref()
+                    .unwrap()
+                    .inner()
+                    .types
+                    .get(*tag as usize)
+                {
+                    Some(RelatedType::Have(data_type)) => data_type.clone(),
+                    _ => panic!(),
+                };
+                let var_name = var.name();
+                let basic_type = related_type.as_basic();
+                let (slot_idx, sub_idx) = context.declare_local(var_name.as_str(), related_type)?;
+                *var = Var::new_local(slot_idx, sub_idx, basic_type);
+                bind_name = Option::Some(var_name);
+            }
+            self.analysis_statements(context, &mut branch.statements)?;
+            if let Option::Some(var_name) = bind_name {
+                context.symbol_table.remove(var_name.as_str());
+            }
             branch.drop_vec = context.indexer.level_sub_block();
             context.expr_stack.pop();
         }
         context.block_stack.pop();
-        Result::Ok(match context.break_stack.pop().unwrap() {
-            BreakType::Type(data_type) => {
-                if data_type.is_none() {
-                    if_else.return_void = true;
-                    ReturnType::Void
-                } else {
-                    ReturnType::Have(data_type)
-                }
-            }
-            BreakType::Uninit => {
-                if_else.return_void = true;
+        let result_type = match context.break_stack.pop().unwrap() {
+            BreakType::Type(data_type) if !data_type.is_none() => ReturnType::Have(data_type),
+            _ => {
+                match_def.return_void = true;
                 ReturnType::Void
             }
-            BreakType::Void => {
-                if_else.return_void = true;
-                ReturnType::Void
+        };
+        if !is_exhaustive {
+            match &result_type {
+                ReturnType::Have(data_type) => {
+                    return Result::Err(AnalysisError::NonExhaustiveMatch {
+                        info: context.info(),
+                        typ: data_type.clone(),
+                    })
+                }
+                ReturnType::Void => {
+                    let msg = format!(
+                        "{} match of type {} is not exhaustive, add a '_' branch to cover the remaining cases",
+                        context.info(),
+                        matched_type
+                    );
+                    self.warn(
+                        context,
+                        Diagnostic::NON_EXHAUSTIVE_MATCH_CODE,
+                        msg,
+                        match_line,
+                    );
+                }
             }
-        })
+        }
+        Result::Ok(result_type)
+    }
+
+    // 返回该key是否已被覆盖 return the key is covered or not
+    #[inline]
+    fn cover<T: PartialEq>(covered: &mut Vec<T>, key: T) -> bool {
+        if covered.contains(&key) {
+            true
+        } else {
+            covered.push(key);
+            false
+        }
     }
 
-    fn fill_classes(&mut self) -> Result<(), AnalysisError> {
-        let mut index = 0;
-        for class in self.status.classes.iter() {
+    fn fill_classes(&mut self, from: usize) -> Result<(), AnalysisError> {
+        for (index, class) in self.status.classes.iter().enumerate().skip(from) {
             self.fill_class(class.clone(), index)?;
-            index += 1;
         }
         Result::Ok(())
     }
     fn fill_class(&self, class: RefCount<GloomClass>, index: usize) -> Result<(), AnalysisError> {
         let (parsed_class, file_index) = self.parsed_classes.get(index).unwrap();
         let parsed_class = parsed_class.clone();
-        let mut class_mut = class.inner_mut();
-        class_mut.is_filled = true;
-        // handle parent class_mut
+        class.inner_mut().is_filled = true;
+        let generics = self.get_generics(&parsed_class.inner().generics, &[], *file_index)?;
+        class.inner_mut().generics = generics.clone();
+        // 字段和函数的类型可能引用这个类 所以不能一直持有可变借用
+        // the types of fields and funcs may refer to this class, so the mutable borrow can't be held all the time
+        // handle parent class
         if let Option::Some(parent_name) = &parsed_class.inner().parent {
             match self.type_map.get(parent_name.as_str()) {
                 None => {
@@ -1976,7 +3025,7 @@ impl Analyzer {
                     if label.tp != MetaType::Class {
                         return Result::Err(AnalysisError::ParentNotAClass {
                             info: "".to_string(),
-                            class: class_mut.to_string(),
+                            class: class.inner().to_string(),
                             parent: parent_name.to_string(),
                         });
                     }
@@ -1989,10 +3038,20 @@ impl Analyzer {
                             .clone();
                         // 没有被填充过
                         if !parent_class.inner().is_filled {
-                            // means parent class_mut is not uninitialized, fill it recursively
+                            // means parent class is not uninitialized, fill it recursively
                             self.fill_class(parent_class.clone(), label.index as usize)?;
                         }
-                        class_mut.set_parent(parent_class);
+                        // 泛型类不能被继承 generic class can't be inherited
+                        let parent_generic_count = parent_class.inner().generics.len();
+                        if parent_generic_count != 0 {
+                            return Result::Err(AnalysisError::MismatchedGenericNum {
+                                info: "".to_string(),
+                                typ: parent_name.to_string(),
+                                expect: parent_generic_count,
+                                found: 0,
+                            });
+                        }
+                        class.inner_mut().set_parent(parent_class);
                     } else {
                         return Result::Err(AnalysisError::UsedPrivateType {
                             info: "".to_string(),
@@ -2004,64 +3063,67 @@ impl Analyzer {
         }
         // fill fields
         for (is_pub, parsed_type, name) in parsed_class.inner().fields.iter() {
-            class_mut.add_field(
-                *is_pub,
-                name.deref().clone(),
-                self.get_type(parsed_type, *file_index)?,
-            );
+            let data_type = self.get_type(parsed_type, *file_index, &generics)?;
+            class
+                .inner_mut()
+                .add_field(*is_pub, name.deref().clone(), data_type);
         }
         // fill funcs
         for (is_pub, name, func) in parsed_class.inner_mut().funcs.iter_mut() {
+            let func_generics = self.get_generics(&func.generics, &generics, *file_index)?;
             let mut params = Vec::with_capacity(func.params.len());
             for (param_name, parsed_type) in func.params.iter() {
                 params.push(Param::new(
                     param_name.clone(),
-                    self.get_type(parsed_type, *file_index)?,
+                    self.get_type(parsed_type, *file_index, &func_generics)?,
                 ));
             }
-            let return_type: ReturnType = match &func.return_type {
+            let mut return_type: ReturnType = match &func.return_type {
                 None => ReturnType::Void,
-                Some(parsed_type) => ReturnType::Have(self.get_type(parsed_type, *file_index)?),
+                Some(parsed_type) => {
+                    ReturnType::Have(self.get_type(parsed_type, *file_index, &func_generics)?)
+                }
             };
+            if func.is_async {
+                return_type = return_type.into_task();
+            }
             // 在不需要move ParsedFunc 的情况下，仅使用ParsedFunc的可变引用将函数体的Vec<Statement> move至status中的GloomClass中
             let body: Vec<Statement> = std::mem::replace(&mut func.body, Vec::with_capacity(0));
-            class_mut.add_func(*is_pub, name.clone(), params, return_type, body)?;
+            class.inner_mut().add_func(
+                *is_pub,
+                name.clone(),
+                params,
+                return_type,
+                body,
+                func.is_generator,
+                func.is_async,
+                func_generics,
+            )?;
         }
         // handle instance funcs
-        class_mut.handle_instance_func(class.clone());
+        class.inner().handle_instance_func(class.clone());
         // handle implemented interface
-        std::mem::drop(class_mut);
-        for interface_name in parsed_class.inner().impl_interfaces.iter() {
-            match self.type_map.get(interface_name.as_str()) {
-                None => {
-                    return Result::Err(AnalysisError::UnknownType {
-                        typ: interface_name.to_string(),
-                    })
-                }
-                Some(label) => {
-                    if label.tp != MetaType::Interface {
-                        return Result::Err(AnalysisError::ImplNotInterface {
-                            inter: interface_name.to_string(),
-                            class: class.inner().name.to_string(),
-                            typ: label.tp.to_string(),
-                        });
-                    }
-                    if label.is_public || label.file_index == *file_index {
-                        let interface = self
-                            .status
-                            .interfaces
-                            .get(label.index as usize)
-                            .unwrap()
-                            .clone();
-                        class.inner().add_impl(interface)?;
-                    } else {
-                        return Result::Err(AnalysisError::UsedPrivateType {
-                            info: "".to_string(),
-                            typ: interface_name.to_string(),
-                        });
-                    }
+        for parsed_type in parsed_class.inner().impl_interfaces.iter() {
+            let interface_type = self.get_type(parsed_type, *file_index, &generics)?;
+            let not_interface = |ref_type: &RefType| AnalysisError::ImplNotInterface {
+                inter: ref_type.to_string(),
+                class: class.inner().name.to_string(),
+                typ: match ref_type {
+                    RefType::Class(_) => MetaType::Class,
+                    RefType::Enum(_) => MetaType::Enum,
+                    _ => MetaType::Builtin,
                 }
-            }
+                .to_string(),
+            };
+            let (interface, args) = match interface_type {
+                DataType::Ref(RefType::Interface(interface)) => (interface, Vec::with_capacity(0)),
+                DataType::Ref(RefType::Generic(generic)) => match *generic {
+                    (RefType::Interface(interface), args) => (interface, args),
+                    (base, _) => return Result::Err(not_interface(&base)),
+                },
+                other => return Result::Err(not_interface(&other.as_ref_type())),
+            };
+            class.inner_mut().add_impl(interface, args)?;
         }
         class.inner_mut().impls.sort_by(|imp1, imp2| {
             imp1.interface
@@ -2072,11 +3134,9 @@ impl Analyzer {
         Result::Ok(())
     }
 
-    fn fill_enums(&mut self) -> Result<(), AnalysisError> {
-        let mut index = 0;
-        for enum_class in self.status.enums.iter() {
+    fn fill_enums(&mut self, from: usize) -> Result<(), AnalysisError> {
+        for (index, enum_class) in self.status.enums.iter().enumerate().skip(from) {
             self.fill_enum(enum_class.clone(), index)?;
-            index += 1;
         }
         Result::Ok(())
     }
@@ -2090,28 +3150,41 @@ impl Analyzer {
         for (name, parsed_type) in parsed_enum.inner().values.iter() {
             let related_type: Option<DataType> = match parsed_type {
                 None => Option::None,
-                Some(parsed_type) => Some(self.get_type(parsed_type, *file_index)?),
+                Some(parsed_type) => Some(self.get_type(parsed_type, *file_index, &[])?),
             };
             enum_class
                 .inner_mut()
                 .add_enum_value(name.deref().clone(), related_type);
         }
         for (func_name, is_pub, func) in parsed_enum.inner_mut().funcs.iter_mut() {
+            let generics = self.get_generics(&func.generics, &[], *file_index)?;
             let mut params = Vec::with_capacity(func.params.len());
             for (name, parsed_type) in func.params.iter() {
                 params.push(Param::new(
                     name.clone(),
-                    self.get_type(parsed_type, *file_index)?,
+                    self.get_type(parsed_type, *file_index, &generics)?,
                 ));
             }
-            let return_type = match func.return_type.borrow() {
+            let mut return_type = match func.return_type.borrow() {
                 None => ReturnType::Void,
-                Some(parsed_type) => ReturnType::Have(self.get_type(parsed_type, *file_index)?),
+                Some(parsed_type) => {
+                    ReturnType::Have(self.get_type(parsed_type, *file_index, &generics)?)
+                }
             };
+            if func.is_async {
+                return_type = return_type.into_task();
+            }
             let body = std::mem::replace(&mut func.body, Vec::with_capacity(0));
-            enum_class
-                .inner_mut()
-                .add_func(func_name.clone(), *is_pub, params, return_type, body);
+            enum_class.inner_mut().add_func(
+                func_name.clone(),
+                *is_pub,
+                params,
+                return_type,
+                body,
+                func.is_generator,
+                func.is_async,
+                generics,
+            );
         }
         enum_class
             .inner_mut()
@@ -2119,11 +3192,9 @@ impl Analyzer {
         Result::Ok(())
     }
 
-    fn analysis_interfaces(&mut self) -> Result<(), AnalysisError> {
-        let mut index = 0;
-        for interface in self.status.interfaces.iter() {
+    fn analysis_interfaces(&mut self, from: usize) -> Result<(), AnalysisError> {
+        for (index, interface) in self.status.interfaces.iter().enumerate().skip(from) {
             self.analysis_interface(interface.clone(), index)?;
-            index += 1;
         }
         Result::Ok(())
     }
@@ -2133,6 +3204,8 @@ impl Analyzer {
         index: usize,
     ) -> Result<(), AnalysisError> {
         let (parsed_interface, file_index) = self.parsed_interfaces.get(index).unwrap();
+        let generics = self.get_generics(&parsed_interface.generics, &[], *file_index)?;
+        interface.inner_mut().generics = generics.clone();
         for parent_name in parsed_interface.parents.iter() {
             match self.type_map.get(parent_name.as_str()) {
                 None => {
@@ -2152,6 +3225,16 @@ impl Analyzer {
                     if parent_interface.inner().len() == 0 {
                         self.analysis_interface(parent_interface.clone(), label.index as usize)?;
                     }
+                    // 泛型接口不能被继承 generic interface can't be extended
+                    let parent_generic_count = parent_interface.inner().generics.len();
+                    if parent_generic_count != 0 {
+                        return Result::Err(AnalysisError::MismatchedGenericNum {
+                            info: "".to_string(),
+                            typ: parent_name.to_string(),
+                            expect: parent_generic_count,
+                            found: 0,
+                        });
+                    }
                     interface
                         .inner_mut()
                         .add_parent(&interface, parent_interface);
@@ -2178,12 +3261,17 @@ impl Analyzer {
                         });
                     }
                 } else {
-                    Param::new(empty_name.clone(), self.get_type(parsed_type, *file_index)?)
+                    Param::new(
+                        empty_name.clone(),
+                        self.get_type(parsed_type, *file_index, &generics)?,
+                    )
                 });
             }
             let return_type = match return_type {
                 None => ReturnType::Void,
-                Some(parsed_type) => ReturnType::Have(self.get_type(parsed_type, *file_index)?),
+                Some(parsed_type) => {
+                    ReturnType::Have(self.get_type(parsed_type, *file_index, &generics)?)
+                }
             };
             let index = interface.inner().funcs.len() as u16;
             match interface.inner_mut().map.borrow_mut().entry(name.clone()) {
@@ -2197,27 +3285,48 @@ impl Analyzer {
                     })
                 }
             }
-            interface
-                .inner_mut()
-                .add_func(RefCount::new(GloomFunc::new_abstract_fn(
-                    name.clone(),
-                    param_data_types,
-                    return_type,
-                    need_self,
-                    *file_index,
-                )))
+            let mut func = GloomFunc::new_abstract_fn(
+                name.clone(),
+                param_data_types,
+                return_type,
+                need_self,
+                *file_index,
+            );
+            func.info.generics = generics.clone();
+            interface.inner_mut().add_func(RefCount::new(func))
         }
         Result::Ok(())
     }
 
     fn load_decl(&mut self, script: &mut ParsedFile) -> Result<(), AnalysisError> {
-        let file_index = self.file_count;
-        self.paths.push(std::mem::replace(
+        script.index = self.new_file(std::mem::replace(
             &mut script.path,
             String::with_capacity(0),
         ));
-        script.index = file_index;
+        self.load_types(script)
+    }
+    fn new_file(&mut self, path: String) -> u16 {
+        let file_index = self.file_count;
+        self.paths.push(path);
         self.file_count += 1;
+        file_index
+    }
+    fn load_types(&mut self, script: &mut ParsedFile) -> Result<(), AnalysisError> {
+        let file_index = script.index;
+        // REPL的输入共用一个文件索引 新的输入替换之前的
+        // inputs of REPL share one file index, the new input replaces the previous one
+        self.statement_spans
+            .insert(file_index, std::mem::take(&mut script.statement_spans));
+        self.ident_spans
+            .insert(file_index, std::mem::take(&mut script.ident_spans));
+        // 导入标准库后可以调用其类型的函数 functions of the types could be called after std library imported
+        let std_type_map = BuiltinClass::std_type_map();
+        for kind in script.std_libs.iter() {
+            for builtin_type in kind.builtin_types() {
+                let index = *std_type_map.get(&builtin_type).unwrap();
+                self.builtin_map.insert(builtin_type, index);
+            }
+        }
         // load empty interface
         for (parsed_inter, is_public) in script.interfaces.iter() {
             let index = self.status.interfaces.len();
@@ -2243,10 +3352,9 @@ impl Analyzer {
                     });
                 }
             };
-            self.status.interfaces.push(RefCount::new(Interface::new(
-                parsed_inter.name.clone(),
-                index as u16,
-            )));
+            let mut interface = Interface::new(parsed_inter.name.clone(), index as u16);
+            interface.generics = Self::generic_names(&parsed_inter.generics);
+            self.status.interfaces.push(RefCount::new(interface));
         }
         // load empty class
         for (class, is_pub) in script.classes.iter() {
@@ -2273,11 +3381,9 @@ impl Analyzer {
                     });
                 }
             };
-            self.status.classes.push(RefCount::new(GloomClass::new(
-                class.name.clone(),
-                file_index,
-                index as u16,
-            )));
+            let mut class_obj = GloomClass::new(class.name.clone(), file_index, index as u16);
+            class_obj.generics = Self::generic_names(&class.generics);
+            self.status.classes.push(RefCount::new(class_obj));
         }
         // load empty enum
         for (enum_class, is_pub) in script.enums.iter() {
@@ -2328,14 +3434,23 @@ impl Analyzer {
         }
         for (name, func, is_pub) in script.funcs.into_iter() {
             let func_index = self.status.funcs.len() as u16;
+            let generics = self.get_generics(&func.generics, &[], script.index)?;
             let mut params = Vec::with_capacity(func.params.len());
             for (name, parsed_type) in func.params.into_iter() {
-                params.push(Param::new(name, self.get_type(&parsed_type, script.index)?));
+                params.push(Param::new(
+                    name,
+                    self.get_type(&parsed_type, script.index, &generics)?,
+                ));
             }
-            let return_type = match func.return_type {
+            let mut return_type = match func.return_type {
                 None => ReturnType::Void,
-                Some(parsed_type) => ReturnType::Have(self.get_type(&parsed_type, script.index)?),
+                Some(parsed_type) => {
+                    ReturnType::Have(self.get_type(&parsed_type, script.index, &generics)?)
+                }
             };
+            if func.is_async {
+                return_type = return_type.into_task();
+            }
             match self.func_map.entry(name.deref().clone()) {
                 Entry::Vacant(entry) => {
                     entry.insert((func_index, false, is_pub, script.index));
@@ -2347,13 +3462,12 @@ impl Analyzer {
                     })
                 }
             }
-            self.status.funcs.push(RefCount::new(GloomFunc::new(
-                name.clone(),
-                file_index,
-                params,
-                return_type,
-                func.body,
-            )));
+            let mut gloom_func =
+                GloomFunc::new(name.clone(), file_index, params, return_type, func.body);
+            gloom_func.info.is_generator = func.is_generator;
+            gloom_func.info.is_async = func.is_async;
+            gloom_func.info.generics = generics;
+            self.status.funcs.push(RefCount::new(gloom_func));
         }
         // statements
         self.status
@@ -2379,13 +3493,16 @@ impl Analyzer {
         &self,
         origin_type: &ParsedType,
         file_index: u16,
+        generics: &[GenericParam],
     ) -> Result<DataType, AnalysisError> {
         match origin_type {
-            ParsedType::Single(single_type) => self.analysis_single_type(single_type, file_index),
+            ParsedType::Single(single_type) => {
+                self.analysis_single_type(single_type, file_index, generics)
+            }
             ParsedType::Tuple(tuple) => {
                 let mut vec = Vec::with_capacity(tuple.vec.len());
                 for parsed_type in tuple.vec.iter() {
-                    vec.push(self.get_type(parsed_type, file_index)?);
+                    vec.push(self.get_type(parsed_type, file_index, generics)?);
                 }
                 Result::Ok(DataType::Ref(RefType::Tuple(Box::new(vec))))
             }
@@ -2397,12 +3514,20 @@ impl Analyzer {
         &self,
         single_type: &SingleType,
         file_index: u16,
+        generics: &[GenericParam],
     ) -> Result<DataType, AnalysisError> {
+        // 类型参数 type parameter
+        if let Some(index) = generics
+            .iter()
+            .rposition(|param| param.name.eq(&single_type.name))
+        {
+            return Result::Ok(generics[index].as_data_type(index as u16));
+        }
         let generic = match &single_type.generic {
             Some(vec) => {
                 let mut types = Vec::with_capacity(vec.len());
                 for parsed_type in vec.iter() {
-                    types.push(self.get_type(parsed_type, file_index)?);
+                    types.push(self.get_type(parsed_type, file_index, generics)?);
                 }
                 Option::Some(types)
             }
@@ -2422,37 +3547,54 @@ impl Analyzer {
         match self.type_map.get(single_type.name.as_str()) {
             Some(label) => {
                 if label.is_public || label.file_index == file_index {
-                    Result::Ok(match label.tp {
-                        MetaType::Interface => DataType::Ref(RefType::Interface(
-                            self.status
-                                .interfaces
-                                .get(label.index as usize)
-                                .unwrap()
-                                .clone(),
-                        )),
-                        MetaType::Class => DataType::Ref(RefType::Class(
-                            self.status
-                                .classes
-                                .get(label.index as usize)
-                                .unwrap()
-                                .clone(),
-                        )),
-                        MetaType::Enum => DataType::Ref(RefType::Enum(
-                            self.status.enums.get(label.index as usize).unwrap().clone(),
-                        )),
-                        MetaType::Builtin => DataType::Ref(
-                            self.status
-                                .builtin_classes
-                                .get(label.index as usize)
-                                .unwrap()
-                                .inner()
-                                .get_ref_type(generic)
-                                .map_err(|err| AnalysisError::GenericError {
-                                    info: "".to_string(),
-                                    error: err,
-                                })?,
+                    let (ref_type, generic_count) = match label.tp {
+                        MetaType::Interface => {
+                            let interface =
+                                self.status.interfaces.get(label.index as usize).unwrap();
+                            let generic_count = interface.inner().generics.len();
+                            (RefType::Interface(interface.clone()), generic_count)
+                        }
+                        MetaType::Class => {
+                            let class = self.status.classes.get(label.index as usize).unwrap();
+                            let generic_count = class.inner().generics.len();
+                            (RefType::Class(class.clone()), generic_count)
+                        }
+                        MetaType::Enum => (
+                            RefType::Enum(
+                                self.status.enums.get(label.index as usize).unwrap().clone(),
+                            ),
+                            0,
                         ),
-                    })
+                        MetaType::Builtin => {
+                            return Result::Ok(DataType::Ref(
+                                self.status
+                                    .builtin_classes
+                                    .get(label.index as usize)
+                                    .unwrap()
+                                    .inner()
+                                    .get_ref_type(generic)
+                                    .map_err(|err| AnalysisError::GenericError {
+                                        info: "".to_string(),
+                                        error: err,
+                                    })?,
+                            ))
+                        }
+                    };
+                    // 未给出类型实参时作为原始类型使用 used as raw type without type arguments
+                    match generic {
+                        Some(args) => {
+                            if args.len() != generic_count {
+                                return Result::Err(AnalysisError::MismatchedGenericNum {
+                                    info: "".to_string(),
+                                    typ: single_type.name.to_string(),
+                                    expect: generic_count,
+                                    found: args.len(),
+                                });
+                            }
+                            Result::Ok(DataType::Ref(RefType::Generic(Box::new((ref_type, args)))))
+                        }
+                        None => Result::Ok(DataType::Ref(ref_type)),
+                    }
                 } else {
                     return Result::Err(AnalysisError::UsedPrivateType {
                         info: "".to_string(),
@@ -2468,11 +3610,371 @@ impl Analyzer {
         }
     }
 
-    pub fn result(self) -> (GloomStatus, StaticTable) {
-        let mut indexer = self.static_indexer.inner_mut();
-        let static_len = indexer.size();
-        let static_drop_vec = indexer.drop_vec();
-        let static_table = StaticTable::new(static_len, static_drop_vec);
+    // 解析泛型参数及其约束 外层的泛型参数在作用域的前面
+    // resolve generic parameters and their bounds, the outer ones are in front of scope
+    fn get_generics(
+        &self,
+        parsed_generics: &[ParsedGeneric],
+        outer: &[GenericParam],
+        file_index: u16,
+    ) -> Result<Vec<GenericParam>, AnalysisError> {
+        let mut generics = outer.to_vec();
+        generics.extend(Self::generic_names(parsed_generics));
+        let mut bounds = Vec::with_capacity(parsed_generics.len());
+        for (name, bound) in parsed_generics.iter() {
+            bounds.push(match bound {
+                Some(parsed_type) => {
+                    let bound = self.get_type(parsed_type, file_index, &generics)?;
+                    let is_interface = match &bound {
+                        DataType::Ref(RefType::Interface(_)) => true,
+                        DataType::Ref(RefType::Generic(generic)) => {
+                            matches!(generic.0, RefType::Interface(_))
+                        }
+                        _ => false,
+                    };
+                    if !is_interface {
+                        return Result::Err(AnalysisError::BoundNotInterface {
+                            param: name.to_string(),
+                            bound,
+                        });
+                    }
+                    Option::Some(bound)
+                }
+                None => Option::None,
+            });
+        }
+        for (param, bound) in generics.iter_mut().skip(outer.len()).zip(bounds) {
+            param.bound = bound;
+        }
+        Result::Ok(generics)
+    }
+
+    // 约束在填充声明时才被解析 bounds are resolved when filling the declaration
+    fn generic_names(parsed_generics: &[ParsedGeneric]) -> Vec<GenericParam> {
+        parsed_generics
+            .iter()
+            .map(|(name, _)| GenericParam {
+                name: name.clone(),
+                bound: Option::None,
+            })
+            .collect()
+    }
+
+    // REPL的每次输入被增量地分析 出错时调用者需要回滚到输入前的检查点
+    // each input of REPL is analysed incrementally, the caller need roll back to the checkpoint before input if error
+    pub fn analysis_repl(
+        &mut self,
+        script: ParsedFile,
+        scope: &mut ReplScope,
+    ) -> Result<(Vec<RefCount<ScriptBody>>, RefCount<ScriptBody>), Vec<Diagnostic>> {
+        let from = self.status.decl_count();
+        // 分析器中的panic同样作为错误 panics in analyzer are also errors
+        let result = match panic::catch_unwind(AssertUnwindSafe(|| {
+            self.analysis_input(script, scope, &from)
+        })) {
+            Ok(result) => result,
+            Err(payload) => {
+                Result::Err(AnalysisError::Panicked(GloomError::from_panic(payload).msg))
+            }
+        };
+        self.status.paths = self.paths.clone();
+        self.diagnose(result)
+    }
+
+    // 合并收集到的错误和中止分析的错误 merge the collected errors and the error which aborts analysis
+    fn diagnose<T>(&self, result: Result<T, AnalysisError>) -> Result<T, Vec<Diagnostic>> {
+        let mut diagnostics = std::mem::take(&mut *self.diagnostics.inner_mut());
+        match result {
+            Ok(value) if diagnostics.is_empty() => Result::Ok(value),
+            Ok(_) => Result::Err(diagnostics),
+            Err(err) => {
+                // 中止分析的错误通常来自声明 没有所在行 the error aborts analysis usually comes from declarations, which has no line
+                diagnostics.push(Diagnostic::from_analysis(err, "", 0, Option::None));
+                Result::Err(diagnostics)
+            }
+        }
+    }
+    pub fn checkpoint(&self, scope: &ReplScope) -> ReplCheckpoint {
+        ReplCheckpoint {
+            decl_count: self.status.decl_count(),
+            file_count: self.file_count,
+            static_count: self.static_indexer.inner().size(),
+            static_map: self.static_map.inner().clone(),
+            scope: scope.clone(),
+        }
+    }
+    pub fn rollback(&mut self, checkpoint: ReplCheckpoint, scope: &mut ReplScope) {
+        let from = checkpoint.decl_count;
+        self.status.interfaces.truncate(from.interfaces);
+        self.status.classes.truncate(from.classes);
+        self.status.enums.truncate(from.enums);
+        self.status.funcs.truncate(from.funcs);
+        self.status.script_bodies.truncate(from.script_bodies);
+        self.parsed_interfaces.truncate(from.interfaces);
+        self.parsed_classes.truncate(from.classes);
+        self.parsed_enums.truncate(from.enums);
+        self.type_map.retain(|_, label| match label.tp {
+            MetaType::Interface => (label.index as usize) < from.interfaces,
+            MetaType::Class => (label.index as usize) < from.classes,
+            MetaType::Enum => (label.index as usize) < from.enums,
+            MetaType::Builtin => true,
+        });
+        self.func_map
+            .retain(|_, (index, is_builtin, _, _)| *is_builtin || (*index as usize) < from.funcs);
+        self.paths.truncate(checkpoint.file_count as usize);
+        self.file_count = checkpoint.file_count;
+        self.static_indexer
+            .inner_mut()
+            .truncate(checkpoint.static_count);
+        *self.static_map.inner_mut() = checkpoint.static_map;
+        *scope = checkpoint.scope;
+        self.status.paths = self.paths.clone();
+    }
+    fn analysis_input(
+        &mut self,
+        mut script: ParsedFile,
+        scope: &mut ReplScope,
+        from: &DeclCount,
+    ) -> Result<(Vec<RefCount<ScriptBody>>, RefCount<ScriptBody>), AnalysisError> {
+        // 所有输入共用一个文件索引 以便访问之前输入的非pub声明
+        // all inputs share one file index, so that the non-pub declarations of previous inputs are accessible
+        script.index = match scope.file_index {
+            Some(file_index) => file_index,
+            None => {
+                let file_index = self.new_file(std::mem::replace(
+                    &mut script.path,
+                    String::with_capacity(0),
+                ));
+                scope.file_index = Option::Some(file_index);
+                file_index
+            }
+        };
+        let file_index = script.index;
+        self.load_types(&mut script)?;
+        self.load(script)?;
+        // 第一个是输入的语句，其余的是导入的文件 the first is the input statements, the others are imported files
+        let mut imports = self.status.script_bodies.split_off(from.script_bodies);
+        let input = imports.remove(0);
+        imports.sort_by_key(|body| Reverse(body.inner().file_index));
+        self.analysis_decls(from)?;
+        for script_body in imports.iter() {
+            let script_body_rc = script_body.clone();
+            let mut script_body_ref = script_body_rc.inner_mut();
+            self.analysis_func(&mut script_body_ref.func, Option::None, DeclaredType::IsNot);
+        }
+        let mut input_ref = input.inner_mut();
+        let func = &mut input_ref.func;
+        let mut context = AnalyzeContext::new(
+            func.info.name.clone(),
+            DeclaredType::IsNot,
+            ReturnType::Void,
+            file_index,
+            self.paths.get(file_index as usize).unwrap().as_str(),
+            Option::None,
+        );
+        // 输入中定义的变量在之后的输入中仍然可见 variables defined in input are still visible in later inputs
+        std::mem::swap(&mut context.symbol_table, &mut scope.symbol_table);
+        std::mem::swap(&mut context.indexer, &mut scope.indexer);
+        context.block_stack.push(BlockType::Func);
+        let result = match &mut func.body {
+            FuncBody::AST(statements) => self.analysis_statements(&mut context, statements),
+            _ => panic!(),
+        };
+        std::mem::swap(&mut context.symbol_table, &mut scope.symbol_table);
+        std::mem::swap(&mut context.indexer, &mut scope.indexer);
+        result?;
+        // 最后一个表达式的值作为输入的结果 the value of the last expression is the result of input
+        if let FuncBody::AST(statements) = &mut func.body {
+            if let Some(Statement::Expr(expr, line)) = statements.last_mut() {
+                let result_statement =
+                    Statement::Return(std::mem::replace(expr, Expression::None), *line);
+                *statements.last_mut().unwrap() = result_statement;
+            }
+        }
+        func.info.captures = context.captures;
+        func.info.local_size = scope.indexer.size();
+        // 局部变量在REPL退出时才被丢弃 local variables are dropped only when REPL exit
+        func.info.drop_slots = Vec::with_capacity(0);
+        drop(input_ref);
+        Result::Ok((imports, input))
+    }
+
+    fn warn(&self, context: &AnalyzeContext, code: &'static str, msg: String, line: u16) {
+        self.warnings
+            .inner_mut()
+            .push(Diagnostic::warning(code, msg, context.file_name, line));
+    }
+
+    // 记录错误并继续分析 record the error and continue to analyse
+    fn report(&self, context: &AnalyzeContext, err: AnalysisError) {
+        if let AnalysisError::UndefinedVar { var, .. } = &err {
+            if context.is_failed_var(var) {
+                return;
+            }
+        }
+        let span = self
+            .statement_spans
+            .get(&context.file_index)
+            .and_then(|spans| spans.get(&context.line))
+            .copied()
+            .map(|span| self.narrow_span(context.file_index, span, &err));
+        self.diagnostics.inner_mut().push(Diagnostic::from_analysis(
+            err,
+            context.file_name,
+            context.line,
+            span,
+        ));
+    }
+
+    // 语句中第一个与错误提到的名称相同的标识符 否则是整条语句
+    // the first identifier in statement that is the same as the name mentioned in error, otherwise the whole statement
+    fn narrow_span(&self, file_index: u16, span: Span, err: &AnalysisError) -> Span {
+        let symbol = match err.symbol() {
+            Some(symbol) => symbol,
+            None => return span,
+        };
+        let idents = match self.ident_spans.get(&file_index) {
+            Some(idents) => idents,
+            None => return span,
+        };
+        let start = idents.partition_point(|(_, ident)| ident.start < span.start);
+        idents[start..]
+            .iter()
+            .take_while(|(_, ident)| ident.end <= span.end)
+            .find(|(name, _)| name.as_str() == symbol)
+            .map_or(span, |(_, ident)| *ident)
+    }
+
+    pub fn status_mut(&mut self) -> &mut GloomStatus {
+        &mut self.status
+    }
+
+    // 注册宿主程序提供的函数 register the function provided by host program
+    pub fn register_func(&mut self, func: GloomFunc) -> u16 {
+        let index = self.status.funcs.len() as u16;
+        self.func_map
+            .insert(func.info.name.to_string(), (index, true, true, 0));
+        self.status.funcs.push(RefCount::new(func));
+        index
+    }
+
+    // 注册宿主程序提供的类 方法随后被添加 register the class provided by host program, methods are added later
+    pub fn register_class(&mut self, name: &str) -> HostClass {
+        let index = self.status.builtin_classes.len() as u16;
+        let class = HostClass {
+            index,
+            name: Rc::new(name.to_string()),
+        };
+        self.status
+            .builtin_classes
+            .push(RefCount::new(BuiltinClass {
+                name: name.to_string(),
+                map: HashMap::new(),
+                funcs: Vec::new(),
+                get_ref_type_fn: BuiltinClass::none_generic_fn(RefType::Host(
+                    index,
+                    class.name.clone(),
+                )),
+            }));
+        self.type_map
+            .insert(name.to_string(), TypeIndex::builtin(index));
+        self.builtin_map.insert(BuiltinType::Host(index), index);
+        class
+    }
+
+    pub fn register_method(&mut self, class: &HostClass, func: GloomFunc) -> u16 {
+        let mut builtin_class = self
+            .status
+            .builtin_classes
+            .get(class.index as usize)
+            .unwrap()
+            .inner_mut();
+        let index = builtin_class.funcs.len() as u16;
+        builtin_class.map.insert(func.info.name.to_string(), index);
+        builtin_class.funcs.push(RefCount::new(func));
+        index
+    }
+
+    // 脚本中直接定义的函数 the functions directly defined in scripts
+    pub fn defined_funcs(&self) -> HashMap<String, u16> {
+        self.func_map
+            .iter()
+            .filter(|(_, (_, is_builtin, _, _))| !*is_builtin)
+            .map(|(name, (index, _, _, _))| (name.clone(), *index))
+            .collect()
+    }
+
+    // 开始记录解析出的符号 start to record the resolved symbols
+    pub fn record_symbols(&mut self) {
+        self.symbols = Option::Some(RefCount::new(Vec::new()));
+    }
+
+    pub fn symbols(&self) -> Vec<Symbol> {
+        match &self.symbols {
+            Some(symbols) => symbols.inner().clone(),
+            None => Vec::new(),
+        }
+    }
+
+    // 取出已收集的警告 REPL的每次输入只输出新的警告
+    // take the collected warnings, each input of REPL only outputs the new warnings
+    pub fn take_warnings(&self) -> Vec<Diagnostic> {
+        std::mem::take(&mut *self.warnings.inner_mut())
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    #[inline]
+    fn record(
+        &self,
+        context: &AnalyzeContext,
+        name: Rc<String>,
+        data_type: &DataType,
+        kind: SymbolKind,
+    ) {
+        if let Some(symbols) = &self.symbols {
+            symbols.inner_mut().push(Symbol {
+                file_index: context.file_index,
+                line: context.line,
+                name,
+                data_type: data_type.clone(),
+                kind,
+            });
+        }
+    }
+
+    // 变量名所指的符号种类 the kind of symbol which the variable name refers to
+    fn symbol_kind(&self, var: &Var, name: &str) -> SymbolKind {
+        match var {
+            Var::Class(_) | Var::Enum(_) | Var::Interface(_) => {
+                let label = self.type_map.get(name).unwrap();
+                SymbolKind::Type(label.tp, label.file_index)
+            }
+            Var::DirectFn(_) => match self.func_map.get(name) {
+                Some((_, false, _, file_index)) => SymbolKind::Func(*file_index),
+                _ => SymbolKind::Builtin,
+            },
+            Var::BuiltinType(_) => SymbolKind::Builtin,
+            _ => SymbolKind::Var,
+        }
+    }
+
+    // 静态变量表的长度以及需要丢弃的下标 the length of static table and the indexes need to be dropped
+    pub fn statics(&self) -> (u16, Vec<u16>) {
+        let indexer = self.static_indexer.inner();
+        (indexer.size(), indexer.drop_vec())
+    }
+
+    pub fn result(mut self) -> (GloomStatus, StaticTable) {
+        let static_table = {
+            let indexer = self.static_indexer.inner_mut();
+            let static_len = indexer.size();
+            let static_drop_vec = indexer.drop_vec();
+            StaticTable::new(static_len, static_drop_vec)
+        };
+        self.status.paths = std::mem::take(&mut self.paths);
         (self.status, static_table)
     }
 
@@ -2489,6 +3991,11 @@ impl Analyzer {
             builtin_map: BuiltinClass::builtin_type_map(),
             static_indexer: RefCount::new(ListIndexer::new()),
             paths: Vec::new(),
+            statement_spans: HashMap::new(),
+            ident_spans: HashMap::new(),
+            diagnostics: RefCount::new(Vec::new()),
+            symbols: Option::None,
+            warnings: RefCount::new(Vec::new()),
         }
     }
 }
@@ -2506,6 +4013,17 @@ pub struct AnalyzeContext<'a> {
     pub break_stack: Vec<BreakType>,
     pub indexer: SlotIndexer,
     pub block_stack: Vec<BlockType>,
+    pub local_names: Vec<(u16, u8, String)>,
+    // 正在分析的语句所在行 the line of statement being analysed
+    pub line: u16,
+    // 作用域中的泛型参数 generic parameters in scope
+    pub generics: Vec<GenericParam>,
+    // 生成器交出的值的类型 the type of values yielded by generator
+    pub yield_type: Option<DataType>,
+    // 在异步函数中 await挂起而非阻塞 in async function, await suspends rather than blocks
+    pub is_async: bool,
+    // 声明失败的变量 使用它们的错误已被报告过 variables failed to declare, the error of them has been reported
+    pub failed_vars: Vec<String>,
 }
 
 impl<'a> AnalyzeContext<'a> {
@@ -2530,7 +4048,57 @@ impl<'a> AnalyzeContext<'a> {
             file_name: file_path,
             indexer: SlotIndexer::new(),
             block_stack: Vec::new(),
+            local_names: Vec::new(),
+            line: 0,
+            generics: Vec::with_capacity(0),
+            yield_type: Option::None,
+            is_async: false,
+            failed_vars: Vec::new(),
+        }
+    }
+
+    pub fn is_failed_var(&self, name: &str) -> bool {
+        self.failed_vars.iter().any(|var| var == name)
+            || self
+                .out_context
+                .is_some_and(|context| context.is_failed_var(name))
+    }
+
+    // 声明局部变量并分配槽位 declare a local variable and allocate a slot for it
+    pub fn declare_local(
+        &mut self,
+        name: &str,
+        data_type: DataType,
+    ) -> Result<(u16, u8), AnalysisError> {
+        if self.symbol_table.contains_key(name) {
+            return Result::Err(AnalysisError::VarAlreadyOccupied {
+                info: self.info(),
+                symbol: name.to_string(),
+            });
         }
+        let (slot_idx, sub_idx) = self.indexer.put(data_type);
+        self.symbol_table
+            .insert(name.to_string(), (slot_idx, sub_idx, true));
+        self.local_names.push((slot_idx, sub_idx, name.to_string()));
+        Result::Ok((slot_idx, sub_idx))
+    }
+
+    // 各个栈的深度 用于出错后恢复 the depth of each stack, used to restore after error
+    pub fn depth(&self) -> (usize, usize, usize, usize) {
+        (
+            self.expr_stack.len(),
+            self.break_stack.len(),
+            self.block_stack.len(),
+            self.indexer.sub_block_depth(),
+        )
+    }
+
+    pub fn restore(&mut self, depth: (usize, usize, usize, usize)) {
+        let (expr_depth, break_depth, block_depth, sub_block_depth) = depth;
+        self.expr_stack.truncate(expr_depth);
+        self.break_stack.truncate(break_depth);
+        self.block_stack.truncate(block_depth);
+        self.indexer.restore_sub_block(sub_block_depth);
     }
 
     pub fn info(&self) -> String {
@@ -2568,3 +4136,39 @@ impl<'a> AnalyzeContext<'a> {
         info
     }
 }
+
+// REPL中所有输入共享的作用域 the scope shared by all inputs in REPL
+#[derive(Clone)]
+pub struct ReplScope {
+    symbol_table: HashMap<String, (u16, u8, IsLocal)>,
+    indexer: SlotIndexer,
+    file_index: Option<u16>,
+}
+
+// REPL输入前的状态 用于出错时回滚 the status before a REPL input, used for rolling back if error
+pub struct ReplCheckpoint {
+    decl_count: DeclCount,
+    file_count: u16,
+    static_count: u16,
+    static_map: HashMap<String, u16>,
+    scope: ReplScope,
+}
+
+impl ReplCheckpoint {
+    pub fn decl_count(&self) -> &DeclCount {
+        &self.decl_count
+    }
+}
+
+impl ReplScope {
+    pub fn new() -> ReplScope {
+        ReplScope {
+            symbol_table: HashMap::new(),
+            indexer: SlotIndexer::new(),
+            file_index: Option::None,
+        }
+    }
+    pub fn drop_slots(&self) -> Vec<u16> {
+        self.indexer.curr_drop_vec().clone()
+    }
+}
@@ -9,8 +9,9 @@ use crate::obj::types::{BasicType, DataType};
 
 pub struct ParsedClass {
     pub name: Rc<String>,
+    pub generics: Vec<ParsedGeneric>,
     pub parent: Option<Rc<String>>,
-    pub impl_interfaces: Vec<Rc<String>>,
+    pub impl_interfaces: Vec<ParsedType>,
     pub fields: Vec<(bool, ParsedType, Rc<String>)>,
     pub funcs: Vec<(bool, Rc<String>, ParsedFunc)>,
 }
@@ -19,14 +20,15 @@ impl Debug for ParsedClass {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Class {}:{:?} impl{:?} {:?} {:?}",
-            self.name, self.parent, self.impl_interfaces, self.fields, self.funcs
+            "Class {}{:?}:{:?} impl{:?} {:?} {:?}",
+            self.name, self.generics, self.parent, self.impl_interfaces, self.fields, self.funcs
         )
     }
 }
 
 pub struct ParsedInterface {
     pub name: Rc<String>,
+    pub generics: Vec<ParsedGeneric>,
     pub parents: Vec<Rc<String>>,
     pub funcs: Vec<(Rc<String>, Vec<ParsedType>, Option<ParsedType>)>,
 }
@@ -35,12 +37,15 @@ impl Debug for ParsedInterface {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Interface {}:{:?} {:?}",
-            self.name, self.parents, self.funcs
+            "Interface {}{:?}:{:?} {:?}",
+            self.name, self.generics, self.parents, self.funcs
         )
     }
 }
 
+// 泛型参数名及其约束 name of generic parameter and its bound
+pub type ParsedGeneric = (Rc<String>, Option<ParsedType>);
+
 pub struct ParsedEnum {
     pub name: Rc<String>,
     pub values: Vec<(Rc<String>, Option<ParsedType>)>,
@@ -59,10 +64,10 @@ pub type Line = u16;
 #[derive(Debug)]
 pub enum Statement {
     Let(Box<(Var, Option<ParsedType>, Expression, Line)>),
-    Static(Box<(Var, Option<ParsedType>, Expression)>),
-    PubStatic(Box<(Var, Option<ParsedType>, Expression)>),
+    Static(Box<(Var, Option<ParsedType>, Expression, Line)>),
+    PubStatic(Box<(Var, Option<ParsedType>, Expression, Line)>),
 
-    LeftValueOp(Box<(LeftValue, LeftValueOp)>),
+    LeftValueOp(Box<(LeftValue, LeftValueOp, Line)>),
 
     Expr(Expression, u16),
     Discard(Expression, u16),
@@ -71,12 +76,40 @@ pub enum Statement {
     While(Box<WhileLoop>),
     For(Box<ForLoop>),
 
+    // 异常处理 exception handling
+    Try(Box<TryCatch>),
+    Throw(Expression, u16),
+
+    // 生成器交出一个值 generator gives out a value
+    Yield(Expression, u16),
+
     Continue(u16),
     Break(u16),
     Return(Expression, u16),
     IfResult(Expression, u16),
 }
 
+impl Statement {
+    pub fn line(&self) -> Line {
+        match self {
+            Statement::Let(let_tuple) => let_tuple.3,
+            Statement::Static(static_tuple) | Statement::PubStatic(static_tuple) => static_tuple.3,
+            Statement::LeftValueOp(left_tuple) => left_tuple.2,
+            Statement::Expr(_, line)
+            | Statement::Discard(_, line)
+            | Statement::Continue(line)
+            | Statement::Break(line)
+            | Statement::Return(_, line)
+            | Statement::IfResult(_, line)
+            | Statement::Throw(_, line)
+            | Statement::Yield(_, line) => *line,
+            Statement::While(while_loop) => while_loop.line,
+            Statement::For(for_loop) => for_loop.line,
+            Statement::Try(try_catch) => try_catch.line,
+        }
+    }
+}
+
 // 16byte
 #[derive(Debug)]
 pub enum Expression {
@@ -92,6 +125,8 @@ pub enum Expression {
     Tuple(Box<Vec<Expression>>),
     Array(Box<(Vec<Expression>, BasicType, bool)>),
     // is array to queue
+    Map(Box<Vec<(Expression, Expression)>>),
+    Set(Box<Vec<Expression>>),
     Construct(Box<Construction>),
 
     // 二元操作 binary operation
@@ -101,12 +136,18 @@ pub enum Expression {
     Cast(Box<(Expression, ParsedType, DataType)>),
     NegOp(Box<Expression>),
     NotOp(Box<Expression>),
+    BitNotOp(Box<Expression>),
+
+    // 等待任务结束 在异步函数中挂起而非阻塞 wait for the task to finish, suspend rather than block in async function
+    Await(Box<(Expression, bool)>),
+    // 把任务交给调度器 hand the task over to the scheduler
+    Spawn(Box<Expression>),
 
     // 条件控制 condition control
     IfElse(Box<IfElse>),
 
     // 模式匹配 pattern match
-    Match(RefCount<(Expression, Vec<(Expression, Vec<Statement>)>)>),
+    Match(Box<MatchDef>),
 
     // 链式的成员变量访问和函数调用 field access and function call
     Chain(Box<(Expression, Vec<Chain>)>),
@@ -304,6 +345,8 @@ pub struct ForLoop {
     pub drop_slots: Vec<u16>,
     pub line: u16,
     pub return_void: bool,
+    // 在异步函数中迭代通道时挂起当前任务 suspend the current task when iterating channel in async function
+    pub suspend: bool,
 }
 
 #[derive(Debug)]
@@ -322,6 +365,27 @@ impl Debug for ForLoop {
     }
 }
 
+pub struct TryCatch {
+    pub statements: Vec<Statement>,
+    pub drop_slots: Vec<u16>,
+    // catch 后的变量 保存错误信息 None 表示 catch _
+    // the variable after catch, holds the error message, None means catch _
+    pub var: Option<Var>,
+    pub catch_statements: Vec<Statement>,
+    pub catch_drop_slots: Vec<u16>,
+    pub line: Line,
+}
+
+impl Debug for TryCatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "try {:?} catch {:?} {:?}",
+            self.statements, self.var, self.catch_statements
+        )
+    }
+}
+
 #[derive(Debug)]
 pub enum FuncExpr {
     Parsed(ParsedFunc),
@@ -338,25 +402,86 @@ impl FuncExpr {
 }
 
 pub struct ParsedFunc {
+    pub generics: Vec<ParsedGeneric>,
     pub params: Vec<(Rc<String>, ParsedType)>,
     pub body: Vec<Statement>,
     pub return_type: Option<ParsedType>,
+    // 函数体中含有yield 调用时返回生成器 the body contains yield, returns a generator when called
+    pub is_generator: bool,
+    // 异步函数 调用时返回任务 async function, returns a task when called
+    pub is_async: bool,
 }
 
 impl Debug for ParsedFunc {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "func{:?}->{:?} {:?}",
-            self.params, self.return_type, self.body
+            "func{:?}{:?}->{:?} {:?}",
+            self.generics, self.params, self.return_type, self.body
         )
     }
 }
 
-#[derive(Debug)]
 pub struct MatchDef {
     pub matched: Expression,
-    pub branches: Vec<(Expression, Vec<Statement>)>,
+    pub branches: Vec<MatchBranch>,
+    pub return_void: bool,
+    // 有分支需要解构关联值时，被匹配的值暂存于该槽位
+    // the matched value is stored in this slot if any branch need destructure the associated value
+    pub matched_slot: Option<u16>,
+}
+
+impl Debug for MatchDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "match {:?} {:?}", self.matched, self.branches)
+    }
+}
+
+#[derive(Debug)]
+pub struct MatchBranch {
+    pub pattern: Pattern,
+    pub statements: Vec<Statement>,
+    pub drop_vec: Vec<u16>,
+    pub line: Line,
+}
+
+pub enum Pattern {
+    Int(i64),
+    Char(char),
+    Bool(bool),
+    Str(Rc<String>),
+    // Enum.Value 或 Enum.Value(var) , 分析后填入tag
+    // Enum.Value or Enum.Value(var) , the tag is filled after analysis
+    Enum {
+        enum_name: Rc<String>,
+        value_name: Rc<String>,
+        tag: u16,
+        bind: Option<Var>,
+    },
+    // _
+    Wildcard,
+}
+
+impl Debug for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Int(i) => write!(f, "{}", i),
+            Pattern::Char(ch) => write!(f, "{:?}", ch),
+            Pattern::Bool(bl) => write!(f, "{}", bl),
+            Pattern::Str(str) => write!(f, "{:?}", str),
+            Pattern::Enum {
+                enum_name,
+                value_name,
+                bind,
+                ..
+            } => match bind {
+                None => write!(f, "{}.{}", enum_name, value_name),
+                Some(Var::Name(name)) => write!(f, "{}.{}({})", enum_name, value_name, name),
+                Some(var) => write!(f, "{}.{}({:?})", enum_name, value_name, var),
+            },
+            Pattern::Wildcard => write!(f, "_"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -382,10 +507,21 @@ pub enum Chain {
         args: Vec<Expression>,
         need_self: bool,
         is_dyn: bool,
+        // 调用所在的行 用于调用栈 the line of the call, used by traceback
+        line: Line,
     },
 
     // expr is func type
-    Call(Vec<Expression>),
+    Call(Vec<Expression>, Line),
+
+    // 枚举值及其关联值 enum value and its associated value, such as Color.Red or Result.Ok(x)
+    EnumValue(u16, Option<Box<Expression>>),
+
+    // 下标访问及元素的基本类型 subscript access and basic type of element, such as a[i]
+    Index(Box<Expression>, BasicType),
+
+    // 切片 slice, such as a[1..3]
+    Slice(Box<(Expression, Expression)>),
 }
 
 impl Debug for Chain {
@@ -399,12 +535,22 @@ impl Debug for Chain {
                 need_self: _need_self,
                 args,
                 is_dyn: _is_dyn,
+                line: _line,
             } => {
                 write!(f, "func[{:?}] {:?}", func, args)
             }
-            Chain::Call(call) => {
+            Chain::Call(call, _) => {
                 write!(f, "call({:?})", call)
             }
+            Chain::EnumValue(tag, val) => {
+                write!(f, "enum[{}] {:?}", tag, val)
+            }
+            Chain::Index(index, t) => {
+                write!(f, "[{:?}]<{:?}>", index, t)
+            }
+            Chain::Slice(range) => {
+                write!(f, "[{:?}..{:?}]", range.0, range.1)
+            }
         }
     }
 }
@@ -413,6 +559,8 @@ impl Debug for Chain {
 pub enum LeftValue {
     Var(Var),
     Chain(Expression, Vec<Chain>),
+    // 容器、下标和元素的基本类型 container, index and basic type of element
+    Index(Box<(Expression, Expression, BasicType)>),
 }
 
 // 用来报错时打印详细信息 used for print details when error occurs
@@ -423,6 +571,9 @@ pub enum SyntaxType {
     Break,
     Return,
     Match,
+    Try,
+    Throw,
+    Yield,
 }
 
 impl Debug for SyntaxType {
@@ -437,6 +588,9 @@ impl Debug for SyntaxType {
                 SyntaxType::Return => "return",
                 SyntaxType::Match => "match",
                 SyntaxType::ForIn => "for-in",
+                SyntaxType::Try => "try-catch",
+                SyntaxType::Throw => "throw",
+                SyntaxType::Yield => "yield",
             }
         )
     }
@@ -447,4 +601,5 @@ pub enum BlockType {
     Func,
     Loop,
     IfElse,
+    Try,
 }
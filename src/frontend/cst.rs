@@ -0,0 +1,180 @@
+use std::fmt::{Display, Formatter};
+
+use crate::frontend::token::Token;
+use crate::frontend::tokenize::Tokenizer;
+
+// 无损的具体语法树 记号之间的空白和注释作为trivia保留 可以还原出原本的源码
+// lossless concrete syntax tree, whitespaces and comments between tokens are kept as trivia,
+// so that the original source code could be restored
+pub struct SyntaxTree {
+    pub nodes: Vec<Node>,
+    // 最后一个记号之后的trivia the trivia after the last token
+    pub trailing: Vec<Trivia>,
+}
+
+pub enum Node {
+    Token(CstToken),
+    Group(Group),
+}
+
+// 括号及其中的内容 brackets and the content in them
+pub struct Group {
+    pub open: CstToken,
+    pub children: Vec<Node>,
+    // 括号未闭合时为None None if the bracket is not closed
+    pub close: Option<CstToken>,
+}
+
+pub struct CstToken {
+    pub token: Token,
+    // 源码中的原文 the original text in source code
+    pub text: String,
+    // 记号之前的trivia the trivia before the token
+    pub leading: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    Newline,
+    // 包括 // 和 # 开头的注释 including the comments start with // and #
+    LineComment(String),
+    BlockComment(String),
+    // 无法识别的字符 unrecognized characters
+    Skipped(String),
+}
+
+impl SyntaxTree {
+    pub fn parse(src: &str) -> SyntaxTree {
+        let (tokens, _, spans) = Tokenizer::new(src.as_bytes().to_vec()).tokenize();
+        // 栈底是最外层 the bottom of stack is the outermost layer
+        let mut stack: Vec<(Option<CstToken>, Vec<Node>)> = vec![(Option::None, Vec::new())];
+        let mut last_end = 0;
+        for (token, span) in tokens.into_iter().zip(spans) {
+            let (start, end) = (span.start as usize, span.end as usize);
+            let cst_token = CstToken {
+                token,
+                text: src[start..end].to_string(),
+                leading: Self::trivia(&src[last_end..start]),
+            };
+            last_end = end;
+            match cst_token.token {
+                Token::LParen | Token::LBracket | Token::LBrace => {
+                    stack.push((Option::Some(cst_token), Vec::new()))
+                }
+                Token::RParen | Token::RBracket | Token::RBrace if stack.len() > 1 => {
+                    let (open, children) = stack.pop().unwrap();
+                    let group = Group {
+                        open: open.unwrap(),
+                        children,
+                        close: Option::Some(cst_token),
+                    };
+                    stack.last_mut().unwrap().1.push(Node::Group(group));
+                }
+                _ => stack.last_mut().unwrap().1.push(Node::Token(cst_token)),
+            }
+        }
+        // 未闭合的括号 unclosed brackets
+        while stack.len() > 1 {
+            let (open, children) = stack.pop().unwrap();
+            let group = Group {
+                open: open.unwrap(),
+                children,
+                close: Option::None,
+            };
+            stack.last_mut().unwrap().1.push(Node::Group(group));
+        }
+        SyntaxTree {
+            nodes: stack.pop().unwrap().1,
+            trailing: Self::trivia(&src[last_end..]),
+        }
+    }
+
+    // 记号之间只有空白、注释和无法识别的字符 there are only whitespaces, comments and unrecognized characters between tokens
+    fn trivia(gap: &str) -> Vec<Trivia> {
+        let mut trivia = Vec::new();
+        let mut rest = gap;
+        while let Some(ch) = rest.chars().next() {
+            let len = if ch == '\n' {
+                trivia.push(Trivia::Newline);
+                1
+            } else if ch.is_whitespace() {
+                let len = rest
+                    .find(|ch: char| ch == '\n' || !ch.is_whitespace())
+                    .unwrap_or(rest.len());
+                trivia.push(Trivia::Whitespace(rest[..len].to_string()));
+                len
+            } else if rest.starts_with("//") || rest.starts_with('#') {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                trivia.push(Trivia::LineComment(rest[..len].to_string()));
+                len
+            } else if let Some(body) = rest.strip_prefix('/').filter(|body| body.starts_with('*')) {
+                // 与分词器一致 '/*/' 也是完整的注释 consistent with the tokenizer, '/*/' is also a complete comment
+                let len = body.find("*/").map_or(rest.len(), |idx| idx + 3);
+                trivia.push(Trivia::BlockComment(rest[..len].to_string()));
+                len
+            } else {
+                let len = rest
+                    .find(|ch: char| ch.is_whitespace() || ch == '#' || ch == '/')
+                    .filter(|len| *len > 0)
+                    .unwrap_or_else(|| rest.len().min(ch.len_utf8()));
+                trivia.push(Trivia::Skipped(rest[..len].to_string()));
+                len
+            };
+            rest = &rest[len..];
+        }
+        trivia
+    }
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trivia::Newline => writeln!(f),
+            Trivia::Whitespace(text)
+            | Trivia::LineComment(text)
+            | Trivia::BlockComment(text)
+            | Trivia::Skipped(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl Display for CstToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for trivia in self.leading.iter() {
+            write!(f, "{}", trivia)?;
+        }
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Token(token) => write!(f, "{}", token),
+            Node::Group(group) => {
+                write!(f, "{}", group.open)?;
+                for node in group.children.iter() {
+                    write!(f, "{}", node)?;
+                }
+                match &group.close {
+                    Some(close) => write!(f, "{}", close),
+                    None => Result::Ok(()),
+                }
+            }
+        }
+    }
+}
+
+// 输出与源码完全相同 the output is exactly the same as source code
+impl Display for SyntaxTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for node in self.nodes.iter() {
+            write!(f, "{}", node)?;
+        }
+        for trivia in self.trailing.iter() {
+            write!(f, "{}", trivia)?;
+        }
+        Result::Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt::{Display, Formatter, Write};
+
+use serde_json::{json, Value as Json};
+
+use crate::frontend::error::AnalysisError;
+use crate::frontend::parse::ParseError;
+use crate::frontend::token::Span;
+
+// 解析和分析阶段的错误 附带位置信息 可以渲染为类似rustc的文本或JSON
+// error of parse and analysis stage with location, could be rendered as rustc-style text or JSON
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub code: &'static str,
+    pub message: String,
+    pub file: String,
+    // 0表示没有行号 0 means no line
+    pub line: u16,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+    pub help: Option<&'static str>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+}
+
+// 源码中的一行 a line in source code
+struct SourceLine<'a> {
+    text: &'a str,
+    // 下划线的起始列和宽度 以字符计 the start column and width of underline, counted in chars
+    column: usize,
+    width: usize,
+}
+
+impl Diagnostic {
+    pub const PARSE_ERROR_CODE: &'static str = "E0001";
+    pub const UNREACHABLE_PATTERN_CODE: &'static str = "W0101";
+    pub const NON_EXHAUSTIVE_MATCH_CODE: &'static str = "W0102";
+
+    pub fn from_parse(err: &ParseError) -> Diagnostic {
+        Diagnostic {
+            level: Level::Error,
+            code: Self::PARSE_ERROR_CODE,
+            message: err.reason.clone(),
+            file: err.path.clone(),
+            line: err.line,
+            span: err.span,
+            notes: Vec::new(),
+            help: Option::None,
+        }
+    }
+
+    pub fn from_analysis(
+        err: AnalysisError,
+        file: &str,
+        line: u16,
+        span: Option<Span>,
+    ) -> Diagnostic {
+        let (message, notes) = Self::split_context(err.to_string(), file);
+        Diagnostic {
+            level: Level::Error,
+            code: err.code(),
+            message,
+            file: file.to_string(),
+            line,
+            span,
+            notes,
+            help: err.help(),
+        }
+    }
+
+    // 分析器的警告 与错误信息的格式相同 warning of analyzer, the message has the same format as error
+    pub fn warning(code: &'static str, message: String, file: &str, line: u16) -> Diagnostic {
+        let (message, notes) = Self::split_context(message, file);
+        Diagnostic {
+            level: Level::Warning,
+            code,
+            message,
+            file: file.to_string(),
+            line,
+            span: Option::None,
+            notes,
+            help: Option::None,
+        }
+    }
+
+    fn split_context(message: String, file: &str) -> (String, Vec<String>) {
+        let mut notes = Vec::new();
+        // 错误信息中含有文件和函数的上下文 通常在开头 其中文件已由位置给出 作为注释保留函数的上下文
+        // error message contains the context of file and function, usually at the start,
+        // the file is given by location, keep the context of function as a note
+        let prefix = format!(" {} => ", file);
+        let (head, mut tail) = match message.find(prefix.as_str()) {
+            Some(index) if !file.is_empty() => {
+                let rest = &message[index + prefix.len()..];
+                match rest.split_once(": ") {
+                    Some((context, msg)) => {
+                        notes.push(format!("in {}", context.trim_end_matches([' ', '>'])));
+                        (&message[..index], msg)
+                    }
+                    None => ("", message.as_str()),
+                }
+            }
+            _ => ("", message.as_str()),
+        };
+        // 没有上下文的错误以空格开头 the error without context starts with a space
+        tail = tail.trim_start();
+        // 行号同样由位置给出 the line is also given by location
+        if tail.starts_with("line ") {
+            if let Some((_, msg)) = tail.split_once(", ") {
+                tail = msg;
+            }
+        }
+        (format!("{}{}", head.trim_start(), tail), notes)
+    }
+
+    // 渲染为类似rustc的文本 src为所在文件的源码 render as rustc-style text, src is the source code of file
+    pub fn render(&self, src: Option<&str>) -> String {
+        let mut text = format!("{}[{}]: {}\n", self.level.as_str(), self.code, self.message);
+        let source_line = src.and_then(|src| self.source_line(src));
+        let line_no = self.line.to_string();
+        let pad = " ".repeat(line_no.len());
+        if !self.file.is_empty() {
+            match (&source_line, self.line) {
+                (Some(source_line), _) => writeln!(
+                    text,
+                    "{}--> {}:{}:{}",
+                    pad,
+                    self.file,
+                    self.line,
+                    source_line.column + 1
+                ),
+                (None, 0) => writeln!(text, "{}--> {}", pad, self.file),
+                (None, line) => writeln!(text, "{}--> {}:{}", pad, self.file, line),
+            }
+            .unwrap();
+        }
+        if let Some(source_line) = &source_line {
+            writeln!(text, "{} |", pad).unwrap();
+            writeln!(text, "{} | {}", line_no, source_line.text).unwrap();
+            writeln!(
+                text,
+                "{} | {}{}",
+                pad,
+                " ".repeat(source_line.column),
+                "^".repeat(source_line.width)
+            )
+            .unwrap();
+        }
+        if !self.notes.is_empty() || self.help.is_some() {
+            writeln!(text, "{} |", pad).unwrap();
+        }
+        for note in self.notes.iter() {
+            writeln!(text, "{} = note: {}", pad, note).unwrap();
+        }
+        if let Some(help) = self.help {
+            writeln!(text, "{} = help: {}", pad, help).unwrap();
+        }
+        text
+    }
+
+    pub fn json(&self, src: Option<&str>) -> Json {
+        let source_line = src.and_then(|src| self.source_line(src));
+        json!({
+            "level": self.level.as_str(),
+            "code": self.code,
+            "message": self.message,
+            "file": self.file,
+            "line": if self.line == 0 { Option::None } else { Option::Some(self.line) },
+            "column": source_line.as_ref().map(|source_line| source_line.column + 1),
+            "span": self.span.map(|span| json!({ "start": span.start, "end": span.end })),
+            "notes": self.notes,
+            "help": self.help,
+            "rendered": self.render(src),
+        })
+    }
+
+    fn source_line<'a>(&self, src: &'a str) -> Option<SourceLine<'a>> {
+        if self.line == 0 {
+            return Option::None;
+        }
+        let mut line_start = 0;
+        for _ in 1..self.line {
+            line_start += src[line_start..].find('\n')? + 1;
+        }
+        let line_end = match src[line_start..].find('\n') {
+            Some(len) => line_start + len,
+            None => src.len(),
+        };
+        let text = src[line_start..line_end].trim_end();
+        let (column, width) = match self.span {
+            // 跨行的范围只标记到行尾 span across lines is only marked to the end of line
+            Some(span)
+                if (span.start as usize) >= line_start && (span.start as usize) <= line_end =>
+            {
+                let start = span.start as usize - line_start;
+                let end = (span.end as usize).min(line_end).max(span.start as usize) - line_start;
+                let column = text.get(..start).map_or(start, |str| str.chars().count());
+                let width = text.get(start..end).map_or(1, |str| str.chars().count());
+                (column, width.max(1))
+            }
+            // 没有范围时标记整行 mark the whole line if there is no span
+            _ => {
+                let trimmed = text.trim_start();
+                let column = text.chars().count() - trimmed.chars().count();
+                (column, trimmed.chars().count().max(1))
+            }
+        };
+        Option::Some(SourceLine {
+            text,
+            column,
+            width,
+        })
+    }
+}
+
+// 没有源码时的文本 the text without source code
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(Option::None).trim_end())
+    }
+}
@@ -39,7 +39,7 @@ pub enum AnalysisError {
         found: ReturnType,
     },
 
-    #[error("{info} expect result type of if-else is {expect}, found {found}")]
+    #[error("{info} expect result type of if-else or match branch is {expect}, found {found}")]
     MismatchedIfElseResultType {
         info: String,
         expect: ReturnType,
@@ -58,6 +58,9 @@ pub enum AnalysisError {
     #[error("unsupported syntax {0}")]
     UnsupportedSyntax(String),
 
+    #[error("analyzer panicked : {0}")]
+    Panicked(String),
+
     #[error(
         "{info} line {line}, the condition of {no}st if-else branch have non-bool type {found}"
     )]
@@ -75,6 +78,13 @@ pub enum AnalysisError {
         found: DataType,
     },
 
+    #[error("{info} line {line}, the value of throw statement should be string, found {found}")]
+    ThrowNotString {
+        info: String,
+        line: u16,
+        found: DataType,
+    },
+
     #[error("{info} expect a type, found void, in {no}st expression of array literal : {elem}")]
     VoidInArray {
         info: String,
@@ -247,6 +257,62 @@ pub enum AnalysisError {
     #[error("{info} can't apply for-in iteration in type {typ}")]
     CannotIter { info: String, typ: DataType },
 
+    #[error("{info} can't apply match in type {typ}, only int char bool String and enum could be matched")]
+    CannotMatch { info: String, typ: DataType },
+
+    #[error("{info} line {line}, pattern {pattern} can't match the value of type {typ}")]
+    MismatchedPatternType {
+        info: String,
+        line: u16,
+        pattern: String,
+        typ: DataType,
+    },
+
+    #[error("{info} line {line}, enum {enum_name} don't have a value named {value}")]
+    UnknownEnumValue {
+        info: String,
+        line: u16,
+        enum_name: String,
+        value: String,
+    },
+
+    #[error("{info} the match that have result type {typ} is not exhaustive, add a '_' branch to cover the remaining cases")]
+    NonExhaustiveMatch { info: String, typ: DataType },
+
+    #[error("{info} enum value {enum_name}.{value} expect an associated value of type {typ}")]
+    MissingEnumValue {
+        info: String,
+        enum_name: String,
+        value: String,
+        typ: DataType,
+    },
+
+    #[error("{info} mismatched number of associated value of enum value {enum_name}.{value}, expect {expect}, found {found}")]
+    MismatchedEnumValueNum {
+        info: String,
+        enum_name: String,
+        value: String,
+        expect: usize,
+        found: usize,
+    },
+
+    #[error("{info} mismatched type of associated value of enum value {enum_name}.{value}, expect {expect}, found {found}")]
+    MismatchedEnumValueType {
+        info: String,
+        enum_name: String,
+        value: String,
+        expect: DataType,
+        found: DataType,
+    },
+
+    #[error("{info} line {line}, enum value {enum_name}.{value} has no associated value to bind")]
+    BindNoneEnumValue {
+        info: String,
+        line: u16,
+        enum_name: String,
+        value: String,
+    },
+
     #[error("{info} declared parent class {parent} of {class} is not a class")]
     ParentNotAClass {
         info: String,
@@ -308,4 +374,269 @@ pub enum AnalysisError {
         expect: usize,
         found: usize,
     },
+
+    #[error("{info} type {typ} need {expect} generic types, found {found}")]
+    MismatchedGenericNum {
+        info: String,
+        typ: String,
+        expect: usize,
+        found: usize,
+    },
+
+    #[error("bound {bound} of generic parameter {param} is not an interface")]
+    BoundNotInterface { param: String, bound: DataType },
+
+    #[error(
+        "{info} type {found} of generic parameter {param} doesn't implement its bound {bound}"
+    )]
+    UnsatisfiedBound {
+        info: String,
+        param: String,
+        bound: DataType,
+        found: DataType,
+    },
+
+    #[error("{info} can't infer the type of generic parameter {param} of {name}")]
+    CannotInferGeneric {
+        info: String,
+        param: String,
+        name: String,
+    },
+
+    #[error(
+        "{info} type {typ} is not hashable, which can't be the key of Map or the element of Set"
+    )]
+    UnhashableType { info: String, typ: DataType },
+
+    #[error("{info} type {typ} can't be indexed by '[]'")]
+    CannotIndex { info: String, typ: DataType },
+
+    #[error("{info} type {typ} can't be sliced by '[..]'")]
+    CannotSlice { info: String, typ: DataType },
+
+    #[error("{info} mismatched index type of {typ}, expect {expect}, found {found}")]
+    MismatchedIndexType {
+        info: String,
+        typ: DataType,
+        expect: DataType,
+        found: DataType,
+    },
+
+    #[error("{info} index of tuple {typ} must be an int literal less than its length")]
+    BadTupleIndex { info: String, typ: DataType },
+
+    #[error("{info} the elements of {typ} can't be assigned")]
+    ImmutableElement { info: String, typ: DataType },
+
+    #[error("{info} can't make a weak reference to the value of basic type {typ}")]
+    WeakOfBasicType { info: String, typ: DataType },
+
+    #[error("{info} function containing 'yield' should return Generator, found {found}")]
+    NotGeneratorReturnType { info: String, found: ReturnType },
+
+    #[error("{info} line {line}, expect yielded type is {expect}, found {found}")]
+    MismatchedYieldType {
+        info: String,
+        line: u16,
+        expect: DataType,
+        found: DataType,
+    },
+
+    #[error("{info} the operand of {keyword} should be a Task, found {found}")]
+    NotTask {
+        info: String,
+        keyword: Token,
+        found: DataType,
+    },
+
+    #[error("{info} unary operator {op} have wrong operand type {typ}")]
+    UnaryOpWrongType {
+        info: String,
+        op: Token,
+        typ: DataType,
+    },
+}
+
+impl AnalysisError {
+    // 错误码 用于在文档中查找错误的说明 error code, used to look up the explanation of error in document
+    pub fn code(&self) -> &'static str {
+        match self {
+            AnalysisError::VarAlreadyOccupied { .. } => "E0101",
+            AnalysisError::FnAlreadyOccupied { .. } => "E0102",
+            AnalysisError::TypeAlreadyOccupied { .. } => "E0103",
+            AnalysisError::MismatchedArgsNum { .. } => "E0104",
+            AnalysisError::VarDeclMismatchedType { .. } => "E0105",
+            AnalysisError::MismatchedReturnType { .. } => "E0106",
+            AnalysisError::MismatchedIfElseResultType { .. } => "E0107",
+            AnalysisError::UnexpectBreak { .. } => "E0108",
+            AnalysisError::UnexpectContinue { .. } => "E0109",
+            AnalysisError::StaticInLoopOfIfElse { .. } => "E0110",
+            AnalysisError::UnsupportedSyntax(..) => "E0111",
+            AnalysisError::Panicked(..) => "E0112",
+            AnalysisError::IfConditionNotBool { .. } => "E0113",
+            AnalysisError::WhileConditionNotBool { .. } => "E0114",
+            AnalysisError::ThrowNotString { .. } => "E0115",
+            AnalysisError::VoidInArray { .. } => "E0116",
+            AnalysisError::UnexpectedNewClassMark { .. } => "E0117",
+            AnalysisError::MismatchedNewFieldNum { .. } => "E0118",
+            AnalysisError::UnknownField { .. } => "E0119",
+            AnalysisError::UndefinedVar { .. } => "E0120",
+            AnalysisError::AssignMismatchedType { .. } => "E0121",
+            AnalysisError::CalcInplaceLeftMismatchedType { .. } => "E0122",
+            AnalysisError::CalcInplaceRightMismatchedType { .. } => "E0123",
+            AnalysisError::NoFieldType { .. } => "E0124",
+            AnalysisError::FieldNotPublic { .. } => "E0125",
+            AnalysisError::MismatchedFieldType { .. } => "E0126",
+            AnalysisError::MismatchedArgType { .. } => "E0127",
+            AnalysisError::UsedPrivateType { .. } => "E0128",
+            AnalysisError::UsedPrivateFunc { .. } => "E0129",
+            AnalysisError::WrongCast { .. } => "E0130",
+            AnalysisError::FuncNotFound { .. } => "E0131",
+            AnalysisError::ChainAfterVoid { .. } => "E0132",
+            AnalysisError::UnImportedBuiltinType { .. } => "E0133",
+            AnalysisError::AccessInterfaceEmptyFn { .. } => "E0134",
+            AnalysisError::CannotCallNonFnType { .. } => "E0135",
+            AnalysisError::UnknownType { .. } => "E0136",
+            AnalysisError::StaticFnNotMethod { .. } => "E0137",
+            AnalysisError::BasicTypeAsCaller { .. } => "E0138",
+            AnalysisError::MismatchedSelfType { .. } => "E0139",
+            AnalysisError::BinOpWrongType { .. } => "E0140",
+            AnalysisError::EqualsWrongType { .. } => "E0141",
+            AnalysisError::RangeWrongArgType { .. } => "E0142",
+            AnalysisError::CannotIter { .. } => "E0143",
+            AnalysisError::CannotMatch { .. } => "E0144",
+            AnalysisError::MismatchedPatternType { .. } => "E0145",
+            AnalysisError::UnknownEnumValue { .. } => "E0146",
+            AnalysisError::NonExhaustiveMatch { .. } => "E0147",
+            AnalysisError::MissingEnumValue { .. } => "E0148",
+            AnalysisError::MismatchedEnumValueNum { .. } => "E0149",
+            AnalysisError::MismatchedEnumValueType { .. } => "E0150",
+            AnalysisError::BindNoneEnumValue { .. } => "E0151",
+            AnalysisError::ParentNotAClass { .. } => "E0152",
+            AnalysisError::ImplNotInterface { .. } => "E0153",
+            AnalysisError::UnexpectedSelf { .. } => "E0154",
+            AnalysisError::InterfaceExtendNonInterface { .. } => "E0155",
+            AnalysisError::GenericError { .. } => "E0156",
+            AnalysisError::FnNotImpl { .. } => "E0157",
+            AnalysisError::MismatchedImplReturnType { .. } => "E0158",
+            AnalysisError::MismatchedImplParamType { .. } => "E0159",
+            AnalysisError::MismatchImplParamLen { .. } => "E0160",
+            AnalysisError::MismatchedGenericNum { .. } => "E0161",
+            AnalysisError::BoundNotInterface { .. } => "E0162",
+            AnalysisError::UnsatisfiedBound { .. } => "E0163",
+            AnalysisError::CannotInferGeneric { .. } => "E0164",
+            AnalysisError::UnhashableType { .. } => "E0165",
+            AnalysisError::CannotIndex { .. } => "E0166",
+            AnalysisError::CannotSlice { .. } => "E0167",
+            AnalysisError::MismatchedIndexType { .. } => "E0168",
+            AnalysisError::BadTupleIndex { .. } => "E0169",
+            AnalysisError::ImmutableElement { .. } => "E0170",
+            AnalysisError::UnaryOpWrongType { .. } => "E0171",
+            AnalysisError::WeakOfBasicType { .. } => "E0172",
+            AnalysisError::NotGeneratorReturnType { .. } => "E0173",
+            AnalysisError::MismatchedYieldType { .. } => "E0174",
+            AnalysisError::NotTask { .. } => "E0175",
+        }
+    }
+
+    // 错误提到的标识符 用于定位错误 the identifier mentioned in error, used to locate the error
+    pub fn symbol(&self) -> Option<&str> {
+        let symbol = match self {
+            AnalysisError::VarAlreadyOccupied { symbol, .. } => symbol,
+            AnalysisError::MismatchedArgsNum { func_name, .. } => func_name,
+            AnalysisError::VarDeclMismatchedType { var, .. } => var,
+            AnalysisError::UnknownField { name, .. } => name,
+            AnalysisError::UndefinedVar { var, .. } => var,
+            AnalysisError::FieldNotPublic { name, .. } => name,
+            AnalysisError::MismatchedFieldType { name, .. } => name,
+            AnalysisError::MismatchedArgType { func, .. } => func,
+            AnalysisError::UsedPrivateType { typ, .. } => typ,
+            AnalysisError::UsedPrivateFunc { func, .. } => func,
+            AnalysisError::FuncNotFound { func, .. } => func,
+            AnalysisError::ChainAfterVoid { func, .. } => func,
+            AnalysisError::UnImportedBuiltinType { typ, .. } => typ,
+            AnalysisError::UnknownType { typ } => typ,
+            AnalysisError::StaticFnNotMethod { func, .. } => func,
+            AnalysisError::BasicTypeAsCaller { func, .. } => func,
+            AnalysisError::MismatchedSelfType { func, .. } => func,
+            AnalysisError::UnknownEnumValue { value, .. } => value,
+            AnalysisError::MismatchedEnumValueNum { value, .. } => value,
+            AnalysisError::MismatchedEnumValueType { value, .. } => value,
+            AnalysisError::BindNoneEnumValue { value, .. } => value,
+            _ => return Option::None,
+        };
+        Option::Some(symbol.as_str())
+    }
+
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            AnalysisError::FieldNotPublic { help, .. } => Option::Some(help),
+            AnalysisError::VarAlreadyOccupied { .. } => {
+                Option::Some("variable shadowing is not supported, use another name")
+            }
+            AnalysisError::UnexpectBreak { .. } => {
+                Option::Some("'break' can only be used in while loop or for-in loop")
+            }
+            AnalysisError::UnexpectContinue { .. } => {
+                Option::Some("'continue' can only be used in while loop or for-in loop")
+            }
+            AnalysisError::StaticInLoopOfIfElse { .. } => Option::Some(
+                "move the static variable declaration to the outermost block of function or script",
+            ),
+            AnalysisError::ThrowNotString { .. } => {
+                Option::Some("throw a String or a caught Error, for example : throw \"error message\"")
+            }
+            AnalysisError::UndefinedVar { .. } => {
+                Option::Some("declare the variable by 'let' before using it")
+            }
+            AnalysisError::UsedPrivateType { .. } | AnalysisError::UsedPrivateFunc { .. } => {
+                Option::Some("add 'pub' before the declaration to make it public")
+            }
+            AnalysisError::UnImportedBuiltinType { .. } => {
+                Option::Some("import the std library which provides this type")
+            }
+            AnalysisError::FnNotImpl { .. } => Option::Some(
+                "implement the function in the class with the same params and return type",
+            ),
+            AnalysisError::UnsatisfiedBound { .. } => {
+                Option::Some("implement the interface of bound by the class of generic type")
+            }
+            AnalysisError::CannotInferGeneric { .. } => Option::Some(
+                "use the generic parameter in the types of function params or class fields",
+            ),
+            AnalysisError::UnhashableType { .. } => {
+                Option::Some("only int, char, bool, String and tuple of them are hashable")
+            }
+            AnalysisError::CannotIndex { .. } => {
+                Option::Some("only Array, Queue, String, tuple and Map could be indexed")
+            }
+            AnalysisError::CannotSlice { .. } => {
+                Option::Some("only Array, Queue and String could be sliced")
+            }
+            AnalysisError::ImmutableElement { .. } => {
+                Option::Some("only the elements of Array, Queue and Map could be assigned")
+            }
+            AnalysisError::WeakOfBasicType { .. } => {
+                Option::Some("only the objects of reference type could be weakly referenced")
+            }
+            AnalysisError::NotGeneratorReturnType { .. } => {
+                Option::Some("declare the return type as Generator<T>, T is the type of yielded values")
+            }
+            AnalysisError::NotTask { .. } => {
+                Option::Some("a Task is returned by calling an async function, sleep or yieldNow")
+            }
+            AnalysisError::CalcInplaceRightMismatchedType {
+                ops: Token::MulEq | Token::DivEq | Token::ModEq,
+                ..
+            } => Option::Some("the right value must be int when the left value is int"),
+            AnalysisError::BinOpWrongType { op, .. } => match op {
+                BinOp::Shl | BinOp::Shr => Option::Some("the operands of shift must be int"),
+                BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => {
+                    Option::Some("the operands of bitwise operator must be both int or both bool")
+                }
+                _ => Option::None,
+            },
+            _ => Option::None,
+        }
+    }
 }
@@ -0,0 +1,249 @@
+use crate::frontend::cst::{CstToken, Group, Node, SyntaxTree, Trivia};
+use crate::frontend::token::Token;
+
+const INDENT: &str = "    ";
+
+// 格式化器 保留注释和换行 统一缩进、空白、可省略的分号、返回值箭头和类型标记
+// formatter, keeps comments and line breaks, unifies indentation, whitespaces,
+// the optional semicolons, return arrows and type marks
+pub struct Formatter {
+    lines: Vec<String>,
+    line: String,
+    // 当前行的缩进层级 indent level of current line
+    line_indent: usize,
+    // 新起一行时的缩进层级 indent level of a new line
+    indent: usize,
+    // 下一个记号之前是否需要空格 whether a space is needed before next token
+    space: bool,
+    // 是否跳过了空行 whether a blank line was skipped
+    blank: bool,
+    // 上一个记号是否是左括号 whether the last token is an opening bracket
+    after_open: bool,
+}
+
+impl Formatter {
+    pub fn format(tree: &SyntaxTree) -> String {
+        let mut formatter = Formatter {
+            lines: Vec::new(),
+            line: String::new(),
+            line_indent: 0,
+            indent: 0,
+            space: false,
+            blank: false,
+            after_open: false,
+        };
+        formatter.nodes(&tree.nodes, Option::None);
+        formatter.trivia(&tree.trailing, false);
+        if !formatter.line.is_empty() {
+            formatter.new_line();
+        }
+        let mut result = formatter.lines.join("\n");
+        result.push('\n');
+        result
+    }
+
+    fn nodes(&mut self, nodes: &[Node], close: Option<&CstToken>) {
+        for (idx, node) in nodes.iter().enumerate() {
+            match node {
+                Node::Token(token) => match token.token {
+                    Token::Semi if Self::is_redundant_semi(nodes, idx, close) => {
+                        self.trivia(&token.leading, false);
+                    }
+                    Token::Colon | Token::SingleArrow => {
+                        self.trivia(&token.leading, false);
+                        self.mark(&token.text);
+                    }
+                    _ => self.token(token),
+                },
+                Node::Group(group) => self.group(group),
+            }
+            if let Some(mark) = Self::omitted_mark(nodes, idx) {
+                self.mark(mark);
+            }
+        }
+    }
+
+    fn group(&mut self, group: &Group) {
+        self.token(&group.open);
+        self.after_open = true;
+        let outer_indent = self.indent;
+        let open_indent = self.line_indent;
+        self.indent = open_indent + 1;
+        self.nodes(&group.children, group.close.as_ref());
+        if let Some(close) = &group.close {
+            self.trivia(&close.leading, true);
+            // 独占一行的右括号与左括号所在行对齐 a closing bracket at line start aligns with the line of opening bracket
+            self.indent = open_indent;
+            self.write(&close.text, true);
+        }
+        self.indent = outer_indent;
+    }
+
+    fn token(&mut self, token: &CstToken) {
+        self.trivia(&token.leading, false);
+        self.write(&token.text, false);
+    }
+
+    // 类型标记和返回值箭头两侧各有一个空格 there is a space on both sides of type marks and return arrows
+    fn mark(&mut self, mark: &str) {
+        self.space = true;
+        self.write(mark, false);
+        self.space = true;
+    }
+
+    fn trivia(&mut self, trivia: &[Trivia], before_close: bool) {
+        for item in trivia.iter() {
+            match item {
+                Trivia::Newline => {
+                    if self.line.is_empty() {
+                        self.blank = true;
+                    } else {
+                        self.new_line();
+                    }
+                }
+                Trivia::Whitespace(_) => self.space = true,
+                Trivia::LineComment(text) | Trivia::BlockComment(text) => {
+                    if !self.line.is_empty() {
+                        self.space = true;
+                    }
+                    self.write(text.trim_end(), before_close);
+                }
+                Trivia::Skipped(text) => self.write(text, before_close),
+            }
+        }
+    }
+
+    fn write(&mut self, text: &str, skip_blank: bool) {
+        if self.line.is_empty() {
+            // 多个空行合并为一个 不保留文件开头、左括号之后和右括号之前的空行
+            // blank lines are merged into one, and removed at the beginning of file, after opening brackets and before closing brackets
+            if self.blank && !self.lines.is_empty() && !self.after_open && !skip_blank {
+                self.lines.push(String::new());
+            }
+            self.line_indent = self.indent;
+            self.line.push_str(&INDENT.repeat(self.indent));
+        } else if self.space {
+            self.line.push(' ');
+        }
+        self.line.push_str(text);
+        self.space = false;
+        self.blank = false;
+        self.after_open = false;
+    }
+
+    fn new_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.lines.push(line.trim_end().to_string());
+        self.space = false;
+        self.blank = false;
+    }
+
+    // 行尾的分号可以省略 除非它位于块的末尾(决定块是否有值)或者省略后下一行会被连接到当前语句
+    // a semicolon at line end could be omitted, unless it is at the end of a block (decides whether the block has a value),
+    // or the next line would be joined into current statement without it
+    fn is_redundant_semi(nodes: &[Node], idx: usize, close: Option<&CstToken>) -> bool {
+        if idx > 0 {
+            if let Node::Token(CstToken {
+                token: Token::Return,
+                ..
+            }) = nodes[idx - 1]
+            {
+                return false;
+            }
+        }
+        let next = match nodes.get(idx + 1) {
+            Some(node) => Self::first_token(node),
+            // 文件末尾 end of file
+            None => return close.is_none(),
+        };
+        next.leading.contains(&Trivia::Newline)
+            && matches!(
+                next.token,
+                Token::Id(_)
+                    | Token::Int(_)
+                    | Token::Num(_)
+                    | Token::Str(_)
+                    | Token::Char(_)
+                    | Token::Bool(_)
+                    | Token::Let
+                    | Token::Static
+                    | Token::Func
+                    | Token::Return
+                    | Token::If
+                    | Token::While
+                    | Token::For
+                    | Token::Break
+                    | Token::Continue
+                    | Token::Try
+                    | Token::Throw
+                    | Token::Yield
+                    | Token::Async
+                    | Token::Await
+                    | Token::Spawn
+                    | Token::Class
+                    | Token::Interface
+                    | Token::Enum
+                    | Token::Import
+                    | Token::Pub
+                    | Token::Match
+            )
+    }
+
+    // 省略了的类型标记 'let a int = 1' 或返回值箭头 'func f() int {}'
+    // the omitted type mark like 'let a int = 1' or return arrow like 'func f() int {}'
+    fn omitted_mark(nodes: &[Node], idx: usize) -> Option<&'static str> {
+        let next = Self::first_token(nodes.get(idx + 1)?);
+        if next.leading.contains(&Trivia::Newline) {
+            return Option::None;
+        }
+        let token_at = |idx: Option<usize>| match idx.and_then(|idx| nodes.get(idx)) {
+            Some(Node::Token(token)) => Option::Some(&token.token),
+            _ => Option::None,
+        };
+        match &nodes[idx] {
+            Node::Token(CstToken {
+                token: Token::Id(_),
+                ..
+            }) => match token_at(idx.checked_sub(1)) {
+                Some(Token::Let) | Some(Token::Static)
+                    if !matches!(next.token, Token::Eq | Token::Colon) =>
+                {
+                    Option::Some(":")
+                }
+                _ => Option::None,
+            },
+            Node::Group(Group {
+                open:
+                    CstToken {
+                        token: Token::LParen,
+                        ..
+                    },
+                ..
+            }) => {
+                let is_func = match token_at(idx.checked_sub(1)) {
+                    Some(Token::Func) => true,
+                    Some(Token::Id(_)) => matches!(token_at(idx.checked_sub(2)), Some(Token::Func)),
+                    _ => false,
+                };
+                if is_func
+                    && !matches!(
+                        next.token,
+                        Token::LBrace | Token::SingleArrow | Token::Func | Token::RBrace
+                    )
+                {
+                    Option::Some("->")
+                } else {
+                    Option::None
+                }
+            }
+            _ => Option::None,
+        }
+    }
+
+    fn first_token(node: &Node) -> &CstToken {
+        match node {
+            Node::Token(token) => token,
+            Node::Group(group) => &group.open,
+        }
+    }
+}
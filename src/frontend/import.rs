@@ -4,8 +4,9 @@ use std::io::{Error, Read};
 
 use hashbrown::HashSet;
 
-use crate::frontend::parse::Parser;
+use crate::frontend::parse::{ParseError, Parser};
 use crate::frontend::script::ParsedFile;
+use crate::frontend::token::Token;
 use crate::frontend::tokenize::Tokenizer;
 use crate::obj::refcount::RefCount;
 use crate::stdlib::StdLibKind;
@@ -19,7 +20,7 @@ impl Importer {
     pub fn import_file(
         name: String,
         importer: RefCount<Importer>,
-    ) -> Result<Option<ParsedFile>, Error> {
+    ) -> Result<Option<ParsedFile>, ParseError> {
         {
             let mut importer_mut = importer.inner_mut();
             let contains = importer_mut.file_set.contains(name.as_str());
@@ -28,16 +29,40 @@ impl Importer {
             }
             importer_mut.file_set.insert(name.clone());
         }
-        let path = name.as_str();
+        let src = Self::read(name.as_str()).map_err(|err| {
+            // 由导入该文件的解析器定位 located by the parser which imports this file
+            ParseError::new(0, format!("failed to read file {} : {}", name, err))
+        })?;
+        let mut tokenizer = Tokenizer::new(src);
+        let (tokens, lines, spans) = tokenizer.tokenize();
+        let parser: Parser = Parser::new(tokens, lines, spans, importer.clone(), name);
+        Result::Ok(Option::Some(parser.parse()?))
+    }
+    // 解析一段源码 用于REPL parse a piece of source code, used by REPL
+    pub fn import_str(
+        src: String,
+        name: String,
+        importer: RefCount<Importer>,
+    ) -> Result<ParsedFile, ParseError> {
+        let mut tokenizer = Tokenizer::new(src.into_bytes());
+        let (mut tokens, mut lines, mut spans) = tokenizer.tokenize();
+        // 解析器不会把最后一个记号作为语句的开头 追加一个'}'作为结尾
+        // the parser never starts a statement with the last token, so append a '}' as the end
+        lines.push(lines.last().copied().unwrap_or(1));
+        spans.push(*spans.last().unwrap());
+        tokens.push(Token::RBrace);
+        Parser::new(tokens, lines, spans, importer, name).parse()
+    }
+    fn read(path: &str) -> Result<Vec<u8>, Error> {
         let mut file = File::open(path)?;
         let mut src: Vec<u8> = Vec::with_capacity(256);
-        file.read_to_end(&mut src).unwrap();
-        let mut tokenizer = Tokenizer::new(src);
-        let (tokens, lines) = tokenizer.tokenize();
-        let parser: Parser = Parser::new(tokens, lines, importer.clone(), name);
-        Result::Ok(Option::Some(parser.parse().unwrap()))
+        file.read_to_end(&mut src)?;
+        Result::Ok(src)
     }
-    pub fn import_std_lib(name: &str, importer: RefCount<Importer>) -> Result<(), String> {
+    pub fn import_std_lib(
+        name: &str,
+        importer: RefCount<Importer>,
+    ) -> Result<StdLibKind, String> {
         match StdLibKind::try_from(name) {
             Ok(kind) => {
                 let mut importer = importer.inner_mut();
@@ -45,7 +70,7 @@ impl Importer {
                 if !already_exists {
                     importer.std_set.insert(kind);
                 }
-                Result::Ok(())
+                Result::Ok(kind)
             }
             Err(err) => Result::Err(err),
         }
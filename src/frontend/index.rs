@@ -98,6 +98,14 @@ impl SlotIndexer {
         self.drop_vec_stack.pop().unwrap()
     }
 
+    // 出错时退出未正常离开的子块 leave the sub blocks that were not left normally when error occurs
+    pub fn sub_block_depth(&self) -> usize {
+        self.drop_vec_stack.len()
+    }
+    pub fn restore_sub_block(&mut self, depth: usize) {
+        self.drop_vec_stack.truncate(depth);
+    }
+
     pub fn get_type(&self, index: u16) -> &DataType {
         self.types.get(index as usize).unwrap()
     }
@@ -106,6 +114,10 @@ impl SlotIndexer {
         (self.max_idx + 1) as u16
     }
 
+    pub fn types(&self) -> &Vec<DataType> {
+        &self.types
+    }
+
     pub fn curr_drop_vec(&self) -> &Vec<u16> {
         self.drop_vec_stack.last().unwrap()
     }
@@ -133,6 +145,20 @@ impl SlotIndexer {
             drop_vec_stack: vec![Vec::new()],
         }
     }
+
+    // 从已分配的槽位类型恢复 用于加载编译后的镜像
+    // restore from the types of allocated slots, used when loading compiled image
+    pub fn from_types(types: Vec<DataType>) -> SlotIndexer {
+        let mut indexer = SlotIndexer::new();
+        for (index, data_type) in types.iter().enumerate() {
+            if let DataType::Ref(_) = data_type {
+                indexer.drop_vec_stack[0].push(index as u16);
+            }
+        }
+        indexer.max_idx = types.len() as i16 - 1;
+        indexer.types = types;
+        indexer
+    }
 }
 
 impl Debug for SlotIndexer {
@@ -1,11 +1,15 @@
 pub mod analysis;
 pub mod ast;
+pub mod cst;
+pub mod diagnostic;
 pub mod error;
+pub mod format;
 pub mod import;
 pub mod index;
 pub mod ops;
 pub mod parse;
 pub mod script;
 pub mod status;
+pub mod symbol;
 pub mod token;
 pub mod tokenize;
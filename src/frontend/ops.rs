@@ -7,6 +7,9 @@ pub enum LeftValueOp {
     Assign(Expression),
     PlusEq(Expression),
     SubEq(Expression),
+    MulEq(Expression),
+    DivEq(Expression),
+    ModEq(Expression),
     PlusOne,
     SubOne,
 }
@@ -23,6 +26,21 @@ pub enum BinOp {
     // *
     Div,
     // /
+    Mod,
+    // %
+    Pow,
+    // **
+    // bitwise : int to int, '&' '|' '^' also bool to bool
+    BitAnd,
+    // &
+    BitOr,
+    // |
+    BitXor,
+    // ^
+    Shl,
+    // <<
+    Shr,
+    // >>
     // compare : num to bool
     Gt,
     // >
@@ -39,8 +57,8 @@ pub enum BinOp {
     // !=
     // logic : bool to bool
     And,
-    // && &
-    Or, // || |
+    // &&
+    Or, // ||
 }
 
 pub enum BinOpType {
@@ -48,6 +66,7 @@ pub enum BinOpType {
     Compare,
     Equal,
     Logic,
+    Bitwise,
 }
 
 impl BinOp {
@@ -58,6 +77,13 @@ impl BinOp {
             BinOp::Sub => BinOpType::Calculate,
             BinOp::Mul => BinOpType::Calculate,
             BinOp::Div => BinOpType::Calculate,
+            BinOp::Mod => BinOpType::Calculate,
+            BinOp::Pow => BinOpType::Calculate,
+            BinOp::BitAnd => BinOpType::Bitwise,
+            BinOp::BitOr => BinOpType::Bitwise,
+            BinOp::BitXor => BinOpType::Bitwise,
+            BinOp::Shl => BinOpType::Bitwise,
+            BinOp::Shr => BinOpType::Bitwise,
             BinOp::Gt => BinOpType::Compare,
             BinOp::Lt => BinOpType::Compare,
             BinOp::GtEq => BinOpType::Compare,
@@ -80,6 +106,13 @@ impl Display for BinOp {
                 BinOp::Sub => "'-'",
                 BinOp::Mul => "'*'",
                 BinOp::Div => "'/'",
+                BinOp::Mod => "'%'",
+                BinOp::Pow => "'**'",
+                BinOp::BitAnd => "'&'",
+                BinOp::BitOr => "'|'",
+                BinOp::BitXor => "'^'",
+                BinOp::Shl => "'<<'",
+                BinOp::Shr => "'>>'",
                 BinOp::Gt => "'>'",
                 BinOp::Lt => "'<'",
                 BinOp::GtEq => "'>='",
@@ -3,17 +3,22 @@ use std::fmt::{Debug, Display, Formatter};
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
+use hashbrown::hash_map::Entry;
+use hashbrown::HashMap;
+
 use crate::frontend::ast::{
     BinOpVec, Chain, Construction, ExprType, Expression, ForIter, ForLoop, FuncExpr, IfBranch,
-    IfElse, LeftValue, ParsedClass, ParsedEnum, ParsedFunc, ParsedInterface, ParsedType,
-    SingleType, Statement, TypeTuple, Var, VarId, WhileLoop,
+    IfElse, LeftValue, MatchBranch, MatchDef, ParsedClass, ParsedEnum, ParsedFunc, ParsedGeneric,
+    ParsedInterface,
+    ParsedType, Pattern, SingleType, Statement, TryCatch, TypeTuple, Var, VarId, WhileLoop,
 };
 use crate::frontend::import::Importer;
 use crate::frontend::ops::{BinOp, LeftValueOp};
 use crate::frontend::script::ParsedFile;
-use crate::frontend::token::Token;
+use crate::frontend::token::{Span, Token};
 use crate::obj::refcount::RefCount;
 use crate::obj::types::{BasicType, DataType, RefType};
+use crate::stdlib::StdLibKind;
 
 pub struct Parser {
     tokens: Vec<Token>,
@@ -23,16 +28,35 @@ pub struct Parser {
     enums: Vec<(ParsedEnum, bool)>,
     funcs: Vec<(Rc<String>, ParsedFunc, bool)>,
     imports: Vec<ParsedFile>,
+    std_libs: Vec<StdLibKind>,
     importer: RefCount<Importer>,
     path: String,
+    // 正在解析的函数体中是否出现了yield whether yield occurs in the function bodies being parsed
+    yields: Vec<bool>,
     pub lines: Vec<u16>,
+    pub spans: Vec<Span>,
+    // 每行第一条语句的范围 用于定位分析错误 the span of the first statement at each line, used to locate analysis errors
+    statement_spans: HashMap<u16, Span>,
 }
 
 impl Parser {
     pub fn parse(mut self) -> Result<ParsedFile, ParseError> {
-        let vec = self.statements()?;
+        let vec = match self.statements() {
+            Ok(vec) => vec,
+            Err(err) => return Result::Err(self.locate(err)),
+        };
+        let ident_spans = self
+            .tokens
+            .iter()
+            .zip(self.spans.iter())
+            .filter_map(|(token, span)| match token {
+                Token::Id(name) => Option::Some((name.clone(), *span)),
+                _ => Option::None,
+            })
+            .collect();
         Result::Ok(ParsedFile {
             imports: self.imports,
+            std_libs: self.std_libs,
             classes: self.classes,
             interfaces: self.interfaces,
             funcs: self.funcs,
@@ -40,19 +64,23 @@ impl Parser {
             statements: vec,
             path: self.path,
             index: 0,
+            statement_spans: self.statement_spans,
+            ident_spans,
         })
     }
 
     fn statements(&mut self) -> Result<Vec<Statement>, ParseError> {
         let mut statements = Vec::new();
         while self.has_next() && !self.test_next(Token::RBrace) {
+            let start = self.curr;
             let token = self.next();
             let statement = match token {
                 Token::Continue => {
+                    let line = self.prev_line();
                     if self.has_next() && self.test_next(Token::Semi) {
                         self.forward();
                     }
-                    Statement::Continue(self.line())
+                    Statement::Continue(line)
                 }
                 Token::Return => {
                     let index = self.curr;
@@ -70,7 +98,7 @@ impl Parser {
                     result_statement
                 }
                 Token::Break => {
-                    let line = self.line();
+                    let line = self.prev_line();
                     if self.has_next() && self.test_next(Token::Semi) {
                         self.forward();
                     }
@@ -101,6 +129,7 @@ impl Parser {
                     Statement::Let(Box::new((Var::Name(var_name), parsed_type, expr, line)))
                 }
                 Token::Static => {
+                    let line = self.line();
                     let var_name = self.identifier()?;
                     let mut parsed_type: Option<ParsedType> = None;
                     if self.test_next(Token::Eq) {
@@ -120,7 +149,7 @@ impl Parser {
                     if self.has_next() && self.test_next(Token::Semi) {
                         self.forward();
                     }
-                    Statement::Static(Box::new((Var::Name(var_name), parsed_type, expr)))
+                    Statement::Static(Box::new((Var::Name(var_name), parsed_type, expr, line)))
                 }
                 Token::RBrace => {
                     self.backward();
@@ -137,7 +166,9 @@ impl Parser {
                     let importer = self.importer.clone();
                     match self.next().clone() {
                         Token::Id(lib) => {
-                            Importer::import_std_lib(lib.as_str(), importer).unwrap();
+                            let kind = Importer::import_std_lib(lib.as_str(), importer)
+                                .map_err(|err| ParseError::new(self.prev_line(), err))?;
+                            self.std_libs.push(kind);
                         }
                         Token::Str(path) => {
                             let mut path = path.to_string();
@@ -162,9 +193,7 @@ impl Parser {
                                 new_path.push_str(path.as_str());
                                 path = new_path;
                             }
-                            if let Some(parsed_file) =
-                                Importer::import_file(path, importer).unwrap()
-                            {
+                            if let Some(parsed_file) = Importer::import_file(path, importer)? {
                                 self.imports.push(parsed_file);
                             }
                         }
@@ -201,7 +230,15 @@ impl Parser {
                         self.funcs.push((func_name, func, true));
                         continue;
                     }
+                    Token::Async => {
+                        self.assert_next(Token::Func)?;
+                        let func_name = self.identifier()?;
+                        let func = self.parse_async_func(false)?;
+                        self.funcs.push((func_name, func, true));
+                        continue;
+                    }
                     Token::Static => {
+                        let line = self.line();
                         let var_name = self.identifier()?;
                         let mut parsed_type: Option<ParsedType> = None;
                         if self.test_next(Token::Eq) {
@@ -222,7 +259,12 @@ impl Parser {
                         if self.has_next() && self.test_next(Token::Semi) {
                             self.forward();
                         }
-                        Statement::PubStatic(Box::new((Var::Name(var_name), parsed_type, expr)))
+                        Statement::PubStatic(Box::new((
+                            Var::Name(var_name),
+                            parsed_type,
+                            expr,
+                            line,
+                        )))
                     }
                     token => {
                         let line = *self.lines.get(self.curr).unwrap();
@@ -278,6 +320,28 @@ impl Parser {
                         continue;
                     }
                 }
+                Token::Async => {
+                    self.assert_next(Token::Func)?;
+                    if self.test_next(Token::LParen) {
+                        // 匿名异步函数 let curr pointer to Token::Async
+                        // nameless async func, let curr pointer to Token::Async
+                        self.backward();
+                        self.backward();
+                        let line = self.line();
+                        let expr = self.expr()?;
+                        if self.has_next() && self.test_next(Token::Semi) {
+                            self.forward();
+                            Statement::Discard(expr, line)
+                        } else {
+                            Statement::Expr(expr, line)
+                        }
+                    } else {
+                        let func_name = self.identifier()?;
+                        let func = self.parse_async_func(false)?;
+                        self.funcs.push((func_name, func, false));
+                        continue;
+                    }
+                }
                 // while循环 while-loop
                 Token::While => {
                     let line = self.line();
@@ -293,6 +357,57 @@ impl Parser {
                         return_void: false,
                     }))
                 }
+                // 异常处理 try-catch
+                Token::Try => {
+                    let line = self.line();
+                    self.assert_next(Token::LBrace)?;
+                    let statements = self.statements()?;
+                    self.assert_next(Token::RBrace)?;
+                    self.assert_next(Token::Catch)
+                        .map_err(|e| e.msg("expect a 'catch' after the block of 'try'"))?;
+                    let var = if self.test_next(Token::Underline) {
+                        self.forward();
+                        Option::None
+                    } else {
+                        Option::Some(Var::Name(self.identifier()?))
+                    };
+                    self.assert_next(Token::LBrace)?;
+                    let catch_statements = self.statements()?;
+                    self.assert_next(Token::RBrace)?;
+                    Statement::Try(Box::new(TryCatch {
+                        statements,
+                        drop_slots: Vec::with_capacity(0),
+                        var,
+                        catch_statements,
+                        catch_drop_slots: Vec::with_capacity(0),
+                        line,
+                    }))
+                }
+                Token::Throw => {
+                    let line = self.line();
+                    let expr = self.expr()?;
+                    if self.has_next() && self.test_next(Token::Semi) {
+                        self.forward();
+                    }
+                    Statement::Throw(expr, line)
+                }
+                Token::Yield => {
+                    let line = self.line();
+                    match self.yields.last_mut() {
+                        Some(yielded) => *yielded = true,
+                        None => {
+                            return Result::Err(ParseError::new(
+                                line,
+                                String::from("'yield' outside of function"),
+                            ))
+                        }
+                    }
+                    let expr = self.expr()?;
+                    if self.has_next() && self.test_next(Token::Semi) {
+                        self.forward();
+                    }
+                    Statement::Yield(expr, line)
+                }
                 // for-循环 for-loop
                 Token::For => {
                     let var_name = self.identifier()?;
@@ -336,6 +451,7 @@ impl Parser {
                         drop_slots: Vec::new(),
                         line,
                         return_void: false,
+                        suspend: false,
                     }))
                 }
                 _ => {
@@ -349,18 +465,31 @@ impl Parser {
                             Token::Eq
                             | Token::SubEq
                             | Token::PlusEq
+                            | Token::MulEq
+                            | Token::DivEq
+                            | Token::ModEq
                             | Token::SubSub
                             | Token::PlusPlus => {
                                 let left_value = match expr {
                                     Expression::Var(var) => LeftValue::Var(*var),
                                     Expression::Chain(chain_box) => {
-                                        let (_, chains) = chain_box.deref();
-                                        if let Chain::Access(_, _) = chains.last().unwrap() {
-                                        } else {
-                                            panic!()
+                                        let (expr, mut chains) = *chain_box;
+                                        match chains.pop().unwrap() {
+                                            Chain::Access(field, basic_type) => {
+                                                chains.push(Chain::Access(field, basic_type));
+                                                LeftValue::Chain(expr, chains)
+                                            }
+                                            // 对下标赋值 assign to subscript
+                                            Chain::Index(index, basic_type) => {
+                                                let container = if chains.is_empty() {
+                                                    expr
+                                                } else {
+                                                    Expression::Chain(Box::new((expr, chains)))
+                                                };
+                                                LeftValue::Index(Box::new((container, *index, basic_type)))
+                                            }
+                                            _ => panic!(),
                                         }
-                                        let (expr, chains) = *chain_box;
-                                        LeftValue::Chain(expr, chains)
                                     }
                                     _ => panic!(),
                                 };
@@ -368,6 +497,9 @@ impl Parser {
                                     Token::Eq => LeftValueOp::Assign(self.expr()?),
                                     Token::SubEq => LeftValueOp::SubEq(self.expr()?),
                                     Token::PlusEq => LeftValueOp::PlusEq(self.expr()?),
+                                    Token::MulEq => LeftValueOp::MulEq(self.expr()?),
+                                    Token::DivEq => LeftValueOp::DivEq(self.expr()?),
+                                    Token::ModEq => LeftValueOp::ModEq(self.expr()?),
                                     Token::SubSub => LeftValueOp::SubOne,
                                     Token::PlusPlus => LeftValueOp::PlusOne,
                                     _ => panic!(),
@@ -376,7 +508,7 @@ impl Parser {
                                     // handle ';'
                                     self.forward();
                                 }
-                                Statement::LeftValueOp(Box::new((left_value, left_value_op)))
+                                Statement::LeftValueOp(Box::new((left_value, left_value_op, line)))
                             }
                             _ => {
                                 self.backward();
@@ -388,86 +520,169 @@ impl Parser {
                     }
                 }
             };
+            self.mark_statement(start, statement.line());
             statements.push(statement)
         }
         Result::Ok(statements)
     }
 
-    fn expr(&mut self) -> Result<Expression, ParseError> {
-        let expr = self.medium_expr()?;
-        let mut op_vec: Option<Vec<(BinOp, Expression)>> = Option::None;
-        while self.has_next() {
-            let bin_op_expr: Option<(BinOp, Expression)>;
-            match self.next() {
-                Token::Plus => bin_op_expr = Some((BinOp::Plus, self.medium_expr()?)),
-                Token::Sub => bin_op_expr = Some((BinOp::Sub, self.medium_expr()?)),
-                Token::And => bin_op_expr = Some((BinOp::And, self.medium_expr()?)),
-                Token::Or => bin_op_expr = Some((BinOp::Or, self.medium_expr()?)),
-                _ => {
-                    self.backward();
-                    break;
-                }
+    // 外层的语句在内层的之后解析完成 所以保留同一行中开始最早的语句
+    // the outer statement is parsed after the inner ones, so keep the statement starts earliest in the same line
+    fn mark_statement(&mut self, start: usize, line: u16) {
+        let (first, last) = match (self.spans.get(start), self.spans.get(self.curr - 1)) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return,
+        };
+        let span = Span::new(first.start as usize, last.end.max(first.end) as usize);
+        match self.statement_spans.entry(line) {
+            Entry::Vacant(entry) => {
+                entry.insert(span);
             }
-            match bin_op_expr {
-                Some(op_tuple) => match &mut op_vec {
-                    None => {
-                        let mut vec = Vec::new();
-                        vec.push(op_tuple);
-                        op_vec = Some(vec);
-                    }
-                    Some(vec) => vec.push(op_tuple),
-                },
-                None => break,
+            Entry::Occupied(mut entry) => {
+                if span.start < entry.get().start {
+                    entry.insert(span);
+                }
             }
         }
-        match op_vec {
-            Some(vec) => Result::Ok(Expression::BinaryOp(Box::new(BinOpVec { left: expr, vec }))),
-            None => Result::Ok(expr),
-        }
+    }
+
+    // 二元运算符的优先级从高到低 precedence of binary operators from high to low :
+    // '**' > '* / %' > '+ -' > '<< >>' > '&' > '^' > '|' > comparison > '&&' > '||'
+    fn expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::and_expr, |_, token| match token {
+            Token::Or => Option::Some(BinOp::Or),
+            _ => Option::None,
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::compare_expr, |_, token| match token {
+            Token::And => Option::Some(BinOp::And),
+            _ => Option::None,
+        })
+    }
+
+    fn compare_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::bit_or_expr, |_, token| match token {
+            Token::Gt => Option::Some(BinOp::Gt),
+            Token::Lt => Option::Some(BinOp::Lt),
+            Token::Eqs => Option::Some(BinOp::Eqs),
+            Token::GtEq => Option::Some(BinOp::GtEq),
+            Token::LtEq => Option::Some(BinOp::LtEq),
+            Token::NotEq => Option::Some(BinOp::NotEq),
+            _ => Option::None,
+        })
+    }
+
+    fn bit_or_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::bit_xor_expr, |_, token| match token {
+            Token::BitOr => Option::Some(BinOp::BitOr),
+            _ => Option::None,
+        })
+    }
+
+    fn bit_xor_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::bit_and_expr, |_, token| match token {
+            Token::BitXor => Option::Some(BinOp::BitXor),
+            _ => Option::None,
+        })
+    }
+
+    fn bit_and_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::shift_expr, |_, token| match token {
+            Token::BitAnd => Option::Some(BinOp::BitAnd),
+            _ => Option::None,
+        })
+    }
+
+    fn shift_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::plus_expr, |parser, token| match token {
+            Token::Shl => Option::Some(BinOp::Shl),
+            // 相邻的两个'>'是右移 two adjacent '>' is shift right
+            Token::Gt if parser.is_shr() => {
+                parser.forward();
+                Option::Some(BinOp::Shr)
+            }
+            _ => Option::None,
+        })
+    }
+
+    fn plus_expr(&mut self) -> Result<Expression, ParseError> {
+        self.binary_expr(Self::medium_expr, |_, token| match token {
+            Token::Plus => Option::Some(BinOp::Plus),
+            Token::Sub => Option::Some(BinOp::Sub),
+            _ => Option::None,
+        })
     }
 
     fn medium_expr(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = self.primary_expr()?;
+        self.binary_expr(Self::cast_expr, |_, token| match token {
+            Token::Mul => Option::Some(BinOp::Mul),
+            Token::Div => Option::Some(BinOp::Div),
+            Token::Mod => Option::Some(BinOp::Mod),
+            _ => Option::None,
+        })
+    }
+
+    fn cast_expr(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.power_expr()?;
         if self.has_next() && self.test_next(Token::As) {
             self.forward();
             let parsed_type = self.parse_type()?;
             expr = Expression::Cast(Box::new((expr, parsed_type, DataType::Ref(RefType::Any))));
         }
-        let mut op_vec: Option<Vec<(BinOp, Expression)>> = Option::None;
+        Result::Ok(expr)
+    }
+
+    // 同一优先级的运算符是左结合的 operators of the same precedence are left associative
+    fn binary_expr(
+        &mut self,
+        operand: fn(&mut Parser) -> Result<Expression, ParseError>,
+        operator: fn(&mut Parser, &Token) -> Option<BinOp>,
+    ) -> Result<Expression, ParseError> {
+        let expr = operand(self)?;
+        let mut op_vec: Vec<(BinOp, Expression)> = Vec::new();
         while self.has_next() {
-            let bin_op_expr: Option<(BinOp, Expression)>;
-            match self.next() {
-                Token::Mul => bin_op_expr = Some((BinOp::Mul, self.primary_expr()?)),
-                Token::Div => bin_op_expr = Some((BinOp::Div, self.primary_expr()?)),
-                Token::Gt => bin_op_expr = Some((BinOp::Gt, self.primary_expr()?)),
-                Token::Lt => bin_op_expr = Some((BinOp::Lt, self.primary_expr()?)),
-                Token::Eqs => bin_op_expr = Some((BinOp::Eqs, self.primary_expr()?)),
-                Token::GtEq => bin_op_expr = Some((BinOp::GtEq, self.primary_expr()?)),
-                Token::LtEq => bin_op_expr = Some((BinOp::LtEq, self.primary_expr()?)),
-                Token::NotEq => bin_op_expr = Some((BinOp::NotEq, self.primary_expr()?)),
-                _ => {
+            let token = self.next().clone();
+            match operator(self, &token) {
+                Some(op) => op_vec.push((op, operand(self)?)),
+                None => {
                     self.backward();
                     break;
                 }
             }
-            match bin_op_expr {
-                Some(op_tuple) => match &mut op_vec {
-                    None => {
-                        let mut vec = Vec::new();
-                        vec.push(op_tuple);
-                        op_vec = Some(vec);
-                    }
-                    Some(vec) => {
-                        vec.push(op_tuple);
-                    }
-                },
-                None => break,
-            }
         }
-        match op_vec {
-            Some(vec) => Result::Ok(Expression::BinaryOp(Box::new(BinOpVec { left: expr, vec }))),
-            None => Result::Ok(expr),
+        if op_vec.is_empty() {
+            Result::Ok(expr)
+        } else {
+            Result::Ok(Expression::BinaryOp(Box::new(BinOpVec {
+                left: expr,
+                vec: op_vec,
+            })))
+        }
+    }
+
+    // 乘方是右结合的 power is right associative, like '2 ** 3 ** 2'
+    fn power_expr(&mut self) -> Result<Expression, ParseError> {
+        let expr = self.primary_expr()?;
+        if self.has_next() && self.test_next(Token::Pow) {
+            self.forward();
+            let exponent = self.power_expr()?;
+            return Result::Ok(Expression::BinaryOp(Box::new(BinOpVec {
+                left: expr,
+                vec: vec![(BinOp::Pow, exponent)],
+            })));
         }
+        Result::Ok(expr)
+    }
+
+    // '>>'是两个记号 以免与嵌套的泛型冲突 比如'Array<Array<int>>'
+    // '>>' is two tokens to avoid conflict with nested generics, like 'Array<Array<int>>'
+    #[inline]
+    fn is_shr(&self) -> bool {
+        self.has_next()
+            && self.test_next(Token::Gt)
+            && self.spans[self.curr - 1].end == self.spans[self.curr].start
     }
 
     fn primary_expr(&mut self) -> Result<Expression, ParseError> {
@@ -482,10 +697,21 @@ impl Parser {
             // 变量、函数调用或成员变量访问 variable、func call or member field access
             Token::Id(id) => {
                 let var_name = id.clone();
+                // 泛型类的对象构造 object construct of generic class, like 'Stack<int>{ ... }'
+                let mut class_type = Option::None;
+                if self.has_next() && self.test_next(Token::Lt) {
+                    let index = self.curr;
+                    self.backward();
+                    match self.parse_type() {
+                        Ok(parsed_type) if self.has_next() && self.test_next(Token::LBrace) => {
+                            class_type = Option::Some(parsed_type);
+                        }
+                        _ => self.rollback(index),
+                    }
+                }
                 // object construct
-                if self.has_next() && self.test_next(Token::LBrace) {
+                if class_type.is_some() || (self.has_next() && self.test_next(Token::LBrace)) {
                     self.forward();
-                    let class_name = var_name.clone();
                     let mut fields = Vec::new();
                     while self.has_next() {
                         match self.next() {
@@ -500,11 +726,12 @@ impl Parser {
                             }
                         }
                     }
+                    let class_type = class_type.unwrap_or(ParsedType::Single(SingleType {
+                        name: var_name,
+                        generic: None,
+                    }));
                     Expression::Construct(Box::new(Construction {
-                        class_type: ExprType::Parsed(ParsedType::Single(SingleType {
-                            name: class_name,
-                            generic: None,
-                        })),
+                        class_type: ExprType::Parsed(class_type),
                         fields,
                     }))
                 } else {
@@ -549,7 +776,7 @@ impl Parser {
                         self.assert_next(Token::RBrace)?;
                         break;
                     } else {
-                        println!("unexpected token near if-else {:?}", self.peek());
+                        eprintln!("unexpected token near if-else {:?}", self.peek());
                         break;
                     }
                 }
@@ -569,7 +796,7 @@ impl Parser {
                     } else if self.test_next(Token::Comma) {
                         self.forward();
                     } else {
-                        println!(
+                        eprintln!(
                             "unexpected token {:?} near tuple parse, line {}",
                             self.peek(),
                             self.line()
@@ -585,59 +812,130 @@ impl Parser {
             // 数组/队列 Array Queue
             Token::LBracket => {
                 let mut vec: Vec<Expression> = Vec::new();
-                while self.has_next() {
-                    vec.push(self.expr()?);
-                    let line = self.line();
-                    match self.next() {
-                        Token::RBracket => {
-                            break;
-                        }
-                        Token::Comma => {}
-                        token => {
-                            return Err(ParseError::new(line, format!("unexpect token when parse array, expect ']' or ',', found {:?}", token)));
+                if self.test_next(Token::RBracket) {
+                    // 空数组 empty array
+                    self.forward();
+                } else {
+                    while self.has_next() {
+                        vec.push(self.expr()?);
+                        let line = self.line();
+                        match self.next() {
+                            Token::RBracket => {
+                                break;
+                            }
+                            Token::Comma => {}
+                            token => {
+                                return Err(ParseError::new(line, format!("unexpect token when parse array, expect ']' or ',', found {:?}", token)));
+                            }
                         }
                     }
                 }
                 Expression::Array(Box::new((vec, BasicType::Ref, false)))
             }
+            // 映射 {k : v} 或 集合 {a, b}  map {k : v} or set {a, b}
+            Token::LBrace => {
+                if self.test_next(Token::RBrace) {
+                    // 空的花括号是空映射 empty braces is empty map
+                    self.forward();
+                    Expression::Map(Box::default())
+                } else {
+                    let first = self.expr()?;
+                    if self.test_next(Token::Colon) {
+                        let mut entries = Vec::new();
+                        let mut key = first;
+                        loop {
+                            self.assert_next(Token::Colon)?;
+                            entries.push((key, self.expr()?));
+                            let line = self.line();
+                            match self.next() {
+                                Token::RBrace => break,
+                                Token::Comma => key = self.expr()?,
+                                token => {
+                                    return Err(ParseError::new(line, format!("unexpect token when parse map, expect '}}' or ',', found {:?}", token)));
+                                }
+                            }
+                        }
+                        Expression::Map(Box::new(entries))
+                    } else {
+                        let mut elems = vec![first];
+                        loop {
+                            let line = self.line();
+                            match self.next() {
+                                Token::RBrace => break,
+                                Token::Comma => elems.push(self.expr()?),
+                                token => {
+                                    return Err(ParseError::new(line, format!("unexpect token when parse set, expect '}}' or ',', found {:?}", token)));
+                                }
+                            }
+                        }
+                        Expression::Set(Box::new(elems))
+                    }
+                }
+            }
             // 匿名函数 Anonymous Function
             // 立即执行函数 immediate exec func
             Token::Func => {
                 let func = self.parse_func(false)?;
                 Expression::Func(Box::new(FuncExpr::Parsed(func)))
             }
+            Token::Async => {
+                self.assert_next(Token::Func)?;
+                let func = self.parse_async_func(false)?;
+                Expression::Func(Box::new(FuncExpr::Parsed(func)))
+            }
+            // 等待或派生任务 await or spawn a task
+            Token::Await => Expression::Await(Box::new((self.primary_expr()?, false))),
+            Token::Spawn => Expression::Spawn(Box::new(self.primary_expr()?)),
             // 匹配 match
             Token::Match => {
                 self.assert_next(Token::LParen)?;
-                let expr = self.expr()?;
+                let matched = self.expr()?;
                 self.assert_next(Token::RParen)?;
                 self.assert_next(Token::LBrace)?;
                 let mut branches = Vec::new();
                 while self.has_next() {
                     match self.next() {
                         Token::RBrace => break,
+                        Token::Comma => continue,
                         _ => {
                             self.backward();
-                            let try_matched = self.expr()?;
+                            let line = self.line();
+                            let pattern = self.parse_pattern()?;
                             self.assert_next(Token::DoubleArrow)?;
-                            if self.test_next(Token::LBrace) {
+                            let statements = if self.test_next(Token::LBrace) {
+                                // pattern => { statements }
                                 self.forward();
                                 let statements = self.statements()?;
-                                branches.push((try_matched, statements));
                                 self.assert_next(Token::RBrace)?;
+                                statements
                             } else {
-                                let statements = self.statements()?;
-                                branches.push((try_matched, statements));
-                                self.assert_next(Token::Comma)?;
-                            }
+                                // pattern => expression ,
+                                let start = self.curr;
+                                let line = self.line();
+                                let statement = Statement::Expr(self.expr()?, line);
+                                self.mark_statement(start, line);
+                                vec![statement]
+                            };
+                            branches.push(MatchBranch {
+                                pattern,
+                                statements,
+                                drop_vec: Vec::with_capacity(0),
+                                line,
+                            });
                         }
                     }
                 }
-                Expression::Match(RefCount::new((expr, branches)))
+                Expression::Match(Box::new(MatchDef {
+                    matched,
+                    branches,
+                    return_void: false,
+                    matched_slot: Option::None,
+                }))
             }
             // 一元操作 Unary operation
             Token::Not => Expression::NotOp(Box::new(self.expr()?)),
             Token::Sub => Expression::NegOp(Box::new(self.expr()?)),
+            Token::BitNot => Expression::BitNotOp(Box::new(self.primary_expr()?)),
             token => {
                 return Result::Err(ParseError::new(
                     line,
@@ -645,7 +943,11 @@ impl Parser {
                 ));
             }
         };
-        if self.has_next() && (self.test_next(Token::Dot) || self.test_next(Token::LParen)) {
+        if self.has_next()
+            && (self.test_next(Token::Dot)
+                || self.test_next(Token::LParen)
+                || self.test_next(Token::LBracket))
+        {
             let mut chains = Vec::new();
             while self.has_next() {
                 match self.next() {
@@ -653,6 +955,7 @@ impl Parser {
                         // field access
                         let field_name = self.identifier()?;
                         if self.has_next() && self.test_next(Token::LParen) {
+                            let line = self.line();
                             self.forward();
                             let mut args = Vec::new();
                             while self.has_next() {
@@ -671,6 +974,7 @@ impl Parser {
                                 args,
                                 need_self: false,
                                 is_dyn: false,
+                                line,
                             })
                         } else {
                             chains.push(Chain::Access(VarId::Name(field_name), BasicType::Ref));
@@ -678,6 +982,7 @@ impl Parser {
                     }
                     Token::LParen => {
                         // function call
+                        let line = self.prev_line();
                         let mut args = Vec::new();
                         while self.has_next() {
                             match self.next() {
@@ -690,7 +995,20 @@ impl Parser {
                                 }
                             }
                         }
-                        chains.push(Chain::Call(args))
+                        chains.push(Chain::Call(args, line))
+                    }
+                    Token::LBracket => {
+                        // 下标或切片 subscript or slice
+                        let index = self.expr()?;
+                        if self.test_next(Token::DotDot) {
+                            self.forward();
+                            let to = self.expr()?;
+                            self.assert_next(Token::RBracket)?;
+                            chains.push(Chain::Slice(Box::new((index, to))));
+                        } else {
+                            self.assert_next(Token::RBracket)?;
+                            chains.push(Chain::Index(Box::new(index), BasicType::Ref));
+                        }
                     }
                     _ => {
                         self.backward();
@@ -703,7 +1021,63 @@ impl Parser {
         Result::Ok(expr)
     }
 
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let line = self.line();
+        let pattern = match self.next() {
+            Token::Underline => Pattern::Wildcard,
+            Token::Int(int) => Pattern::Int(*int),
+            Token::Char(ch) => Pattern::Char(*ch),
+            Token::Bool(bl) => Pattern::Bool(*bl),
+            Token::Str(str) => Pattern::Str(str.clone()),
+            Token::Sub => match self.next() {
+                Token::Int(int) => Pattern::Int(-*int),
+                token => {
+                    return Result::Err(ParseError::new(
+                        line,
+                        format!(
+                            "expect a int literal after '-' in pattern, found {:?}",
+                            token
+                        ),
+                    ))
+                }
+            },
+            // Enum.Value  Enum.Value(var)  Enum.Value(_)
+            Token::Id(enum_name) => {
+                let enum_name = enum_name.clone();
+                self.assert_next(Token::Dot)?;
+                let value_name = self.identifier()?;
+                let bind = if self.test_next(Token::LParen) {
+                    self.forward();
+                    let bind = if self.test_next(Token::Underline) {
+                        self.forward();
+                        Option::None
+                    } else {
+                        Option::Some(Var::Name(self.identifier()?))
+                    };
+                    self.assert_next(Token::RParen)?;
+                    bind
+                } else {
+                    Option::None
+                };
+                Pattern::Enum {
+                    enum_name,
+                    value_name,
+                    tag: 0,
+                    bind,
+                }
+            }
+            token => {
+                return Result::Err(ParseError::new(
+                    line,
+                    format!("unexpected token {:?} when parse match pattern", token),
+                ))
+            }
+        };
+        Result::Ok(pattern)
+    }
+
     fn parse_func(&mut self, is_mem_func: bool) -> Result<ParsedFunc, ParseError> {
+        let generics = self.parse_generics()?;
         self.assert_next(Token::LParen)?;
         let mut param_vec = Vec::new();
         while self.has_next() {
@@ -733,17 +1107,38 @@ impl Parser {
             return_type = Some(self.parse_type()?)
         }
         self.assert_next(Token::LBrace)?;
-        let statements = self.statements()?;
+        self.yields.push(false);
+        let statements = self.statements();
+        let is_generator = self.yields.pop().unwrap();
+        let statements = statements?;
         self.assert_next(Token::RBrace)?;
         Result::Ok(ParsedFunc {
+            generics,
             params: param_vec,
             body: statements,
             return_type,
+            is_generator,
+            is_async: false,
         })
     }
 
+    // 'async func' 之后的部分 the part after 'async func'
+    fn parse_async_func(&mut self, is_mem_func: bool) -> Result<ParsedFunc, ParseError> {
+        let line = self.line();
+        let mut func = self.parse_func(is_mem_func)?;
+        if func.is_generator {
+            return Result::Err(ParseError::new(
+                line,
+                String::from("'yield' is not allowed in async function"),
+            ));
+        }
+        func.is_async = true;
+        Result::Ok(func)
+    }
+
     fn parse_class(&mut self) -> Result<ParsedClass, ParseError> {
         let name = self.identifier()?;
+        let generics = self.parse_generics()?;
         let mut parent_class = None;
         let mut impl_vec = Vec::with_capacity(0);
         let mut field_vec = Vec::new();
@@ -757,14 +1152,14 @@ impl Parser {
         if self.test_next(Token::Impl) {
             self.forward();
             while self.has_next() {
-                impl_vec.push(self.identifier()?);
+                impl_vec.push(
+                    self.parse_type()
+                        .map_err(|e| e.msg("expect an interface name"))?,
+                );
                 if self.test_next(Token::Comma) {
                     self.forward();
-                }
-                if self.test_next(Token::LBrace) {
-                    break;
                 } else {
-                    panic!()
+                    break;
                 }
             }
         }
@@ -790,6 +1185,13 @@ impl Parser {
                 let func_name = self.identifier()?;
                 let parsed_func = self.parse_func(true)?;
                 func_vec.push((is_public, func_name, parsed_func));
+            } else if self.test_next(Token::Async) {
+                // async function
+                self.forward();
+                self.assert_next(Token::Func)?;
+                let func_name = self.identifier()?;
+                let parsed_func = self.parse_async_func(true)?;
+                func_vec.push((is_public, func_name, parsed_func));
             } else {
                 // field
                 let parsed_type = self
@@ -803,6 +1205,7 @@ impl Parser {
         }
         Result::Ok(ParsedClass {
             name,
+            generics,
             parent: parent_class,
             impl_interfaces: impl_vec,
             fields: field_vec,
@@ -812,6 +1215,7 @@ impl Parser {
 
     fn parse_interface(&mut self) -> Result<ParsedInterface, ParseError> {
         let name = self.identifier()?;
+        let generics = self.parse_generics()?;
         let mut parents = Vec::new();
         if self.test_next(Token::Colon) {
             self.forward();
@@ -882,11 +1286,43 @@ impl Parser {
         }
         Result::Ok(ParsedInterface {
             name,
+            generics,
             parents,
             funcs,
         })
     }
 
+    // 泛型参数 generic parameters, like '<T : Comparable<T>, E>'
+    fn parse_generics(&mut self) -> Result<Vec<ParsedGeneric>, ParseError> {
+        if !self.test_next(Token::Lt) {
+            return Result::Ok(Vec::with_capacity(0));
+        }
+        self.forward();
+        let mut generics = Vec::new();
+        while self.has_next() {
+            match self.next() {
+                Token::Gt => break,
+                Token::Comma => continue,
+                _ => {
+                    self.backward();
+                    let name = self
+                        .identifier()
+                        .map_err(|e| e.msg("expect a generic parameter name"))?;
+                    let mut bound = Option::None;
+                    if self.test_next(Token::Colon) {
+                        self.forward();
+                        bound = Option::Some(
+                            self.parse_type()
+                                .map_err(|e| e.msg("expect an interface as bound"))?,
+                        );
+                    }
+                    generics.push((name, bound));
+                }
+            }
+        }
+        Result::Ok(generics)
+    }
+
     fn parse_enum(&mut self) -> Result<ParsedEnum, ParseError> {
         let enum_name = self.identifier()?;
         let mut enum_values = Vec::new();
@@ -895,6 +1331,7 @@ impl Parser {
         while self.has_next() {
             match self.next().clone() {
                 Token::RBrace => break,
+                Token::Comma => continue,
                 Token::Id(id) => {
                     let value_name = id.clone();
                     if self.test_next(Token::LParen) {
@@ -909,10 +1346,16 @@ impl Parser {
                     }
                 }
                 Token::Pub => {
-                    if let Token::Func = self.next() {
+                    let token = self.next().clone();
+                    if let Token::Func = token {
                         let name = self.identifier()?;
                         let func = self.parse_func(true)?;
                         funcs.push((name, true, func));
+                    } else if let Token::Async = token {
+                        self.assert_next(Token::Func)?;
+                        let name = self.identifier()?;
+                        let func = self.parse_async_func(true)?;
+                        funcs.push((name, true, func));
                     } else {
                         return Result::Err(ParseError::new(
                             self.line(),
@@ -925,6 +1368,12 @@ impl Parser {
                     let func = self.parse_func(true)?;
                     funcs.push((name, false, func));
                 }
+                Token::Async => {
+                    self.assert_next(Token::Func)?;
+                    let name = self.identifier()?;
+                    let func = self.parse_async_func(true)?;
+                    funcs.push((name, false, func));
+                }
                 token => {
                     return Result::Err(ParseError::new(
                         self.line(),
@@ -957,10 +1406,12 @@ impl Parser {
             self.curr += 1;
             Result::Ok(())
         } else {
-            Result::Err(ParseError::new(
+            let mut err = ParseError::new(
                 self.line(),
                 format!("[assert_next] expect token {:?} in fact {:?}", token, curr),
-            ))
+            );
+            err.span = self.spans.get(self.curr).copied();
+            Result::Err(err)
         }
     }
     #[inline]
@@ -992,12 +1443,36 @@ impl Parser {
             Result::Ok(name.clone())
         } else {
             Result::Err(ParseError::new(
-                self.line(),
+                self.prev_line(),
                 format!("expect identifier in fact found token {:?}", curr),
             ))
         }
     }
 
+    // 为错误补充所在文件和出错记号的范围 add the file and the span of wrong token to the error
+    fn locate(&self, mut err: ParseError) -> ParseError {
+        // 已经被导入文件的解析器定位 already located by the parser of imported file
+        if !err.path.is_empty() {
+            return err;
+        }
+        if err.line == 0 {
+            err.line = self.lines.get(self.curr).copied().unwrap_or(0);
+        }
+        if err.span.is_some() {
+            err.path = self.path.clone();
+            return err;
+        }
+        // 出错的记号通常刚被消耗 the token caused error is usually just consumed
+        let curr = self.curr.min(self.tokens.len());
+        let index = [curr.saturating_sub(1), curr]
+            .iter()
+            .copied()
+            .find(|index| self.lines.get(*index) == Some(&err.line));
+        err.span = index.and_then(|index| self.spans.get(index).copied());
+        err.path = self.path.clone();
+        err
+    }
+
     #[inline]
     fn line(&self) -> u16 {
         match self.lines.get(self.curr) {
@@ -1008,6 +1483,12 @@ impl Parser {
         }
     }
 
+    // 刚刚读取的token所在的行 the line of the token just read
+    #[inline]
+    fn prev_line(&self) -> u16 {
+        *self.lines.get(self.curr - 1).unwrap()
+    }
+
     fn parse_type(&mut self) -> Result<ParsedType, ParseError> {
         if self.test_next(Token::LParen) {
             self.forward();
@@ -1052,20 +1533,25 @@ impl Parser {
     pub fn new(
         tokens: Vec<Token>,
         lines: Vec<u16>,
+        spans: Vec<Span>,
         importer: RefCount<Importer>,
         path: String,
     ) -> Parser {
         return Parser {
             tokens,
             lines,
+            spans,
             curr: 0,
             classes: Vec::with_capacity(0),
             interfaces: Vec::with_capacity(0),
             enums: Vec::with_capacity(0),
             funcs: Vec::new(),
             imports: Vec::new(),
+            std_libs: Vec::new(),
             importer,
             path,
+            yields: Vec::new(),
+            statement_spans: HashMap::new(),
         };
     }
 }
@@ -1073,19 +1559,23 @@ impl Parser {
 pub struct ParseError {
     pub line: u16,
     pub reason: String,
+    pub span: Option<Span>,
+    pub path: String,
 }
 
 impl ParseError {
     pub fn new(line: u16, reason: String) -> ParseError {
-        ParseError { line, reason }
+        ParseError {
+            line,
+            reason,
+            span: Option::None,
+            path: String::new(),
+        }
     }
 
     pub fn msg(mut self, msg: &'static str) -> ParseError {
         self.reason.push_str(msg);
-        ParseError {
-            line: self.line,
-            reason: self.reason,
-        }
+        self
     }
 }
 
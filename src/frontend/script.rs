@@ -1,10 +1,16 @@
 use std::rc::Rc;
 
-use crate::frontend::ast::{ParsedClass, ParsedEnum, ParsedFunc, ParsedInterface, Statement};
+use hashbrown::HashMap;
+
+use crate::frontend::ast::{Line, ParsedClass, ParsedEnum, ParsedFunc, ParsedInterface, Statement};
+use crate::frontend::token::Span;
 use crate::obj::func::GloomFunc;
+use crate::stdlib::StdLibKind;
 
 pub struct ParsedFile {
     pub imports: Vec<ParsedFile>,
+    // 导入的标准库 imported std libraries
+    pub std_libs: Vec<StdLibKind>,
     pub classes: Vec<(ParsedClass, bool)>,
     pub interfaces: Vec<(ParsedInterface, bool)>,
     pub enums: Vec<(ParsedEnum, bool)>,
@@ -12,6 +18,10 @@ pub struct ParsedFile {
     pub statements: Vec<Statement>,
     pub path: String,
     pub index: u16,
+    // 每行第一条语句的范围 the span of the first statement at each line
+    pub statement_spans: HashMap<Line, Span>,
+    // 所有标识符的范围 按位置排列 the spans of all identifiers, sorted by position
+    pub ident_spans: Vec<(Rc<String>, Span)>,
 }
 
 #[derive(Debug)]
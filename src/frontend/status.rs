@@ -17,6 +17,8 @@ pub struct GloomStatus {
     pub enums: Vec<RefCount<GloomEnumClass>>,
     pub funcs: Vec<RefCount<GloomFunc>>,
     pub script_bodies: Vec<RefCount<ScriptBody>>,
+    // 源文件的路径 下标即为 file_index  paths of source files, the index is file_index
+    pub paths: Vec<String>,
 }
 
 impl Debug for GloomStatus {
@@ -48,7 +50,7 @@ impl TypeIndex {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MetaType {
     Interface,
     Class,
@@ -82,7 +84,27 @@ impl TypeIndex {
     }
 }
 
+// 已加载的声明的数量 用于增量地分析和生成代码
+// the count of loaded declarations, used for analysis and generate code incrementally
+#[derive(Copy, Clone)]
+pub struct DeclCount {
+    pub interfaces: usize,
+    pub classes: usize,
+    pub enums: usize,
+    pub funcs: usize,
+    pub script_bodies: usize,
+}
+
 impl GloomStatus {
+    pub fn decl_count(&self) -> DeclCount {
+        DeclCount {
+            interfaces: self.interfaces.len(),
+            classes: self.classes.len(),
+            enums: self.enums.len(),
+            funcs: self.funcs.len(),
+            script_bodies: self.script_bodies.len(),
+        }
+    }
     pub fn new() -> GloomStatus {
         GloomStatus {
             builtin_classes: BuiltinClass::classes(),
@@ -91,6 +113,7 @@ impl GloomStatus {
             enums: Vec::new(),
             funcs: BuiltInFuncs::func_list(),
             script_bodies: Vec::new(),
+            paths: Vec::new(),
         }
     }
 }
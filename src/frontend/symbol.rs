@@ -0,0 +1,29 @@
+use std::rc::Rc;
+
+use crate::frontend::status::MetaType;
+use crate::obj::types::DataType;
+
+// 分析器解析出的符号 用于语言服务器的悬停提示和跳转定义
+// symbol resolved by analyzer, used for hover and go-to-definition of language server
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub file_index: u16,
+    pub line: u16,
+    pub name: Rc<String>,
+    pub data_type: DataType,
+    pub kind: SymbolKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum SymbolKind {
+    // 局部变量或静态变量 local or static variable
+    Var,
+    // 脚本中声明的类型和函数 u16是声明所在的文件
+    // type and function declared in script, the u16 is the file where it's declared
+    Type(MetaType, u16),
+    Func(u16),
+    // 内置的类型和函数 没有定义的位置 builtin type and function, which have no location of definition
+    Builtin,
+    // 字段或函数 DataType是所属的类型 field or function, the DataType is the type it belongs to
+    Member(DataType),
+}
@@ -16,7 +16,21 @@ pub enum Token {
     // -
     Mul,
     // *
-    Div, // /
+    Div,
+    // /
+    Mod,
+    // %
+    Pow,
+    // **
+    BitAnd,
+    // &
+    BitOr,
+    // |
+    BitXor,
+    // ^
+    BitNot,
+    // ~
+    Shl, // <<
 
     Eq,
     // =
@@ -36,6 +50,12 @@ pub enum Token {
     // +=
     SubEq,
     // -=
+    MulEq,
+    // *=
+    DivEq,
+    // /=
+    ModEq,
+    // %=
     PlusPlus,
     // ++
     SubSub, // --
@@ -60,6 +80,8 @@ pub enum Token {
     // !
     Dot,
     // .
+    DotDot,
+    // ..
     Colon,
     // :
     DoubleArrow,
@@ -90,6 +112,13 @@ pub enum Token {
     Static,
     Enum,
     As,
+    Try,
+    Catch,
+    Throw,
+    Yield,
+    Async,
+    Await,
+    Spawn,
 }
 
 impl Display for Token {
@@ -109,6 +138,13 @@ impl Debug for Token {
                 Token::Sub => "'-'",
                 Token::Mul => "'*'",
                 Token::Div => "'/'",
+                Token::Mod => "'%'",
+                Token::Pow => "'**'",
+                Token::BitAnd => "'&'",
+                Token::BitOr => "'|'",
+                Token::BitXor => "'^'",
+                Token::BitNot => "'~'",
+                Token::Shl => "'<<'",
                 Token::Eq => "'='",
                 Token::Eqs => "'=='",
                 Token::Gt => "'>'",
@@ -118,6 +154,9 @@ impl Debug for Token {
                 Token::NotEq => "'!='",
                 Token::PlusEq => "'+='",
                 Token::SubEq => "'-='",
+                Token::MulEq => "'*='",
+                Token::DivEq => "'/='",
+                Token::ModEq => "'%='",
                 Token::PlusPlus => "'++'",
                 Token::SubSub => "'--'",
                 Token::LParen => "'('",
@@ -130,6 +169,7 @@ impl Debug for Token {
                 Token::Comma => "','",
                 Token::Not => "'!'",
                 Token::Dot => "'.'",
+                Token::DotDot => "'..'",
                 Token::Colon => "':'",
                 Token::SingleArrow => "->",
                 Token::Let => "'let'",
@@ -155,6 +195,13 @@ impl Debug for Token {
                 Token::Static => "'static'",
                 Token::Enum => "'enum'",
                 Token::As => "'as'",
+                Token::Try => "'try'",
+                Token::Catch => "'catch'",
+                Token::Throw => "'throw'",
+                Token::Yield => "'yield'",
+                Token::Async => "'async'",
+                Token::Await => "'await'",
+                Token::Spawn => "'spawn'",
 
                 Token::Int(n) => {
                     s = format!("'{}'", n);
@@ -184,3 +231,20 @@ impl Debug for Token {
         )
     }
 }
+
+// 记号在源码中的字节范围 左闭右开 the byte range of token in source code, half-open
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    #[inline]
+    pub fn new(start: usize, end: usize) -> Span {
+        Span {
+            start: start as u32,
+            end: end as u32,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::panic::panic_any;
 use std::rc::Rc;
 use std::str::FromStr;
 
-use crate::frontend::token::Token;
+use crate::frontend::token::{Span, Token};
 
 pub struct Tokenizer {
     src: Vec<u8>,
@@ -11,10 +11,12 @@ pub struct Tokenizer {
 }
 
 impl Tokenizer {
-    pub fn tokenize(&mut self) -> (Vec<Token>, Vec<u16>) {
+    pub fn tokenize(&mut self) -> (Vec<Token>, Vec<u16>, Vec<Span>) {
         let mut tokens: Vec<Token> = Vec::with_capacity(self.src.len() / 2);
         let mut lines: Vec<u16> = Vec::with_capacity(tokens.len());
+        let mut spans: Vec<Span> = Vec::with_capacity(tokens.len());
         while self.curr < self.src.len() {
+            let start = self.curr;
             let byte = *self.src.get(self.curr).unwrap();
             match byte {
                 b'\n' => {
@@ -29,7 +31,34 @@ impl Tokenizer {
                     lines.push(self.line);
                 }
                 b'*' => {
-                    tokens.push(Token::Mul);
+                    tokens.push(match self.peek_u8() {
+                        b'*' => {
+                            self.curr += 1;
+                            Token::Pow
+                        }
+                        b'=' => {
+                            self.curr += 1;
+                            Token::MulEq
+                        }
+                        _ => Token::Mul,
+                    });
+                    lines.push(self.line);
+                }
+                b'%' => {
+                    tokens.push(if self.peek_u8() == b'=' {
+                        self.curr += 1;
+                        Token::ModEq
+                    } else {
+                        Token::Mod
+                    });
+                    lines.push(self.line);
+                }
+                b'^' => {
+                    tokens.push(Token::BitXor);
+                    lines.push(self.line);
+                }
+                b'~' => {
+                    tokens.push(Token::BitNot);
                     lines.push(self.line);
                 }
                 b';' => {
@@ -49,7 +78,12 @@ impl Tokenizer {
                     lines.push(self.line);
                 }
                 b'.' => {
-                    tokens.push(Token::Dot);
+                    if self.src.get(self.curr + 1) == Option::Some(&b'.') {
+                        tokens.push(Token::DotDot);
+                        self.curr += 1;
+                    } else {
+                        tokens.push(Token::Dot);
+                    }
                     lines.push(self.line);
                 }
                 b'[' => {
@@ -118,23 +152,30 @@ impl Tokenizer {
                     if self.peek_u8() == b'=' {
                         self.curr += 1;
                         tokens.push(Token::LtEq)
+                    } else if self.peek_u8() == b'<' {
+                        self.curr += 1;
+                        tokens.push(Token::Shl)
                     } else {
                         tokens.push(Token::Lt)
                     };
                     lines.push(self.line);
                 }
                 b'&' => {
-                    if self.peek_u8() == b'&' {
+                    tokens.push(if self.peek_u8() == b'&' {
                         self.curr += 1;
-                    }
-                    tokens.push(Token::And);
+                        Token::And
+                    } else {
+                        Token::BitAnd
+                    });
                     lines.push(self.line);
                 }
                 b'|' => {
-                    if self.peek_u8() == b'|' {
+                    tokens.push(if self.peek_u8() == b'|' {
                         self.curr += 1;
-                    }
-                    tokens.push(Token::Or);
+                        Token::Or
+                    } else {
+                        Token::BitOr
+                    });
                     lines.push(self.line);
                 }
                 b'=' => {
@@ -159,6 +200,11 @@ impl Tokenizer {
                         self.curr += 1;
                         self.skip_annotation_block()
                     }
+                    b'=' => {
+                        self.curr += 1;
+                        tokens.push(Token::DivEq);
+                        lines.push(self.line);
+                    }
                     _ => {
                         tokens.push(Token::Div);
                         lines.push(self.line);
@@ -176,6 +222,7 @@ impl Tokenizer {
                 byte if (byte >= b'0' && byte <= b'9') || byte == b'-' => {
                     tokens.push(self.parse_num());
                     lines.push(self.line);
+                    spans.push(Span::new(start, self.curr));
                     // 此时self.curr已经指向下一个u8了，不应当+1
                     continue;
                 }
@@ -187,25 +234,34 @@ impl Tokenizer {
                 byte if Self::is_valid_header(byte) => {
                     tokens.push(self.parse_identifier());
                     lines.push(self.line);
+                    spans.push(Span::new(start, self.curr));
                     // 同上
                     continue;
                 }
                 byte if byte <= b' ' => {}
-                _ => println!("{}", byte as char),
+                _ => eprintln!("{}", byte as char),
+            }
+            // 其余记号在此记录范围 此时self.curr指向记号的最后一个u8
+            // the span of other tokens is recorded here, self.curr points to the last u8 of token now
+            if spans.len() < tokens.len() {
+                spans.push(Span::new(start, self.curr + 1));
             }
             self.curr += 1;
         }
         lines.push(self.line + 1);
-        (tokens, lines)
+        spans.push(Span::new(self.src.len(), self.src.len()));
+        (tokens, lines, spans)
     }
     fn parse_num(&mut self) -> Token {
         let mut vec: Vec<u8> = Vec::new();
         let mut is_float: bool = false;
         while self.curr < self.src.len() {
             let byte = *self.src.get(self.curr).unwrap();
+            // 1..3 中的点是范围而不是小数点 the dots in 1..3 is a range rather than decimal point
+            let is_range = byte == b'.' && self.src.get(self.curr + 1) == Option::Some(&b'.');
             if (byte >= b'0' && byte <= b'9') || byte == b'-' {
                 vec.push(byte);
-            } else if byte == b'.' && !is_float {
+            } else if byte == b'.' && !is_float && !is_range {
                 vec.push(byte);
                 is_float = true;
             } else {
@@ -245,6 +301,13 @@ impl Tokenizer {
             "for" => Token::For,
             "break" => Token::Break,
             "continue" => Token::Continue,
+            "try" => Token::Try,
+            "catch" => Token::Catch,
+            "throw" => Token::Throw,
+            "yield" => Token::Yield,
+            "async" => Token::Async,
+            "await" => Token::Await,
+            "spawn" => Token::Spawn,
             "class" => Token::Class,
             "interface" => Token::Interface,
             "import" => Token::Import,
@@ -343,7 +406,7 @@ impl Tokenizer {
         (byte >= b'a' && byte <= b'z') || (byte >= b'A' && byte <= b'Z') || byte == b'_'
     }
 
-    pub(crate) fn new(src: Vec<u8>) -> Tokenizer {
+    pub fn new(src: Vec<u8>) -> Tokenizer {
         Tokenizer {
             src,
             curr: 0,
@@ -1 +1,930 @@
+use std::any::Any;
+use std::mem::transmute;
+use std::panic::{self, AssertUnwindSafe};
 
+use cranelift::codegen;
+use cranelift::prelude::*;
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+use hashbrown::HashMap;
+
+use crate::bytecode::code::ByteCode;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{FuncBody, FuncInfo, GloomFunc, ReturnType};
+use crate::obj::types::DataType;
+use crate::vm::constant::ConstantPool;
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value as GloomValue};
+
+// 函数被调用的次数达到该值后尝试JIT编译
+// try to compile the function after it is called such times
+pub const JIT_THRESHOLD: u32 = 64;
+
+// JIT编译后的函数签名，参数与返回值均以i64传递，num以其二进制位表示，bool为0或1
+// signature of compiled function, args and result are passed as i64, num as its bits, bool as 0 or 1
+type JitFn = extern "C" fn(*const GloomVM, *const i64) -> i64;
+// 编译后的代码通过该函数调用其他函数  compiled code call other functions by this
+type JitCallFn = extern "C" fn(*const GloomVM, i64, *mut i64) -> i64;
+// 编译后的代码中整数运算溢出时调用该函数 compiled code call this when int arithmetic overflows
+type JitOverflowFn = extern "C" fn(*const GloomVM, i64);
+
+// 溢出的运算 以下标传给 overflow_from_jit  the overflowed operation, passed to overflow_from_jit by index
+const OVERFLOW_OPS: [&str; 4] = ["add", "subtract", "multiply", "negate"];
+const OVERFLOW_ADD: usize = 0;
+const OVERFLOW_SUB: usize = 1;
+const OVERFLOW_MUL: usize = 2;
+const OVERFLOW_NEG: usize = 3;
+
+pub struct GloomJit {
+    module: JITModule,
+    ctx: codegen::Context,
+    builder_ctx: FunctionBuilderContext,
+    records: HashMap<*const GloomFunc, JitRecord>,
+    compiled_count: usize,
+    // 编译后的代码中被调用的函数抛出的错误 the error thrown by function called in compiled code
+    error: Option<GloomError>,
+    // 被调用的函数中虚拟机内部的panic 返回到Rust栈帧后继续展开
+    // the internal panic of vm in the called function, continue to unwind after returned to rust frame
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+enum JitRecord {
+    Counting(u32),
+    Compiled(*const u8),
+    // 含有解释器才能处理的字节码 contains bytecode that only interpreter can handle
+    Unsupported,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Kind {
+    Int,
+    Num,
+    Bool,
+    // 只存在于编译期的值 values only exist when compiling
+    Func(u16),
+    Void,
+}
+
+impl Kind {
+    fn from_data_type(data_type: &DataType) -> Option<Kind> {
+        match data_type {
+            DataType::Int => Option::Some(Kind::Int),
+            DataType::Num => Option::Some(Kind::Num),
+            DataType::Bool => Option::Some(Kind::Bool),
+            _ => Option::None,
+        }
+    }
+    fn from_return_type(return_type: &ReturnType) -> Option<Kind> {
+        match return_type {
+            ReturnType::Void => Option::Some(Kind::Void),
+            ReturnType::Have(data_type) => Kind::from_data_type(data_type),
+        }
+    }
+    fn ir_type(self) -> Type {
+        match self {
+            Kind::Num => types::F64,
+            _ => types::I64,
+        }
+    }
+}
+
+impl GloomJit {
+    pub fn new() -> GloomJit {
+        let module = JITModule::new(JITBuilder::new(default_libcall_names()));
+        GloomJit {
+            ctx: module.make_context(),
+            module,
+            builder_ctx: FunctionBuilderContext::new(),
+            records: HashMap::new(),
+            compiled_count: 0,
+            error: Option::None,
+            panic: Option::None,
+        }
+    }
+
+    // 记录一次调用，返回已编译的函数 record a call, return the compiled function if there is
+    pub fn hot(
+        &mut self,
+        func: &GloomFunc,
+        constant_pool: &ConstantPool,
+        status: &GloomStatus,
+    ) -> Option<*const u8> {
+        let key = func as *const GloomFunc;
+        let count = match self.records.get_mut(&key) {
+            Some(JitRecord::Compiled(ptr)) => return Option::Some(*ptr),
+            Some(JitRecord::Unsupported) => return Option::None,
+            Some(JitRecord::Counting(count)) => {
+                *count += 1;
+                *count
+            }
+            None => {
+                self.records.insert(key, JitRecord::Counting(1));
+                1
+            }
+        };
+        if count < JIT_THRESHOLD {
+            return Option::None;
+        }
+        let result = match &func.body {
+            FuncBody::ByteCodes(bytecodes) => {
+                self.compile(&func.info, bytecodes, constant_pool, status)
+            }
+            _ => Option::None,
+        };
+        self.records.insert(
+            key,
+            match result {
+                Some(ptr) => JitRecord::Compiled(ptr),
+                None => JitRecord::Unsupported,
+            },
+        );
+        result
+    }
+
+    fn compile(
+        &mut self,
+        info: &FuncInfo,
+        bytecodes: &[ByteCode],
+        constant_pool: &ConstantPool,
+        status: &GloomStatus,
+    ) -> Option<*const u8> {
+        if !info.captures.is_empty() || !Self::is_supported(bytecodes) {
+            return Option::None;
+        }
+        let return_kind = Kind::from_return_type(&info.return_type)?;
+        let mut param_kinds = Vec::with_capacity(info.params.len());
+        for param in info.params.iter() {
+            param_kinds.push((param.index, Kind::from_data_type(&param.data_type)?));
+        }
+
+        let ptr_type = self.module.target_config().pointer_type();
+        self.ctx.func.signature.params.push(AbiParam::new(ptr_type));
+        self.ctx.func.signature.params.push(AbiParam::new(ptr_type));
+        self.ctx
+            .func
+            .signature
+            .returns
+            .push(AbiParam::new(types::I64));
+
+        let builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.builder_ctx);
+        let translator = Translator {
+            builder,
+            bytecodes,
+            constant_pool,
+            status,
+            ptr_type,
+            vm_ptr: Option::None,
+            return_kind,
+            stack: Vec::new(),
+            stack_vars: HashMap::new(),
+            locals: HashMap::new(),
+            blocks: HashMap::new(),
+            states: HashMap::new(),
+            var_count: 0,
+        };
+        if translator.translate(&param_kinds).is_none() {
+            // 翻译中途失败时构建器的状态是不完整的 the builder state is incomplete when failed halfway
+            self.builder_ctx = FunctionBuilderContext::new();
+            self.module.clear_context(&mut self.ctx);
+            return Option::None;
+        }
+
+        self.compiled_count += 1;
+        let name = format!("{}#{}", info.name, self.compiled_count);
+        let func_id = self
+            .module
+            .declare_function(name.as_str(), Linkage::Local, &self.ctx.func.signature)
+            .ok()?;
+        let defined = self.module.define_function(
+            func_id,
+            &mut self.ctx,
+            &mut codegen::binemit::NullTrapSink {},
+            &mut codegen::binemit::NullStackMapSink {},
+        );
+        self.module.clear_context(&mut self.ctx);
+        defined.ok()?;
+        self.module.finalize_definitions();
+        Option::Some(self.module.get_finalized_function(func_id))
+    }
+
+    #[inline]
+    pub fn set_error(&mut self, error: GloomError) {
+        self.error = Option::Some(error);
+    }
+
+    #[inline]
+    pub fn take_error(&mut self) -> Option<GloomError> {
+        self.error.take()
+    }
+
+    #[inline]
+    pub fn set_panic(&mut self, payload: Box<dyn Any + Send>) {
+        self.panic = Option::Some(payload);
+    }
+
+    #[inline]
+    pub fn take_panic(&mut self) -> Option<Box<dyn Any + Send>> {
+        self.panic.take()
+    }
+
+    fn is_supported(bytecodes: &[ByteCode]) -> bool {
+        bytecodes.iter().all(|code| {
+            matches!(
+                code,
+                ByteCode::Pop
+                    | ByteCode::CopyTop
+                    | ByteCode::LoadConstInt(_)
+                    | ByteCode::LoadConstNum(_)
+                    | ByteCode::LoadDirectInt(_)
+                    | ByteCode::LoadDirectNum(_)
+                    | ByteCode::LoadDirectBool(_)
+                    | ByteCode::ReadLocal(_, _)
+                    | ByteCode::WriteLocalInt(_, _)
+                    | ByteCode::WriteLocalNum(_, _)
+                    | ByteCode::WriteLocalBool(_, _)
+                    | ByteCode::DropLocal(_)
+                    | ByteCode::NotOp
+                    | ByteCode::NegOp
+                    | ByteCode::Plus
+                    | ByteCode::Sub
+                    | ByteCode::Mul
+                    | ByteCode::Div
+                    | ByteCode::BitNot
+                    | ByteCode::BitAnd
+                    | ByteCode::BitOr
+                    | ByteCode::BitXor
+                    | ByteCode::PlusOne
+                    | ByteCode::SubOne
+                    | ByteCode::GreaterThan
+                    | ByteCode::LessThan
+                    | ByteCode::GreaterThanEquals
+                    | ByteCode::LessThanEquals
+                    | ByteCode::Equals
+                    | ByteCode::NotEquals
+                    | ByteCode::LogicAnd
+                    | ByteCode::LogicOr
+                    | ByteCode::LoadDirectDefFn(_)
+                    | ByteCode::CallTopFn { .. }
+                    | ByteCode::AsInt
+                    | ByteCode::AsNum
+                    | ByteCode::AsBool
+                    | ByteCode::JumpIf(_)
+                    | ByteCode::JumpIfNot(_)
+                    | ByteCode::Jump(_)
+                    | ByteCode::Return
+            )
+        })
+    }
+}
+
+struct Translator<'a> {
+    builder: FunctionBuilder<'a>,
+    bytecodes: &'a [ByteCode],
+    constant_pool: &'a ConstantPool,
+    status: &'a GloomStatus,
+    ptr_type: Type,
+    vm_ptr: Option<Value>,
+    return_kind: Kind,
+    // 操作数栈上每个位置的每种类型对应一个变量，由cranelift负责SSA构造
+    // each kind at each depth of operand stack is a variable, cranelift does the SSA construction
+    stack: Vec<(Kind, Option<Value>)>,
+    stack_vars: HashMap<(usize, Kind), Variable>,
+    locals: HashMap<(u16, u8), (Kind, Variable)>,
+    blocks: HashMap<usize, Block>,
+    // 到达基本块时操作数栈的类型 kinds of operand stack when reach the block
+    states: HashMap<usize, Vec<Kind>>,
+    var_count: usize,
+}
+
+impl<'a> Translator<'a> {
+    fn translate(mut self, param_kinds: &[((u16, u8), Kind)]) -> Option<()> {
+        // 局部变量的类型由写入它的字节码决定，槽位不会被复用
+        // the kind of local is decided by the bytecode writes it, slots are never reused
+        let mut local_kinds: HashMap<(u16, u8), Kind> = param_kinds.iter().copied().collect();
+        for (idx, code) in self.bytecodes.iter().enumerate() {
+            let kind = match code {
+                ByteCode::WriteLocalInt(slot_idx, sub_idx) => ((*slot_idx, *sub_idx), Kind::Int),
+                ByteCode::WriteLocalNum(slot_idx, sub_idx) => ((*slot_idx, *sub_idx), Kind::Num),
+                ByteCode::WriteLocalBool(slot_idx, sub_idx) => ((*slot_idx, *sub_idx), Kind::Bool),
+                ByteCode::Jump(label) | ByteCode::JumpIf(label) | ByteCode::JumpIfNot(label) => {
+                    self.blocks.insert(*label as usize, Block::from_u32(0));
+                    self.blocks.insert(idx + 1, Block::from_u32(0));
+                    continue;
+                }
+                ByteCode::Return => {
+                    self.blocks.insert(idx + 1, Block::from_u32(0));
+                    continue;
+                }
+                _ => continue,
+            };
+            if *local_kinds.entry(kind.0).or_insert(kind.1) != kind.1 {
+                return Option::None;
+            }
+        }
+        let length = self.bytecodes.len();
+        self.blocks.insert(0, Block::from_u32(0));
+        self.blocks.insert(length, Block::from_u32(0));
+        if self.blocks.keys().any(|idx| *idx > length) {
+            return Option::None;
+        }
+        for block in self.blocks.values_mut() {
+            *block = self.builder.create_block();
+        }
+
+        // 入口块读取参数并初始化局部变量 entry block reads args and init locals
+        let entry = self.builder.create_block();
+        self.builder.append_block_params_for_function_params(entry);
+        self.builder.switch_to_block(entry);
+        self.vm_ptr = Option::Some(self.builder.block_params(entry)[0]);
+        let args_ptr = self.builder.block_params(entry)[1];
+        for (index, kind) in local_kinds.iter() {
+            let var = self.new_var(kind.ir_type());
+            let val = match param_kinds
+                .iter()
+                .position(|(param_index, _)| param_index == index)
+            {
+                Some(arg_idx) => self.builder.ins().load(
+                    kind.ir_type(),
+                    MemFlags::trusted(),
+                    args_ptr,
+                    (arg_idx * 8) as i32,
+                ),
+                None => self.zero(*kind),
+            };
+            self.builder.def_var(var, val);
+            self.locals.insert(*index, (*kind, var));
+        }
+        self.states.insert(0, Vec::new());
+        let first = *self.blocks.get(&0).unwrap();
+        self.builder.ins().jump(first, &[]);
+
+        let mut terminated = true;
+        for pc in 0..=length {
+            if let Some(block) = self.blocks.get(&pc).copied() {
+                if !terminated {
+                    self.record(pc)?;
+                    self.builder.ins().jump(block, &[]);
+                }
+                match self.states.get(&pc) {
+                    Some(state) => {
+                        let state = state.clone();
+                        self.builder.switch_to_block(block);
+                        self.stack = state.into_iter().map(|kind| (kind, None)).collect();
+                        terminated = false;
+                    }
+                    // 不可达的代码 unreachable code
+                    None => terminated = true,
+                }
+            }
+            if terminated {
+                continue;
+            }
+            if pc == length {
+                // 执行到末尾时与解释器一样没有结果 no result when reach the end, same as interpreter
+                let zero = self.builder.ins().iconst(types::I64, 0);
+                self.builder.ins().return_(&[zero]);
+                break;
+            }
+            terminated = self.translate_code(*self.bytecodes.get(pc).unwrap())?;
+        }
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+        Option::Some(())
+    }
+
+    // 返回是否是基本块的结尾 return whether it's the end of basic block
+    fn translate_code(&mut self, code: ByteCode) -> Option<bool> {
+        match code {
+            ByteCode::Pop => {
+                self.pop()?;
+            }
+            ByteCode::CopyTop => {
+                let (kind, val) = self.pop()?;
+                self.push(kind, val);
+                self.push(kind, val);
+            }
+            ByteCode::LoadConstInt(idx) => {
+                let int = *self.constant_pool.int.get(idx as usize)?;
+                let val = self.builder.ins().iconst(types::I64, int);
+                self.push(Kind::Int, Some(val));
+            }
+            ByteCode::LoadConstNum(idx) => {
+                let num = *self.constant_pool.num.get(idx as usize)?;
+                let val = self.builder.ins().f64const(num);
+                self.push(Kind::Num, Some(val));
+            }
+            ByteCode::LoadDirectInt(int) => {
+                let val = self.builder.ins().iconst(types::I64, int as i64);
+                self.push(Kind::Int, Some(val));
+            }
+            ByteCode::LoadDirectNum(num) => {
+                let val = self.builder.ins().f64const(num as f64);
+                self.push(Kind::Num, Some(val));
+            }
+            ByteCode::LoadDirectBool(bl) => {
+                let val = self.builder.ins().iconst(types::I64, bl as i64);
+                self.push(Kind::Bool, Some(val));
+            }
+            ByteCode::ReadLocal(slot_idx, sub_idx) => {
+                let (kind, var) = *self.locals.get(&(slot_idx, sub_idx))?;
+                let val = self.builder.use_var(var);
+                self.push(kind, Some(val));
+            }
+            ByteCode::WriteLocalInt(slot_idx, sub_idx)
+            | ByteCode::WriteLocalNum(slot_idx, sub_idx)
+            | ByteCode::WriteLocalBool(slot_idx, sub_idx) => {
+                let (kind, var) = *self.locals.get(&(slot_idx, sub_idx))?;
+                let (val_kind, val) = self.pop()?;
+                let val = self.convert(val_kind, val?, kind)?;
+                self.builder.def_var(var, val);
+            }
+            // 编译的函数中没有引用类型的局部变量 there is no local of ref type in compiled function
+            ByteCode::DropLocal(_) => {}
+            ByteCode::NotOp => {
+                let val = self.pop_kind(Kind::Bool)?;
+                let val = self.builder.ins().bxor_imm(val, 1);
+                self.push(Kind::Bool, Some(val));
+            }
+            ByteCode::NegOp => {
+                let (kind, val) = self.pop()?;
+                let val = match kind {
+                    Kind::Int => {
+                        let val = val?;
+                        let overflow = self.builder.ins().icmp_imm(IntCC::Equal, val, i64::MIN);
+                        self.check_overflow(overflow, OVERFLOW_NEG)?;
+                        self.builder.ins().ineg(val)
+                    }
+                    Kind::Num => self.builder.ins().fneg(val?),
+                    _ => return Option::None,
+                };
+                self.push(kind, Some(val));
+            }
+            ByteCode::Plus | ByteCode::Sub | ByteCode::Mul | ByteCode::Div => {
+                // 结果类型与左值相同 the kind of result is same as the left value
+                let (right_kind, right) = self.pop()?;
+                let (left_kind, left) = self.pop()?;
+                let right = self.convert(right_kind, right?, left_kind)?;
+                let left = left?;
+                let val = match (left_kind, code) {
+                    // 整数除零会产生硬件异常 交给解释器处理
+                    // integer division by zero causes hardware trap, leave it to interpreter
+                    (Kind::Int, ByteCode::Div) => return Option::None,
+                    (Kind::Int, code) => self.checked_int_op(code, left, right)?,
+                    (Kind::Num, ByteCode::Plus) => self.builder.ins().fadd(left, right),
+                    (Kind::Num, ByteCode::Sub) => self.builder.ins().fsub(left, right),
+                    (Kind::Num, ByteCode::Mul) => self.builder.ins().fmul(left, right),
+                    (Kind::Num, ByteCode::Div) => self.builder.ins().fdiv(left, right),
+                    _ => return Option::None,
+                };
+                self.push(left_kind, Some(val));
+            }
+            ByteCode::BitNot => {
+                let val = self.pop_kind(Kind::Int)?;
+                let val = self.builder.ins().bnot(val);
+                self.push(Kind::Int, Some(val));
+            }
+            // bool也是0或1的整数 bool is also an int of 0 or 1
+            ByteCode::BitAnd | ByteCode::BitOr | ByteCode::BitXor => {
+                let (right_kind, right) = self.pop()?;
+                let (left_kind, left) = self.pop()?;
+                if left_kind != right_kind || !matches!(left_kind, Kind::Int | Kind::Bool) {
+                    return Option::None;
+                }
+                let (left, right) = (left?, right?);
+                let ins = self.builder.ins();
+                let val = match code {
+                    ByteCode::BitAnd => ins.band(left, right),
+                    ByteCode::BitOr => ins.bor(left, right),
+                    _ => ins.bxor(left, right),
+                };
+                self.push(left_kind, Some(val));
+            }
+            ByteCode::PlusOne | ByteCode::SubOne => {
+                let (kind, val) = self.pop()?;
+                let val = val?;
+                let delta = if let ByteCode::PlusOne = code { 1 } else { -1 };
+                let val = match kind {
+                    Kind::Int => {
+                        let (limit, op) = if delta > 0 {
+                            (i64::MAX, OVERFLOW_ADD)
+                        } else {
+                            (i64::MIN, OVERFLOW_SUB)
+                        };
+                        let overflow = self.builder.ins().icmp_imm(IntCC::Equal, val, limit);
+                        self.check_overflow(overflow, op)?;
+                        self.builder.ins().iadd_imm(val, delta)
+                    }
+                    Kind::Num => {
+                        let delta = self.builder.ins().f64const(delta as f64);
+                        self.builder.ins().fadd(val, delta)
+                    }
+                    _ => return Option::None,
+                };
+                self.push(kind, Some(val));
+            }
+            ByteCode::GreaterThan
+            | ByteCode::LessThan
+            | ByteCode::GreaterThanEquals
+            | ByteCode::LessThanEquals
+            | ByteCode::Equals
+            | ByteCode::NotEquals => {
+                let (right_kind, right) = self.pop()?;
+                let (left_kind, left) = self.pop()?;
+                let right = self.convert(right_kind, right?, left_kind)?;
+                let left = left?;
+                let cond = match left_kind {
+                    Kind::Int | Kind::Bool => {
+                        let cc = match code {
+                            ByteCode::Equals => IntCC::Equal,
+                            ByteCode::NotEquals => IntCC::NotEqual,
+                            _ if left_kind == Kind::Bool => return Option::None,
+                            ByteCode::GreaterThan => IntCC::SignedGreaterThan,
+                            ByteCode::LessThan => IntCC::SignedLessThan,
+                            ByteCode::GreaterThanEquals => IntCC::SignedGreaterThanOrEqual,
+                            _ => IntCC::SignedLessThanOrEqual,
+                        };
+                        self.builder.ins().icmp(cc, left, right)
+                    }
+                    Kind::Num => {
+                        let cc = match code {
+                            ByteCode::Equals => FloatCC::Equal,
+                            ByteCode::NotEquals => FloatCC::NotEqual,
+                            ByteCode::GreaterThan => FloatCC::GreaterThan,
+                            ByteCode::LessThan => FloatCC::LessThan,
+                            ByteCode::GreaterThanEquals => FloatCC::GreaterThanOrEqual,
+                            _ => FloatCC::LessThanOrEqual,
+                        };
+                        self.builder.ins().fcmp(cc, left, right)
+                    }
+                    _ => return Option::None,
+                };
+                let val = self.builder.ins().bint(types::I64, cond);
+                self.push(Kind::Bool, Some(val));
+            }
+            ByteCode::LogicAnd | ByteCode::LogicOr => {
+                let right = self.pop_kind(Kind::Bool)?;
+                let left = self.pop_kind(Kind::Bool)?;
+                let val = if let ByteCode::LogicAnd = code {
+                    self.builder.ins().band(left, right)
+                } else {
+                    self.builder.ins().bor(left, right)
+                };
+                self.push(Kind::Bool, Some(val));
+            }
+            ByteCode::AsInt | ByteCode::AsNum | ByteCode::AsBool => {
+                let (kind, val) = self.pop()?;
+                let target = match code {
+                    ByteCode::AsInt => Kind::Int,
+                    ByteCode::AsNum => Kind::Num,
+                    _ => Kind::Bool,
+                };
+                let val = match (kind, target) {
+                    (Kind::Bool, Kind::Int) => val?,
+                    (Kind::Bool, Kind::Num) => self.builder.ins().fcvt_from_sint(types::F64, val?),
+                    _ => self.convert(kind, val?, target)?,
+                };
+                self.push(target, Some(val));
+            }
+            ByteCode::LoadDirectDefFn(idx) => self.push(Kind::Func(idx), None),
+            ByteCode::CallTopFn { nargs } => self.call(nargs as usize)?,
+            ByteCode::Jump(label) => {
+                self.record(label as usize)?;
+                let block = *self.blocks.get(&(label as usize))?;
+                self.builder.ins().jump(block, &[]);
+                return Option::Some(true);
+            }
+            ByteCode::JumpIf(label) | ByteCode::JumpIfNot(label) => {
+                let cond = self.pop_kind(Kind::Bool)?;
+                self.record(label as usize)?;
+                let block = *self.blocks.get(&(label as usize))?;
+                if let ByteCode::JumpIf(_) = code {
+                    self.builder.ins().brnz(cond, block, &[]);
+                } else {
+                    self.builder.ins().brz(cond, block, &[]);
+                }
+            }
+            ByteCode::Return => {
+                let val = match (self.return_kind, self.stack.is_empty()) {
+                    (Kind::Void, _) | (_, true) => self.builder.ins().iconst(types::I64, 0),
+                    (kind, false) => {
+                        let (val_kind, val) = self.pop()?;
+                        let val = self.convert(val_kind, val?, kind)?;
+                        self.as_i64_bits(kind, val)
+                    }
+                };
+                self.builder.ins().return_(&[val]);
+                return Option::Some(true);
+            }
+            _ => return Option::None,
+        }
+        Option::Some(false)
+    }
+
+    // 通过 call_from_jit 调用函数 call the function by call_from_jit
+    fn call(&mut self, nargs: usize) -> Option<()> {
+        let mut args = Vec::with_capacity(nargs);
+        for _ in 0..nargs {
+            args.push(self.pop()?);
+        }
+        args.reverse();
+        let fn_idx = match self.pop()? {
+            (Kind::Func(idx), _) => idx,
+            _ => return Option::None,
+        };
+        let func = self.status.funcs.get(fn_idx as usize)?.clone();
+        let func = func.inner();
+        if func.info.params.len() != nargs || func.info.need_self {
+            return Option::None;
+        }
+        let return_kind = Kind::from_return_type(&func.info.return_type)?;
+
+        // 参数之后是出错的标记位 the error flag is after args
+        let slot = self.builder.create_stack_slot(StackSlotData::new(
+            StackSlotKind::ExplicitSlot,
+            ((nargs + 1) * 8) as u32,
+        ));
+        let flag_offset = (nargs * 8) as i32;
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().stack_store(zero, slot, flag_offset);
+        for (idx, ((kind, val), param)) in args.into_iter().zip(func.info.params.iter()).enumerate()
+        {
+            let param_kind = Kind::from_data_type(&param.data_type)?;
+            let val = self.convert(kind, val?, param_kind)?;
+            let val = self.as_i64_bits(param_kind, val);
+            self.builder.ins().stack_store(val, slot, (idx * 8) as i32);
+        }
+        let args_ptr = self.builder.ins().stack_addr(self.ptr_type, slot, 0);
+        let vm_ptr = self.vm_ptr?;
+        let fn_idx = self.builder.ins().iconst(types::I64, fn_idx as i64);
+
+        let mut sig = Signature::new(self.builder.func.signature.call_conv);
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.returns.push(AbiParam::new(types::I64));
+        let sig = self.builder.import_signature(sig);
+        let callee = self
+            .builder
+            .ins()
+            .iconst(self.ptr_type, call_from_jit as JitCallFn as usize as i64);
+        let inst = self
+            .builder
+            .ins()
+            .call_indirect(sig, callee, &[vm_ptr, fn_idx, args_ptr]);
+        let result = self.builder.inst_results(inst)[0];
+        // 出错时直接返回 错误由调用方从虚拟机中取出
+        // return directly when error occurs, the caller takes the error from vm
+        let failed = self.builder.ins().stack_load(types::I64, slot, flag_offset);
+        let error_block = self.builder.create_block();
+        let next_block = self.builder.create_block();
+        self.builder.ins().brnz(failed, error_block, &[]);
+        self.builder.ins().jump(next_block, &[]);
+        self.builder.switch_to_block(error_block);
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().return_(&[zero]);
+        self.builder.switch_to_block(next_block);
+        let result = match return_kind {
+            Kind::Void => None,
+            Kind::Num => Some(self.builder.ins().bitcast(types::F64, result)),
+            _ => Some(result),
+        };
+        self.push(return_kind, result);
+        Option::Some(())
+    }
+
+    // 检查溢出的整数加减乘 溢出时与解释器一样是运行时错误
+    // int add, subtract and multiply checking overflow, which is runtime error as the interpreter does
+    fn checked_int_op(&mut self, code: ByteCode, left: Value, right: Value) -> Option<Value> {
+        let (val, overflow, op) = match code {
+            // 两个操作数同号而结果异号时溢出 overflow when operands have the same sign but the result not
+            ByteCode::Plus => {
+                let val = self.builder.ins().iadd(left, right);
+                let left_changed = self.builder.ins().bxor(left, val);
+                let right_changed = self.builder.ins().bxor(right, val);
+                let both = self.builder.ins().band(left_changed, right_changed);
+                let overflow = self.builder.ins().icmp_imm(IntCC::SignedLessThan, both, 0);
+                (val, overflow, OVERFLOW_ADD)
+            }
+            // 两个操作数异号而结果与左值异号时溢出
+            // overflow when operands have different signs and the result differs from the left in sign
+            ByteCode::Sub => {
+                let val = self.builder.ins().isub(left, right);
+                let operands = self.builder.ins().bxor(left, right);
+                let left_changed = self.builder.ins().bxor(left, val);
+                let both = self.builder.ins().band(operands, left_changed);
+                let overflow = self.builder.ins().icmp_imm(IntCC::SignedLessThan, both, 0);
+                (val, overflow, OVERFLOW_SUB)
+            }
+            // 乘积的高64位不是低64位的符号扩展时溢出
+            // overflow when the high 64 bits of product is not the sign extension of the low 64 bits
+            ByteCode::Mul => {
+                let val = self.builder.ins().imul(left, right);
+                let high = self.builder.ins().smulhi(left, right);
+                let sign = self.builder.ins().sshr_imm(val, 63);
+                let overflow = self.builder.ins().icmp(IntCC::NotEqual, high, sign);
+                (val, overflow, OVERFLOW_MUL)
+            }
+            _ => return Option::None,
+        };
+        self.check_overflow(overflow, op)?;
+        Option::Some(val)
+    }
+
+    // 溢出时通过 overflow_from_jit 记录错误并直接返回 错误由调用方从虚拟机中取出
+    // record the error by overflow_from_jit and return directly when overflow, the caller takes the error from vm
+    fn check_overflow(&mut self, overflow: Value, op: usize) -> Option<()> {
+        let error_block = self.builder.create_block();
+        let next_block = self.builder.create_block();
+        self.builder.ins().brnz(overflow, error_block, &[]);
+        self.builder.ins().jump(next_block, &[]);
+        self.builder.switch_to_block(error_block);
+        let mut sig = Signature::new(self.builder.func.signature.call_conv);
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        let sig = self.builder.import_signature(sig);
+        let callee = self.builder.ins().iconst(
+            self.ptr_type,
+            overflow_from_jit as JitOverflowFn as usize as i64,
+        );
+        let vm_ptr = self.vm_ptr?;
+        let op = self.builder.ins().iconst(types::I64, op as i64);
+        self.builder.ins().call_indirect(sig, callee, &[vm_ptr, op]);
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().return_(&[zero]);
+        self.builder.switch_to_block(next_block);
+        Option::Some(())
+    }
+
+    fn convert(&mut self, from: Kind, val: Value, to: Kind) -> Option<Value> {
+        match (from, to) {
+            (Kind::Int, Kind::Num) => {
+                Option::Some(self.builder.ins().fcvt_from_sint(types::F64, val))
+            }
+            (Kind::Num, Kind::Int) => {
+                Option::Some(self.builder.ins().fcvt_to_sint_sat(types::I64, val))
+            }
+            (from, to) if from == to => Option::Some(val),
+            _ => Option::None,
+        }
+    }
+
+    fn as_i64_bits(&mut self, kind: Kind, val: Value) -> Value {
+        match kind {
+            Kind::Num => self.builder.ins().bitcast(types::I64, val),
+            _ => val,
+        }
+    }
+
+    fn zero(&mut self, kind: Kind) -> Value {
+        match kind {
+            Kind::Num => self.builder.ins().f64const(0.0),
+            _ => self.builder.ins().iconst(types::I64, 0),
+        }
+    }
+
+    fn new_var(&mut self, typ: Type) -> Variable {
+        let var = Variable::new(self.var_count);
+        self.var_count += 1;
+        self.builder.declare_var(var, typ);
+        var
+    }
+
+    fn push(&mut self, kind: Kind, val: Option<Value>) {
+        let depth = self.stack.len();
+        if let Some(val) = val {
+            let var = match self.stack_vars.get(&(depth, kind)) {
+                Some(var) => *var,
+                None => {
+                    let var = self.new_var(kind.ir_type());
+                    self.stack_vars.insert((depth, kind), var);
+                    var
+                }
+            };
+            self.builder.def_var(var, val);
+        }
+        self.stack.push((kind, val));
+    }
+
+    fn pop(&mut self) -> Option<(Kind, Option<Value>)> {
+        let (kind, val) = self.stack.pop()?;
+        let depth = self.stack.len();
+        let val = match (kind, val) {
+            (Kind::Func(_), _) | (Kind::Void, _) => None,
+            (_, Some(val)) => Some(val),
+            // 来自其他基本块的值 the value comes from other block
+            (kind, None) => Some(self.builder.use_var(*self.stack_vars.get(&(depth, kind))?)),
+        };
+        Option::Some((kind, val))
+    }
+
+    fn pop_kind(&mut self, expect: Kind) -> Option<Value> {
+        match self.pop()? {
+            (kind, Some(val)) if kind == expect => Option::Some(val),
+            _ => Option::None,
+        }
+    }
+
+    // 记录跳转目标处操作数栈的类型，各条路径必须一致
+    // record the kinds of operand stack at the jump target, all paths must be consistent
+    fn record(&mut self, label: usize) -> Option<()> {
+        let state: Vec<Kind> = self.stack.iter().map(|(kind, _)| *kind).collect();
+        match self.states.get(&label) {
+            Some(recorded) if recorded.ne(&state) => Option::None,
+            Some(_) => Option::Some(()),
+            None => {
+                self.states.insert(label, state);
+                Option::Some(())
+            }
+        }
+    }
+}
+
+// 调用JIT编译后的函数 call the compiled function
+pub fn call(
+    ptr: *const u8,
+    vm: &GloomVM,
+    info: &FuncInfo,
+    args: GloomArgs,
+) -> Result<GloomValue, GloomError> {
+    let args: Vec<i64> = info
+        .params
+        .iter()
+        .zip(args.vec.iter())
+        .map(|(param, arg)| into_i64(&param.data_type, arg))
+        .collect::<Result<_, _>>()?;
+    let func = unsafe { transmute::<*const u8, JitFn>(ptr) };
+    let result = func(vm, args.as_ptr());
+    if let Some(payload) = vm.take_jit_panic() {
+        panic::resume_unwind(payload);
+    }
+    match vm.take_jit_error() {
+        Some(mut error) => {
+            vm.trace(&mut error, info, Option::None);
+            Result::Err(error)
+        }
+        None => Result::Ok(from_i64(&info.return_type, result)),
+    }
+}
+
+// 被调用的函数出错时 设置参数后的标记位并保存错误 编译后的代码检查标记位后立即返回
+// 虚拟机内部的panic同样先保存 在返回到调用编译后代码的Rust栈帧后继续展开
+// when the callee fails, set the flag after args and keep the error, the compiled code returns immediately after check the flag,
+// the internal panic of vm is also kept first, and continues to unwind after returned to the rust frame calling the compiled code
+extern "C" fn call_from_jit(vm: *const GloomVM, fn_idx: i64, args: *mut i64) -> i64 {
+    let vm = unsafe { &*vm };
+    let func = vm.status().funcs.get(fn_idx as usize).unwrap().clone();
+    let func = func.inner();
+    let nargs = func.info.params.len();
+    let raw_args = unsafe { std::slice::from_raw_parts_mut(args, nargs + 1) };
+    // 不能让panic穿过JIT编译的栈帧 panic can't unwind through the frame of compiled code
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let args = func
+            .info
+            .params
+            .iter()
+            .zip(raw_args.iter())
+            .map(|(param, arg)| from_i64(&ReturnType::Have(param.data_type.clone()), *arg))
+            .collect();
+        let result = vm.call_fn(&func, GloomArgs::new(args))?;
+        Result::Ok(match &func.info.return_type {
+            ReturnType::Void => 0,
+            ReturnType::Have(data_type) => into_i64(data_type, &result)?,
+        })
+    }));
+    match result {
+        Ok(Ok(result)) => return result,
+        Ok(Err(error)) => vm.set_jit_error(error),
+        Err(payload) => vm.set_jit_panic(payload),
+    }
+    raw_args[nargs] = 1;
+    0
+}
+
+// 错误信息与解释器相同 the error message is the same as interpreter
+extern "C" fn overflow_from_jit(vm: *const GloomVM, op: i64) {
+    let vm = unsafe { &*vm };
+    let op = OVERFLOW_OPS[op as usize];
+    vm.set_jit_error(GloomError::new(format!("attempt to {} with overflow", op)));
+}
+
+#[inline]
+fn into_i64(data_type: &DataType, val: &GloomValue) -> Result<i64, GloomError> {
+    Result::Ok(match data_type {
+        DataType::Int => val.assert_int_include_num()?,
+        DataType::Num => val.assert_num_include_int()?.to_bits() as i64,
+        DataType::Bool => val.assert_bool()? as i64,
+        _ => panic!("{:?} can't pass to jit code", val),
+    })
+}
+
+#[inline]
+fn from_i64(return_type: &ReturnType, val: i64) -> GloomValue {
+    match return_type {
+        ReturnType::Void => GloomValue::None,
+        ReturnType::Have(DataType::Int) => GloomValue::Int(val),
+        ReturnType::Have(DataType::Num) => GloomValue::Num(f64::from_bits(val as u64)),
+        ReturnType::Have(DataType::Bool) => GloomValue::Bool(val != 0),
+        ReturnType::Have(data_type) => panic!("{} can't return from jit code", data_type),
+    }
+}
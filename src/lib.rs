@@ -0,0 +1,8 @@
+pub mod builtin;
+pub mod bytecode;
+pub mod engine;
+pub mod frontend;
+pub mod jit;
+pub mod obj;
+pub mod stdlib;
+pub mod vm;
@@ -0,0 +1,759 @@
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use hashbrown::HashMap;
+use serde_json::{json, Value as Json};
+
+use gloomscript::builtin::classes::BuiltinClass;
+use gloomscript::frontend::analysis::Analyzer;
+use gloomscript::frontend::diagnostic::{Diagnostic, Level};
+use gloomscript::frontend::error::AnalysisError;
+use gloomscript::frontend::import::Importer;
+use gloomscript::frontend::status::MetaType;
+use gloomscript::frontend::symbol::{Symbol, SymbolKind};
+use gloomscript::frontend::token::{Span, Token};
+use gloomscript::frontend::tokenize::Tokenizer;
+use gloomscript::obj::func::{GloomFunc, ReturnType};
+use gloomscript::obj::refcount::RefCount;
+use gloomscript::obj::types::{BuiltinType, DataType, RefType};
+use gloomscript::vm::error::GloomError;
+
+// 通过标准输入输出通信的语言服务器 每次修改文档都会重新分析
+// language server communicating over stdio, the document is analysed again after every change
+pub struct LanguageServer {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+struct Document {
+    path: String,
+    text: String,
+    // 解析失败时保留上一次的结果 keep the last result if failed to parse
+    analysis: Option<Analysis>,
+}
+
+struct Analysis {
+    symbols: Vec<Symbol>,
+    // 文档自身的文件索引 the file index of the document itself
+    file_index: u16,
+    paths: Vec<String>,
+    types: HashMap<String, (MetaType, u16)>,
+    builtin_classes: Vec<RefCount<BuiltinClass>>,
+}
+
+// JSON-RPC的错误码 error codes of JSON-RPC
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+
+// LSP中的补全项种类 kinds of completion item in LSP
+const COMPLETION_METHOD: u8 = 2;
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_FIELD: u8 = 5;
+const COMPLETION_ENUM_MEMBER: u8 = 20;
+
+type RequestResult = Result<Json, (i64, String)>;
+
+impl LanguageServer {
+    pub fn new() -> LanguageServer {
+        LanguageServer {
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    // 返回进程的退出码 return the exit code of process
+    pub fn run(mut self) -> i32 {
+        // panic信息由默认hook打印到标准错误 不会干扰标准输出上的通信
+        // panics are printed to stderr by the default hook, which doesn't disturb the communication on stdout
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        let mut exit_code = 1;
+        while let Some(message) = Self::read_message(&mut reader) {
+            match message {
+                Ok(message) => {
+                    if message["method"] == "exit" {
+                        exit_code = if self.shutdown { 0 } else { 1 };
+                        break;
+                    }
+                    self.handle(message);
+                }
+                Err(err) => Self::send(json!({
+                    "jsonrpc": "2.0",
+                    "id": Json::Null,
+                    "error": { "code": PARSE_ERROR, "message": err },
+                })),
+            }
+        }
+        exit_code
+    }
+
+    fn read_message(reader: &mut impl BufRead) -> Option<Result<Json, String>> {
+        let mut length = Option::None;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).ok()? == 0 {
+                return Option::None;
+            }
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = header.split_once(':') {
+                if key.eq_ignore_ascii_case("Content-Length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+        let length = match length {
+            Some(length) => length,
+            None => return Option::Some(Result::Err(String::from("missing Content-Length"))),
+        };
+        let mut content = vec![0; length];
+        reader.read_exact(&mut content).ok()?;
+        Option::Some(serde_json::from_slice(&content).map_err(|err| err.to_string()))
+    }
+
+    fn send(message: Json) {
+        let content = message.to_string();
+        let stdout = io::stdout();
+        let mut writer = stdout.lock();
+        let _ = write!(
+            writer,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        );
+        let _ = writer.flush();
+    }
+
+    fn handle(&mut self, message: Json) {
+        let method = match message["method"].as_str() {
+            Some(method) => method.to_string(),
+            // 客户端对请求的回复 服务器不发送请求 response of client, the server sends no request
+            None => return,
+        };
+        let params = &message["params"];
+        match message.get("id") {
+            // 请求 request
+            Some(id) => {
+                let result = if self.shutdown {
+                    Result::Err((INVALID_REQUEST, String::from("the server is shut down")))
+                } else {
+                    match panic::catch_unwind(AssertUnwindSafe(|| {
+                        self.request(method.as_str(), params)
+                    })) {
+                        Ok(result) => result,
+                        Err(payload) => {
+                            Result::Err((INTERNAL_ERROR, GloomError::from_panic(payload).msg))
+                        }
+                    }
+                };
+                Self::send(match result {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, msg)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": msg },
+                    }),
+                });
+            }
+            // 通知 notification
+            None => {
+                let _ =
+                    panic::catch_unwind(AssertUnwindSafe(|| self.notify(method.as_str(), params)));
+            }
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> RequestResult {
+        match method {
+            "initialize" => Result::Ok(json!({
+                "capabilities": {
+                    // 每次修改都发送整个文档 send the whole document after every change
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                },
+                "serverInfo": { "name": "gloom-lsp", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Result::Ok(Json::Null)
+            }
+            "textDocument/hover" => Result::Ok(self.hover(params).unwrap_or(Json::Null)),
+            "textDocument/definition" => Result::Ok(self.definition(params).unwrap_or(Json::Null)),
+            "textDocument/completion" => {
+                Result::Ok(Json::Array(self.completion(params).unwrap_or_default()))
+            }
+            _ => Result::Err((METHOD_NOT_FOUND, format!("unsupported method {}", method))),
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: &Json) {
+        let uri = match params["textDocument"]["uri"].as_str() {
+            Some(uri) => uri.to_string(),
+            None => return,
+        };
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = params["textDocument"]["text"].as_str() {
+                    self.update(uri, text.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.update(uri, text.to_string());
+                }
+            }
+            // 被导入的文件可能已经改变 the imported files may have changed
+            "textDocument/didSave" => {
+                if let Some(document) = self.documents.get(&uri) {
+                    let text = document.text.clone();
+                    self.update(uri, text);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                Self::send(json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    // 重新分析文档并发布诊断 analyse the document again and publish diagnostics
+    fn update(&mut self, uri: String, text: String) {
+        let path = uri_to_path(uri.as_str());
+        let (diagnostics, analysis) = analyse(path.as_str(), text.as_str());
+        let diagnostics: Vec<Json> = diagnostics
+            .iter()
+            .map(|diagnostic| lsp_diagnostic(diagnostic, path.as_str(), text.as_str()))
+            .collect();
+        Self::send(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }));
+        let document = self.documents.entry(uri).or_insert(Document {
+            path,
+            text: String::new(),
+            analysis: Option::None,
+        });
+        document.text = text;
+        if analysis.is_some() {
+            document.analysis = analysis;
+        }
+    }
+
+    // 光标处的标识符以及它对应的符号 the identifier at cursor and its symbol
+    fn symbol_at(&self, params: &Json) -> Option<(&Document, &Analysis, &Symbol, Span)> {
+        let document = self
+            .documents
+            .get(params["textDocument"]["uri"].as_str()?)?;
+        let analysis = document.analysis.as_ref()?;
+        let offset = offset_at(document.text.as_str(), &params["position"]);
+        let (tokens, lines, spans) = tokenize(document.text.as_str())?;
+        let index = (0..tokens.len()).find(|index| {
+            let span = spans[*index];
+            matches!(tokens[*index], Token::Id(_))
+                && span.start as usize <= offset
+                && offset <= span.end as usize
+        })?;
+        let name = match &tokens[index] {
+            Token::Id(name) => name,
+            _ => panic!(),
+        };
+        let symbol = analysis.symbols.iter().find(|symbol| {
+            symbol.file_index == analysis.file_index
+                && symbol.line == lines[index]
+                && symbol.name == *name
+        })?;
+        Option::Some((document, analysis, symbol, spans[index]))
+    }
+
+    fn hover(&self, params: &Json) -> Option<Json> {
+        let (document, _, symbol, span) = self.symbol_at(params)?;
+        let label = match &symbol.kind {
+            SymbolKind::Var => format!("let {} : {}", symbol.name, symbol.data_type),
+            SymbolKind::Type(meta_type, _) => format!("{} {}", meta_type, symbol.name),
+            SymbolKind::Func(_) | SymbolKind::Builtin => {
+                format!("{} : {}", symbol.name, symbol.data_type)
+            }
+            SymbolKind::Member(owner) => {
+                format!("{}.{} : {}", owner, symbol.name, symbol.data_type)
+            }
+        };
+        Option::Some(json!({
+            "contents": { "kind": "markdown", "value": format!("```gloom\n{}\n```", label) },
+            "range": range(document.text.as_str(), span),
+        }))
+    }
+
+    fn definition(&self, params: &Json) -> Option<Json> {
+        let (document, analysis, symbol, _) = self.symbol_at(params)?;
+        let (file_index, meta_type, type_name, member) = match &symbol.kind {
+            SymbolKind::Type(meta_type, file_index) => (
+                *file_index,
+                Option::Some(*meta_type),
+                symbol.name.to_string(),
+                Option::None,
+            ),
+            SymbolKind::Func(file_index) => (
+                *file_index,
+                Option::None,
+                symbol.name.to_string(),
+                Option::None,
+            ),
+            // 字段和函数定义在所属的类型中 fields and functions are defined in the type they belong to
+            SymbolKind::Member(DataType::Ref(owner)) => {
+                let type_name = match owner {
+                    RefType::Class(class) | RefType::MetaClass(class) => {
+                        class.inner().name.to_string()
+                    }
+                    RefType::Enum(class) | RefType::MetaEnum(class) => {
+                        class.inner().name.to_string()
+                    }
+                    RefType::Interface(interface) | RefType::MetaInterface(interface) => {
+                        interface.inner().name.to_string()
+                    }
+                    _ => return Option::None,
+                };
+                let (meta_type, file_index) = analysis.types.get(type_name.as_str())?;
+                (
+                    *file_index,
+                    Option::Some(*meta_type),
+                    type_name,
+                    Option::Some(symbol.name.as_str()),
+                )
+            }
+            _ => return Option::None,
+        };
+        let path = analysis.paths.get(file_index as usize)?;
+        let (text, uri) = if *path == document.path {
+            (document.text.clone(), params["textDocument"]["uri"].clone())
+        } else {
+            (
+                std::fs::read_to_string(path).ok()?,
+                Json::String(path_to_uri(path)),
+            )
+        };
+        let span = find_definition(text.as_str(), meta_type, type_name.as_str(), member)?;
+        Option::Some(json!({ "uri": uri, "range": range(text.as_str(), span) }))
+    }
+
+    // 补全'.'之后的字段和函数 complete the fields and functions after '.'
+    fn completion(&self, params: &Json) -> Option<Vec<Json>> {
+        let document = self
+            .documents
+            .get(params["textDocument"]["uri"].as_str()?)?;
+        let analysis = document.analysis.as_ref()?;
+        let offset = offset_at(document.text.as_str(), &params["position"]);
+        let (tokens, lines, spans) = tokenize(&document.text[..offset])?;
+        // 跳过已经输入的部分名称 skip the partial name already typed
+        let mut dot = tokens.len().checked_sub(1)?;
+        if let Token::Id(_) = tokens[dot] {
+            dot = dot.checked_sub(1)?;
+        }
+        if tokens[dot] != Token::Dot || spans[dot].end as usize > offset {
+            return Option::None;
+        }
+        let receiver = dot.checked_sub(1)?;
+        let data_type = match &tokens[receiver] {
+            Token::Str(_) => DataType::Ref(RefType::String),
+            // 文档正在编辑 取行号最接近的符号 the document is being edited, take the symbol with nearest line
+            Token::Id(name) => analysis
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.file_index == analysis.file_index && symbol.name == *name)
+                .min_by_key(|symbol| (symbol.line as i32 - lines[receiver] as i32).abs())?
+                .data_type
+                .clone(),
+            _ => return Option::None,
+        };
+        Option::Some(members(&data_type, &analysis.builtin_classes))
+    }
+}
+
+fn analyse(path: &str, text: &str) -> (Vec<Diagnostic>, Option<Analysis>) {
+    let importer = RefCount::new(Importer::new());
+    let parsed_file = match panic::catch_unwind(AssertUnwindSafe(|| {
+        Importer::import_str(text.to_string(), path.to_string(), importer)
+    })) {
+        Ok(Ok(parsed_file)) => parsed_file,
+        Ok(Err(err)) => return (vec![Diagnostic::from_parse(&err)], Option::None),
+        Err(payload) => {
+            let err = AnalysisError::Panicked(GloomError::from_panic(payload).msg);
+            return (
+                vec![Diagnostic::from_analysis(err, path, 0, Option::None)],
+                Option::None,
+            );
+        }
+    };
+    let mut analyzer = Analyzer::new();
+    analyzer.record_symbols();
+    let mut diagnostics =
+        match panic::catch_unwind(AssertUnwindSafe(|| analyzer.analysis(parsed_file, false))) {
+            Ok(Ok(())) => Vec::new(),
+            Ok(Err(diagnostics)) => diagnostics,
+            Err(payload) => {
+                let err = AnalysisError::Panicked(GloomError::from_panic(payload).msg);
+                vec![Diagnostic::from_analysis(err, path, 0, Option::None)]
+            }
+        };
+    diagnostics.append(&mut analyzer.take_warnings());
+    let paths = analyzer.paths().to_vec();
+    let file_index = paths.iter().position(|file| file == path).unwrap_or(0) as u16;
+    let types = analyzer
+        .type_map
+        .iter()
+        .map(|(name, index)| (name.clone(), (index.tp, index.file_index)))
+        .collect();
+    let analysis = Analysis {
+        symbols: analyzer.symbols(),
+        file_index,
+        paths,
+        types,
+        builtin_classes: analyzer.status_mut().builtin_classes.clone(),
+    };
+    (diagnostics, Option::Some(analysis))
+}
+
+fn tokenize(text: &str) -> Option<(Vec<Token>, Vec<u16>, Vec<Span>)> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        Tokenizer::new(text.as_bytes().to_vec()).tokenize()
+    }))
+    .ok()
+}
+
+fn lsp_diagnostic(diagnostic: &Diagnostic, path: &str, text: &str) -> Json {
+    let mut message = diagnostic.message.clone();
+    // 其他文件中的错误显示在文档的开头 errors in other files are shown at the start of document
+    let in_document = diagnostic.file.is_empty() || diagnostic.file == path;
+    if !in_document {
+        message = format!("{}:{}: {}", diagnostic.file, diagnostic.line, message);
+    }
+    for note in diagnostic.notes.iter() {
+        message.push_str(format!("\nnote: {}", note).as_str());
+    }
+    if let Some(help) = diagnostic.help {
+        message.push_str(format!("\nhelp: {}", help).as_str());
+    }
+    let range = match (in_document, diagnostic.span) {
+        (true, Some(span)) => range(text, span),
+        (true, None) if diagnostic.line > 0 => line_range(text, diagnostic.line),
+        _ => json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 0 },
+        }),
+    };
+    json!({
+        "range": range,
+        "severity": if diagnostic.level == Level::Error { 1 } else { 2 },
+        "code": diagnostic.code,
+        "source": "gloom",
+        "message": message,
+    })
+}
+
+// 在文件中查找类型、函数或类型成员的声明 find the declaration of type, function or member of type in file
+fn find_definition(
+    text: &str,
+    meta_type: Option<MetaType>,
+    name: &str,
+    member: Option<&str>,
+) -> Option<Span> {
+    let (tokens, _, spans) = tokenize(text)?;
+    let keyword = match meta_type {
+        Some(MetaType::Class) => Token::Class,
+        Some(MetaType::Interface) => Token::Interface,
+        Some(MetaType::Enum) => Token::Enum,
+        Some(MetaType::Builtin) => return Option::None,
+        None => Token::Func,
+    };
+    // 声明只在最外层 declarations are only in the outermost layer
+    let mut depth = 0;
+    let mut index = Option::None;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LBrace => depth += 1,
+            Token::RBrace => depth -= 1,
+            Token::Id(id) if depth == 0 && id.as_str() == name && idx > 0 => {
+                if tokens[idx - 1] == keyword {
+                    index = Option::Some(idx);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let index = index?;
+    let member = match member {
+        Some(member) => member,
+        None => return Option::Some(spans[index]),
+    };
+    // 成员在类型的大括号中 不在参数列表中 members are in the braces of type, not in the param list
+    let mut depth = 0;
+    let mut paren_depth = 0;
+    for (idx, token) in tokens.iter().enumerate().skip(index + 1) {
+        match token {
+            Token::LBrace => depth += 1,
+            Token::RBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Token::LParen => paren_depth += 1,
+            Token::RParen => paren_depth -= 1,
+            Token::Id(id) if depth == 1 && paren_depth == 0 && id.as_str() == member => {
+                return Option::Some(spans[idx]);
+            }
+            _ => {}
+        }
+    }
+    Option::None
+}
+
+// 类型的字段和函数 fields and functions of the type
+fn members(data_type: &DataType, builtin_classes: &[RefCount<BuiltinClass>]) -> Vec<Json> {
+    let ref_type = match data_type {
+        DataType::Ref(ref_type) => ref_type,
+        _ => return Vec::new(),
+    };
+    let mut items = Vec::new();
+    match ref_type {
+        RefType::Class(class) | RefType::MetaClass(class) => {
+            let is_meta = matches!(ref_type, RefType::MetaClass(_));
+            let class = class.inner();
+            for (name, (index, _, _, is_fn)) in class.map.iter() {
+                if *is_fn {
+                    let func = class.funcs.get(*index as usize).unwrap().inner();
+                    // 类型只能调用静态函数 type could only call static functions
+                    if func.info.need_self != is_meta {
+                        items.push(completion_item(name, COMPLETION_METHOD, signature(&func)));
+                    }
+                } else if !is_meta {
+                    let field_type = class.field_indexer.get_type(*index);
+                    items.push(completion_item(
+                        name,
+                        COMPLETION_FIELD,
+                        field_type.to_string(),
+                    ));
+                }
+            }
+        }
+        RefType::Enum(class) | RefType::MetaEnum(class) => {
+            let is_meta = matches!(ref_type, RefType::MetaEnum(_));
+            let class = class.inner();
+            if is_meta {
+                for name in class.names.iter() {
+                    items.push(completion_item(
+                        name,
+                        COMPLETION_ENUM_MEMBER,
+                        class.name.to_string(),
+                    ));
+                }
+            }
+            for (name, (index, _)) in class.func_map.iter() {
+                let func = class.funcs.get(*index as usize).unwrap().inner();
+                if func.info.need_self != is_meta {
+                    items.push(completion_item(name, COMPLETION_METHOD, signature(&func)));
+                }
+            }
+        }
+        RefType::Interface(interface) => {
+            let interface = interface.inner();
+            for (name, index) in interface.map.iter() {
+                let func = interface.funcs.get(*index as usize).unwrap().inner();
+                items.push(completion_item(name, COMPLETION_METHOD, signature(&func)));
+            }
+        }
+        RefType::MataBuiltinType(builtin_type) => {
+            items = builtin_members(*builtin_type, false, builtin_classes);
+        }
+        RefType::Func(_)
+        | RefType::Array(_)
+        | RefType::Queue(_)
+        | RefType::Map(_)
+        | RefType::Set(_)
+        | RefType::Option(_)
+        | RefType::String
+        | RefType::Weak(_)
+        | RefType::Generator(_)
+        | RefType::Task(_)
+        | RefType::Timer
+        | RefType::Channel(_)
+        | RefType::Error => {
+            items = builtin_members(ref_type.as_built_type(), true, builtin_classes);
+        }
+        _ => {}
+    }
+    items
+}
+
+fn builtin_members(
+    builtin_type: BuiltinType,
+    need_self: bool,
+    builtin_classes: &[RefCount<BuiltinClass>],
+) -> Vec<Json> {
+    // 标准库的类型只在导入后可用 补全时也列出它们的成员
+    // types of std library are only available after imported, their members are listed as well
+    let mut type_map = BuiltinClass::builtin_type_map();
+    type_map.extend(BuiltinClass::std_type_map());
+    let index = match type_map.get(&builtin_type) {
+        Some(index) => *index,
+        None => return Vec::new(),
+    };
+    let class = builtin_classes.get(index as usize).unwrap().inner();
+    let mut items = Vec::new();
+    for (name, index) in class.map.iter() {
+        let func = class.funcs.get(*index as usize).unwrap().inner();
+        if func.info.need_self == need_self {
+            let kind = if need_self {
+                COMPLETION_METHOD
+            } else {
+                COMPLETION_FUNCTION
+            };
+            items.push(completion_item(name, kind, signature(&func)));
+        }
+    }
+    items
+}
+
+fn completion_item(name: &str, kind: u8, detail: String) -> Json {
+    json!({ "label": name, "kind": kind, "detail": detail })
+}
+
+fn signature(func: &GloomFunc) -> String {
+    let params: Vec<String> = func
+        .info
+        .params
+        .iter()
+        .map(|param| {
+            format!("{} {}", param.data_type, param.name)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    match &func.info.return_type {
+        ReturnType::Void => format!("func {}({})", func.info.name, params.join(", ")),
+        ReturnType::Have(data_type) => {
+            format!(
+                "func {}({}) {}",
+                func.info.name,
+                params.join(", "),
+                data_type
+            )
+        }
+    }
+}
+
+// LSP中的列以UTF-16计数 columns in LSP are counted in UTF-16
+fn offset_at(text: &str, position: &Json) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let mut offset = 0;
+    for _ in 0..line {
+        match text[offset..].find('\n') {
+            Some(len) => offset += len + 1,
+            None => return text.len(),
+        }
+    }
+    let mut count = 0;
+    for (idx, ch) in text[offset..].char_indices() {
+        if count >= character || ch == '\n' {
+            return offset + idx;
+        }
+        count += ch.len_utf16();
+    }
+    text.len()
+}
+
+fn position_of(text: &str, offset: usize) -> Json {
+    let offset = offset.min(text.len());
+    let before = text.get(..offset).unwrap_or(text);
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    json!({ "line": line, "character": character })
+}
+
+fn range(text: &str, span: Span) -> Json {
+    json!({
+        "start": position_of(text, span.start as usize),
+        "end": position_of(text, span.end as usize),
+    })
+}
+
+// 没有范围时标记整行 mark the whole line if there is no span
+fn line_range(text: &str, line: u16) -> Json {
+    let src_line = text.lines().nth(line as usize - 1).unwrap_or("");
+    let trimmed = src_line.trim_start();
+    let start: usize = src_line[..src_line.len() - trimmed.len()]
+        .chars()
+        .map(char::len_utf16)
+        .sum();
+    let end: usize = src_line.trim_end().chars().map(char::len_utf16).sum();
+    json!({
+        "start": { "line": line - 1, "character": start },
+        "end": { "line": line - 1, "character": end },
+    })
+}
+
+fn uri_to_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            if let Some(decoded) = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                bytes.push(decoded);
+                continue;
+            }
+            bytes.push(byte);
+            bytes.extend(hex);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8_lossy(&bytes).to_string();
+    // windows下形如 /C:/dir windows path like /C:/dir
+    match path.as_bytes() {
+        [b'/', _, b':', ..] => path[1..].to_string(),
+        _ => path,
+    }
+}
+
+fn path_to_uri(path: &str) -> String {
+    let path = std::fs::canonicalize(Path::new(path))
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+        .replace('\\', "/");
+    let mut uri = String::from(if path.starts_with('/') {
+        "file://"
+    } else {
+        "file:///"
+    });
+    for ch in path.chars() {
+        match ch {
+            ' ' | '#' | '%' | '?' => uri.push_str(format!("%{:02X}", ch as u32).as_str()),
+            ch => uri.push(ch),
+        }
+    }
+    uri
+}
@@ -1,19 +1,25 @@
-use crate::bytecode::gen::CodeGenerator;
-use crate::frontend::analysis::Analyzer;
-use crate::frontend::import::Importer;
-use crate::frontend::status::GloomStatus;
-use crate::obj::refcount::RefCount;
-use crate::vm::machine::GloomVM;
-use crate::vm::static_table::StaticTable;
 use clap::{App, Arg};
+use gloomscript::bytecode::disasm::Disassembler;
+use gloomscript::bytecode::gen::CodeGenerator;
+use gloomscript::bytecode::image;
+use gloomscript::frontend::analysis::Analyzer;
+use gloomscript::frontend::cst::SyntaxTree;
+use gloomscript::frontend::diagnostic::{Diagnostic, Level};
+use gloomscript::frontend::format::Formatter;
+use gloomscript::frontend::import::Importer;
+use gloomscript::frontend::status::GloomStatus;
+use gloomscript::obj::refcount::RefCount;
+use gloomscript::vm::constant::ConstantPool;
+use gloomscript::vm::machine::GloomVM;
+use gloomscript::vm::static_table::StaticTable;
+use hashbrown::HashMap;
+use lsp::LanguageServer;
+use repl::Repl;
+use std::ffi::OsStr;
+use std::path::Path;
 
-mod builtin;
-mod bytecode;
-mod frontend;
-mod jit;
-mod obj;
-mod stdlib;
-mod vm;
+mod lsp;
+mod repl;
 
 fn main() -> Result<(), String> {
     let mut app = App::new("Gloom Script")
@@ -25,10 +31,94 @@ fn main() -> Result<(), String> {
                 .about("Run a script")
                 .arg(
                     Arg::new("FILE")
-                        .help("Sets the script to run")
+                        .help("Sets the script or the compiled .gsc file to run")
                         .required(true)
                         .index(1),
                 )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .takes_value(true)
+                        .possible_values(["human", "json"])
+                        .default_value("human")
+                        .help("Sets the format of compile errors"),
+                )
+                .arg(
+                    Arg::new("debug")
+                        .short('d')
+                        .long("debug")
+                        .help("Enable debug mode"),
+                )
+                .arg(
+                    Arg::new("jit")
+                        .long("jit")
+                        .conflicts_with("no-jit")
+                        .help("Compile hot functions to native code (default)"),
+                )
+                .arg(
+                    Arg::new("no-jit")
+                        .long("no-jit")
+                        .help("Run all functions by the interpreter"),
+                ),
+        )
+        .subcommand(
+            App::new("compile")
+                .about("Compile a script to a bytecode image")
+                .arg(
+                    Arg::new("FILE")
+                        .help("Sets the script to compile")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .takes_value(true)
+                        .help(
+                            "Sets the output file, defaults to the script path with .gsc extension",
+                        ),
+                )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .takes_value(true)
+                        .possible_values(["human", "json"])
+                        .default_value("human")
+                        .help("Sets the format of compile errors"),
+                )
+                .arg(
+                    Arg::new("debug")
+                        .short('d')
+                        .long("debug")
+                        .help("Enable debug mode"),
+                ),
+        )
+        .subcommand(
+            App::new("disasm")
+                .about("Print the bytecode of a script")
+                .arg(
+                    Arg::new("FILE")
+                        .help("Sets the script or the compiled .gsc file to disassemble")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(["text", "json"])
+                        .default_value("text")
+                        .help("Sets the output format"),
+                )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .takes_value(true)
+                        .possible_values(["human", "json"])
+                        .default_value("human")
+                        .help("Sets the format of compile errors"),
+                )
                 .arg(
                     Arg::new("debug")
                         .short('d')
@@ -36,6 +126,38 @@ fn main() -> Result<(), String> {
                         .help("Enable debug mode"),
                 ),
         )
+        .subcommand(
+            App::new("repl")
+                .about("Start an interactive interpreter")
+                .arg(
+                    Arg::new("jit")
+                        .long("jit")
+                        .conflicts_with("no-jit")
+                        .help("Compile hot functions to native code (default)"),
+                )
+                .arg(
+                    Arg::new("no-jit")
+                        .long("no-jit")
+                        .help("Run all functions by the interpreter"),
+                ),
+        )
+        .subcommand(App::new("lsp").about("Start a language server communicating over stdio"))
+        .subcommand(
+            App::new("fmt")
+                .about("Format scripts in place")
+                .arg(
+                    Arg::new("FILE")
+                        .help("Sets the scripts to format")
+                        .required(true)
+                        .multiple_values(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Check whether the scripts are formatted without writing them"),
+                ),
+        )
         .subcommand(
             App::new("check")
                 .about("Check a script")
@@ -45,6 +167,14 @@ fn main() -> Result<(), String> {
                         .required(true)
                         .index(1),
                 )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .takes_value(true)
+                        .possible_values(["human", "json"])
+                        .default_value("human")
+                        .help("Sets the format of compile errors"),
+                )
                 .arg(
                     Arg::new("debug")
                         .short('d')
@@ -61,8 +191,10 @@ fn main() -> Result<(), String> {
         matches.subcommand_matches("run").map(|m| {
             status = false;
             let debug = m.is_present("debug");
+            let jit = !m.is_present("no-jit");
             let path = m.value_of("FILE").unwrap();
-            run_script(path.to_string(), debug)
+            let error_format = m.value_of("error-format").unwrap();
+            run_script(path.to_string(), debug, jit, error_format)
         });
     }
 
@@ -72,10 +204,65 @@ fn main() -> Result<(), String> {
             status = false;
             let debug = m.is_present("debug");
             let path = m.value_of("FILE").unwrap();
-            parse_file(path.to_string(), debug)
+            let error_format = m.value_of("error-format").unwrap();
+            parse_file(path.to_string(), debug, error_format)
         });
     }
 
+    // gloom compile <FILE> [-o <OUTPUT>]
+    if status {
+        if let Some(m) = matches.subcommand_matches("compile") {
+            status = false;
+            let debug = m.is_present("debug");
+            let path = m.value_of("FILE").unwrap();
+            let output = match m.value_of("output") {
+                Some(output) => output.to_string(),
+                None => Path::new(path)
+                    .with_extension(image::EXTENSION)
+                    .to_string_lossy()
+                    .to_string(),
+            };
+            let error_format = m.value_of("error-format").unwrap();
+            compile_script(path.to_string(), output, debug, error_format)?
+        }
+    }
+
+    // gloom disasm <FILE> [--format <text|json>]
+    if status {
+        if let Some(m) = matches.subcommand_matches("disasm") {
+            status = false;
+            let debug = m.is_present("debug");
+            let path = m.value_of("FILE").unwrap();
+            let format = m.value_of("format").unwrap();
+            let error_format = m.value_of("error-format").unwrap();
+            disasm_script(path.to_string(), debug, format, error_format)
+        }
+    }
+
+    // gloom repl
+    if status {
+        if let Some(m) = matches.subcommand_matches("repl") {
+            status = false;
+            let jit = !m.is_present("no-jit");
+            Repl::new(jit).run()
+        }
+    }
+
+    // gloom lsp
+    if status && matches.subcommand_matches("lsp").is_some() {
+        std::process::exit(LanguageServer::new().run())
+    }
+
+    // gloom fmt [--check] <FILE>...
+    if status {
+        if let Some(m) = matches.subcommand_matches("fmt") {
+            status = false;
+            let check = m.is_present("check");
+            let paths = m.values_of("FILE").unwrap().collect();
+            format_files(paths, check)
+        }
+    }
+
     if status {
         app.print_help().map_err(|e| e.to_string())?
     }
@@ -83,26 +270,160 @@ fn main() -> Result<(), String> {
     Ok(())
 }
 
-fn run_script(path: String, debug: bool) {
+fn run_script(path: String, debug: bool, jit: bool, error_format: &str) {
+    let (status, constant_pool, static_table) = load_program(path, debug, error_format);
+
+    // run
+    let mut vm = GloomVM::new(static_table, constant_pool, status, jit);
+    vm.set_debug(debug);
+    if let Err(error) = vm.run() {
+        eprintln!("{}", error.traceback());
+        std::process::exit(1);
+    }
+}
+
+fn disasm_script(path: String, debug: bool, format: &str, error_format: &str) {
+    let (status, constant_pool, _) = load_program(path, debug, error_format);
+    let disassembler = Disassembler::new(&status, &constant_pool);
+    match format {
+        "json" => println!("{:#}", disassembler.json()),
+        _ => print!("{}", disassembler.text()),
+    }
+}
+
+// 加载脚本或编译后的镜像 load the script or the compiled image
+fn load_program(
+    path: String,
+    debug: bool,
+    error_format: &str,
+) -> (GloomStatus, ConstantPool, StaticTable) {
+    let is_image = Path::new(&path).extension() == Some(OsStr::new(image::EXTENSION));
+    if is_image {
+        // 编译后的镜像不需要经过前端 compiled image doesn't need the frontend
+        let result = std::fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| image::read_image(&bytes).map_err(|err| err.to_string()));
+        match result {
+            Ok(image) => image,
+            Err(err) => {
+                eprintln!("failed to load {} : {}", path, err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        // check file
+        let (mut status, static_table) = parse_file(path, debug, error_format);
+        // code generation
+        let constant_pool = CodeGenerator::new().generate(&mut status);
+        (status, constant_pool, static_table)
+    }
+}
+
+fn compile_script(
+    path: String,
+    output: String,
+    debug: bool,
+    error_format: &str,
+) -> Result<(), String> {
     // check file
-    let (mut status, static_table) = parse_file(path, debug);
+    let (mut status, static_table) = parse_file(path, debug, error_format);
 
     // code generation
     let constant_pool = CodeGenerator::new().generate(&mut status);
 
-    // run
-    GloomVM::new(static_table, constant_pool, status).run();
+    let bytes = image::write_image(&status, &constant_pool, &static_table);
+    std::fs::write(&output, bytes).map_err(|err| format!("failed to write {} : {}", output, err))
 }
 
-fn parse_file(path: String, debug: bool) -> (GloomStatus, StaticTable) {
+fn parse_file(path: String, debug: bool, error_format: &str) -> (GloomStatus, StaticTable) {
     // lexer and parse
     let importer = RefCount::new(Importer::new());
-    let parsed_file = Importer::import_file(path, importer).unwrap().unwrap();
+    let parsed_file = match Importer::import_file(path.clone(), importer) {
+        Ok(parsed_file) => parsed_file.unwrap(),
+        Err(err) => {
+            let mut diagnostic = Diagnostic::from_parse(&err);
+            if diagnostic.file.is_empty() {
+                diagnostic.file = path;
+            }
+            report(vec![diagnostic], error_format);
+            std::process::exit(1);
+        }
+    };
     // analyse
     let mut analyzer = Analyzer::new();
-    analyzer
-        .analysis(parsed_file, debug)
-        .map_err(|err| err.to_string())
-        .unwrap();
+    let result = analyzer.analysis(parsed_file, debug);
+    // 警告在错误之前输出 warnings are output before errors
+    let mut diagnostics = analyzer.take_warnings();
+    if let Err(mut errors) = result {
+        diagnostics.append(&mut errors);
+        report(diagnostics, error_format);
+        std::process::exit(1);
+    }
+    report(diagnostics, error_format);
     analyzer.result()
 }
+
+// 格式化脚本 check模式下只列出未格式化的文件 有未格式化的文件时以1退出
+// format scripts, only lists the unformatted files in check mode, and exits with 1 if there are any
+fn format_files(paths: Vec<&str>, check: bool) {
+    let mut unformatted = false;
+    for path in paths {
+        let src = match std::fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("failed to read {} : {}", path, err);
+                std::process::exit(1);
+            }
+        };
+        // 不格式化有语法错误的脚本 scripts with syntax errors are not formatted
+        let importer = RefCount::new(Importer::new());
+        if let Err(err) = Importer::import_file(path.to_string(), importer) {
+            let mut diagnostic = Diagnostic::from_parse(&err);
+            if diagnostic.file.is_empty() {
+                diagnostic.file = path.to_string();
+            }
+            report(vec![diagnostic], "human");
+            std::process::exit(1);
+        }
+        let formatted = Formatter::format(&SyntaxTree::parse(&src));
+        if formatted == src {
+            continue;
+        }
+        if check {
+            println!("{} is not formatted", path);
+            unformatted = true;
+        } else if let Err(err) = std::fs::write(path, formatted) {
+            eprintln!("failed to write {} : {}", path, err);
+            std::process::exit(1);
+        }
+    }
+    if unformatted {
+        std::process::exit(1);
+    }
+}
+
+// 打印编译错误和警告 json格式下每行一个 print compile errors and warnings, one per line in json format
+fn report(diagnostics: Vec<Diagnostic>, error_format: &str) {
+    let mut sources: HashMap<String, Option<String>> = HashMap::new();
+    for diagnostic in diagnostics.iter() {
+        let src = sources
+            .entry(diagnostic.file.clone())
+            .or_insert_with(|| std::fs::read_to_string(&diagnostic.file).ok())
+            .as_deref();
+        match error_format {
+            "json" => eprintln!("{}", diagnostic.json(src)),
+            _ => eprintln!("{}", diagnostic.render(src)),
+        }
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.level == Level::Error)
+        .count();
+    if error_format != "json" {
+        match errors {
+            0 => {}
+            1 => eprintln!("error: aborting due to previous error"),
+            len => eprintln!("error: aborting due to {} previous errors", len),
+        }
+    }
+}
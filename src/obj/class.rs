@@ -15,13 +15,13 @@ use crate::obj::func::{GloomFunc, GloomFuncObj, Param, ReturnType};
 use crate::obj::interface::Interface;
 use crate::obj::object::{GloomObjRef, Object, ObjectType};
 use crate::obj::refcount::RefCount;
-use crate::obj::types::{DataType, RefType};
+use crate::obj::types::{DataType, GenericParam, RefType};
 use crate::vm::machine::GloomVM;
 use crate::vm::value::Value;
 
 pub struct GloomClass {
     pub name: Rc<String>,
-    parent: Option<RefCount<GloomClass>>,
+    pub parent: Option<RefCount<GloomClass>>,
     pub impls: Vec<InterfaceImpl>,
     pub map: HashMap<String, (u16, u8, IsPub, IsMemFunc)>,
     pub field_indexer: SlotIndexer,
@@ -31,6 +31,7 @@ pub struct GloomClass {
     pub field_count: u16,
     pub fn_drop_idx: u16,
     pub is_filled: bool,
+    pub generics: Vec<GenericParam>,
 }
 
 pub type IsMemFunc = bool;
@@ -39,8 +40,10 @@ pub type IsPub = bool;
 #[derive(Debug, Clone)]
 pub struct InterfaceImpl {
     pub interface: RefCount<Interface>,
-    fn_table: Vec<u16>,
+    pub fn_table: Vec<u16>,
     // index : interface_fn_index, elem : class_fn_index
+    // 泛型接口的类型实参 type arguments of generic interface
+    pub args: Vec<DataType>,
 }
 
 impl GloomClass {
@@ -57,6 +60,7 @@ impl GloomClass {
             field_count: 0,
             class_index,
             is_filled: false,
+            generics: Vec::with_capacity(0),
         }
     }
 
@@ -76,14 +80,17 @@ impl GloomClass {
 
     // 最后再调用，因为会检查接口抽象方法是否实现 last to call this function,
     // because this function will check the abstract functions declared in the interface are implemented by this class or not
-    pub fn add_impl(&self, interface_rf: RefCount<Interface>) -> Result<(), AnalysisError> {
+    pub fn add_impl(
+        &mut self,
+        interface_rf: RefCount<Interface>,
+        args: Vec<DataType>,
+    ) -> Result<(), AnalysisError> {
         let interface = interface_rf.inner();
         let mut fn_table = Vec::with_capacity(interface.funcs.len());
         for abstract_func in interface.funcs.iter() {
             let abstract_func = abstract_func.inner();
             let name = &abstract_func.info.name;
-            let expect_params = &abstract_func.info.params;
-            let expect_return_type = &abstract_func.info.return_type;
+            let expect_return_type = &abstract_func.info.return_type.substitute(&args);
             match self.map.get(name.as_str()) {
                 None => {
                     return Result::Err(AnalysisError::FnNotImpl {
@@ -115,6 +122,7 @@ impl GloomClass {
                             found: real_return_type.clone(),
                         });
                     }
+                    let expect_params = &abstract_func.info.params;
                     if found_params.len() != expect_params.len() {
                         return Result::Err(AnalysisError::MismatchImplParamLen {
                             func: name.to_string(),
@@ -129,7 +137,7 @@ impl GloomClass {
                         param_iter.next();
                     }
                     for (idx, (found_param, expect_param)) in param_iter {
-                        let expect_type = &expect_param.data_type;
+                        let expect_type = &expect_param.data_type.substitute(&args);
                         let found_type = &found_param.data_type;
                         if !found_type.belong_to(expect_type) {
                             return Result::Err(AnalysisError::MismatchedImplParamType {
@@ -146,12 +154,10 @@ impl GloomClass {
             }
         }
 
-        let mut vec = unsafe {
-            &mut *(&(self.impls) as *const Vec<InterfaceImpl> as *mut Vec<InterfaceImpl>)
-        };
-        vec.push(InterfaceImpl {
+        self.impls.push(InterfaceImpl {
             interface: interface_rf.clone(),
             fn_table,
+            args,
         });
         Result::Ok(())
     }
@@ -170,6 +176,9 @@ impl GloomClass {
         params: Vec<Param>,
         return_type: ReturnType,
         body: Vec<Statement>,
+        is_generator: bool,
+        is_async: bool,
+        generics: Vec<GenericParam>,
     ) -> Result<(), AnalysisError> {
         let index = self.funcs.len() as u16;
         // found drop fn
@@ -180,16 +189,20 @@ impl GloomClass {
         {
             self.fn_drop_idx = index;
         }
+        let mut func = GloomFunc::new(
+            func_name.clone(),
+            self.file_index,
+            params,
+            return_type,
+            body,
+        );
+        func.info.is_generator = is_generator;
+        func.info.is_async = is_async;
+        func.info.generics = generics;
         match self.map.entry(func_name.deref().clone()) {
             Entry::Vacant(entry) => {
                 entry.insert((index, 0, is_pub, true));
-                self.funcs.push(RefCount::new(GloomFunc::new(
-                    func_name,
-                    self.file_index,
-                    params,
-                    return_type,
-                    body,
-                )));
+                self.funcs.push(RefCount::new(func));
             }
             Entry::Occupied(mut entry) => {
                 /*return Result::Err(AnalysisError::FnAlreadyOccupied {
@@ -197,13 +210,7 @@ impl GloomClass {
                     typ: self.name.to_string()
                 })*/
                 let (fn_idx, _, _, _) = *entry.get();
-                *self.funcs.get_mut(fn_idx as usize).unwrap() = RefCount::new(GloomFunc::new(
-                    func_name,
-                    self.file_index,
-                    params,
-                    return_type,
-                    body,
-                ));
+                *self.funcs.get_mut(fn_idx as usize).unwrap() = RefCount::new(func);
                 entry.replace_entry((fn_idx, 0, is_pub, true));
             }
         }
@@ -240,6 +247,19 @@ impl GloomClass {
         false
     }
 
+    // 实现了给出类型实参的泛型接口 implements the generic interface with the type arguments
+    pub fn is_impl_with(&self, interface: &RefCount<Interface>, args: &[DataType]) -> bool {
+        for real_impl in self.impls.iter() {
+            if (real_impl.interface.eq(interface)
+                && RefType::generic_args_eq(&real_impl.args, args))
+                || real_impl.interface.inner().derived_from(interface)
+            {
+                return true;
+            }
+        }
+        false
+    }
+
     #[inline]
     pub fn len(&self) -> u16 {
         self.field_indexer.size()
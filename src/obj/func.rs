@@ -9,7 +9,8 @@ use crate::frontend::ast::Statement;
 use crate::frontend::status::GloomStatus;
 use crate::obj::object::{GloomObjRef, Object, ObjectType};
 use crate::obj::refcount::RefCount;
-use crate::obj::types::{BasicType, DataType, RefType};
+use crate::obj::types::{BasicType, DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
 use crate::vm::machine::GloomVM;
 use crate::vm::value::{GloomArgs, Value};
 
@@ -61,6 +62,20 @@ impl Object for GloomFuncObj {
     fn field(&self, _: u16, _: u8) -> Value {
         panic!()
     }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(captures) = self.captures.try_borrow() {
+            for value in captures.iter() {
+                if let Value::Ref(rf) = value {
+                    f(rf);
+                }
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        self.captures.borrow_mut().clear();
+    }
 }
 
 impl GloomFuncObj {
@@ -96,7 +111,7 @@ pub struct GloomFunc {
     pub body: FuncBody,
 }
 
-pub type BuiltinFn = Rc<dyn Fn(&GloomVM, GloomArgs) -> Value>;
+pub type BuiltinFn = Rc<dyn Fn(&GloomVM, GloomArgs) -> Result<Value, GloomError>>;
 
 impl GloomFunc {
     pub fn new(
@@ -115,8 +130,13 @@ impl GloomFunc {
                 drop_slots: Vec::with_capacity(0),
                 local_size: 0,
                 need_self: false,
+                is_generator: false,
+                is_async: false,
                 file_index,
                 stack_size: 0,
+                lines: Vec::with_capacity(0),
+                local_names: Vec::with_capacity(0),
+                generics: Vec::with_capacity(0),
             },
             body: FuncBody::AST(statements),
         }
@@ -137,8 +157,13 @@ impl GloomFunc {
                 drop_slots: Vec::with_capacity(0),
                 local_size: 0,
                 need_self,
+                is_generator: false,
+                is_async: false,
                 file_index: 0,
                 stack_size: 0,
+                lines: Vec::with_capacity(0),
+                local_names: Vec::with_capacity(0),
+                generics: Vec::with_capacity(0),
             },
             body: FuncBody::Builtin(func),
         }
@@ -159,8 +184,13 @@ impl GloomFunc {
                 drop_slots: Vec::with_capacity(0),
                 local_size: 0,
                 need_self,
+                is_generator: false,
+                is_async: false,
                 file_index: 0,
                 stack_size: 0,
+                lines: Vec::with_capacity(0),
+                local_names: Vec::with_capacity(0),
+                generics: Vec::with_capacity(0),
             },
             body: FuncBody::Jit(func),
         }
@@ -180,9 +210,14 @@ impl GloomFunc {
                 captures: Vec::with_capacity(0),
                 drop_slots: Vec::with_capacity(0),
                 need_self,
+                is_generator: false,
+                is_async: false,
                 file_index,
                 local_size: 0,
                 stack_size: 0,
+                lines: Vec::with_capacity(0),
+                local_names: Vec::with_capacity(0),
+                generics: Vec::with_capacity(0),
             },
             body: FuncBody::None,
         }
@@ -248,9 +283,34 @@ pub struct FuncInfo {
     pub captures: Vec<Capture>,
     pub drop_slots: Vec<u16>,
     pub need_self: bool,
+    // 调用时返回生成器而非执行函数体 returns a generator rather than executing the body when called
+    pub is_generator: bool,
+    // 调用时返回挂起在开头的任务 returns a task suspended at the beginning when called
+    pub is_async: bool,
     pub file_index: u16,
     pub local_size: u16,
     pub stack_size: u16,
+    // 每条语句的第一个字节码的位置及其行号 按位置升序排列
+    // the position of first bytecode of every statement and its line, sorted by position
+    pub lines: Vec<(u32, u16)>,
+    // 局部变量的槽位及其名称 the slot of local variables and their names
+    pub local_names: Vec<(u16, u8, String)>,
+    // 作用域中的泛型参数 所属类的在前 generic parameters in scope, the ones of belonged class are in front
+    pub generics: Vec<GenericParam>,
+}
+
+impl FuncInfo {
+    // 查找字节码所在的行 find the line of the bytecode
+    pub fn line(&self, pc: usize) -> Option<u16> {
+        let idx = self
+            .lines
+            .partition_point(|(start, _)| *start as usize <= pc);
+        if idx == 0 {
+            Option::None
+        } else {
+            Option::Some(self.lines[idx - 1].1)
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -298,6 +358,33 @@ impl ReturnType {
         }
     }
 
+    #[inline]
+    pub fn substitute(&self, args: &[DataType]) -> ReturnType {
+        match self {
+            ReturnType::Void => ReturnType::Void,
+            ReturnType::Have(data_type) => ReturnType::Have(data_type.substitute(args)),
+        }
+    }
+
+    // 异步函数返回以函数体的返回值为结果的任务 async function returns a task resulting in the return value of its body
+    pub fn into_task(self) -> ReturnType {
+        ReturnType::Have(DataType::Ref(RefType::Task(Box::new(match self {
+            ReturnType::Void => DataType::Ref(RefType::None),
+            ReturnType::Have(data_type) => data_type,
+        }))))
+    }
+
+    // into_task的逆过程 得到异步函数体的返回值类型 the inverse of into_task, get the return type of async function body
+    pub fn task_result(&self) -> ReturnType {
+        match self {
+            ReturnType::Have(DataType::Ref(RefType::Task(result))) => match result.as_ref() {
+                DataType::Ref(RefType::None) => ReturnType::Void,
+                data_type => ReturnType::Have(data_type.clone()),
+            },
+            return_type => return_type.clone(),
+        }
+    }
+
     pub fn belongs_to(&self, other: &ReturnType) -> bool {
         match self {
             ReturnType::Void => match other {
@@ -0,0 +1,174 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::option::GloomOption;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::frame::Frame;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::Value;
+
+// 含有yield的函数被调用时返回的惰性序列 每次恢复执行到下一个yield
+// lazy sequence returned by calling a function containing yield, each resumption executes until next yield
+pub struct GloomGenerator {
+    pub func: RefCount<GloomFunc>,
+    // 挂起的栈帧 运行中或结束后为None the suspended frame, None when running or finished
+    frame: RefCell<Option<Frame>>,
+    running: Cell<bool>,
+}
+
+impl GloomGenerator {
+    #[inline]
+    pub fn new(func: RefCount<GloomFunc>, frame: Frame) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomGenerator {
+            func,
+            frame: RefCell::new(Option::Some(frame)),
+            running: Cell::new(false),
+        }))
+    }
+
+    // 取出栈帧以恢复执行 已结束时返回None take the frame to resume, return None if finished
+    pub fn take_frame(&self) -> Option<Frame> {
+        if self.running.get() {
+            panic!("generator {} is already running", self.func.inner().info.name);
+        }
+        let frame = self.frame.borrow_mut().take();
+        self.running.set(frame.is_some());
+        frame
+    }
+
+    // 在yield处挂起或结束时调用 called when suspended at yield or finished
+    pub fn put_frame(&self, frame: Option<Frame>) {
+        self.running.set(false);
+        *self.frame.borrow_mut() = frame;
+    }
+}
+
+impl Debug for GloomGenerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.running.get() {
+            "running"
+        } else if self.frame.borrow().is_some() {
+            "suspended"
+        } else {
+            "finished"
+        };
+        write!(f, "Generator({} {})", self.func.inner().info.name, state)
+    }
+}
+
+impl Object for GloomGenerator {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Generator
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    // 未结束的生成器仍持有局部变量 the unfinished generator still holds local variables
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        if let Some(mut frame) = self.frame.borrow_mut().take() {
+            frame.release(vm);
+        }
+    }
+
+    fn iter(&self, rf: &GloomObjRef) -> GloomObjRef {
+        rf.clone()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    // 恢复执行需要虚拟机 由虚拟机处理 resuming needs the vm, handled by vm
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::GENERATOR_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(frame) = self.frame.try_borrow() {
+            if let Some(frame) = frame.as_ref() {
+                frame.trace(f);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        if let Some(mut frame) = self.frame.borrow_mut().take() {
+            frame.clear();
+        }
+    }
+}
+
+impl Drop for GloomGenerator {
+    fn drop(&mut self) {
+        // 局部变量中的引用是ManuallyDrop 需要手动释放 references in locals are ManuallyDrop, release them manually
+        self.clear_refs();
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_generator_class() -> BuiltinClass {
+        // 交出的值的类型T是第一个类型参数 type T of yielded values is the first type parameter
+        let item_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let item = item_param.as_data_type(0);
+        let generator_type = DataType::Ref(RefType::Generator(Box::new(item.clone())));
+        let mut methods = Methods::new(generator_type, vec![item_param]);
+        methods.add(
+            "next",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Option(Box::new(item)))),
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let generator = args.vec[0].as_ref().downcast::<GloomGenerator>();
+                match vm.resume(generator) {
+                    Ok(Value::None) => Result::Ok(Value::Ref(GloomOption::new(Option::None))),
+                    Ok(value) => Result::Ok(Value::Ref(GloomOption::new(Option::Some(value)))),
+                    Err(error) => Result::Err(error),
+                }
+            }),
+        );
+        BuiltinClass {
+            name: "Generator".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Generator(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Generator<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    Result::Ok(RefType::Generator(Box::new(vec.pop().unwrap())))
+                }
+            }),
+        }
+    }
+}
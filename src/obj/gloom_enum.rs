@@ -6,13 +6,15 @@ use std::rc::Rc;
 
 use hashbrown::HashMap;
 
+use crate::builtin::string::GloomString;
 use crate::frontend::ast::Statement;
 use crate::frontend::status::GloomStatus;
 use crate::obj::class::IsPub;
-use crate::obj::func::{FuncBody, FuncInfo, GloomFunc, Param, ReturnType};
+use crate::obj::func::{FuncBody, FuncInfo, GloomFunc, GloomFuncObj, Param, ReturnType};
 use crate::obj::object::{GloomObjRef, Object, ObjectType};
 use crate::obj::refcount::RefCount;
-use crate::obj::types::{DataType, RefType};
+use crate::obj::tuple::GloomTuple;
+use crate::obj::types::{DataType, GenericParam, RefType};
 use crate::vm::machine::GloomVM;
 use crate::vm::value::Value;
 
@@ -27,6 +29,7 @@ pub struct GloomEnum {
 pub struct GloomEnumClass {
     pub name: Rc<String>,
     pub types: Vec<RelatedType>,
+    pub names: Vec<String>,
     pub enum_map: HashMap<String, u16>,
     pub func_map: HashMap<String, (u16, IsPub)>,
     pub funcs: Vec<RefCount<GloomFunc>>,
@@ -85,6 +88,18 @@ impl Object for GloomEnum {
     fn field(&self, _: u16, _: u8) -> Value {
         panic!()
     }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(val) = self.val.try_borrow() {
+            if let Value::Ref(rf) = &*val {
+                f(rf);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        self.val.replace(Value::None);
+    }
 }
 
 impl GloomEnumClass {
@@ -92,6 +107,7 @@ impl GloomEnumClass {
         GloomEnumClass {
             name,
             types: Vec::new(),
+            names: Vec::new(),
             enum_map: HashMap::new(),
             func_map: HashMap::new(),
             funcs: Vec::new(),
@@ -100,6 +116,7 @@ impl GloomEnumClass {
     }
     pub fn add_enum_value(&mut self, name: String, related_type: Option<DataType>) {
         let index = self.types.len();
+        self.names.push(name.clone());
         self.enum_map.insert(name, index as u16);
         self.types.push(match related_type {
             None => RelatedType::None,
@@ -113,6 +130,9 @@ impl GloomEnumClass {
         params: Vec<Param>,
         return_type: ReturnType,
         body: Vec<Statement>,
+        is_generator: bool,
+        is_async: bool,
+        generics: Vec<GenericParam>,
     ) {
         let index = self.funcs.len();
         self.func_map
@@ -126,8 +146,13 @@ impl GloomEnumClass {
                 drop_slots: Vec::with_capacity(0),
                 local_size: 0,
                 need_self: false,
+                is_generator,
+                is_async,
                 file_index: self.file_index,
                 stack_size: 0,
+                lines: Vec::with_capacity(0),
+                local_names: Vec::with_capacity(0),
+                generics,
             },
             body: FuncBody::AST(body),
         }));
@@ -140,9 +165,116 @@ impl GloomEnumClass {
     }
 }
 
+impl GloomEnum {
+    #[inline]
+    pub fn new(tag: u16, val: Value, class: RefCount<GloomEnumClass>) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomEnum {
+            tag: Cell::new(tag),
+            val: RefCell::new(val),
+            class,
+        }))
+    }
+
+    // 同一枚举类的同一枚举值，且关联值相等
+    // same enum value of the same enum class, and the associated values are equal
+    pub fn equals(&self, other: &GloomObjRef) -> bool {
+        if let ObjectType::Enum = other.obj_type() {
+            let other = other.downcast::<GloomEnum>();
+            self.class.eq(&other.class)
+                && self.tag.get() == other.tag.get()
+                && match (&*self.val.borrow(), &*other.val.borrow()) {
+                    (Value::None, Value::None) => true,
+                    (val, other_val) => payload_equals(val, other_val),
+                }
+        } else {
+            false
+        }
+    }
+}
+
+// 关联值按结构比较 字符串按内容 元组与嵌套的枚举逐个比较
+// associated values are compared by structure, string by content, tuple and nested enum one by one
+fn payload_equals(val: &Value, other: &Value) -> bool {
+    if let (Value::Ref(rf), Value::Ref(other_rf)) = (val, other) {
+        match (rf.obj_type(), other_rf.obj_type()) {
+            (ObjectType::String, ObjectType::String) => {
+                return rf.downcast::<GloomString>().0.borrow().as_str()
+                    == other_rf.downcast::<GloomString>().0.borrow().as_str();
+            }
+            (ObjectType::Tuple, ObjectType::Tuple) => {
+                let values = rf.downcast::<GloomTuple>().values();
+                let other_values = other_rf.downcast::<GloomTuple>().values();
+                return values.len() == other_values.len()
+                    && values
+                        .iter()
+                        .zip(other_values.iter())
+                        .all(|(val, other_val)| payload_equals(val, other_val));
+            }
+            (ObjectType::Enum, _) => return rf.downcast::<GloomEnum>().equals(other_rf),
+            _ => {}
+        }
+    }
+    // 类型不同的值不相等 values of different types are not equal
+    val.equals(other.clone()).unwrap_or(false)
+}
+
 impl Debug for GloomEnum {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Object of {}", self.class.inner().name)
+        let class = self.class.inner();
+        let name = class.names.get(self.tag.get() as usize).unwrap();
+        match &*self.val.borrow() {
+            Value::None => write!(f, "{}.{}", class.name, name),
+            val => write!(f, "{}.{}({:?})", class.name, name, val),
+        }
+    }
+}
+
+pub struct GloomEnumClassObj {
+    pub class: RefCount<GloomEnumClass>,
+}
+
+impl Object for GloomEnumClassObj {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::MetaEnum
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn drop_by_vm(&self, _: &GloomVM, _: &GloomObjRef) {}
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, _: u16, _: &GloomStatus) -> RefCount<GloomFunc> {
+        panic!()
+    }
+
+    fn field(&self, i1: u16, _: u8) -> Value {
+        Value::Ref(GloomFuncObj::new_func(
+            self.class.inner().funcs.get(i1 as usize).unwrap().clone(),
+        ))
+    }
+}
+
+impl Debug for GloomEnumClassObj {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.class)
+    }
+}
+
+impl GloomEnumClassObj {
+    #[inline]
+    pub fn new(class: RefCount<GloomEnumClass>) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomEnumClassObj { class }))
     }
 }
 
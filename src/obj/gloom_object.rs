@@ -30,13 +30,21 @@ impl Object for GloomObject {
     fn drop_by_vm(&self, vm: &GloomVM, rf: &GloomObjRef) {
         let class = self.class.inner();
         if class.fn_drop_idx < u16::MAX {
-            vm.call_fn(
+            // 析构函数中的错误无法被捕获 the error in drop function can't be caught
+            if let Err(error) = vm.call_fn(
                 &*class.funcs.get(class.fn_drop_idx as usize).unwrap().inner(),
                 GloomArgs::new(vec![Value::Ref(rf.clone())]),
-            );
+            ) {
+                eprintln!(
+                    "warning : ignored an error in drop function of class {} : {}",
+                    class.name, error
+                );
+            }
         }
         for idx in class.ref_index_iter() {
-            vm.drop_object(self.table.slot(*idx).get_ref());
+            if let Slot::Ref(rf) = self.table.slot(*idx) {
+                vm.drop_object(rf);
+            }
         }
     }
 
@@ -64,6 +72,24 @@ impl Object for GloomObject {
     fn field(&self, i1: u16, i2: u8) -> Value {
         self.read_field(i1, i2)
     }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        for idx in self.class.inner().ref_index_iter() {
+            if let Slot::Ref(rf) = self.table.slot(*idx) {
+                f(rf);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        for idx in self.class.inner().ref_index_iter() {
+            if let Slot::Ref(mut rf) = self.table.slot_mut(*idx).take() {
+                unsafe {
+                    ManuallyDrop::drop(&mut rf);
+                }
+            }
+        }
+    }
 }
 
 impl GloomObject {
@@ -125,6 +151,8 @@ impl GloomObject {
 
 impl Drop for GloomObject {
     fn drop(&mut self) {
+        // 引用类型的字段是ManuallyDrop 需要手动释放 fields of ref type are ManuallyDrop, release them manually
+        self.clear_refs();
         self.table.dealloc(self.class.inner().len())
     }
 }
@@ -5,7 +5,7 @@ use hashbrown::HashMap;
 
 use crate::obj::func::{FuncBody, FuncInfo, GloomFunc, ReturnType};
 use crate::obj::refcount::RefCount;
-use crate::obj::types::{DataType, RefType};
+use crate::obj::types::{DataType, GenericParam, RefType};
 
 pub struct Interface {
     pub name: Rc<String>,
@@ -14,6 +14,7 @@ pub struct Interface {
     pub funcs: Vec<RefCount<GloomFunc>>,
     pub map: HashMap<Rc<String>, u16>,
     pub interface_index: u16,
+    pub generics: Vec<GenericParam>,
 }
 
 impl Interface {
@@ -24,6 +25,7 @@ impl Interface {
             funcs: Vec::new(),
             map: HashMap::new(),
             interface_index: index,
+            generics: Vec::with_capacity(0),
         }
     }
     #[inline]
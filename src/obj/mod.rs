@@ -1,5 +1,6 @@
 pub mod class;
 pub mod func;
+pub mod generator;
 pub mod gloom_enum;
 pub mod gloom_object;
 pub mod interface;
@@ -7,6 +8,7 @@ pub mod object;
 pub mod range;
 pub mod refcount;
 pub mod table;
+pub mod task;
 pub mod tuple;
 pub mod types;
 pub mod weak;
@@ -27,6 +27,10 @@ impl GloomObjRef {
         GloomObjRef { obj }
     }
     #[inline(always)]
+    pub fn addr(&self) -> *const () {
+        Rc::as_ptr(&self.obj) as *const ()
+    }
+    #[inline(always)]
     pub fn count(&self) -> usize {
         Rc::strong_count(&self.obj)
     }
@@ -46,6 +50,16 @@ impl GloomObjRef {
         self.obj.drop_by_vm(vm, self);
     }
 
+    #[inline(always)]
+    pub fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        self.obj.trace(f);
+    }
+
+    #[inline(always)]
+    pub fn clear_refs(&self) {
+        self.obj.clear_refs();
+    }
+
     #[inline(always)]
     pub fn at(&self, index: &mut usize) -> Option<Value> {
         self.obj.at(index)
@@ -93,6 +107,10 @@ pub trait Object: Debug {
     // object type should impl
     fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc>;
     fn field(&self, i1: u16, i2: u8) -> Value;
+    // 可能构成循环引用的类型应实现 遍历持有的引用 以及在被循环回收时断开它们
+    // type could form reference cycle should impl, visit the held references, and break them when collected as cycle
+    fn trace(&self, _: &mut dyn FnMut(&GloomObjRef)) {}
+    fn clear_refs(&self) {}
 }
 
 pub enum ObjectType {
@@ -111,7 +129,17 @@ pub enum ObjectType {
     String,
     Array,
     Queue,
+    Map,
+    Set,
+    Option,
+    Weak,
+    Generator,
+    Task,
+    Timer,
+    Channel,
+    Error,
     Tuple,
     ListIter,
     RangeIter,
+    Host,
 }
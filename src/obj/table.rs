@@ -20,6 +20,15 @@ impl Table {
             Table { ptr }
         }
     }
+    // 扩容到new_len 新的槽位初始化为Null grow to new_len, the new slots are initialized as Null
+    pub fn grow(&mut self, old_len: u16, new_len: u16) {
+        let mut table = Table::new(new_len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.ptr, table.ptr, old_len as usize);
+        }
+        std::mem::swap(self, &mut table);
+        table.dealloc(old_len);
+    }
     pub fn dealloc(&mut self, len: u16) {
         let layout = Layout::array::<MaybeUninit<Slot>>(len as usize).unwrap();
         unsafe { alloc::dealloc(self.ptr as *mut u8, layout) }
@@ -0,0 +1,333 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::map::drop_value;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::frame::Frame;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::Value;
+
+// 任务要完成的工作 the work of a task
+pub enum TaskBody {
+    // 异步函数的栈帧 运行中或结束后为None the frame of async function, None when running or finished
+    Coroutine(RefCount<GloomFunc>, RefCell<Option<Frame>>),
+    // 挂起直到期限 第一次执行时确定期限 suspend until the deadline, which is decided at the first run
+    Sleep(Duration, Cell<Option<Instant>>),
+    // 让出一次执行权 give up the execution once
+    Yield(Cell<bool>),
+    // 向通道发送消息 发送后为None send the message to the channel, None after sent
+    Send(GloomObjRef, RefCell<Option<Value>>),
+    // 从通道接收消息 是否把结果包装为可选值
+    // receive a message from the channel, whether to wrap the result as option
+    Recv(GloomObjRef, bool),
+}
+
+// 调用异步函数得到的可等待的任务 由调度器或等待它的任务驱动执行
+// awaitable task returned by calling async function, driven by the scheduler or the task awaiting it
+pub struct GloomTask {
+    body: TaskBody,
+    running: Cell<bool>,
+    // 已交给调度器 handed over to the scheduler
+    spawned: Cell<bool>,
+    // 结束后的结果或错误信息 the result or error message after finished
+    result: RefCell<Option<Result<Value, String>>>,
+    // 等待它结束的顶层任务 top-level tasks waiting for it to finish
+    waiters: RefCell<Vec<GloomObjRef>>,
+}
+
+impl GloomTask {
+    #[inline]
+    pub fn new(func: RefCount<GloomFunc>, frame: Frame) -> GloomObjRef {
+        GloomTask::with_body(TaskBody::Coroutine(
+            func,
+            RefCell::new(Option::Some(frame)),
+        ))
+    }
+
+    #[inline]
+    pub fn sleep(duration: Duration) -> GloomObjRef {
+        GloomTask::with_body(TaskBody::Sleep(duration, Cell::new(Option::None)))
+    }
+
+    #[inline]
+    pub fn yield_now() -> GloomObjRef {
+        GloomTask::with_body(TaskBody::Yield(Cell::new(false)))
+    }
+
+    #[inline]
+    pub fn send(channel: GloomObjRef, message: Value) -> GloomObjRef {
+        GloomTask::with_body(TaskBody::Send(channel, RefCell::new(Option::Some(message))))
+    }
+
+    #[inline]
+    pub fn recv(channel: GloomObjRef, wrap: bool) -> GloomObjRef {
+        GloomTask::with_body(TaskBody::Recv(channel, wrap))
+    }
+
+    fn with_body(body: TaskBody) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomTask {
+            body,
+            running: Cell::new(false),
+            spawned: Cell::new(false),
+            result: RefCell::new(Option::None),
+            waiters: RefCell::new(Vec::new()),
+        }))
+    }
+
+    #[inline]
+    pub fn body(&self) -> &TaskBody {
+        &self.body
+    }
+
+    // 取出异步函数的栈帧以恢复执行 take the frame of async function to resume
+    pub fn take_frame(&self) -> Frame {
+        if self.running.get() {
+            panic!("{:?} is already running", self);
+        }
+        let frame = match &self.body {
+            TaskBody::Coroutine(_, frame) => frame.borrow_mut().take().unwrap(),
+            _ => panic!(),
+        };
+        self.running.set(true);
+        frame
+    }
+
+    // 在await处挂起或结束时调用 called when suspended at await or finished
+    pub fn put_frame(&self, frame: Option<Frame>) {
+        self.running.set(false);
+        if let TaskBody::Coroutine(_, slot) = &self.body {
+            *slot.borrow_mut() = frame;
+        }
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.result.borrow().is_some()
+    }
+
+    #[inline]
+    pub fn is_spawned(&self) -> bool {
+        self.spawned.get()
+    }
+
+    // 返回是否需要加入调度器 return whether it needs to be added into the scheduler
+    pub fn spawn(&self) -> bool {
+        let need = !self.spawned.get() && !self.is_finished();
+        self.spawned.set(true);
+        need
+    }
+
+    // 结束后的结果 失败的任务每次被等待时都抛出错误
+    // the result after finished, the failed task raises the error every time it is awaited
+    pub fn result(&self) -> Option<Result<Value, GloomError>> {
+        self.result.borrow().as_ref().map(|result| match result {
+            Ok(value) => Result::Ok(value.clone()),
+            Err(msg) => Result::Err(GloomError::new(msg.clone())),
+        })
+    }
+
+    // 记录结果 返回需要唤醒的任务 record the result, return the tasks need to be woken
+    pub fn finish(&self, result: Result<Value, String>) -> Vec<GloomObjRef> {
+        *self.result.borrow_mut() = Option::Some(result);
+        std::mem::take(&mut *self.waiters.borrow_mut())
+    }
+
+    #[inline]
+    pub fn has_waiters(&self) -> bool {
+        !self.waiters.borrow().is_empty()
+    }
+
+    #[inline]
+    pub fn add_waiter(&self, task: GloomObjRef) {
+        self.waiters.borrow_mut().push(task);
+    }
+}
+
+impl Debug for GloomTask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match self.result.borrow().as_ref() {
+            Some(Ok(_)) => "done",
+            Some(Err(_)) => "failed",
+            None if self.running.get() => "running",
+            None => "pending",
+        };
+        match &self.body {
+            TaskBody::Coroutine(func, _) => {
+                write!(f, "Task({} {})", func.inner().info.name, state)
+            }
+            TaskBody::Sleep(duration, _) => {
+                write!(f, "Task(sleep {}ms {})", duration.as_millis(), state)
+            }
+            TaskBody::Yield(_) => write!(f, "Task(yieldNow {})", state),
+            TaskBody::Send(channel, _) => write!(f, "Task(send to {:?} {})", channel, state),
+            TaskBody::Recv(channel, _) => write!(f, "Task(recv from {:?} {})", channel, state),
+        }
+    }
+}
+
+impl Object for GloomTask {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Task
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        match &self.body {
+            TaskBody::Coroutine(_, frame) => {
+                if let Some(mut frame) = frame.borrow_mut().take() {
+                    frame.release(vm);
+                }
+            }
+            TaskBody::Send(channel, message) => {
+                if let Some(message) = message.borrow_mut().take() {
+                    drop_value(vm, &message);
+                }
+                vm.drop_object(channel);
+            }
+            TaskBody::Recv(channel, _) => vm.drop_object(channel),
+            _ => {}
+        }
+        if let Some(Ok(value)) = self.result.borrow_mut().take() {
+            drop_value(vm, &value);
+        }
+        let waiters = std::mem::take(&mut *self.waiters.borrow_mut());
+        for waiter in waiters.iter() {
+            vm.drop_object(waiter);
+        }
+    }
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::TASK_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        match &self.body {
+            TaskBody::Coroutine(_, frame) => {
+                if let Ok(frame) = frame.try_borrow() {
+                    if let Some(frame) = frame.as_ref() {
+                        frame.trace(f);
+                    }
+                }
+            }
+            TaskBody::Send(channel, message) => {
+                f(channel);
+                if let Ok(message) = message.try_borrow() {
+                    if let Some(Value::Ref(rf)) = message.as_ref() {
+                        f(rf);
+                    }
+                }
+            }
+            TaskBody::Recv(channel, _) => f(channel),
+            _ => {}
+        }
+        if let Ok(result) = self.result.try_borrow() {
+            if let Some(Ok(Value::Ref(rf))) = result.as_ref() {
+                f(rf);
+            }
+        }
+        if let Ok(waiters) = self.waiters.try_borrow() {
+            for waiter in waiters.iter() {
+                f(waiter);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        match &self.body {
+            TaskBody::Coroutine(_, frame) => {
+                if let Some(mut frame) = frame.borrow_mut().take() {
+                    frame.clear();
+                }
+            }
+            TaskBody::Send(_, message) => {
+                message.borrow_mut().take();
+            }
+            _ => {}
+        }
+        self.result.borrow_mut().take();
+        self.waiters.borrow_mut().clear();
+    }
+}
+
+impl Drop for GloomTask {
+    fn drop(&mut self) {
+        // 局部变量中的引用是ManuallyDrop 需要手动释放 references in locals are ManuallyDrop, release them manually
+        self.clear_refs();
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_task_class() -> BuiltinClass {
+        // 结果的类型T是第一个类型参数 type T of the result is the first type parameter
+        let result_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let result = result_param.as_data_type(0);
+        let task_type = DataType::Ref(RefType::Task(Box::new(result)));
+        let mut methods = Methods::new(task_type, vec![result_param]);
+        methods.add(
+            "isDone",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                Result::Ok(Value::Bool(
+                    args.vec[0].as_ref().downcast::<GloomTask>().is_finished(),
+                ))
+            }),
+        );
+        BuiltinClass {
+            name: "Task".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Task(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Task<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    Result::Ok(RefType::Task(Box::new(vec.pop().unwrap())))
+                }
+            }),
+        }
+    }
+}
@@ -53,6 +53,16 @@ impl Object for GloomTuple {
     fn field(&self, _: u16, _: u8) -> Value {
         panic!()
     }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(vec) = self.vec.try_borrow() {
+            for value in vec.iter() {
+                if let Value::Ref(rf) = value {
+                    f(rf);
+                }
+            }
+        }
+    }
 }
 
 impl Debug for GloomTuple {
@@ -77,4 +87,8 @@ impl GloomTuple {
             vec: RefCell::new(vec),
         }))
     }
+    #[inline]
+    pub fn values(&self) -> Vec<Value> {
+        self.vec.borrow().clone()
+    }
 }
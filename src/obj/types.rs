@@ -1,5 +1,6 @@
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::Deref;
+use std::rc::Rc;
 
 use crate::obj::class::GloomClass;
 use crate::obj::func::ReturnType;
@@ -85,6 +86,10 @@ impl DataType {
         }
     }
     #[inline]
+    pub fn is_string(&self) -> bool {
+        matches!(self, DataType::Ref(RefType::String))
+    }
+    #[inline]
     pub fn is_bool(&self) -> bool {
         match self {
             DataType::Bool => true,
@@ -104,6 +109,41 @@ impl DataType {
         }
     }
     #[inline]
+    pub fn substitute(&self, args: &[DataType]) -> DataType {
+        match self {
+            DataType::Ref(ref_type) => ref_type.substitute(args),
+            basic_type => basic_type.clone(),
+        }
+    }
+    #[inline]
+    pub fn infer(&self, actual: &DataType, args: &mut [Option<DataType>]) {
+        if let DataType::Ref(ref_type) = self {
+            ref_type.infer(actual, args);
+        }
+    }
+    #[inline]
+    pub fn is_type_param(&self) -> bool {
+        matches!(self, DataType::Ref(RefType::TypeParam(_)))
+    }
+    // 可以作为Map的键或Set的元素 类型参数和Any在运行时检查
+    // could be key of Map or element of Set, type parameter and Any are checked at runtime
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            DataType::Int | DataType::Char | DataType::Bool => true,
+            DataType::Num => false,
+            DataType::Ref(ref_type) => match ref_type {
+                RefType::Int
+                | RefType::Char
+                | RefType::Bool
+                | RefType::String
+                | RefType::Any
+                | RefType::TypeParam(_) => true,
+                RefType::Tuple(types) => types.iter().all(|tp| tp.is_hashable()),
+                _ => false,
+            },
+        }
+    }
+    #[inline]
     pub fn is_none(&self) -> bool {
         match self {
             DataType::Ref(RefType::None) => true,
@@ -173,6 +213,26 @@ pub enum RefType {
     Weak(Box<DataType>),
     Array(Box<DataType>),
     Queue(Box<DataType>),
+    Map(Box<(DataType, DataType)>),
+    Set(Box<DataType>),
+    // 可能没有值 maybe has no value
+    Option(Box<DataType>),
+    // 生成器交出的值 the values given out by generator
+    Generator(Box<DataType>),
+    // 异步函数的结果 the result of async function
+    Task(Box<DataType>),
+    // 标准库Timer的定时器句柄 timer handle of std library Timer
+    Timer,
+    // 标准库Channel的消息通道 message channel of std library Channel
+    Channel(Box<DataType>),
+    // 被捕获的运行时错误 caught runtime error
+    Error,
+    // 泛型类或泛型接口的类型参数 type parameter of generic class, interface or function
+    TypeParam(Box<TypeParam>),
+    // 给出了类型实参的泛型类或泛型接口 generic class or interface with type arguments
+    Generic(Box<(RefType, Vec<DataType>)>),
+    // 宿主程序注册的类 index和类名 class registered by host program, index and name
+    Host(u16, Rc<String>),
 
     Int,
     Num,
@@ -181,6 +241,49 @@ pub enum RefType {
     String,
 }
 
+// 泛型参数的声明 declaration of generic parameter, like 'T : Comparable<T>'
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenericParam {
+    pub name: Rc<String>,
+    // 约束 只能是接口 bound, could only be interface
+    pub bound: Option<DataType>,
+}
+
+impl GenericParam {
+    pub fn as_data_type(&self, index: u16) -> DataType {
+        let bound = match &self.bound {
+            Some(DataType::Ref(RefType::Interface(interface))) => Option::Some(interface.clone()),
+            Some(DataType::Ref(RefType::Generic(generic))) => match &generic.0 {
+                RefType::Interface(interface) => Option::Some(interface.clone()),
+                _ => Option::None,
+            },
+            _ => Option::None,
+        };
+        DataType::Ref(RefType::TypeParam(Box::new(TypeParam {
+            index,
+            name: self.name.clone(),
+            bound,
+        })))
+    }
+}
+
+// 类的类型参数在前 函数自己的在后 type parameters of class are in front of the function's own
+#[derive(Clone, Debug)]
+pub struct TypeParam {
+    pub index: u16,
+    pub name: Rc<String>,
+    // 约束的接口 泛型实参被擦除 the interface of bound, with type arguments erased
+    pub bound: Option<RefCount<Interface>>,
+}
+
+// 解析约束时类型参数的约束还未确定 所以不比较约束
+// the bound of type parameter is not determined yet when resolving bounds, so it's not compared
+impl PartialEq for TypeParam {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.name.eq(&other.name)
+    }
+}
+
 pub struct FuncType {
     params: Vec<DataType>,
     return_type: ReturnType,
@@ -196,6 +299,14 @@ impl RefType {
             RefType::Class(cls) => match other {
                 RefType::Class(class) => cls.eq(class) || cls.inner().is_derived_from(class),
                 RefType::Interface(interface) => cls.inner().is_impl_from(interface),
+                RefType::Generic(generic) => match &generic.0 {
+                    // 未给出类型实参的类可以作为泛型类使用 class without type arguments could be used as generic class
+                    RefType::Class(class) => cls.eq(class) || cls.inner().is_derived_from(class),
+                    RefType::Interface(interface) => {
+                        cls.inner().is_impl_with(interface, &generic.1)
+                    }
+                    _ => false,
+                },
                 _ => false,
             },
             RefType::Interface(inter) => match other {
@@ -203,6 +314,47 @@ impl RefType {
                 RefType::Interface(interface) => {
                     inter.eq(interface) || inter.inner().derived_from(interface)
                 }
+                RefType::Generic(generic) => match &generic.0 {
+                    RefType::Interface(interface) => {
+                        inter.eq(interface) || inter.inner().derived_from(interface)
+                    }
+                    _ => false,
+                },
+                _ => false,
+            },
+            RefType::Generic(generic) => {
+                let (base, args) = generic.deref();
+                match other {
+                    RefType::Generic(other_generic) => {
+                        base.belong_to(&other_generic.0)
+                            && RefType::generic_args_eq(args, &other_generic.1)
+                    }
+                    _ => base.belong_to(other),
+                }
+            }
+            RefType::TypeParam(param) => {
+                if self.eq(other) {
+                    return true;
+                }
+                // 有约束的类型参数可以作为约束的接口使用 type parameter could be used as the interface of its bound
+                match &param.bound {
+                    Some(interface) => RefType::Interface(interface.clone()).belong_to(other),
+                    None => false,
+                }
+            }
+            // 空的字面量的元素类型是Any empty literal has the element type Any
+            RefType::Map(map_type) => match other {
+                RefType::Map(other_map_type) => RefType::generic_args_eq(
+                    &[map_type.0.clone(), map_type.1.clone()],
+                    &[other_map_type.0.clone(), other_map_type.1.clone()],
+                ),
+                _ => false,
+            },
+            RefType::Set(elem) => match other {
+                RefType::Set(other_elem) => RefType::generic_args_eq(
+                    std::slice::from_ref(elem.deref()),
+                    std::slice::from_ref(other_elem.deref()),
+                ),
                 _ => false,
             },
             RefType::Func(func_type) => {
@@ -219,9 +371,136 @@ impl RefType {
                     false
                 }
             }
+            // 创建时未确定消息类型的通道可以作为任意消息类型的通道使用
+            // channel created without message type could be used as channel of any message type
+            RefType::Channel(message) => match other {
+                RefType::Channel(other_message) => RefType::generic_args_eq(
+                    std::slice::from_ref(message.deref()),
+                    std::slice::from_ref(other_message.deref()),
+                ),
+                _ => false,
+            },
             ref_type => ref_type.eq(other),
         }
     }
+    // 基本类型与其包装类视为相同 basic type and its boxed class are regarded as the same
+    pub fn generic_args_eq(args: &[DataType], other_args: &[DataType]) -> bool {
+        args.len() == other_args.len()
+            && args.iter().zip(other_args.iter()).all(|(arg, other_arg)| {
+                let (arg, other_arg) = (arg.as_ref_type(), other_arg.as_ref_type());
+                arg.eq(&RefType::Any) || other_arg.eq(&RefType::Any) || arg.eq(&other_arg)
+            })
+    }
+
+    // 把类型参数替换为类型实参 replace type parameters with type arguments
+    pub fn substitute(&self, args: &[DataType]) -> DataType {
+        let ref_type = match self {
+            RefType::TypeParam(param) => match args.get(param.index as usize) {
+                Some(arg) => return arg.clone(),
+                None => self.clone(),
+            },
+            RefType::Generic(generic) => RefType::Generic(Box::new((
+                generic.0.clone(),
+                generic.1.iter().map(|arg| arg.substitute(args)).collect(),
+            ))),
+            RefType::Tuple(vec) => {
+                RefType::Tuple(Box::new(vec.iter().map(|tp| tp.substitute(args)).collect()))
+            }
+            RefType::Func(func_type) => {
+                let (params, return_type, any_ok) = func_type.deref();
+                RefType::Func(Box::new((
+                    params.iter().map(|tp| tp.substitute(args)).collect(),
+                    return_type.substitute(args),
+                    *any_ok,
+                )))
+            }
+            RefType::Weak(generic) => RefType::Weak(Box::new(generic.substitute(args))),
+            RefType::Array(generic) => RefType::Array(Box::new(generic.substitute(args))),
+            RefType::Queue(generic) => RefType::Queue(Box::new(generic.substitute(args))),
+            RefType::Map(map_type) => RefType::Map(Box::new((
+                map_type.0.substitute(args),
+                map_type.1.substitute(args),
+            ))),
+            RefType::Set(generic) => RefType::Set(Box::new(generic.substitute(args))),
+            RefType::Option(generic) => RefType::Option(Box::new(generic.substitute(args))),
+            RefType::Generator(generic) => {
+                RefType::Generator(Box::new(generic.substitute(args)))
+            }
+            RefType::Task(generic) => RefType::Task(Box::new(generic.substitute(args))),
+            RefType::Channel(generic) => RefType::Channel(Box::new(generic.substitute(args))),
+            ref_type => ref_type.clone(),
+        };
+        DataType::Ref(ref_type)
+    }
+
+    // 通过实际类型推断类型参数 infer type parameters by the actual type
+    pub fn infer(&self, actual: &DataType, args: &mut [Option<DataType>]) {
+        let actual_ref = match actual {
+            DataType::Ref(ref_type) => ref_type,
+            basic_type => {
+                if let RefType::TypeParam(param) = self {
+                    if let Some(arg @ None) = args.get_mut(param.index as usize) {
+                        *arg = Option::Some(basic_type.clone());
+                    }
+                }
+                return;
+            }
+        };
+        match (self, actual_ref) {
+            // none不能确定类型 none can't determine the type
+            (RefType::TypeParam(param), actual_ref) if !actual_ref.eq(&RefType::None) => {
+                if let Some(arg @ None) = args.get_mut(param.index as usize) {
+                    *arg = Option::Some(actual.clone());
+                }
+            }
+            (RefType::Generic(generic), RefType::Generic(actual_generic)) => {
+                for (tp, actual) in generic.1.iter().zip(actual_generic.1.iter()) {
+                    tp.infer(actual, args);
+                }
+            }
+            // 类实现了泛型接口 class implements generic interface
+            (RefType::Generic(generic), RefType::Class(class)) => {
+                if let RefType::Interface(interface) = &generic.0 {
+                    let class = class.inner();
+                    if let Some(real_impl) = class.impls.iter().find(|i| i.interface.eq(interface))
+                    {
+                        for (tp, actual) in generic.1.iter().zip(real_impl.args.iter()) {
+                            tp.infer(actual, args);
+                        }
+                    }
+                }
+            }
+            (RefType::Tuple(vec), RefType::Tuple(actual_vec)) => {
+                for (tp, actual) in vec.iter().zip(actual_vec.iter()) {
+                    tp.infer(actual, args);
+                }
+            }
+            (RefType::Func(func_type), RefType::Func(actual_func_type)) => {
+                for (tp, actual) in func_type.0.iter().zip(actual_func_type.0.iter()) {
+                    tp.infer(actual, args);
+                }
+                if let (ReturnType::Have(tp), ReturnType::Have(actual)) =
+                    (&func_type.1, &actual_func_type.1)
+                {
+                    tp.infer(actual, args);
+                }
+            }
+            (RefType::Weak(tp), RefType::Weak(actual))
+            | (RefType::Array(tp), RefType::Array(actual))
+            | (RefType::Queue(tp), RefType::Queue(actual))
+            | (RefType::Set(tp), RefType::Set(actual))
+            | (RefType::Option(tp), RefType::Option(actual))
+            | (RefType::Generator(tp), RefType::Generator(actual))
+            | (RefType::Task(tp), RefType::Task(actual))
+            | (RefType::Channel(tp), RefType::Channel(actual)) => tp.infer(actual, args),
+            (RefType::Map(map_type), RefType::Map(actual)) => {
+                map_type.0.infer(&actual.0, args);
+                map_type.1.infer(&actual.1, args);
+            }
+            _ => {}
+        }
+    }
+
     #[inline]
     pub fn as_built_type(&self) -> BuiltinType {
         match self {
@@ -229,6 +508,15 @@ impl RefType {
             RefType::Weak(_) => BuiltinType::Weak,
             RefType::Array(_) => BuiltinType::Array,
             RefType::Queue(_) => BuiltinType::Queue,
+            RefType::Map(_) => BuiltinType::Map,
+            RefType::Set(_) => BuiltinType::Set,
+            RefType::Option(_) => BuiltinType::Option,
+            RefType::Generator(_) => BuiltinType::Generator,
+            RefType::Task(_) => BuiltinType::Task,
+            RefType::Timer => BuiltinType::Timer,
+            RefType::Channel(_) => BuiltinType::Channel,
+            RefType::Error => BuiltinType::Error,
+            RefType::Host(index, _) => BuiltinType::Host(*index),
             RefType::Int => BuiltinType::Int,
             RefType::Num => BuiltinType::Num,
             RefType::Char => BuiltinType::Char,
@@ -253,6 +541,24 @@ impl Display for RefType {
                 RefType::Weak(generic) => format!("Weak<{:?}>", generic),
                 RefType::Array(generic) => format!("Array<{:?}>", generic),
                 RefType::Queue(generic) => format!("Queue<{:?}>", generic),
+                RefType::Map(map_type) => format!("Map<{:?},{:?}>", map_type.0, map_type.1),
+                RefType::Set(generic) => format!("Set<{:?}>", generic),
+                RefType::Option(generic) => format!("Option<{:?}>", generic),
+                RefType::Generator(generic) => format!("Generator<{:?}>", generic),
+                RefType::Task(generic) => format!("Task<{:?}>", generic),
+                RefType::Channel(generic) => format!("Channel<{:?}>", generic),
+                RefType::Host(_, name) => name.to_string(),
+                RefType::TypeParam(param) => param.name.to_string(),
+                RefType::Generic(generic) => format!(
+                    "{}<{}>",
+                    generic.0,
+                    generic
+                        .1
+                        .iter()
+                        .map(|arg| arg.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
                 ref_type => format!("{:?}", ref_type),
             }
         )
@@ -270,6 +576,15 @@ pub enum BuiltinType {
     Weak,
     Array,
     Queue,
+    Map,
+    Set,
+    Option,
+    Generator,
+    Task,
+    Timer,
+    Channel,
+    Error,
+    Host(u16),
 }
 
 impl BuiltinType {
@@ -286,8 +601,16 @@ impl BuiltinType {
             "String" => BuiltinType::String,
             "Array" => BuiltinType::Array,
             "Queue" => BuiltinType::Queue,
+            "Map" => BuiltinType::Map,
+            "Set" => BuiltinType::Set,
+            "Option" => BuiltinType::Option,
             "Func" => BuiltinType::Func,
             "Weak" => BuiltinType::Weak,
+            "Generator" => BuiltinType::Generator,
+            "Task" => BuiltinType::Task,
+            "Timer" => BuiltinType::Timer,
+            "Channel" => BuiltinType::Channel,
+            "Error" => BuiltinType::Error,
             _ => return Option::None,
         };
         Option::Some(builtin_type)
@@ -303,6 +626,15 @@ impl BuiltinType {
             BuiltinType::Weak => "Weak",
             BuiltinType::Array => "Array",
             BuiltinType::Queue => "Queue",
+            BuiltinType::Map => "Map",
+            BuiltinType::Set => "Set",
+            BuiltinType::Option => "Option",
+            BuiltinType::Generator => "Generator",
+            BuiltinType::Task => "Task",
+            BuiltinType::Timer => "Timer",
+            BuiltinType::Channel => "Channel",
+            BuiltinType::Error => "Error",
+            BuiltinType::Host(_) => "Host",
         }
     }
 }
@@ -1,16 +1,153 @@
+use std::any::Any;
 use std::fmt::{Debug, Formatter};
-use std::rc::Weak;
+use std::rc::{Rc, Weak};
 
-use crate::obj::object::Object;
-use crate::obj::types::DataType;
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::option::GloomOption;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
 
-pub struct GloomWeak {
-    wk: Weak<dyn Object>,
-    generic: DataType,
+// 不持有对象的引用 不影响引用计数 reference which doesn't own the object, doesn't affect the ref count
+pub struct GloomWeak(pub Weak<dyn Object>);
+
+impl GloomWeak {
+    #[inline]
+    pub fn new(rf: &GloomObjRef) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomWeak(rf.weak())))
+    }
+
+    // 对象已被释放或正在被回收时返回None
+    // return None if the object has been released or is being finalized
+    pub fn upgrade(&self, vm: &GloomVM) -> Option<GloomObjRef> {
+        if vm.is_finalizing(self.0.as_ptr() as *const ()) {
+            return Option::None;
+        }
+        self.0.upgrade().map(GloomObjRef::new)
+    }
 }
 
 impl Debug for GloomWeak {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Object of Weak<{:?}>", self.generic)
+        match self.0.upgrade() {
+            Some(obj) => write!(f, "Weak({:?})", obj),
+            None => write!(f, "Weak(none)"),
+        }
+    }
+}
+
+impl Object for GloomWeak {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Weak
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    // 弱引用不拥有对象 所以不释放它 weak reference doesn't own the object, so don't release it
+    fn drop_by_vm(&self, _: &GloomVM, _: &GloomObjRef) {}
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::WEAK_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+}
+
+impl BuiltinClass {
+    pub fn gloom_weak_class() -> BuiltinClass {
+        // 被引用对象的类型T是第一个类型参数 type T of referenced object is the first type parameter
+        let object_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let object = object_param.as_data_type(0);
+        let weak_type = DataType::Ref(RefType::Weak(Box::new(object.clone())));
+        let mut methods = Methods::new(weak_type.clone(), vec![object_param]);
+        methods.add_static(
+            "of",
+            vec![Methods::param("object", object.clone())],
+            ReturnType::Have(weak_type),
+            Rc::new(|_, args| match &args.vec[0] {
+                Value::Ref(rf) => Result::Ok(Value::Ref(GloomWeak::new(rf))),
+                value => Result::Err(GloomError::new(format!(
+                    "can't make a weak reference to value {:?}",
+                    value
+                ))),
+            }),
+        );
+        methods.add(
+            "upgrade",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Option(Box::new(object)))),
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                let option = weak_of_args(&args).upgrade(vm).map(Value::Ref);
+                Result::Ok(Value::Ref(GloomOption::new(option)))
+            }),
+        );
+        methods.add(
+            "isAlive",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| Result::Ok(Value::Bool(weak_of_args(&args).upgrade(vm).is_some()))),
+        );
+        BuiltinClass {
+            name: "Weak".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Weak(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Weak<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    let object = vec.pop().unwrap();
+                    if !matches!(object, DataType::Ref(_)) {
+                        return Result::Err(format!(
+                            "type {} of Weak is not a reference type",
+                            object
+                        ));
+                    }
+                    Result::Ok(RefType::Weak(Box::new(object)))
+                }
+            }),
+        }
     }
 }
+
+#[inline]
+fn weak_of_args(args: &GloomArgs) -> &GloomWeak {
+    args.vec[0].as_ref().downcast::<GloomWeak>()
+}
@@ -0,0 +1,235 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+
+use gloomscript::bytecode::gen::CodeGenerator;
+use gloomscript::frontend::analysis::{Analyzer, ReplScope};
+use gloomscript::frontend::diagnostic::Diagnostic;
+use gloomscript::frontend::import::Importer;
+use gloomscript::frontend::status::GloomStatus;
+use gloomscript::obj::func::FuncBody;
+use gloomscript::obj::refcount::RefCount;
+use gloomscript::vm::constant::ConstantPool;
+use gloomscript::vm::error::GloomError;
+use gloomscript::vm::frame::Frame;
+use gloomscript::vm::machine::GloomVM;
+use gloomscript::vm::static_table::StaticTable;
+use gloomscript::vm::value::{GloomArgs, Value};
+
+// 交互式解释器 所有输入共享同一个分析器、虚拟机以及局部变量帧
+// interactive interpreter, all inputs share the same analyzer, vm and the frame of local variables
+pub struct Repl {
+    importer: RefCount<Importer>,
+    analyzer: Analyzer,
+    scope: ReplScope,
+    vm: GloomVM,
+    frame: Frame,
+}
+
+impl Repl {
+    const FILE_NAME: &'static str = "<stdin>";
+
+    pub fn new(enable_jit: bool) -> Repl {
+        Repl {
+            importer: RefCount::new(Importer::new()),
+            analyzer: Analyzer::new(),
+            scope: ReplScope::new(),
+            vm: GloomVM::new(
+                StaticTable::new(0, Vec::new()),
+                ConstantPool::new(),
+                GloomStatus::new(),
+                enable_jit,
+            ),
+            frame: Frame::new(0, 0),
+        }
+    }
+
+    pub fn run(mut self) {
+        let mut editor = Editor::<()>::new();
+        let history = Self::history_path();
+        if let Some(path) = &history {
+            // 第一次运行时历史文件不存在 the history file doesn't exist at the first time
+            let _ = editor.load_history(path);
+        }
+        println!(
+            "GloomScript {} REPL, input 'exit' or press Ctrl-D to quit",
+            env!("CARGO_PKG_VERSION")
+        );
+        let mut src = String::new();
+        loop {
+            let prompt = if src.is_empty() { ">>> " } else { "... " };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    if src.is_empty() && line.trim() == "exit" {
+                        break;
+                    }
+                    src.push_str(line.as_str());
+                    src.push('\n');
+                    // 括号未闭合时继续读取下一行 continue to read next line if brackets are not closed
+                    if Self::is_incomplete(src.as_str()) {
+                        continue;
+                    }
+                    let input = std::mem::take(&mut src);
+                    if input.trim().is_empty() {
+                        continue;
+                    }
+                    editor.add_history_entry(input.trim_end());
+                    // 内部错误不应结束会话 an internal error should not end the session
+                    match panic::catch_unwind(AssertUnwindSafe(|| self.eval(input))) {
+                        Ok(Ok(Value::None)) => {}
+                        Ok(Ok(value)) => println!("{:?}", value),
+                        Ok(Err(msg)) => eprintln!("{}", msg),
+                        Err(payload) => eprintln!("{}", GloomError::internal(payload)),
+                    }
+                }
+                // Ctrl-C 丢弃当前的输入 Ctrl-C discards current input
+                Err(ReadlineError::Interrupted) => src.clear(),
+                Err(_) => break,
+            }
+        }
+        for slot_idx in self.scope.drop_slots() {
+            self.frame.drop_local(&self.vm, slot_idx);
+        }
+        if let Some(path) = &history {
+            if let Err(err) = editor.save_history(path) {
+                println!("warning : failed to save REPL history : {}", err);
+            }
+        }
+    }
+
+    // 错误所在的行是输入中的行 the line of error is the line in input
+    fn render(diagnostics: &[Diagnostic], src: &str) -> String {
+        let rendered: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(Option::Some(src)))
+            .collect();
+        rendered.join("\n").trim_end().to_string()
+    }
+
+    fn eval(&mut self, src: String) -> Result<Value, String> {
+        let parsed_file = Importer::import_str(
+            src.clone(),
+            Self::FILE_NAME.to_string(),
+            self.importer.clone(),
+        )
+        .map_err(|err| Self::render(&[Diagnostic::from_parse(&err)], src.as_str()))?;
+        // analyse
+        std::mem::swap(self.analyzer.status_mut(), self.vm.status_mut());
+        let checkpoint = self.analyzer.checkpoint(&self.scope);
+        let from = *checkpoint.decl_count();
+        let result = self.analyzer.analysis_repl(parsed_file, &mut self.scope);
+        let warnings = self.analyzer.take_warnings();
+        if !warnings.is_empty() {
+            eprintln!("{}", Self::render(&warnings, src.as_str()));
+        }
+        let (imports, input) = match result {
+            Ok(result) => result,
+            Err(diagnostics) => {
+                self.analyzer.rollback(checkpoint, &mut self.scope);
+                std::mem::swap(self.analyzer.status_mut(), self.vm.status_mut());
+                return Result::Err(Self::render(&diagnostics, src.as_str()));
+            }
+        };
+
+        // code generation
+        let constant_pool = std::mem::replace(self.vm.constant_pool_mut(), ConstantPool::new());
+        let mut generator = CodeGenerator::with_pool(constant_pool);
+        let status = self.analyzer.status_mut();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            generator.generate_decls(status, &from);
+            for script_body in imports.iter() {
+                generator.generate_func(&mut script_body.inner_mut().func);
+            }
+            generator.generate_func(&mut input.inner_mut().func);
+        }));
+        *self.vm.constant_pool_mut() = generator.into_pool();
+        if let Err(payload) = result {
+            // 生成代码失败的声明同样需要回滚 the declarations failed to generate code also need to be rolled back
+            self.analyzer.rollback(checkpoint, &mut self.scope);
+            std::mem::swap(self.analyzer.status_mut(), self.vm.status_mut());
+            return Result::Err(format!(
+                "failed to generate code : {}",
+                GloomError::from_panic(payload).msg
+            ));
+        }
+        std::mem::swap(self.analyzer.status_mut(), self.vm.status_mut());
+
+        // 新声明的静态变量和局部变量 new declared static and local variables
+        let (static_len, static_drop_vec) = self.analyzer.statics();
+        self.vm.static_table_mut().grow(static_len, static_drop_vec);
+        self.frame.grow_local(input.inner().func.info.local_size);
+
+        // run
+        for script_body in imports.iter() {
+            self.vm
+                .call_fn(
+                    &script_body.inner().func,
+                    GloomArgs::new(Vec::with_capacity(0)),
+                )
+                .map_err(|err| err.traceback())?;
+        }
+        let input = input.inner();
+        let value = match &input.func.body {
+            FuncBody::ByteCodes(bytecodes) => self
+                .vm
+                .interpret(bytecodes, &mut self.frame, &input.func.info)
+                .map_err(|err| err.traceback())?,
+            _ => panic!(),
+        };
+        // 执行本次输入派生的任务 run the tasks spawned by this input
+        self.vm.run_tasks().map_err(|err| err.traceback())?;
+        Result::Ok(value)
+    }
+
+    // 是否有未闭合的括号、字符串或注释 whether there are unclosed brackets, strings or comments
+    fn is_incomplete(src: &str) -> bool {
+        let mut depth = 0;
+        let mut chars = src.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' | '\'' => loop {
+                    match chars.next() {
+                        Some(c) if c == ch => break,
+                        Some(_) => {}
+                        None => return true,
+                    }
+                },
+                '#' => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    let mut last = ' ';
+                    loop {
+                        match chars.next() {
+                            Some('/') if last == '*' => break,
+                            Some(c) => last = c,
+                            None => return true,
+                        }
+                    }
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        depth > 0
+    }
+
+    fn history_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".gloom_history"))
+    }
+}
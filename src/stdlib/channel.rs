@@ -0,0 +1,304 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::builtin::map::drop_value;
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::task::GloomTask;
+use crate::obj::types::{DataType, GenericParam, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::value::{GloomArgs, Value};
+
+// 任务之间传递消息的通道 缓冲区满时发送者挂起 为空时接收者挂起
+// channel passing messages between tasks, senders suspend when the buffer is full and receivers suspend when it is empty
+pub struct GloomChannel {
+    // None表示无界 None means unbounded
+    capacity: Option<usize>,
+    buffer: RefCell<VecDeque<Value>>,
+    closed: Cell<bool>,
+    // 等待发送或接收的顶层任务 top-level tasks waiting to send or receive
+    senders: RefCell<VecDeque<GloomObjRef>>,
+    receivers: RefCell<VecDeque<GloomObjRef>>,
+}
+
+impl GloomChannel {
+    pub fn new(capacity: Option<usize>) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomChannel {
+            capacity,
+            buffer: RefCell::new(VecDeque::new()),
+            closed: Cell::new(false),
+            senders: RefCell::new(VecDeque::new()),
+            receivers: RefCell::new(VecDeque::new()),
+        }))
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.borrow().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.borrow().is_empty()
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        match self.capacity {
+            Some(capacity) => self.len() >= capacity,
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn push(&self, value: Value) {
+        self.buffer.borrow_mut().push_back(value);
+    }
+
+    #[inline]
+    pub fn pop(&self) -> Option<Value> {
+        self.buffer.borrow_mut().pop_front()
+    }
+
+    #[inline]
+    pub fn add_sender(&self, task: GloomObjRef) {
+        self.senders.borrow_mut().push_back(task);
+    }
+
+    #[inline]
+    pub fn add_receiver(&self, task: GloomObjRef) {
+        self.receivers.borrow_mut().push_back(task);
+    }
+
+    // 缓冲区有了空位 取出一个等待的发送者 the buffer has room, take a waiting sender
+    #[inline]
+    pub fn take_sender(&self) -> Option<GloomObjRef> {
+        self.senders.borrow_mut().pop_front()
+    }
+
+    // 缓冲区有了消息 取出一个等待的接收者 the buffer has a message, take a waiting receiver
+    #[inline]
+    pub fn take_receiver(&self) -> Option<GloomObjRef> {
+        self.receivers.borrow_mut().pop_front()
+    }
+
+    // 关闭后不能再发送 返回所有需要唤醒的任务
+    // no more sending after closed, return all the tasks need to be woken
+    pub fn close(&self) -> Vec<GloomObjRef> {
+        self.closed.set(true);
+        let mut tasks: Vec<GloomObjRef> = self.senders.borrow_mut().drain(..).collect();
+        tasks.extend(self.receivers.borrow_mut().drain(..));
+        tasks
+    }
+}
+
+impl Debug for GloomChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.closed.get() { " closed" } else { "" };
+        match self.capacity {
+            Some(capacity) => write!(f, "Channel({}/{}{})", self.len(), capacity, state),
+            None => write!(f, "Channel({}{})", self.len(), state),
+        }
+    }
+}
+
+impl Object for GloomChannel {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Channel
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        for value in self.buffer.borrow_mut().drain(..) {
+            drop_value(vm, &value);
+        }
+        for task in self.senders.borrow_mut().drain(..) {
+            vm.drop_object(&task);
+        }
+        for task in self.receivers.borrow_mut().drain(..) {
+            vm.drop_object(&task);
+        }
+    }
+
+    // 通道自身就是迭代器 消息由虚拟机接收 the channel itself is the iterator, messages are received by vm
+    fn iter(&self, rf: &GloomObjRef) -> GloomObjRef {
+        rf.clone()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::CHANNEL_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(buffer) = self.buffer.try_borrow() {
+            for value in buffer.iter() {
+                if let Value::Ref(rf) = value {
+                    f(rf);
+                }
+            }
+        }
+        for tasks in [&self.senders, &self.receivers] {
+            if let Ok(tasks) = tasks.try_borrow() {
+                for task in tasks.iter() {
+                    f(task);
+                }
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        self.buffer.borrow_mut().clear();
+        self.senders.borrow_mut().clear();
+        self.receivers.borrow_mut().clear();
+    }
+}
+
+#[inline]
+fn channel_of_args(args: &GloomArgs) -> &GloomChannel {
+    args.vec[0].as_ref().downcast::<GloomChannel>()
+}
+
+impl BuiltinClass {
+    pub fn gloom_channel_class() -> BuiltinClass {
+        // 消息的类型T是第一个类型参数 type T of message is the first type parameter
+        let message_param = GenericParam {
+            name: Rc::new(String::from("T")),
+            bound: Option::None,
+        };
+        let message = message_param.as_data_type(0);
+        let channel_type = DataType::Ref(RefType::Channel(Box::new(message.clone())));
+        // 创建时不确定消息类型 由声明的变量类型给出
+        // message type is unknown when created, which is given by the type of declared variable
+        let any_channel = DataType::Ref(RefType::Channel(Box::new(DataType::Ref(RefType::Any))));
+        let mut methods = Methods::new(channel_type, vec![message_param]);
+        methods.add_plain_static(
+            "bounded",
+            vec![Methods::param("capacity", DataType::Int)],
+            ReturnType::Have(any_channel.clone()),
+            Rc::new(|_, args| {
+                let capacity = args.vec[0].assert_int()?;
+                if capacity <= 0 {
+                    return Result::Err(GloomError::new(format!(
+                        "the capacity of bounded channel should be positive, found {}",
+                        capacity
+                    )));
+                }
+                Result::Ok(Value::Ref(GloomChannel::new(Option::Some(
+                    capacity as usize,
+                ))))
+            }),
+        );
+        methods.add_plain_static(
+            "unbounded",
+            Vec::with_capacity(0),
+            ReturnType::Have(any_channel),
+            Rc::new(|_, _| Result::Ok(Value::Ref(GloomChannel::new(Option::None)))),
+        );
+        methods.add(
+            "send",
+            vec![Methods::param("message", message.clone())],
+            ReturnType::Have(DataType::Ref(RefType::Task(Box::new(DataType::Ref(
+                RefType::None,
+            ))))),
+            Vec::with_capacity(0),
+            Rc::new(|_, mut args| {
+                let message = args.vec.pop().unwrap();
+                Result::Ok(Value::Ref(GloomTask::send(
+                    args.vec[0].as_ref().clone(),
+                    message,
+                )))
+            }),
+        );
+        // 通道关闭并且没有剩余的消息时得到空的可选值
+        // get an empty option when the channel is closed and no message remains
+        methods.add(
+            "recv",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Ref(RefType::Task(Box::new(DataType::Ref(
+                RefType::Option(Box::new(message)),
+            ))))),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| {
+                Result::Ok(Value::Ref(GloomTask::recv(
+                    args.vec[0].as_ref().clone(),
+                    true,
+                )))
+            }),
+        );
+        methods.add(
+            "close",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                vm.close_channel(channel_of_args(&args));
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "isClosed",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(channel_of_args(&args).is_closed()))),
+        );
+        methods.add(
+            "len",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Int(channel_of_args(&args).len() as i64))),
+        );
+        BuiltinClass {
+            name: "Channel".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: Box::new(|option| match option {
+                None => Result::Ok(RefType::Channel(Box::new(DataType::Ref(RefType::Any)))),
+                Some(mut vec) => {
+                    if vec.len() != 1 {
+                        return Result::Err(format!(
+                            "type Channel<T> need only one generic type, found {:?}",
+                            vec
+                        ));
+                    }
+                    Result::Ok(RefType::Channel(Box::new(vec.pop().unwrap())))
+                }
+            }),
+        }
+    }
+}
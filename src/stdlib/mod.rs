@@ -1,5 +1,10 @@
 use std::convert::TryFrom;
 
+use crate::obj::types::BuiltinType;
+
+pub mod channel;
+pub mod timer;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum StdLibKind {
     RegExp,
@@ -25,3 +30,14 @@ impl TryFrom<&str> for StdLibKind {
         }
     }
 }
+
+impl StdLibKind {
+    // 导入后可以使用的内置类型 builtin types could be used after imported
+    pub fn builtin_types(&self) -> Vec<BuiltinType> {
+        match self {
+            StdLibKind::Timer => vec![BuiltinType::Timer],
+            StdLibKind::Channel => vec![BuiltinType::Channel],
+            _ => Vec::with_capacity(0),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::builtin::classes::{BuiltinClass, Methods};
+use crate::frontend::status::GloomStatus;
+use crate::obj::func::{GloomFunc, ReturnType};
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+use crate::obj::refcount::RefCount;
+use crate::obj::task::GloomTask;
+use crate::obj::types::{DataType, RefType};
+use crate::vm::error::GloomError;
+use crate::vm::machine::GloomVM;
+use crate::vm::scheduler::TimerKey;
+use crate::vm::value::{GloomArgs, Value};
+
+// 由事件循环在期限到达时调用回调函数的定时器 interval为None时只调用一次
+// timer whose callback is called by the event loop when the deadline arrives, called only once if interval is None
+pub struct GloomTimer {
+    callback: RefCell<Option<GloomObjRef>>,
+    interval: Option<Duration>,
+    active: Cell<bool>,
+    // 在调度器中登记的位置 用于取消 the registration in scheduler, used to cancel
+    key: Cell<Option<TimerKey>>,
+}
+
+impl GloomTimer {
+    pub fn new(callback: GloomObjRef, interval: Option<Duration>) -> GloomObjRef {
+        GloomObjRef::new(Rc::new(GloomTimer {
+            callback: RefCell::new(Option::Some(callback)),
+            interval,
+            active: Cell::new(true),
+            key: Cell::new(Option::None),
+        }))
+    }
+
+    #[inline]
+    pub fn callback(&self) -> Option<GloomObjRef> {
+        self.callback.borrow().clone()
+    }
+
+    #[inline]
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    #[inline]
+    pub fn key(&self) -> Option<TimerKey> {
+        self.key.get()
+    }
+
+    #[inline]
+    pub fn set_key(&self, key: Option<TimerKey>) {
+        self.key.set(key);
+    }
+
+    // 期限到达 返回是否需要调用回调函数 the deadline arrives, return whether the callback needs to be called
+    pub fn fire(&self) -> bool {
+        self.key.set(Option::None);
+        let active = self.active.get();
+        if self.interval.is_none() {
+            self.active.set(false);
+        }
+        active
+    }
+
+    // 返回需要从调度器中移除的登记 return the registration needs to be removed from scheduler
+    pub fn cancel(&self) -> Option<TimerKey> {
+        self.active.set(false);
+        self.key.take()
+    }
+}
+
+impl Debug for GloomTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.active.get() {
+            "active"
+        } else {
+            "inactive"
+        };
+        match self.interval {
+            Some(interval) => write!(f, "Timer(every {}ms {})", interval.as_millis(), state),
+            None => write!(f, "Timer(once {})", state),
+        }
+    }
+}
+
+impl Object for GloomTimer {
+    fn obj_type(&self) -> ObjectType {
+        ObjectType::Timer
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn drop_by_vm(&self, vm: &GloomVM, _: &GloomObjRef) {
+        if let Some(callback) = self.callback.borrow_mut().take() {
+            vm.drop_object(&callback);
+        }
+    }
+
+    fn iter(&self, _: &GloomObjRef) -> GloomObjRef {
+        panic!()
+    }
+
+    fn at(&self, _: &mut usize) -> Option<Value> {
+        panic!()
+    }
+
+    fn next(&self) -> Value {
+        panic!()
+    }
+
+    fn method(&self, index: u16, status: &GloomStatus) -> RefCount<GloomFunc> {
+        status
+            .builtin_classes
+            .get(BuiltinClass::TIMER_INDEX)
+            .unwrap()
+            .inner()
+            .funcs
+            .get(index as usize)
+            .unwrap()
+            .clone()
+    }
+
+    fn field(&self, _: u16, _: u8) -> Value {
+        panic!()
+    }
+
+    fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        if let Ok(callback) = self.callback.try_borrow() {
+            if let Some(callback) = callback.as_ref() {
+                f(callback);
+            }
+        }
+    }
+
+    fn clear_refs(&self) {
+        self.callback.borrow_mut().take();
+    }
+}
+
+#[inline]
+fn timer_of_args(args: &GloomArgs) -> &GloomTimer {
+    args.vec[0].as_ref().downcast::<GloomTimer>()
+}
+
+#[inline]
+fn millis_of_args(args: &GloomArgs, index: usize) -> Result<Duration, GloomError> {
+    let millis = args.vec[index].assert_int()?;
+    if millis < 0 {
+        return Result::Err(GloomError::new(format!("negative time {}ms", millis)));
+    }
+    Result::Ok(Duration::from_millis(millis as u64))
+}
+
+// 创建定时器并交给事件循环 create a timer and hand it over to the event loop
+fn schedule(vm: &GloomVM, args: GloomArgs, repeat: bool) -> Result<Value, GloomError> {
+    let delay = millis_of_args(&args, 1)?;
+    let interval = if repeat {
+        if delay.is_zero() {
+            return Result::Err(GloomError::new(String::from(
+                "the interval of timer should be positive",
+            )));
+        }
+        Option::Some(delay)
+    } else {
+        Option::None
+    };
+    let timer = GloomTimer::new(args.vec[0].as_ref().clone(), interval);
+    vm.set_timer(&timer, delay);
+    Result::Ok(Value::Ref(timer))
+}
+
+impl BuiltinClass {
+    pub fn gloom_timer_class() -> BuiltinClass {
+        let timer_type = DataType::Ref(RefType::Timer);
+        let callback_type = Methods::func_type(Vec::with_capacity(0), ReturnType::Void);
+        let mut methods = Methods::new(timer_type.clone(), Vec::with_capacity(0));
+        methods.add_static(
+            "setTimeout",
+            vec![
+                Methods::param("callback", callback_type.clone()),
+                Methods::param("millis", DataType::Int),
+            ],
+            ReturnType::Have(timer_type.clone()),
+            Rc::new(|vm, args| schedule(vm, args, false)),
+        );
+        methods.add_static(
+            "setInterval",
+            vec![
+                Methods::param("callback", callback_type),
+                Methods::param("millis", DataType::Int),
+            ],
+            ReturnType::Have(timer_type),
+            Rc::new(|vm, args| schedule(vm, args, true)),
+        );
+        // 从虚拟机启动开始经过的毫秒数 不受系统时间调整的影响
+        // milliseconds elapsed since the vm started, not affected by the adjustment of system time
+        methods.add_static(
+            "now",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Int),
+            Rc::new(|vm, _| Result::Ok(Value::Int(vm.clock().as_millis() as i64))),
+        );
+        methods.add_static(
+            "sleep",
+            vec![Methods::param("millis", DataType::Int)],
+            ReturnType::Have(DataType::Ref(RefType::Task(Box::new(DataType::Ref(
+                RefType::None,
+            ))))),
+            Rc::new(|_, args| Result::Ok(Value::Ref(GloomTask::sleep(millis_of_args(&args, 0)?)))),
+        );
+        methods.add(
+            "cancel",
+            Vec::with_capacity(0),
+            ReturnType::Void,
+            Vec::with_capacity(0),
+            Rc::new(|vm, args| {
+                vm.cancel_timer(timer_of_args(&args));
+                Result::Ok(Value::None)
+            }),
+        );
+        methods.add(
+            "isActive",
+            Vec::with_capacity(0),
+            ReturnType::Have(DataType::Bool),
+            Vec::with_capacity(0),
+            Rc::new(|_, args| Result::Ok(Value::Bool(timer_of_args(&args).is_active()))),
+        );
+        BuiltinClass {
+            name: "Timer".to_string(),
+            map: methods.map,
+            funcs: methods.funcs,
+            get_ref_type_fn: BuiltinClass::none_generic_fn(RefType::Timer),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::rc::Weak;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::obj::object::{GloomObjRef, Object, ObjectType};
+
+// 分配多少个对象后自动回收一次循环引用 collect cycles automatically after allocating this many objects
+pub const GC_THRESHOLD: usize = 10000;
+
+// 引用计数之上的循环引用回收器 采用试删除算法(Bacon–Rajan)
+// cycle collector on top of reference counting, using trial deletion (Bacon–Rajan)
+pub struct CycleCollector {
+    // 可能是垃圾环一部分的对象 不持有它们以免影响引用计数
+    // objects may be part of garbage cycle, don't own them to avoid affecting the ref count
+    roots: Vec<Weak<dyn Object>>,
+    // 已记录的对象在roots中的下标 index in roots of recorded objects
+    buffered: HashMap<*const (), usize>,
+    allocated: usize,
+}
+
+impl CycleCollector {
+    pub fn new() -> CycleCollector {
+        CycleCollector {
+            roots: Vec::new(),
+            buffered: HashMap::new(),
+            allocated: 0,
+        }
+    }
+
+    // 引用计数减少后仍不为零的对象可能是垃圾环的根
+    // object whose ref count is still non-zero after decreasing may be the root of garbage cycle
+    #[inline]
+    pub fn possible_root(&mut self, rf: &GloomObjRef) {
+        if may_cycle(rf) {
+            // 地址可能被新对象复用 所以总是更新弱引用 the address may be reused by new object, so always update the weak reference
+            match self.buffered.get(&rf.addr()) {
+                Some(index) => self.roots[*index] = rf.weak(),
+                None => {
+                    self.buffered.insert(rf.addr(), self.roots.len());
+                    self.roots.push(rf.weak());
+                }
+            }
+        }
+    }
+
+    // 返回是否达到了自动回收的阈值 return whether the threshold of automatic collection is reached
+    #[inline]
+    pub fn allocate(&mut self, rf: &GloomObjRef) -> bool {
+        self.possible_root(rf);
+        self.allocated += 1;
+        if self.allocated >= GC_THRESHOLD {
+            self.allocated = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn take_roots(&mut self) -> Vec<GloomObjRef> {
+        self.buffered.clear();
+        self.roots
+            .drain(..)
+            .filter_map(|weak| weak.upgrade().map(GloomObjRef::new))
+            .collect()
+    }
+}
+
+impl Default for CycleCollector {
+    fn default() -> Self {
+        CycleCollector::new()
+    }
+}
+
+// 找出从根可达的 只被彼此引用的对象 find objects reachable from roots which are only referenced by each other
+pub fn find_garbage(roots: Vec<GloomObjRef>) -> Vec<GloomObjRef> {
+    // 标记灰色 收集根可达的所有对象 mark gray, collect all objects reachable from roots
+    let mut nodes: HashMap<*const (), GloomObjRef> = HashMap::new();
+    // 按发现的顺序析构 finalize in the order of discovery
+    let mut order: Vec<*const ()> = Vec::new();
+    let mut stack: Vec<GloomObjRef> = roots.into_iter().rev().collect();
+    while let Some(rf) = stack.pop() {
+        if nodes.contains_key(&rf.addr()) {
+            continue;
+        }
+        rf.trace(&mut |child| {
+            if may_cycle(child) && !nodes.contains_key(&child.addr()) {
+                stack.push(child.clone());
+            }
+        });
+        order.push(rf.addr());
+        nodes.insert(rf.addr(), rf);
+    }
+    // 试删除 减去对象之间的引用 trial deletion, subtract the references between objects
+    let mut internal: HashMap<*const (), usize> = HashMap::new();
+    for rf in nodes.values() {
+        rf.trace(&mut |child| {
+            if nodes.contains_key(&child.addr()) {
+                *internal.entry(child.addr()).or_insert(0) += 1;
+            }
+        });
+    }
+    // 每个对象在nodes中被持有一次 还有剩余计数的对象被外部引用
+    // each object is held once in nodes, the objects having remaining count are referenced externally
+    let mut live: Vec<*const ()> = nodes
+        .iter()
+        .filter(|(addr, rf)| rf.count() - 1 > internal.get(*addr).copied().unwrap_or(0))
+        .map(|(addr, _)| *addr)
+        .collect();
+    // 标记黑色 外部引用的对象可达的对象都是存活的 mark black, objects reachable from the externally referenced are alive
+    let mut black: HashSet<*const ()> = HashSet::new();
+    while let Some(addr) = live.pop() {
+        if !black.insert(addr) {
+            continue;
+        }
+        nodes.get(&addr).unwrap().trace(&mut |child| {
+            if nodes.contains_key(&child.addr()) && !black.contains(&child.addr()) {
+                live.push(child.addr());
+            }
+        });
+    }
+    order
+        .into_iter()
+        .filter(|addr| !black.contains(addr))
+        .map(|addr| nodes.remove(&addr).unwrap())
+        .collect()
+}
+
+#[inline]
+fn may_cycle(rf: &GloomObjRef) -> bool {
+    matches!(
+        rf.obj_type(),
+        ObjectType::Class
+            | ObjectType::Enum
+            | ObjectType::Func
+            | ObjectType::Array
+            | ObjectType::Queue
+            | ObjectType::Map
+            | ObjectType::Set
+            | ObjectType::Tuple
+            | ObjectType::Option
+            | ObjectType::Generator
+            | ObjectType::Task
+            | ObjectType::Timer
+            | ObjectType::Channel
+    )
+}
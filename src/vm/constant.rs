@@ -1,12 +1,20 @@
+use hashbrown::HashMap;
+
+use crate::builtin::boxed::{GloomBool, GloomChar, GloomInt};
+use crate::builtin::string::GloomString;
 use crate::obj::func::GloomFunc;
-use crate::obj::object::GloomObjRef;
+use crate::obj::gloom_enum::GloomEnum;
+use crate::obj::object::{GloomObjRef, ObjectType};
 use crate::obj::refcount::RefCount;
+use crate::vm::value::Value;
 
 pub struct ConstantPool {
     pub int: Vec<i64>,
     pub num: Vec<f64>,
     pub str: Vec<GloomObjRef>,
     pub nameless_fn: Vec<RefCount<GloomFunc>>,
+    pub jump_table: Vec<JumpTable>,
+    pub try_table: Vec<TryBlock>,
 }
 
 const CONST_POOL_INIT_CAP: usize = 8;
@@ -18,6 +26,88 @@ impl ConstantPool {
             num: Vec::with_capacity(CONST_POOL_INIT_CAP),
             str: Vec::with_capacity(CONST_POOL_INIT_CAP),
             nameless_fn: Vec::with_capacity(CONST_POOL_INIT_CAP),
+            jump_table: Vec::new(),
+            try_table: Vec::new(),
+        }
+    }
+}
+
+// match表达式使用的跳转表 the jump table used by match expression
+// int char bool 以及枚举的tag 都作为 int key
+// int char bool and the tag of enum are all used as int key
+pub struct JumpTable {
+    pub int_map: HashMap<i64, u32>,
+    pub str_map: HashMap<String, u32>,
+    // 没有分支被匹配时跳转的位置 the label to jump when no branch matched
+    pub default: u32,
+}
+
+impl JumpTable {
+    pub fn new() -> JumpTable {
+        JumpTable {
+            int_map: HashMap::new(),
+            str_map: HashMap::new(),
+            default: u32::MAX,
+        }
+    }
+
+    #[inline]
+    pub fn label(&self, value: &Value) -> u32 {
+        let key = match value {
+            Value::Int(i) => *i,
+            Value::Char(ch) => *ch as i64,
+            Value::Bool(bl) => *bl as i64,
+            Value::Ref(rf) => match rf.obj_type() {
+                ObjectType::Int => rf.downcast::<GloomInt>().0.get(),
+                ObjectType::Char => rf.downcast::<GloomChar>().0.get() as i64,
+                ObjectType::Bool => rf.downcast::<GloomBool>().0.get() as i64,
+                ObjectType::Enum => rf.downcast::<GloomEnum>().tag.get() as i64,
+                ObjectType::String => {
+                    return match self
+                        .str_map
+                        .get(rf.downcast::<GloomString>().0.borrow().as_str())
+                    {
+                        Some(label) => *label,
+                        None => self.default,
+                    }
+                }
+                _ => panic!("can't match {:?}", rf),
+            },
+            value => panic!("can't match {:?}", value),
+        };
+        match self.int_map.get(&key) {
+            Some(label) => *label,
+            None => self.default,
         }
     }
 }
+
+// try-catch语句的异常处理表项 the handler entry of try-catch statement
+// [start, end) 是try块的字节码范围 is the bytecode range of try block
+pub struct TryBlock {
+    pub start: u32,
+    pub end: u32,
+    // 发生错误时跳转的位置 the label to jump when error occurs
+    pub handler: u32,
+}
+
+impl TryBlock {
+    pub fn new() -> TryBlock {
+        TryBlock {
+            start: u32::MAX,
+            end: u32::MAX,
+            handler: u32::MAX,
+        }
+    }
+    pub fn from(start: u32, end: u32, handler: u32) -> TryBlock {
+        TryBlock {
+            start,
+            end,
+            handler,
+        }
+    }
+    #[inline]
+    pub fn contains(&self, pc: usize) -> bool {
+        pc >= self.start as usize && pc < self.end as usize
+    }
+}
@@ -0,0 +1,86 @@
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+// 运行时错误 可以被 try-catch 捕获  runtime error, can be caught by try-catch
+#[derive(Clone)]
+pub struct GloomError {
+    pub msg: String,
+    // 错误经过的函数 最内层的在前 the functions the error passed through, the innermost first
+    pub traceback: Vec<TraceFrame>,
+}
+
+#[derive(Clone)]
+pub struct TraceFrame {
+    pub file: String,
+    pub func: Rc<String>,
+    // JIT编译后的函数没有行号 compiled function by JIT has no line
+    pub line: Option<u16>,
+}
+
+impl GloomError {
+    pub fn new(msg: String) -> GloomError {
+        GloomError {
+            msg,
+            traceback: Vec::new(),
+        }
+    }
+
+    // 将Rust的panic转换为运行时错误 convert the panic of rust into runtime error
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> GloomError {
+        let msg = match payload.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(msg) => msg.to_string(),
+                Err(_) => String::from("unknown runtime error"),
+            },
+        };
+        GloomError::new(msg)
+    }
+
+    // 虚拟机内部的panic不是脚本的错误 不能被try-catch捕获
+    // the panic inside vm is not an error of script, which can't be caught by try-catch
+    pub fn internal(payload: Box<dyn Any + Send>) -> GloomError {
+        let mut error = GloomError::from_panic(payload);
+        error.msg = format!("internal error : {}", error.msg);
+        error
+    }
+
+    #[inline]
+    pub fn trace(&mut self, file: String, func: Rc<String>, line: Option<u16>) {
+        self.traceback.push(TraceFrame { file, func, line });
+    }
+
+    // 类似Python的调用栈信息 python-style traceback
+    pub fn traceback(&self) -> String {
+        let mut str = String::from("Traceback (most recent call last):\n");
+        for frame in self.traceback.iter().rev() {
+            match frame.line {
+                Some(line) => str.push_str(
+                    format!(
+                        "  File \"{}\", line {}, in {}\n",
+                        frame.file, line, frame.func
+                    )
+                    .as_str(),
+                ),
+                None => str.push_str(
+                    format!("  File \"{}\", in {} (jit)\n", frame.file, frame.func).as_str(),
+                ),
+            }
+        }
+        str.push_str(format!("runtime error : {}", self.msg).as_str());
+        str
+    }
+}
+
+impl Debug for GloomError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl Display for GloomError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
@@ -4,6 +4,8 @@ use std::mem::ManuallyDrop;
 use crate::obj::func::{Capture, Param};
 use crate::obj::object::GloomObjRef;
 use crate::obj::types::{BasicType, DataType};
+use crate::vm::constant::ConstantPool;
+use crate::vm::error::GloomError;
 use crate::vm::machine::GloomVM;
 use crate::vm::slot::Slot;
 use crate::vm::value::{GloomArgs, Value};
@@ -11,6 +13,11 @@ use crate::vm::value::{GloomArgs, Value};
 pub struct Frame {
     stack: Vec<Value>,
     local: Box<[Slot]>,
+    // 进入的try块在try表中的下标 以及进入时的栈深度
+    // the index in try table of entered try blocks, and the stack depth when enter
+    handlers: Vec<(u16, usize)>,
+    // 生成器或异步函数挂起时恢复执行的位置 the position to resume when the generator or async function is suspended
+    suspended: Option<usize>,
 }
 
 impl Debug for Frame {
@@ -36,39 +43,55 @@ impl Frame {
         Frame {
             stack: Vec::with_capacity(stack_size as usize),
             local: vec.into_boxed_slice(),
+            handlers: Vec::new(),
+            suspended: Option::None,
+        }
+    }
+    // REPL中新声明的局部变量 the local variables newly declared in REPL
+    pub fn grow_local(&mut self, local_size: u16) {
+        if local_size as usize > self.local.len() {
+            let mut vec = std::mem::take(&mut self.local).into_vec();
+            vec.resize_with(local_size as usize, || Slot::Null);
+            self.local = vec.into_boxed_slice();
         }
     }
     #[inline]
-    pub fn fill_args(&mut self, params: &Vec<Param>, args: GloomArgs) {
+    pub fn fill_args(&mut self, params: &Vec<Param>, args: GloomArgs) -> Result<(), GloomError> {
         for (param, arg) in params.iter().zip(args.vec.into_iter()) {
             let (slot_idx, sub_idx) = param.index;
             match &param.data_type {
-                DataType::Int => self.write_int(arg.assert_int(), slot_idx, sub_idx),
-                DataType::Num => self.write_num(arg.assert_num(), slot_idx, sub_idx),
-                DataType::Char => self.write_char(arg.assert_char(), slot_idx, sub_idx),
-                DataType::Bool => self.write_bool(arg.assert_bool(), slot_idx, sub_idx),
+                DataType::Int => self.write_int(arg.assert_int()?, slot_idx, sub_idx),
+                DataType::Num => self.write_num(arg.assert_num()?, slot_idx, sub_idx),
+                DataType::Char => self.write_char(arg.assert_char()?, slot_idx, sub_idx),
+                DataType::Bool => self.write_bool(arg.assert_bool()?, slot_idx, sub_idx),
                 DataType::Ref(_) => {
-                    let option = self.write_ref(arg.assert_into_ref(), slot_idx);
+                    let option = self.write_ref(arg.assert_into_ref()?, slot_idx);
                     debug_assert!(option.is_none())
                 }
             }
         }
+        Result::Ok(())
     }
     #[inline]
-    pub fn fill_capture(&mut self, captures: &Vec<Capture>, captured: &Vec<Value>) {
+    pub fn fill_capture(
+        &mut self,
+        captures: &Vec<Capture>,
+        captured: &Vec<Value>,
+    ) -> Result<(), GloomError> {
         for (capture, val) in captures.iter().zip(captured.iter()) {
             let (slot_idx, sub_idx) = (capture.to_slot_idx, capture.to_sub_idx);
             match capture.basic_type {
-                BasicType::Int => self.write_int(val.assert_int(), slot_idx, sub_idx),
-                BasicType::Num => self.write_num(val.assert_num(), slot_idx, sub_idx),
-                BasicType::Char => self.write_char(val.assert_char(), slot_idx, sub_idx),
-                BasicType::Bool => self.write_bool(val.assert_bool(), slot_idx, sub_idx),
+                BasicType::Int => self.write_int(val.assert_int()?, slot_idx, sub_idx),
+                BasicType::Num => self.write_num(val.assert_num()?, slot_idx, sub_idx),
+                BasicType::Char => self.write_char(val.assert_char()?, slot_idx, sub_idx),
+                BasicType::Bool => self.write_bool(val.assert_bool()?, slot_idx, sub_idx),
                 BasicType::Ref => {
-                    let option = self.write_ref(val.clone().assert_into_ref(), slot_idx);
+                    let option = self.write_ref(val.clone().assert_into_ref()?, slot_idx);
                     debug_assert!(option.is_none())
                 }
             }
         }
+        Result::Ok(())
     }
     #[inline]
     pub fn stack_not_empty(&self) -> bool {
@@ -109,6 +132,94 @@ impl Frame {
         }
     }
 
+    #[inline]
+    pub fn enter_try(&mut self, try_idx: u16, constant_pool: &ConstantPool, pc: usize) {
+        // 丢弃已经离开的try块 discard the try blocks have been left
+        while let Some((idx, _)) = self.handlers.last() {
+            if *idx == try_idx || !constant_pool.try_table[*idx as usize].contains(pc) {
+                self.handlers.pop();
+            } else {
+                break;
+            }
+        }
+        self.handlers.push((try_idx, self.stack.len()));
+    }
+
+    // 找到包含出错位置的try块 返回其handler的位置
+    // find the try block contains the error position, return the label of its handler
+    pub fn unwind(&mut self, vm: &GloomVM, constant_pool: &ConstantPool, pc: usize) -> Option<u32> {
+        while let Some((idx, stack_len)) = self.handlers.pop() {
+            let try_block = &constant_pool.try_table[idx as usize];
+            if try_block.contains(pc) {
+                self.truncate_stack(vm, stack_len);
+                return Option::Some(try_block.handler);
+            }
+        }
+        self.truncate_stack(vm, 0);
+        Option::None
+    }
+
+    #[inline]
+    fn truncate_stack(&mut self, vm: &GloomVM, len: usize) {
+        while self.stack.len() > len {
+            if let Value::Ref(rf) = self.stack.pop().unwrap() {
+                vm.drop_object(&rf);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn suspend(&mut self, pc: usize) {
+        self.suspended = Option::Some(pc);
+    }
+
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.is_some()
+    }
+
+    // 恢复执行的位置 从头执行时为0 the position to resume, 0 when executing from the beginning
+    #[inline]
+    pub fn take_resume_pc(&mut self) -> usize {
+        self.suspended.take().unwrap_or(0)
+    }
+
+    // 释放栈和所有局部变量持有的引用 release the references held by stack and all local variables
+    pub fn release(&mut self, vm: &GloomVM) {
+        self.truncate_stack(vm, 0);
+        for slot_idx in 0..self.local.len() {
+            if let Slot::Ref(_) = &self.local[slot_idx] {
+                self.drop_local(vm, slot_idx as u16);
+            }
+        }
+    }
+
+    // 遍历栈和局部变量持有的引用 visit the references held by stack and local variables
+    pub fn trace(&self, f: &mut dyn FnMut(&GloomObjRef)) {
+        for value in self.stack.iter() {
+            if let Value::Ref(rf) = value {
+                f(rf);
+            }
+        }
+        for slot in self.local.iter() {
+            if let Slot::Ref(rf) = slot {
+                f(rf);
+            }
+        }
+    }
+
+    // 不经过虚拟机直接断开引用 break the references directly without vm
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        for slot in self.local.iter_mut() {
+            if let Slot::Ref(mut rf) = slot.take() {
+                unsafe {
+                    ManuallyDrop::drop(&mut rf);
+                }
+            }
+        }
+    }
+
     pub fn print_stack(&self) {
         println!("{:?}", self.stack);
     }
@@ -1,23 +1,40 @@
+use std::any::Any;
+use std::cell::RefCell;
 use std::collections::VecDeque;
-use std::mem::{transmute, ManuallyDrop};
-use std::ops::Deref;
+use std::mem::ManuallyDrop;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
 
 use crate::builtin::array::{GloomArray, RawArray};
+use crate::builtin::error::GloomErrorObj;
+use crate::builtin::map::{drop_value, GloomMap, RawMap};
 use crate::builtin::obj::BuiltinClassObj;
+use crate::builtin::option::GloomOption;
 use crate::builtin::queue::{GloomQueue, RawQueue};
+use crate::builtin::set::GloomSet;
+use crate::builtin::string::GloomString;
 use crate::bytecode::code::ByteCode;
 use crate::frontend::status::GloomStatus;
+use crate::jit;
+use crate::jit::GloomJit;
 use crate::obj::class::GloomClassObj;
-use crate::obj::func::{FuncBody, GloomFunc, GloomFuncObj};
-use crate::obj::gloom_enum::GloomEnum;
+use crate::obj::func::{FuncBody, FuncInfo, GloomFunc, GloomFuncObj};
+use crate::obj::generator::GloomGenerator;
+use crate::obj::gloom_enum::{GloomEnum, GloomEnumClassObj};
 use crate::obj::gloom_object::GloomObject;
 use crate::obj::object::{GloomObjRef, ObjectType};
 use crate::obj::range::RangeIter;
 use crate::obj::refcount::RefCount;
+use crate::obj::task::{GloomTask, TaskBody};
 use crate::obj::tuple::GloomTuple;
 use crate::obj::types::BasicType;
+use crate::stdlib::channel::GloomChannel;
+use crate::stdlib::timer::GloomTimer;
+use crate::vm::collector::{self, CycleCollector};
 use crate::vm::constant::ConstantPool;
+use crate::vm::error::GloomError;
 use crate::vm::frame::Frame;
+use crate::vm::scheduler::Scheduler;
 use crate::vm::static_table::StaticTable;
 use crate::vm::value::{GloomArgs, Value};
 
@@ -25,6 +42,13 @@ pub struct GloomVM {
     static_table: StaticTable,
     constant_pool: ConstantPool,
     status: GloomStatus,
+    jit: Option<RefCell<GloomJit>>,
+    // 正在被回收的对象的地址 address of objects being finalized
+    finalizing: RefCell<Vec<*const ()>>,
+    collector: RefCell<CycleCollector>,
+    scheduler: RefCell<Scheduler>,
+    // 调试模式下报告回收的对象数量 report the number of collected objects in debug mode
+    debug: bool,
 }
 
 impl GloomVM {
@@ -32,73 +56,525 @@ impl GloomVM {
         static_table: StaticTable,
         constant_pool: ConstantPool,
         status: GloomStatus,
+        enable_jit: bool,
     ) -> Self {
         GloomVM {
             static_table,
             constant_pool,
             status,
+            jit: if enable_jit {
+                Option::Some(RefCell::new(GloomJit::new()))
+            } else {
+                Option::None
+            },
+            finalizing: RefCell::new(Vec::new()),
+            collector: RefCell::new(CycleCollector::new()),
+            scheduler: RefCell::new(Scheduler::new()),
+            debug: false,
         }
     }
-    pub fn run(mut self) {
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+    pub fn run(&mut self) -> Result<(), GloomError> {
         let mut script_bodies =
             std::mem::replace(&mut self.status.script_bodies, Vec::with_capacity(0));
-        for script in script_bodies.iter_mut() {
-            self.call_fn(&script.inner().func, GloomArgs::new(Vec::with_capacity(0)));
+        // panic是虚拟机内部的错误 脚本无法捕获 只在最外层转换为内部错误 它的信息和位置已由panic钩子打印
+        // panic is the internal error of vm which scripts can't catch, it is converted to internal error only at the outermost,
+        // its message and location have been printed by the panic hook
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            for script in script_bodies.iter_mut() {
+                self.call_fn(&script.inner().func, GloomArgs::new(Vec::with_capacity(0)))?;
+            }
+            // 脚本执行完后继续执行派生的任务 continue to run the spawned tasks after scripts finished
+            self.run_tasks()
+        }));
+        match result {
+            Ok(result) => result,
+            Err(payload) => Result::Err(GloomError::internal(payload)),
         }
     }
-    pub fn call_fn(&self, func: &GloomFunc, args: GloomArgs) -> Value {
+    pub fn call_fn(&self, func: &GloomFunc, args: GloomArgs) -> Result<Value, GloomError> {
         match &func.body {
             FuncBody::Builtin(func) => func(self, args),
             FuncBody::ByteCodes(bytecodes) => {
+                if let Option::Some(ptr) = self.hot(func) {
+                    return jit::call(ptr, self, &func.info, args);
+                }
                 let mut frame = Frame::new(func.info.stack_size, func.info.local_size);
-                frame.fill_args(&func.info.params, args);
-                /*for (idx,code) in bytecodes.iter().enumerate() {
-                    println!("#{:3} {:?}",idx,code);
-                }*/
-                let value = self.interpret(bytecodes, &mut frame);
+                frame.fill_args(&func.info.params, args)?;
+                let value = self.interpret(bytecodes, &mut frame, &func.info);
                 for idx in func.info.drop_slots.iter() {
                     frame.drop_local(self, *idx);
                 }
                 value
             }
-            FuncBody::Jit(ptr) => {
-                let func = unsafe { transmute::<_, extern "C" fn(GloomArgs) -> Value>(ptr) };
-                func(args)
-            }
+            FuncBody::Jit(ptr) => jit::call(*ptr, self, &func.info, args),
             unknown => panic!("unknown func body {:?} of {:?}", unknown, func),
         }
     }
-    pub fn call(&self, func_obj: &GloomFuncObj, args: GloomArgs) -> Value {
+    pub fn call(&self, func_obj: &GloomFuncObj, args: GloomArgs) -> Result<Value, GloomError> {
         let func = func_obj.func.inner();
         match &func.body {
             FuncBody::Builtin(func) => func(self, args),
+            FuncBody::ByteCodes(_) if func.info.is_generator || func.info.is_async => {
+                Result::Ok(Value::Ref(self.suspended(
+                    &func_obj.func,
+                    args,
+                    &*func_obj.captures.borrow(),
+                )?))
+            }
             FuncBody::ByteCodes(bytecodes) => {
+                if let Option::Some(ptr) = self.hot(&func) {
+                    return jit::call(ptr, self, &func.info, args);
+                }
                 let mut frame = Frame::new(func.info.stack_size, func.info.local_size);
-                frame.fill_args(&func.info.params, args);
-                frame.fill_capture(&func.info.captures, &*func_obj.captures.borrow());
-                let value = self.interpret(bytecodes, &mut frame);
+                frame.fill_args(&func.info.params, args)?;
+                frame.fill_capture(&func.info.captures, &*func_obj.captures.borrow())?;
+                let value = self.interpret(bytecodes, &mut frame, &func.info);
                 for idx in func.info.drop_slots.iter() {
                     frame.drop_local(self, *idx);
                 }
                 value
             }
-            FuncBody::Jit(ptr) => {
-                let func = unsafe { transmute::<_, extern "C" fn(GloomArgs) -> Value>(ptr) };
-                func(args)
-            }
+            FuncBody::Jit(ptr) => jit::call(*ptr, self, &func.info, args),
             unknown => panic!("unknown func body {:?} of {:?}", unknown, func),
         }
     }
 
+    // 调用可能是生成器或异步函数的函数 call the function which may be a generator or async function
+    pub fn invoke(&self, func: &RefCount<GloomFunc>, args: GloomArgs) -> Result<Value, GloomError> {
+        let info = &func.inner().info;
+        if info.is_generator || info.is_async {
+            return Result::Ok(Value::Ref(self.suspended(
+                func,
+                args,
+                &Vec::with_capacity(0),
+            )?));
+        }
+        self.call_fn(&func.inner(), args)
+    }
+
+    // 生成器函数和异步函数被调用时不执行 返回挂起在开头的生成器或任务
+    // generator and async function are not executed when called, return a generator or task suspended at the beginning
+    fn suspended(
+        &self,
+        func: &RefCount<GloomFunc>,
+        args: GloomArgs,
+        captured: &Vec<Value>,
+    ) -> Result<GloomObjRef, GloomError> {
+        let (frame, is_async) = {
+            let func = func.inner();
+            let mut frame = Frame::new(func.info.stack_size, func.info.local_size);
+            frame.fill_args(&func.info.params, args)?;
+            frame.fill_capture(&func.info.captures, captured)?;
+            (frame, func.info.is_async)
+        };
+        Result::Ok(self.track(if is_async {
+            GloomTask::new(func.clone(), frame)
+        } else {
+            GloomGenerator::new(func.clone(), frame)
+        }))
+    }
+
+    // 恢复生成器执行到下一个yield 结束后返回None
+    // resume the generator until next yield, return None after finished
+    pub fn resume(&self, generator: &GloomGenerator) -> Result<Value, GloomError> {
+        let mut frame = match generator.take_frame() {
+            Some(frame) => frame,
+            None => return Result::Ok(Value::None),
+        };
+        let func = generator.func.inner();
+        let result = self.interpret(func.body.bytecodes(), &mut frame, &func.info);
+        match result {
+            Ok(value) if frame.is_suspended() => {
+                generator.put_frame(Option::Some(frame));
+                Result::Ok(value)
+            }
+            // 生成器函数的返回值被丢弃 the return value of generator function is discarded
+            Ok(value) => {
+                if let Value::Ref(rf) = value {
+                    self.drop_object(&rf);
+                }
+                frame.release(self);
+                generator.put_frame(Option::None);
+                Result::Ok(Value::None)
+            }
+            Err(error) => {
+                frame.release(self);
+                generator.put_frame(Option::None);
+                Result::Err(error)
+            }
+        }
+    }
+
+    // 执行任务直到结束或挂起 结束时返回结果 挂起时返回None 它会登记自己被唤醒的时机
+    // run the task until it finishes or suspends, return the result if finished or None if suspended, it registers when to be woken by itself
+    pub fn poll(&self, rf: &GloomObjRef) -> Result<Option<Value>, GloomError> {
+        let task = rf.downcast::<GloomTask>();
+        if let Some(result) = task.result() {
+            return result.map(Option::Some);
+        }
+        let poll = match task.body() {
+            TaskBody::Coroutine(func, _) => {
+                let mut frame = task.take_frame();
+                let func = func.inner();
+                match self.interpret(func.body.bytecodes(), &mut frame, &func.info) {
+                    Ok(_) if frame.is_suspended() => {
+                        task.put_frame(Option::Some(frame));
+                        Result::Ok(Option::None)
+                    }
+                    Ok(value) => {
+                        frame.release(self);
+                        task.put_frame(Option::None);
+                        Result::Ok(Option::Some(value))
+                    }
+                    Err(error) => {
+                        frame.release(self);
+                        task.put_frame(Option::None);
+                        Result::Err(error)
+                    }
+                }
+            }
+            TaskBody::Sleep(duration, deadline) => match deadline.get() {
+                Some(deadline) if deadline <= Instant::now() => Result::Ok(Option::Some(Value::None)),
+                Some(deadline) => {
+                    let current = self.current_task()?;
+                    self.scheduler.borrow_mut().wake_at(deadline, current);
+                    Result::Ok(Option::None)
+                }
+                // 至少挂起一次 suspend at least once
+                None => {
+                    let at = Instant::now() + *duration;
+                    deadline.set(Option::Some(at));
+                    let current = self.current_task()?;
+                    self.scheduler.borrow_mut().wake_at(at, current);
+                    Result::Ok(Option::None)
+                }
+            },
+            TaskBody::Yield(yielded) => {
+                if yielded.replace(true) {
+                    Result::Ok(Option::Some(Value::None))
+                } else {
+                    let current = self.current_task()?;
+                    self.scheduler.borrow_mut().wake(current);
+                    Result::Ok(Option::None)
+                }
+            }
+            TaskBody::Send(channel, message) => self.send(channel, message),
+            TaskBody::Recv(channel, wrap) => self.recv(channel).map(|option| {
+                option.map(|message| {
+                    if *wrap {
+                        Value::Ref(GloomOption::new(message))
+                    } else {
+                        message.unwrap_or(Value::None)
+                    }
+                })
+            }),
+        };
+        let waiters = match &poll {
+            Ok(Some(value)) => task.finish(Result::Ok(value.clone())),
+            Err(error) => task.finish(Result::Err(error.msg.clone())),
+            Ok(None) => return poll,
+        };
+        let mut scheduler = self.scheduler.borrow_mut();
+        for waiter in waiters {
+            scheduler.wake(waiter);
+        }
+        poll
+    }
+
+    // 缓冲区满时登记当前任务并挂起 向关闭的通道发送是运行时错误
+    // register the current task and suspend if the buffer is full, sending to closed channel is runtime error
+    fn send(
+        &self,
+        rf: &GloomObjRef,
+        message: &RefCell<Option<Value>>,
+    ) -> Result<Option<Value>, GloomError> {
+        let channel = rf.downcast::<GloomChannel>();
+        if channel.is_closed() {
+            if let Some(message) = message.borrow_mut().take() {
+                drop_value(self, &message);
+            }
+            return Result::Err(GloomError::new(format!("send to closed {:?}", channel)));
+        }
+        if channel.is_full() {
+            let current = self.current_task()?;
+            channel.add_sender(current);
+            return Result::Ok(Option::None);
+        }
+        channel.push(message.borrow_mut().take().unwrap());
+        if let Some(receiver) = channel.take_receiver() {
+            self.scheduler.borrow_mut().wake(receiver);
+        }
+        Result::Ok(Option::Some(Value::None))
+    }
+
+    // 接收到消息时返回Some(Some) 通道关闭且为空时返回Some(None) 为空时登记当前任务并返回None
+    // return Some(Some) if received a message, Some(None) if the channel is closed and empty, register the current task and return None if empty
+    fn recv(&self, rf: &GloomObjRef) -> Result<Option<Option<Value>>, GloomError> {
+        let channel = rf.downcast::<GloomChannel>();
+        match channel.pop() {
+            Some(message) => {
+                if let Some(sender) = channel.take_sender() {
+                    self.scheduler.borrow_mut().wake(sender);
+                }
+                Result::Ok(Option::Some(Option::Some(message)))
+            }
+            None if channel.is_closed() => Result::Ok(Option::Some(Option::None)),
+            None => {
+                let current = self.current_task()?;
+                channel.add_receiver(current);
+                Result::Ok(Option::None)
+            }
+        }
+    }
+
+    // 唤醒所有等待的任务 发送者会得到错误 接收者取完剩余的消息后结束
+    // wake all the waiting tasks, senders get the error and receivers finish after the remaining messages are taken
+    pub fn close_channel(&self, channel: &GloomChannel) {
+        let tasks = channel.close();
+        let mut scheduler = self.scheduler.borrow_mut();
+        for task in tasks {
+            scheduler.wake(task);
+        }
+    }
+
+    // 等待任务结束 由调度器驱动的任务结束后唤醒当前任务 其它任务在当前任务中执行
+    // wait for the task to finish, the current task is woken after the task driven by scheduler finished, other tasks run inside the current task
+    fn await_task(&self, rf: &GloomObjRef) -> Result<Option<Value>, GloomError> {
+        let task = rf.downcast::<GloomTask>();
+        if task.is_spawned() && !task.is_finished() {
+            task.add_waiter(self.current_task()?);
+            return Result::Ok(Option::None);
+        }
+        self.poll(rf)
+    }
+
+    // 把任务交给调度器 hand the task over to the scheduler
+    pub fn spawn(&self, rf: &GloomObjRef) {
+        if rf.downcast::<GloomTask>().spawn() {
+            self.scheduler.borrow_mut().wake(rf.clone());
+        }
+    }
+
+    // 运行事件循环直到任务结束 run the event loop until the task finishes
+    pub fn block_on(&self, rf: &GloomObjRef) -> Result<Value, GloomError> {
+        let task = rf.downcast::<GloomTask>();
+        self.spawn(rf);
+        loop {
+            if let Some(result) = task.result() {
+                return result;
+            }
+            if !self.run_once()? {
+                return Result::Err(GloomError::new(format!(
+                    "{:?} will never finish, no task could be run",
+                    task
+                )));
+            }
+        }
+    }
+
+    // 执行所有剩余的任务 run all the remaining tasks
+    pub fn run_tasks(&self) -> Result<(), GloomError> {
+        while self.run_once()? {}
+        Result::Ok(())
+    }
+
+    // 执行一个就绪的任务 没有可执行的任务时返回false
+    // 没有任务等待的任务出错时 错误会中止事件循环
+    // run a ready task, return false if there is no task to run
+    // the error of task which no task is waiting for stops the event loop
+    fn run_once(&self) -> Result<bool, GloomError> {
+        let next = self.scheduler.borrow_mut().next_task();
+        let task = match next {
+            Some(task) => task,
+            None => return Result::Ok(false),
+        };
+        if let ObjectType::Timer = task.obj_type() {
+            let result = self.fire_timer(&task);
+            self.drop_object(&task);
+            return result.map(|_| true);
+        }
+        let outer = self.scheduler.borrow_mut().set_current(Option::Some(task.clone()));
+        let waited = task.downcast::<GloomTask>().has_waiters();
+        let result = self.poll(&task);
+        self.scheduler.borrow_mut().set_current(outer);
+        self.drop_object(&task);
+        match result {
+            Err(error) if !waited => Result::Err(error),
+            _ => Result::Ok(true),
+        }
+    }
+
+    // 在延迟之后由事件循环调用定时器的回调函数 the event loop calls the callback of timer after the delay
+    pub fn set_timer(&self, rf: &GloomObjRef, delay: Duration) {
+        self.schedule_timer(rf, Instant::now() + delay);
+    }
+
+    fn schedule_timer(&self, rf: &GloomObjRef, deadline: Instant) {
+        let key = self.scheduler.borrow_mut().wake_at(deadline, rf.clone());
+        rf.downcast::<GloomTimer>().set_key(Option::Some(key));
+    }
+
+    pub fn cancel_timer(&self, timer: &GloomTimer) {
+        if let Some(key) = timer.cancel() {
+            let removed = self.scheduler.borrow_mut().cancel(&key);
+            if let Some(rf) = removed {
+                self.drop_object(&rf);
+            }
+        }
+    }
+
+    // 单调时钟 从虚拟机创建开始经过的时间 monotonic clock, the time elapsed since the vm was created
+    #[inline]
+    pub fn clock(&self) -> Duration {
+        self.scheduler.borrow().elapsed()
+    }
+
+    // 调用到期的定时器的回调函数 间隔定时器在回调后重新登记 回调函数出错时定时器被取消
+    // call the callback of due timer, interval timer is registered again after the callback, the timer is cancelled if the callback fails
+    fn fire_timer(&self, rf: &GloomObjRef) -> Result<(), GloomError> {
+        let timer = rf.downcast::<GloomTimer>();
+        let deadline = timer.key().map(|(deadline, _)| deadline);
+        if !timer.fire() {
+            return Result::Ok(());
+        }
+        let callback = match timer.callback() {
+            Some(callback) => callback,
+            None => return Result::Ok(()),
+        };
+        // 回调函数不属于任何任务 the callback doesn't belong to any task
+        let outer = self.scheduler.borrow_mut().set_current(Option::None);
+        let result = self.call(
+            callback.downcast::<GloomFuncObj>(),
+            GloomArgs::new(Vec::with_capacity(0)),
+        );
+        self.scheduler.borrow_mut().set_current(outer);
+        if let Err(error) = result {
+            timer.cancel();
+            return Result::Err(error);
+        }
+        if let (Some(interval), true) = (timer.interval(), timer.is_active()) {
+            // 从上次的期限开始计算 避免误差累积 count from the last deadline to avoid accumulated drift
+            let now = Instant::now();
+            let next = deadline.map_or(now, |deadline| deadline + interval).max(now);
+            self.schedule_timer(rf, next);
+        }
+        Result::Ok(())
+    }
+
+    #[inline]
+    fn current_task(&self) -> Result<GloomObjRef, GloomError> {
+        match self.scheduler.borrow().current() {
+            Some(task) => Result::Ok(task),
+            None => Result::Err(GloomError::new(String::from("no task is running"))),
+        }
+    }
+
+    // 记录调用次数，返回JIT编译后的函数  count the call, return the function compiled by JIT
+    #[inline]
+    fn hot(&self, func: &GloomFunc) -> Option<*const u8> {
+        match &self.jit {
+            Some(jit) => jit
+                .borrow_mut()
+                .hot(func, &self.constant_pool, &self.status),
+            None => Option::None,
+        }
+    }
+
+    #[inline]
+    pub fn set_jit_error(&self, error: GloomError) {
+        if let Some(jit) = &self.jit {
+            jit.borrow_mut().set_error(error);
+        }
+    }
+
+    #[inline]
+    pub fn take_jit_error(&self) -> Option<GloomError> {
+        match &self.jit {
+            Some(jit) => jit.borrow_mut().take_error(),
+            None => Option::None,
+        }
+    }
+
+    #[inline]
+    pub fn set_jit_panic(&self, payload: Box<dyn Any + Send>) {
+        if let Some(jit) = &self.jit {
+            jit.borrow_mut().set_panic(payload);
+        }
+    }
+
+    #[inline]
+    pub fn take_jit_panic(&self) -> Option<Box<dyn Any + Send>> {
+        match &self.jit {
+            Some(jit) => jit.borrow_mut().take_panic(),
+            None => Option::None,
+        }
+    }
+
+    // 记录错误经过的函数 record the function the error passed through
+    pub fn trace(&self, error: &mut GloomError, info: &FuncInfo, line: Option<u16>) {
+        let file = match self.status.paths.get(info.file_index as usize) {
+            Some(path) => path.clone(),
+            None => String::from("<unknown>"),
+        };
+        error.trace(file, info.name.clone(), line);
+    }
+
     #[inline]
-    pub fn interpret(&self, bytecodes: &Vec<ByteCode>, frame: &mut Frame) -> Value {
-        let mut pc: usize = 0;
+    pub fn status(&self) -> &GloomStatus {
+        &self.status
+    }
+    pub fn status_mut(&mut self) -> &mut GloomStatus {
+        &mut self.status
+    }
+    pub fn constant_pool_mut(&mut self) -> &mut ConstantPool {
+        &mut self.constant_pool
+    }
+    pub fn static_table_mut(&mut self) -> &mut StaticTable {
+        &mut self.static_table
+    }
+
+    pub fn interpret(
+        &self,
+        bytecodes: &[ByteCode],
+        frame: &mut Frame,
+        info: &FuncInfo,
+    ) -> Result<Value, GloomError> {
+        let mut pc: usize = frame.take_resume_pc();
+        loop {
+            let mut error = match self.execute(bytecodes, frame, &mut pc) {
+                Ok(value) => return Result::Ok(value),
+                Err(error) => error,
+            };
+            // pc 在执行字节码之前已经自增 pc has been increased before execute the bytecode
+            match frame.unwind(self, &self.constant_pool, pc - 1) {
+                Some(handler) => {
+                    frame.push(Value::Ref(GloomErrorObj::new(error)));
+                    pc = handler as usize;
+                }
+                None => {
+                    self.trace(&mut error, info, info.line(pc - 1));
+                    return Result::Err(error);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn execute(
+        &self,
+        bytecodes: &[ByteCode],
+        frame: &mut Frame,
+        pc: &mut usize,
+    ) -> Result<Value, GloomError> {
         let length = bytecodes.len();
         let mut result = Value::None;
-        while pc < length {
-            let code = *bytecodes.get(pc).unwrap();
+        while *pc < length {
+            let code = *bytecodes.get(*pc).unwrap();
             // frame.print_stack();
-            pc += 1;
+            *pc += 1;
             match code {
                 ByteCode::Pop => match frame.pop() {
                     Value::Ref(rf) => self.drop_object(&rf),
@@ -139,8 +615,10 @@ impl GloomVM {
                         self.status.classes.get(idx as usize).unwrap().clone(),
                     )));
                 }
-                ByteCode::LoadEnum(_) => {
-                    panic!()
+                ByteCode::LoadEnum(idx) => {
+                    frame.push(Value::Ref(GloomEnumClassObj::new(
+                        self.status.enums.get(idx as usize).unwrap().clone(),
+                    )));
                 }
                 ByteCode::LoadBuiltinType(idx) => frame.push(Value::Ref(BuiltinClassObj::new(
                     self.status
@@ -153,23 +631,23 @@ impl GloomVM {
                     frame.push(frame.read(slot_idx, sub_idx));
                 }
                 ByteCode::WriteLocalInt(slot_idx, sub_idx) => {
-                    let i = frame.pop().assert_int();
+                    let i = frame.pop().assert_int()?;
                     frame.write_int(i, slot_idx, sub_idx);
                 }
                 ByteCode::WriteLocalNum(slot_idx, sub_idx) => {
-                    let n = frame.pop().assert_num();
+                    let n = frame.pop().assert_num()?;
                     frame.write_num(n, slot_idx, sub_idx);
                 }
                 ByteCode::WriteLocalChar(slot_idx, sub_idx) => {
-                    let c = frame.pop().assert_char();
+                    let c = frame.pop().assert_char()?;
                     frame.write_char(c, slot_idx, sub_idx);
                 }
                 ByteCode::WriteLocalBool(slot_idx, sub_idx) => {
-                    let b = frame.pop().assert_bool();
+                    let b = frame.pop().assert_bool()?;
                     frame.write_bool(b, slot_idx, sub_idx);
                 }
                 ByteCode::WriteLocalRef(slot_idx) => {
-                    let rf = frame.pop().assert_into_ref();
+                    let rf = frame.pop().assert_into_ref()?;
                     let option = frame.write_ref(rf, slot_idx);
                     self.drop_option_manually(option);
                 }
@@ -178,35 +656,35 @@ impl GloomVM {
                 }
                 ByteCode::WriteStaticInt(slot_idx) => {
                     self.static_table
-                        .write_int(slot_idx, frame.pop().assert_int());
+                        .write_int(slot_idx, frame.pop().assert_int()?);
                 }
                 ByteCode::WriteStaticNum(slot_idx) => {
                     self.static_table
-                        .write_num(slot_idx, frame.pop().assert_num());
+                        .write_num(slot_idx, frame.pop().assert_num()?);
                 }
                 ByteCode::WriteStaticChar(slot_idx) => {
                     self.static_table
-                        .write_char(slot_idx, frame.pop().assert_char());
+                        .write_char(slot_idx, frame.pop().assert_char()?);
                 }
                 ByteCode::WriteStaticBool(slot_idx) => {
                     self.static_table
-                        .write_bool(slot_idx, frame.pop().assert_bool());
+                        .write_bool(slot_idx, frame.pop().assert_bool()?);
                 }
                 ByteCode::WriteStaticRef(slot_idx) => {
                     let rf = self
                         .static_table
-                        .write_ref(slot_idx, frame.pop().assert_into_ref());
+                        .write_ref(slot_idx, frame.pop().assert_into_ref()?);
                     self.drop_option_manually(rf);
                 }
                 ByteCode::ReadField(slot_idx, sub_idx) => {
                     frame.push(frame.top().as_ref().read_field(slot_idx, sub_idx));
                 }
                 ByteCode::ReadFieldAndPop(slot_idx, sub_idx) => {
-                    let field = frame.pop().assert_into_ref().read_field(slot_idx, sub_idx);
+                    let field = frame.pop().assert_into_ref()?.read_field(slot_idx, sub_idx);
                     frame.push(field);
                 }
                 ByteCode::WriteFieldInt(slot_idx, sub_idx) => {
-                    let i = frame.pop().assert_int();
+                    let i = frame.pop().assert_int()?;
                     frame
                         .top()
                         .as_ref()
@@ -214,7 +692,7 @@ impl GloomVM {
                         .write_field_int(slot_idx, sub_idx, i);
                 }
                 ByteCode::WriteFieldNum(slot_idx, sub_idx) => {
-                    let n = frame.pop().assert_num();
+                    let n = frame.pop().assert_num()?;
                     frame
                         .top()
                         .as_ref()
@@ -222,7 +700,7 @@ impl GloomVM {
                         .write_field_num(slot_idx, sub_idx, n);
                 }
                 ByteCode::WriteFieldChar(slot_idx, sub_idx) => {
-                    let c = frame.pop().assert_char();
+                    let c = frame.pop().assert_char()?;
                     frame
                         .top()
                         .as_ref()
@@ -230,7 +708,7 @@ impl GloomVM {
                         .write_field_char(slot_idx, sub_idx, c);
                 }
                 ByteCode::WriteFieldBool(slot_idx, sub_idx) => {
-                    let b = frame.pop().assert_bool();
+                    let b = frame.pop().assert_bool()?;
                     frame
                         .top()
                         .as_ref()
@@ -238,7 +716,7 @@ impl GloomVM {
                         .write_field_bool(slot_idx, sub_idx, b);
                 }
                 ByteCode::WriteFieldRef(slot_idx) => {
-                    let rf = frame.pop().assert_into_ref();
+                    let rf = frame.pop().assert_into_ref()?;
                     let option = frame
                         .top()
                         .as_ref()
@@ -246,76 +724,135 @@ impl GloomVM {
                         .write_field_ref(slot_idx, rf);
                     self.drop_option_manually(option);
                 }
+                ByteCode::ReadIndex => {
+                    let index = frame.pop();
+                    let value = self.read_index(frame.top().as_ref(), &index)?;
+                    frame.push(index);
+                    frame.push(value);
+                }
+                ByteCode::ReadIndexAndPop => {
+                    let index = frame.pop();
+                    let container = frame.pop().assert_into_ref()?;
+                    frame.push(self.read_index(&container, &index)?);
+                }
+                ByteCode::WriteIndex => {
+                    let value = frame.pop();
+                    let index = frame.pop();
+                    let container = frame.pop().assert_into_ref()?;
+                    self.write_index(&container, index, value)?;
+                }
+                ByteCode::ReadSlice => {
+                    let to = frame.pop().assert_int()?;
+                    let from = frame.pop().assert_int()?;
+                    let container = frame.pop().assert_into_ref()?;
+                    frame.push(Value::Ref(self.read_slice(&container, from, to)?));
+                }
                 ByteCode::DropLocal(slot_idx) => {
                     frame.drop_local(self, slot_idx);
                 }
                 ByteCode::NotOp => {
-                    frame.top_mut().not();
+                    frame.top_mut().not()?;
                 }
                 ByteCode::NegOp => {
-                    frame.top_mut().neg();
+                    frame.top_mut().neg()?;
                 }
                 ByteCode::Plus => {
                     let val = frame.pop();
-                    frame.top_mut().plus(val);
+                    frame.top_mut().plus(val)?;
                 }
                 ByteCode::Sub => {
                     let val = frame.pop();
-                    frame.top_mut().sub(val);
+                    frame.top_mut().sub(val)?;
                 }
                 ByteCode::Mul => {
                     let val = frame.pop();
-                    frame.top_mut().multiply(val);
+                    frame.top_mut().multiply(val)?;
                 }
                 ByteCode::Div => {
                     let val = frame.pop();
-                    frame.top_mut().divide(val);
+                    frame.top_mut().divide(val)?;
+                }
+                ByteCode::Mod => {
+                    let val = frame.pop();
+                    frame.top_mut().modulo(val)?;
+                }
+                ByteCode::Pow => {
+                    let val = frame.pop();
+                    frame.top_mut().power(val)?;
+                }
+                ByteCode::BitNot => {
+                    frame.top_mut().bit_not()?;
+                }
+                ByteCode::BitAnd => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    frame.push(left.bit_and(right)?);
+                }
+                ByteCode::BitOr => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    frame.push(left.bit_or(right)?);
+                }
+                ByteCode::BitXor => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    frame.push(left.bit_xor(right)?);
+                }
+                ByteCode::ShiftLeft => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    frame.push(left.shift_left(right)?);
+                }
+                ByteCode::ShiftRight => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    frame.push(left.shift_right(right)?);
                 }
                 ByteCode::PlusOne => {
-                    frame.top_mut().plus_one();
+                    frame.top_mut().plus_one()?;
                 }
                 ByteCode::SubOne => {
-                    frame.top_mut().sub_one();
+                    frame.top_mut().sub_one()?;
                 }
                 ByteCode::GreaterThan => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(left.greater_than(right)));
+                    frame.push(Value::Bool(left.greater_than(right)?));
                 }
                 ByteCode::LessThan => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(left.less_than(right)));
+                    frame.push(Value::Bool(left.less_than(right)?));
                 }
                 ByteCode::GreaterThanEquals => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(left.greater_equal(right)));
+                    frame.push(Value::Bool(left.greater_equal(right)?));
                 }
                 ByteCode::LessThanEquals => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(left.less_equal(right)));
+                    frame.push(Value::Bool(left.less_equal(right)?));
                 }
                 ByteCode::Equals => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(left.equals(right)));
+                    frame.push(Value::Bool(left.equals(right)?));
                 }
                 ByteCode::NotEquals => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(!left.equals(right)));
+                    frame.push(Value::Bool(!left.equals(right)?));
                 }
                 ByteCode::LogicAnd => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(right.assert_bool() && left.assert_bool()));
+                    frame.push(Value::Bool(right.assert_bool()? && left.assert_bool()?));
                 }
                 ByteCode::LogicOr => {
                     let right = frame.pop();
                     let left = frame.pop();
-                    frame.push(Value::Bool(right.assert_bool() || left.assert_bool()));
+                    frame.push(Value::Bool(right.assert_bool()? || left.assert_bool()?));
                 }
                 ByteCode::LoadDirectDefFn(idx) => {
                     frame.push(Value::Ref(GloomFuncObj::new_func(
@@ -337,7 +874,9 @@ impl GloomVM {
                             captured.push(frame.read(capture.from_slot_idx, capture.from_sub_idx));
                         }
                     }
-                    frame.push(Value::Ref(GloomFuncObj::new_closure(func_ref, captured)));
+                    frame.push(Value::Ref(
+                        self.track(GloomFuncObj::new_closure(func_ref, captured)),
+                    ));
                 }
                 ByteCode::CallTopFn { nargs } => {
                     let mut args = Vec::with_capacity(nargs as usize);
@@ -345,9 +884,9 @@ impl GloomVM {
                         args.push(frame.pop());
                     }
                     args.reverse();
-                    let func_rf = frame.pop().assert_into_ref();
+                    let func_rf = frame.pop().assert_into_ref()?;
                     let func = func_rf.downcast::<GloomFuncObj>();
-                    let result = self.call(func, GloomArgs::new(args));
+                    let result = self.call(func, GloomArgs::new(args))?;
                     frame.push(result);
                 }
                 ByteCode::CallStaticFn { index, nargs } => {
@@ -356,7 +895,7 @@ impl GloomVM {
                         args.push(frame.pop());
                     }
                     args.reverse();
-                    let rf = frame.pop().assert_into_ref();
+                    let rf = frame.pop().assert_into_ref()?;
                     let func: RefCount<GloomFunc>;
                     match rf.obj_type() {
                         ObjectType::Class => {
@@ -389,6 +928,16 @@ impl GloomVM {
                                 .unwrap()
                                 .clone();
                         }
+                        ObjectType::MetaEnum => {
+                            let enum_class_obj = rf.downcast::<GloomEnumClassObj>();
+                            func = enum_class_obj
+                                .class
+                                .inner()
+                                .funcs
+                                .get(index as usize)
+                                .unwrap()
+                                .clone();
+                        }
                         ObjectType::MetaBuiltinType => {
                             let class = rf.downcast::<BuiltinClassObj>();
                             func = class
@@ -401,7 +950,7 @@ impl GloomVM {
                         }
                         _ => panic!(),
                     };
-                    let result = self.call_fn(&*func.inner(), GloomArgs::new(args));
+                    let result = self.invoke(&func, GloomArgs::new(args))?;
                     frame.push(result);
                 }
                 ByteCode::CallMethod { index, nargs } => {
@@ -413,7 +962,7 @@ impl GloomVM {
                     let func = obj_val.as_ref().method(index, &self.status);
                     args.push(obj_val);
                     args.reverse();
-                    let result = self.call_fn(&*func.inner(), GloomArgs::new(args));
+                    let result = self.invoke(&func, GloomArgs::new(args))?;
                     frame.push(result);
                 }
                 ByteCode::CallMethodDyn {
@@ -433,29 +982,41 @@ impl GloomVM {
                     };
                     args.push(obj_val);
                     args.reverse();
-                    let result = self.call_fn(func.inner().deref(), GloomArgs::new(args));
+                    let result = self.invoke(&func, GloomArgs::new(args))?;
                     frame.push(result);
                 }
                 ByteCode::Jump(label) => {
-                    pc = label as usize;
+                    *pc = label as usize;
                 }
                 ByteCode::JumpIf(label) => {
-                    if frame.pop().assert_bool() {
-                        pc = label as usize;
+                    if frame.pop().assert_bool()? {
+                        *pc = label as usize;
                     }
                 }
                 ByteCode::JumpIfNot(label) => {
-                    if !frame.pop().assert_bool() {
-                        pc = label as usize;
+                    if !frame.pop().assert_bool()? {
+                        *pc = label as usize;
                     }
                 }
                 ByteCode::JumpIfNone(label) => {
                     let is_none = frame.top().is_none();
                     if is_none {
-                        pc = label as usize;
+                        *pc = label as usize;
                         frame.pop();
                     }
                 }
+                ByteCode::JumpTable(table_idx) => {
+                    let matched = frame.pop();
+                    *pc = self
+                        .constant_pool
+                        .jump_table
+                        .get(table_idx as usize)
+                        .unwrap()
+                        .label(&matched) as usize;
+                    if let Value::Ref(rf) = matched {
+                        self.drop_object(&rf);
+                    }
+                }
                 ByteCode::Return => {
                     if frame.stack_not_empty() {
                         result = frame.pop();
@@ -467,155 +1028,420 @@ impl GloomVM {
                     for _ in 0..len {
                         tuple.push(frame.pop());
                     }
-                    frame.push(Value::Ref(GloomTuple::new(tuple)));
+                    frame.push(Value::Ref(self.track(GloomTuple::new(tuple))));
                 }
                 ByteCode::CollectArray(basic_type, len) => match basic_type {
                     BasicType::Int => {
                         let mut array = Vec::with_capacity(len as usize);
                         for _ in 0..len {
-                            array.push(frame.pop().assert_int());
+                            array.push(frame.pop().assert_int()?);
                         }
                         frame.push(Value::Ref(GloomArray::new(RawArray::IntVec(array))));
                     }
                     BasicType::Num => {
                         let mut array = Vec::with_capacity(len as usize);
                         for _ in 0..len {
-                            array.push(frame.pop().assert_num());
+                            array.push(frame.pop().assert_num()?);
                         }
                         frame.push(Value::Ref(GloomArray::new(RawArray::NumVec(array))));
                     }
                     BasicType::Char => {
                         let mut array = Vec::with_capacity(len as usize);
                         for _ in 0..len {
-                            array.push(frame.pop().assert_char());
+                            array.push(frame.pop().assert_char()?);
                         }
                         frame.push(Value::Ref(GloomArray::new(RawArray::CharVec(array))));
                     }
                     BasicType::Bool => {
                         let mut array = Vec::with_capacity(len as usize);
                         for _ in 0..len {
-                            array.push(frame.pop().assert_bool());
+                            array.push(frame.pop().assert_bool()?);
                         }
                         frame.push(Value::Ref(GloomArray::new(RawArray::BoolVec(array))));
                     }
                     BasicType::Ref => {
                         let mut array = Vec::with_capacity(len as usize);
                         for _ in 0..len {
-                            array.push(frame.pop().assert_into_ref());
+                            array.push(frame.pop().assert_into_ref()?);
                         }
-                        frame.push(Value::Ref(GloomArray::new(RawArray::RefVec(array))));
+                        frame.push(Value::Ref(
+                            self.track(GloomArray::new(RawArray::RefVec(array))),
+                        ));
                     }
                 },
+                ByteCode::CollectMap(len) => {
+                    let mut map = RawMap::new();
+                    for _ in 0..len {
+                        let key = frame.pop();
+                        let value = frame.pop();
+                        // 重复的键以后面的为准 the latter one of duplicated keys wins
+                        if let Some(old) = map.insert(key, value)? {
+                            drop_value(self, &old);
+                        }
+                    }
+                    frame.push(Value::Ref(self.track(GloomMap::new(map))));
+                }
+                ByteCode::CollectSet(len) => {
+                    let mut elems = Vec::with_capacity(len as usize);
+                    for _ in 0..len {
+                        elems.push(frame.pop());
+                    }
+                    frame.push(Value::Ref(self.track(GloomSet::from_values(elems)?)));
+                }
                 ByteCode::CollectQueue(basic_type, len) => match basic_type {
                     BasicType::Int => {
                         let mut queue = VecDeque::with_capacity(len as usize);
                         for _ in 0..len {
-                            queue.push_back(frame.pop().assert_int());
+                            queue.push_back(frame.pop().assert_int()?);
                         }
                         frame.push(Value::Ref(GloomQueue::new(RawQueue::IntQue(queue))));
                     }
                     BasicType::Num => {
                         let mut queue = VecDeque::with_capacity(len as usize);
                         for _ in 0..len {
-                            queue.push_back(frame.pop().assert_num());
+                            queue.push_back(frame.pop().assert_num()?);
                         }
                         frame.push(Value::Ref(GloomQueue::new(RawQueue::NumQue(queue))));
                     }
                     BasicType::Char => {
                         let mut queue = VecDeque::with_capacity(len as usize);
                         for _ in 0..len {
-                            queue.push_back(frame.pop().assert_char());
+                            queue.push_back(frame.pop().assert_char()?);
                         }
                         frame.push(Value::Ref(GloomQueue::new(RawQueue::CharQue(queue))));
                     }
                     BasicType::Bool => {
                         let mut queue = VecDeque::with_capacity(len as usize);
                         for _ in 0..len {
-                            queue.push_back(frame.pop().assert_bool());
+                            queue.push_back(frame.pop().assert_bool()?);
                         }
                         frame.push(Value::Ref(GloomQueue::new(RawQueue::BoolQue(queue))));
                     }
                     BasicType::Ref => {
                         let mut queue = VecDeque::with_capacity(len as usize);
                         for _ in 0..len {
-                            queue.push_back(frame.pop().assert_into_ref());
+                            queue.push_back(frame.pop().assert_into_ref()?);
                         }
-                        frame.push(Value::Ref(GloomQueue::new(RawQueue::RefQue(queue))));
+                        frame.push(Value::Ref(
+                            self.track(GloomQueue::new(RawQueue::RefQue(queue))),
+                        ));
                     }
                 },
                 ByteCode::Construct(class_idx) => {
-                    frame.push(Value::Ref(GloomObject::new(
+                    frame.push(Value::Ref(self.track(GloomObject::new(
                         self.status.classes.get(class_idx as usize).unwrap().clone(),
-                    )));
+                    ))));
+                }
+                ByteCode::ConstructEnum(tag) => {
+                    let class = frame
+                        .pop()
+                        .assert_into_ref()?
+                        .downcast::<GloomEnumClassObj>()
+                        .class
+                        .clone();
+                    frame.push(Value::Ref(GloomEnum::new(tag, Value::None, class)));
+                }
+                ByteCode::ConstructEnumWithVal(tag) => {
+                    let val = frame.pop();
+                    let class = frame
+                        .pop()
+                        .assert_into_ref()?
+                        .downcast::<GloomEnumClassObj>()
+                        .class
+                        .clone();
+                    frame.push(Value::Ref(self.track(GloomEnum::new(tag, val, class))));
+                }
+                ByteCode::ReadEnumVal => {
+                    let rf = frame.pop().assert_into_ref()?;
+                    let val = rf.downcast::<GloomEnum>().val.borrow().clone();
+                    frame.push(val);
+                    self.drop_object(&rf);
                 }
                 ByteCode::RangeIter => {
-                    let start = frame.pop().assert_int();
-                    let end = frame.pop().assert_int();
-                    let step = frame.pop().assert_int();
+                    let start = frame.pop().assert_int()?;
+                    let end = frame.pop().assert_int()?;
+                    let step = frame.pop().assert_int()?;
                     frame.push(Value::Ref(RangeIter::new(start, end, step)));
                 }
                 ByteCode::InvokeIter => {
-                    let iter = frame.pop().assert_into_ref().iter();
+                    let iter = frame.pop().assert_into_ref()?.iter();
                     frame.push(Value::Ref(iter));
                 }
                 ByteCode::InvokeNext => {
-                    let next = frame.top().as_ref().next();
+                    let iter = frame.top().as_ref();
+                    let next = match iter.obj_type() {
+                        ObjectType::Generator => self.resume(iter.downcast::<GloomGenerator>())?,
+                        // 运行事件循环直到收到消息或通道关闭 run the event loop until received a message or the channel closed
+                        ObjectType::Channel => {
+                            let task = GloomTask::recv(iter.clone(), false);
+                            let next = self.block_on(&task);
+                            self.drop_object(&task);
+                            next?
+                        }
+                        _ => iter.next(),
+                    };
                     frame.push(next);
                 }
                 ByteCode::AsInt => {
-                    let int = frame.pop().assert_int_form_num_liked();
+                    let int = frame.pop().assert_int_form_num_liked()?;
                     frame.push(Value::Int(int));
                 }
                 ByteCode::AsNum => {
-                    let num = frame.pop().assert_num_include_int();
+                    let num = frame.pop().assert_num_include_int()?;
                     frame.push(Value::Num(num));
                 }
                 ByteCode::AsChar => {
-                    let char = frame.pop().assert_char_include_int();
+                    let char = frame.pop().assert_char_include_int()?;
                     frame.push(Value::Char(char));
                 }
                 ByteCode::AsBool => {
-                    let bool = frame.pop().assert_bool();
+                    let bool = frame.pop().assert_bool()?;
                     frame.push(Value::Bool(bool));
                 }
                 ByteCode::AsRef => {
-                    let rf = frame.pop().assert_into_ref();
+                    let rf = frame.pop().assert_into_ref()?;
                     frame.push(Value::Ref(rf));
                 }
+                ByteCode::EnterTry(try_idx) => {
+                    frame.enter_try(try_idx, &self.constant_pool, *pc);
+                }
+                // 再次抛出捕获的错误时保留原来的调用栈 keep the original traceback when throw the caught error again
+                ByteCode::Throw => {
+                    let rf = frame.pop().assert_into_ref()?;
+                    let error = match rf.obj_type() {
+                        ObjectType::Error => rf.downcast::<GloomErrorObj>().0.clone(),
+                        _ => GloomError::new(rf.downcast::<GloomString>().0.borrow().clone()),
+                    };
+                    self.drop_object(&rf);
+                    return Result::Err(error);
+                }
+                ByteCode::Yield => {
+                    result = frame.pop();
+                    frame.suspend(*pc);
+                    break;
+                }
+                ByteCode::Await => match self.await_task(frame.top().as_ref())? {
+                    Some(value) => {
+                        if let Value::Ref(rf) = frame.pop() {
+                            self.drop_object(&rf);
+                        }
+                        frame.push(value);
+                    }
+                    // 恢复时重新执行这条字节码 检查任务是否结束
+                    // re-execute this bytecode when resumed, check whether the task finished
+                    None => {
+                        frame.suspend(*pc - 1);
+                        break;
+                    }
+                },
+                ByteCode::AwaitBlocking => {
+                    let value = self.block_on(frame.top().as_ref())?;
+                    if let Value::Ref(rf) = frame.pop() {
+                        self.drop_object(&rf);
+                    }
+                    frame.push(value);
+                }
+                ByteCode::Spawn => self.spawn(frame.top().as_ref()),
+                ByteCode::AwaitNext => match self.recv(frame.top().as_ref())? {
+                    Some(next) => frame.push(next.unwrap_or(Value::None)),
+                    // 恢复时重新接收 receive again when resumed
+                    None => {
+                        frame.suspend(*pc - 1);
+                        break;
+                    }
+                },
                 ByteCode::JumpIfStaticInit { label, static_idx } => {
                     if self.static_table.is_init(static_idx) {
-                        pc = label as usize;
+                        *pc = label as usize;
                     }
                 }
             }
         }
-        result
+        Result::Ok(result)
+    }
+
+    // 下标和切片越界是运行时错误 subscript or slice out of bounds is runtime error
+    fn check_index(index: i64, len: usize, what: &str) -> Result<usize, GloomError> {
+        if index < 0 || index as usize >= len {
+            return Result::Err(GloomError::new(format!(
+                "index {} out of bounds for {} of length {}",
+                index, what, len
+            )));
+        }
+        Result::Ok(index as usize)
+    }
+
+    fn check_slice(from: i64, to: i64, len: usize, what: &str) -> Result<(), GloomError> {
+        if from < 0 || to < from || to as usize > len {
+            return Result::Err(GloomError::new(format!(
+                "slice {}..{} out of bounds for {} of length {}",
+                from, to, what, len
+            )));
+        }
+        Result::Ok(())
+    }
+
+    fn read_index(&self, container: &GloomObjRef, index: &Value) -> Result<Value, GloomError> {
+        Result::Ok(match container.obj_type() {
+            ObjectType::Array => {
+                let array = container.downcast::<GloomArray>().0.borrow();
+                let index = GloomVM::check_index(index.assert_int()?, array.len(), "array")?;
+                array.get(index as i64)?
+            }
+            ObjectType::Queue => {
+                let queue = container.downcast::<GloomQueue>();
+                let len = queue.0.borrow().len();
+                let index = GloomVM::check_index(index.assert_int()?, len, "queue")?;
+                queue.get(index).unwrap()
+            }
+            ObjectType::String => {
+                let string = container.downcast::<GloomString>();
+                let index = index.assert_int()?;
+                GloomVM::check_index(index, string.char_len(), "string")?;
+                Value::Char(string.char_at(index)?)
+            }
+            ObjectType::Tuple => {
+                let values = container.downcast::<GloomTuple>().values();
+                let index = GloomVM::check_index(index.assert_int()?, values.len(), "tuple")?;
+                values[index].clone()
+            }
+            ObjectType::Map => {
+                let option = container.downcast::<GloomMap>().0.borrow().get(index)?;
+                match option {
+                    Some(value) => value,
+                    None => {
+                        return Result::Err(GloomError::new(format!(
+                            "key {:?} not found in map",
+                            index
+                        )))
+                    }
+                }
+            }
+            _ => return Result::Err(GloomError::new(format!("{:?} can't be indexed", container))),
+        })
+    }
+
+    fn write_index(
+        &self,
+        container: &GloomObjRef,
+        index: Value,
+        value: Value,
+    ) -> Result<(), GloomError> {
+        match container.obj_type() {
+            ObjectType::Array => {
+                let mut array = container.downcast::<GloomArray>().0.borrow_mut();
+                let index = GloomVM::check_index(index.assert_int()?, array.len(), "array")?;
+                array.set(self, index as i64, value)?;
+            }
+            ObjectType::Queue => {
+                let mut queue = container.downcast::<GloomQueue>().0.borrow_mut();
+                let index = GloomVM::check_index(index.assert_int()?, queue.len(), "queue")?;
+                queue.set(self, index, value)?;
+            }
+            ObjectType::Map => {
+                let old = container
+                    .downcast::<GloomMap>()
+                    .0
+                    .borrow_mut()
+                    .insert(index, value)?;
+                if let Some(old) = old {
+                    drop_value(self, &old);
+                }
+            }
+            _ => return Result::Err(GloomError::new(format!("{:?} can't be indexed", container))),
+        }
+        Result::Ok(())
+    }
+
+    fn read_slice(
+        &self,
+        container: &GloomObjRef,
+        from: i64,
+        to: i64,
+    ) -> Result<GloomObjRef, GloomError> {
+        Result::Ok(match container.obj_type() {
+            ObjectType::Array => {
+                let array = container.downcast::<GloomArray>().0.borrow();
+                GloomVM::check_slice(from, to, array.len(), "array")?;
+                GloomArray::new(array.slice(from, to)?)
+            }
+            ObjectType::Queue => {
+                let queue = container.downcast::<GloomQueue>().0.borrow();
+                GloomVM::check_slice(from, to, queue.len(), "queue")?;
+                GloomQueue::new(queue.slice(from as usize, to as usize))
+            }
+            ObjectType::String => {
+                let string = container.downcast::<GloomString>();
+                GloomVM::check_slice(from, to, string.char_len(), "string")?;
+                GloomString::new(string.substring(from, to)?)
+            }
+            _ => return Result::Err(GloomError::new(format!("{:?} can't be sliced", container))),
+        })
     }
 
     #[inline]
     pub fn drop_object(&self, rf: &GloomObjRef) {
         if rf.count() == 1 {
-            rf.drop_by_vm(self);
+            self.finalize(rf);
+        } else {
+            self.collector.borrow_mut().possible_root(rf);
         }
     }
     #[inline]
     pub fn drop_object_manually(&self, mut rf: ManuallyDrop<GloomObjRef>) {
-        if rf.count() == 1 {
-            rf.drop_by_vm(self);
-        }
+        self.drop_object(&rf);
         unsafe {
             ManuallyDrop::drop(&mut rf);
         }
     }
     #[inline]
     pub fn drop_option_manually(&self, option: Option<ManuallyDrop<GloomObjRef>>) {
-        if let Some(mut rf) = option {
-            if rf.count() == 1 {
-                rf.drop_by_vm(self);
-            }
-            unsafe {
-                ManuallyDrop::drop(&mut rf);
-            }
+        if let Some(rf) = option {
+            self.drop_object_manually(rf);
+        }
+    }
+
+    // 析构函数可能通过弱引用访问正在被回收的对象 所以记录它们
+    // drop function may access the object being finalized by weak reference, so record them
+    fn finalize(&self, rf: &GloomObjRef) {
+        self.finalizing.borrow_mut().push(rf.addr());
+        rf.drop_by_vm(self);
+        self.finalizing.borrow_mut().pop();
+    }
+    #[inline]
+    pub fn is_finalizing(&self, addr: *const ()) -> bool {
+        self.finalizing.borrow().contains(&addr)
+    }
+
+    // 记录新分配的对象 达到阈值时回收循环引用 track the new object, collect cycles when reach the threshold
+    #[inline]
+    fn track(&self, rf: GloomObjRef) -> GloomObjRef {
+        if self.collector.borrow_mut().allocate(&rf) {
+            self.collect_cycles();
+        }
+        rf
+    }
+
+    // 回收只被彼此引用的对象 返回回收的数量 collect objects only referenced by each other, return the number
+    pub fn collect_cycles(&self) -> usize {
+        let roots = self.collector.borrow_mut().take_roots();
+        let garbage = collector::find_garbage(roots);
+        // 先调用所有析构函数再断开引用 析构函数中仍可以访问环中的其他对象
+        // call all drop functions before breaking references, other objects in the cycle are still accessible in drop function
+        let base = self.finalizing.borrow().len();
+        self.finalizing
+            .borrow_mut()
+            .extend(garbage.iter().map(GloomObjRef::addr));
+        for rf in garbage.iter() {
+            rf.drop_by_vm(self);
+        }
+        self.finalizing.borrow_mut().truncate(base);
+        for rf in garbage.iter() {
+            rf.clear_refs();
+        }
+        if self.debug {
+            println!("gc : collected {} objects in cycles", garbage.len());
         }
+        garbage.len()
     }
 }
@@ -1,6 +1,9 @@
+pub mod collector;
 pub mod constant;
+pub mod error;
 pub mod frame;
 pub mod machine;
+pub mod scheduler;
 pub mod slot;
 pub mod static_table;
 pub mod value;
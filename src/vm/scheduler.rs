@@ -0,0 +1,99 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::obj::object::GloomObjRef;
+
+// 定时唤醒在调度器中的登记 the registration of timed wake-up in scheduler
+pub type TimerKey = (Instant, u64);
+
+// 单线程的任务调度器 每个任务运行到挂起或结束为止
+// single-threaded task scheduler, each task runs until it suspends or finishes
+pub struct Scheduler {
+    // 可以继续执行的任务 先进先出 tasks ready to continue, first in first out
+    ready: VecDeque<GloomObjRef>,
+    // 按期限和登记顺序排列的定时唤醒 任务或定时器
+    // timed wake-ups ordered by deadline and registration, tasks or timers
+    timers: BTreeMap<TimerKey, GloomObjRef>,
+    registered: u64,
+    // 单调时钟的起点 the start of monotonic clock
+    epoch: Instant,
+    // 正在执行的顶层任务 the top-level task being executed
+    current: Option<GloomObjRef>,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler {
+            ready: VecDeque::new(),
+            timers: BTreeMap::new(),
+            registered: 0,
+            epoch: Instant::now(),
+            current: Option::None,
+        }
+    }
+
+    #[inline]
+    pub fn wake(&mut self, task: GloomObjRef) {
+        self.ready.push_back(task);
+    }
+
+    pub fn wake_at(&mut self, deadline: Instant, task: GloomObjRef) -> TimerKey {
+        let key = (deadline, self.registered);
+        self.timers.insert(key, task);
+        self.registered += 1;
+        key
+    }
+
+    // 取消尚未到期的定时唤醒 cancel the timed wake-up which is not due yet
+    #[inline]
+    pub fn cancel(&mut self, key: &TimerKey) -> Option<GloomObjRef> {
+        self.timers.remove(key)
+    }
+
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+
+    #[inline]
+    pub fn current(&self) -> Option<GloomObjRef> {
+        self.current.clone()
+    }
+
+    // 返回之前的顶层任务 return the previous top-level task
+    #[inline]
+    pub fn set_current(&mut self, task: Option<GloomObjRef>) -> Option<GloomObjRef> {
+        std::mem::replace(&mut self.current, task)
+    }
+
+    // 下一个要执行的任务 没有就绪的任务时睡眠到最早的期限 全部执行完时返回None
+    // the next task to run, sleep until the earliest deadline if no task is ready, return None if all finished
+    pub fn next_task(&mut self) -> Option<GloomObjRef> {
+        // 到期的任务排在已就绪的任务之后 due tasks are queued after the ready ones
+        self.fire(Instant::now());
+        if self.ready.is_empty() {
+            let (deadline, _) = *self.timers.keys().next()?;
+            let now = Instant::now();
+            if deadline > now {
+                std::thread::sleep(deadline - now);
+            }
+            self.fire(deadline);
+        }
+        self.ready.pop_front()
+    }
+
+    fn fire(&mut self, now: Instant) {
+        while let Some(entry) = self.timers.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            self.ready.push_back(entry.remove());
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
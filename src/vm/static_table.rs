@@ -26,6 +26,14 @@ impl StaticTable {
             drop_vec,
         }
     }
+    // REPL中新声明的静态变量 the static variables newly declared in REPL
+    pub fn grow(&mut self, len: u16, drop_vec: Vec<u16>) {
+        if len > self.len {
+            self.table.grow(self.len, len);
+            self.len = len;
+        }
+        self.drop_vec = drop_vec;
+    }
     #[inline(always)]
     pub fn read(&self, slot_idx: u16) -> Value {
         match self.table.slot(slot_idx) {
@@ -119,6 +127,10 @@ impl ListIndexer {
         self.types.len() as u16
     }
 
+    pub fn truncate(&mut self, len: u16) {
+        self.types.truncate(len as usize);
+    }
+
     pub fn drop_vec(&self) -> Vec<u16> {
         let mut drop_vec = Vec::new();
         for (idx, typ) in self.types.iter().enumerate() {
@@ -1,7 +1,11 @@
+use std::convert::TryFrom;
 use std::fmt::{Debug, Formatter};
 
 use crate::builtin::boxed::{GloomBool, GloomChar, GloomInt, GloomNum};
+use crate::builtin::string::GloomString;
+use crate::obj::gloom_enum::GloomEnum;
 use crate::obj::object::{GloomObjRef, ObjectType};
+use crate::vm::error::GloomError;
 
 #[derive(Clone)]
 pub enum Value {
@@ -42,37 +46,51 @@ impl Value {
         }
     }
 
+    // 类型不匹配的运行时错误 runtime error of type mismatched
+    #[cold]
+    fn mismatch(&self, expect: &str) -> GloomError {
+        GloomError::new(format!("expect {}, found {:?}", expect, self))
+    }
+
+    #[cold]
+    fn unsupported(&self, op: &str, other: &Value) -> GloomError {
+        GloomError::new(format!(
+            "unsupported operation {:?} {} {:?}",
+            self, op, other
+        ))
+    }
+
     #[inline(always)]
-    pub fn assert_int(&self) -> i64 {
-        self.as_int().unwrap()
+    pub fn assert_int(&self) -> Result<i64, GloomError> {
+        self.as_int().ok_or_else(|| self.mismatch("int"))
     }
 
     #[inline(always)]
-    pub fn assert_int_include_num(&self) -> i64 {
+    pub fn assert_int_include_num(&self) -> Result<i64, GloomError> {
         match self.as_int() {
             None => match self.as_num() {
-                None => panic!(),
-                Some(i) => i as i64,
+                None => Result::Err(self.mismatch("int")),
+                Some(i) => Result::Ok(i as i64),
             },
-            Some(i) => i,
+            Some(i) => Result::Ok(i),
         }
     }
 
     #[inline(always)]
-    pub fn assert_int_form_num_liked(&self) -> i64 {
+    pub fn assert_int_form_num_liked(&self) -> Result<i64, GloomError> {
         match self {
-            Value::Int(int) => *int,
-            Value::Num(num) => *num as i64,
-            Value::Char(ch) => *ch as i64,
-            Value::Bool(bl) => *bl as i64,
+            Value::Int(int) => Result::Ok(*int),
+            Value::Num(num) => Result::Ok(*num as i64),
+            Value::Char(ch) => Result::Ok(*ch as i64),
+            Value::Bool(bl) => Result::Ok(*bl as i64),
             Value::Ref(obj_ref) => match obj_ref.obj_type() {
-                ObjectType::Int => obj_ref.downcast::<GloomInt>().0.get(),
-                ObjectType::Num => obj_ref.downcast::<GloomNum>().0.get() as i64,
-                ObjectType::Char => obj_ref.downcast::<GloomChar>().0.get() as i64,
-                ObjectType::Bool => obj_ref.downcast::<GloomBool>().0.get() as i64,
-                _ => panic!("{:?} as i64 ?", self),
+                ObjectType::Int => Result::Ok(obj_ref.downcast::<GloomInt>().0.get()),
+                ObjectType::Num => Result::Ok(obj_ref.downcast::<GloomNum>().0.get() as i64),
+                ObjectType::Char => Result::Ok(obj_ref.downcast::<GloomChar>().0.get() as i64),
+                ObjectType::Bool => Result::Ok(obj_ref.downcast::<GloomBool>().0.get() as i64),
+                _ => Result::Err(self.mismatch("int")),
             },
-            _ => panic!("{:?} as i64 ?", self),
+            _ => Result::Err(self.mismatch("int")),
         }
     }
 
@@ -91,18 +109,18 @@ impl Value {
         }
     }
     #[inline(always)]
-    pub fn assert_num(&self) -> f64 {
-        self.as_num().unwrap()
+    pub fn assert_num(&self) -> Result<f64, GloomError> {
+        self.as_num().ok_or_else(|| self.mismatch("num"))
     }
 
     #[inline(always)]
-    pub fn assert_num_include_int(&self) -> f64 {
+    pub fn assert_num_include_int(&self) -> Result<f64, GloomError> {
         match self.as_num() {
             None => match self.as_int() {
-                None => panic!(),
-                Some(i) => i as f64,
+                None => Result::Err(self.mismatch("num")),
+                Some(i) => Result::Ok(i as f64),
             },
-            Some(i) => i,
+            Some(i) => Result::Ok(i),
         }
     }
 
@@ -121,21 +139,21 @@ impl Value {
         }
     }
     #[inline(always)]
-    pub fn assert_char(&self) -> char {
-        self.as_char().unwrap()
+    pub fn assert_char(&self) -> Result<char, GloomError> {
+        self.as_char().ok_or_else(|| self.mismatch("char"))
     }
 
     #[inline(always)]
-    pub fn assert_char_include_int(&self) -> char {
+    pub fn assert_char_include_int(&self) -> Result<char, GloomError> {
         match self {
-            Value::Char(i) => *i,
-            Value::Int(i) => *i as u8 as char,
+            Value::Char(i) => Result::Ok(*i),
+            Value::Int(i) => Result::Ok(*i as u8 as char),
             Value::Ref(obj) => match obj.obj_type() {
-                ObjectType::Int => obj.downcast::<GloomInt>().0.get() as u8 as char,
-                ObjectType::Char => obj.downcast::<GloomChar>().0.get(),
-                _ => panic!("{:?} as char ?", self),
+                ObjectType::Int => Result::Ok(obj.downcast::<GloomInt>().0.get() as u8 as char),
+                ObjectType::Char => Result::Ok(obj.downcast::<GloomChar>().0.get()),
+                _ => Result::Err(self.mismatch("char")),
             },
-            _ => panic!("{:?} as char ?", self),
+            _ => Result::Err(self.mismatch("char")),
         }
     }
 
@@ -155,8 +173,8 @@ impl Value {
     }
 
     #[inline(always)]
-    pub fn assert_bool(&self) -> bool {
-        self.as_bool().unwrap()
+    pub fn assert_bool(&self) -> Result<bool, GloomError> {
+        self.as_bool().ok_or_else(|| self.mismatch("bool"))
     }
 
     #[inline]
@@ -169,14 +187,14 @@ impl Value {
     }
 
     #[inline(always)]
-    pub fn assert_into_ref(self) -> GloomObjRef {
+    pub fn assert_into_ref(self) -> Result<GloomObjRef, GloomError> {
         match self {
-            Value::Int(i) => GloomInt::new(i),
-            Value::Num(i) => GloomNum::new(i),
-            Value::Char(i) => GloomChar::new(i),
-            Value::Bool(i) => GloomBool::new(i),
-            Value::Ref(obj) => obj,
-            Value::None => panic!(),
+            Value::Int(i) => Result::Ok(GloomInt::new(i)),
+            Value::Num(i) => Result::Ok(GloomNum::new(i)),
+            Value::Char(i) => Result::Ok(GloomChar::new(i)),
+            Value::Bool(i) => Result::Ok(GloomBool::new(i)),
+            Value::Ref(obj) => Result::Ok(obj),
+            Value::None => Result::Err(Value::None.mismatch("object")),
         }
     }
 
@@ -189,7 +207,7 @@ impl Value {
     }
 
     #[inline]
-    pub fn not(&mut self) {
+    pub fn not(&mut self) -> Result<(), GloomError> {
         match self {
             Value::Bool(bl) => {
                 *bl = !*bl;
@@ -200,15 +218,16 @@ impl Value {
                     bl.0.set(!bl.0.get());
                 }
             }
-            _ => panic!(),
+            _ => return Result::Err(self.mismatch("bool")),
         }
+        Result::Ok(())
     }
 
     #[inline]
-    pub fn neg(&mut self) {
+    pub fn neg(&mut self) -> Result<(), GloomError> {
         match self {
             Value::Int(i) => {
-                *i = -*i;
+                *i = checked(i.checked_neg(), "negate")?;
             }
             Value::Num(n) => {
                 *n = -*n;
@@ -216,73 +235,77 @@ impl Value {
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
                     let i = rf.downcast::<GloomInt>();
-                    i.0.set(i.0.get());
+                    i.0.set(checked(i.0.get().checked_neg(), "negate")?);
                 }
                 ObjectType::Num => {
                     let n = rf.downcast::<GloomNum>();
-                    n.0.set(n.0.get());
+                    n.0.set(-n.0.get());
                 }
-                _ => panic!(),
+                _ => return Result::Err(self.mismatch("int or num")),
             },
-            _ => panic!(),
+            _ => return Result::Err(self.mismatch("int or num")),
         }
+        Result::Ok(())
     }
 
     #[inline]
-    pub fn plus(&mut self, val: Value) {
+    pub fn plus(&mut self, val: Value) -> Result<(), GloomError> {
         match self {
             Value::Int(int) => {
-                *int += val.assert_int_include_num();
+                *int = checked(int.checked_add(val.assert_int_include_num()?), "add")?;
             }
             Value::Num(num) => {
-                *num += val.assert_num_include_int();
+                *num += val.assert_num_include_int()?;
             }
             Value::Ref(obj_ref) => match obj_ref.obj_type() {
                 ObjectType::Int => {
                     let int_obj = obj_ref.downcast::<GloomInt>();
-                    let int_val = int_obj.0.get() + val.assert_int();
+                    let int_val = checked(int_obj.0.get().checked_add(val.assert_int()?), "add")?;
                     int_obj.0.set(int_val);
                 }
                 ObjectType::Num => {
                     let obj = obj_ref.downcast::<GloomNum>();
-                    let num_val = obj.0.get() + val.assert_num();
+                    let num_val = obj.0.get() + val.assert_num()?;
                     obj.0.set(num_val);
                 }
-                _ => panic!(),
+                _ => return Result::Err(self.unsupported("+", &val)),
             },
-            _ => panic!(),
+            _ => return Result::Err(self.unsupported("+", &val)),
         }
+        Result::Ok(())
     }
     #[inline]
-    pub fn sub(&mut self, val: Value) {
+    pub fn sub(&mut self, val: Value) -> Result<(), GloomError> {
         match self {
             Value::Int(int) => {
-                *int -= val.assert_int_include_num();
+                *int = checked(int.checked_sub(val.assert_int_include_num()?), "subtract")?;
             }
             Value::Num(num) => {
-                *num -= val.assert_num_include_int();
+                *num -= val.assert_num_include_int()?;
             }
             Value::Ref(obj_ref) => match obj_ref.obj_type() {
                 ObjectType::Int => {
                     let int_obj = obj_ref.downcast::<GloomInt>();
-                    let int_val = int_obj.0.get() - val.assert_int();
+                    let int_val =
+                        checked(int_obj.0.get().checked_sub(val.assert_int()?), "subtract")?;
                     int_obj.0.set(int_val);
                 }
                 ObjectType::Num => {
                     let obj = obj_ref.downcast::<GloomNum>();
-                    let num_val = obj.0.get() - val.assert_num();
+                    let num_val = obj.0.get() - val.assert_num()?;
                     obj.0.set(num_val);
                 }
-                _ => panic!(),
+                _ => return Result::Err(self.unsupported("-", &val)),
             },
-            _ => panic!(),
+            _ => return Result::Err(self.unsupported("-", &val)),
         }
+        Result::Ok(())
     }
     #[inline]
-    pub fn plus_one(&mut self) {
+    pub fn plus_one(&mut self) -> Result<(), GloomError> {
         match self {
             Value::Int(int) => {
-                *int += 1;
+                *int = checked(int.checked_add(1), "add")?;
             }
             Value::Num(num) => {
                 *num += 1.0;
@@ -290,7 +313,7 @@ impl Value {
             Value::Ref(obj_ref) => match obj_ref.obj_type() {
                 ObjectType::Int => {
                     let int_obj = obj_ref.downcast::<GloomInt>();
-                    let int_val = int_obj.0.get() + 1;
+                    let int_val = checked(int_obj.0.get().checked_add(1), "add")?;
                     int_obj.0.set(int_val);
                 }
                 ObjectType::Num => {
@@ -298,16 +321,17 @@ impl Value {
                     let num_val = obj.0.get() + 1.0;
                     obj.0.set(num_val);
                 }
-                _ => panic!(),
+                _ => return Result::Err(self.mismatch("int or num")),
             },
-            _ => panic!(),
+            _ => return Result::Err(self.mismatch("int or num")),
         }
+        Result::Ok(())
     }
     #[inline]
-    pub fn sub_one(&mut self) {
+    pub fn sub_one(&mut self) -> Result<(), GloomError> {
         match self {
             Value::Int(int) => {
-                *int -= 1;
+                *int = checked(int.checked_sub(1), "subtract")?;
             }
             Value::Num(num) => {
                 *num -= 1.0;
@@ -315,7 +339,7 @@ impl Value {
             Value::Ref(obj_ref) => match obj_ref.obj_type() {
                 ObjectType::Int => {
                     let int_obj = obj_ref.downcast::<GloomInt>();
-                    let int_val = int_obj.0.get() - 1;
+                    let int_val = checked(int_obj.0.get().checked_sub(1), "subtract")?;
                     int_obj.0.set(int_val);
                 }
                 ObjectType::Num => {
@@ -323,147 +347,275 @@ impl Value {
                     let num_val = obj.0.get() - 1.0;
                     obj.0.set(num_val);
                 }
-                _ => panic!(),
+                _ => return Result::Err(self.mismatch("int or num")),
             },
-            _ => panic!(),
+            _ => return Result::Err(self.mismatch("int or num")),
         }
+        Result::Ok(())
     }
 
     #[inline]
-    pub fn equals(&self, other: Value) -> bool {
-        match self {
-            Value::Int(int) => *int == other.assert_int_include_num(),
-            Value::Num(num) => *num == other.assert_num_include_int(),
-            Value::Char(ch) => *ch == other.assert_char(),
-            Value::Bool(bl) => *bl == other.assert_bool(),
+    pub fn equals(&self, other: Value) -> Result<bool, GloomError> {
+        Result::Ok(match self {
+            Value::Int(int) => *int == other.assert_int_include_num()?,
+            Value::Num(num) => *num == other.assert_num_include_int()?,
+            Value::Char(ch) => *ch == other.assert_char()?,
+            Value::Bool(bl) => *bl == other.assert_bool()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
-                    rf.downcast::<GloomInt>().0.get() == other.assert_int_include_num()
+                    rf.downcast::<GloomInt>().0.get() == other.assert_int_include_num()?
                 }
                 ObjectType::Num => {
-                    rf.downcast::<GloomNum>().0.get() == other.assert_num_include_int()
+                    rf.downcast::<GloomNum>().0.get() == other.assert_num_include_int()?
                 }
-                ObjectType::Char => rf.downcast::<GloomChar>().0.get() == other.assert_char(),
-                ObjectType::Bool => rf.downcast::<GloomBool>().0.get() == other.assert_bool(),
-                _ => rf.addr_eqs(&other.assert_into_ref()),
+                ObjectType::Char => rf.downcast::<GloomChar>().0.get() == other.assert_char()?,
+                ObjectType::Bool => rf.downcast::<GloomBool>().0.get() == other.assert_bool()?,
+                ObjectType::Enum => rf.downcast::<GloomEnum>().equals(&other.assert_into_ref()?),
+                _ => rf.addr_eqs(&other.assert_into_ref()?),
             },
             Value::None => false,
-        }
+        })
     }
 
     #[inline]
-    pub fn greater_than(&self, other: Value) -> bool {
-        match self {
-            Value::Int(int) => *int > other.assert_int_include_num(),
-            Value::Num(num) => *num > other.assert_num_include_int(),
+    pub fn greater_than(&self, other: Value) -> Result<bool, GloomError> {
+        Result::Ok(match self {
+            Value::Int(int) => *int > other.assert_int_include_num()?,
+            Value::Num(num) => *num > other.assert_num_include_int()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
-                    rf.downcast::<GloomInt>().0.get() > other.assert_int_include_num()
+                    rf.downcast::<GloomInt>().0.get() > other.assert_int_include_num()?
                 }
                 ObjectType::Num => {
-                    rf.downcast::<GloomNum>().0.get() > other.assert_num_include_int()
+                    rf.downcast::<GloomNum>().0.get() > other.assert_num_include_int()?
                 }
-                _ => panic!("{:?} > {:?} ?", self, other),
+                _ => return Result::Err(self.unsupported(">", &other)),
             },
-            _ => panic!("{:?} > {:?} ?", self, other),
-        }
+            _ => return Result::Err(self.unsupported(">", &other)),
+        })
     }
 
     #[inline]
-    pub fn less_than(&self, other: Value) -> bool {
-        match self {
-            Value::Int(int) => *int < other.assert_int_include_num(),
-            Value::Num(num) => *num < other.assert_num_include_int(),
+    pub fn less_than(&self, other: Value) -> Result<bool, GloomError> {
+        Result::Ok(match self {
+            Value::Int(int) => *int < other.assert_int_include_num()?,
+            Value::Num(num) => *num < other.assert_num_include_int()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
-                    rf.downcast::<GloomInt>().0.get() < other.assert_int_include_num()
+                    rf.downcast::<GloomInt>().0.get() < other.assert_int_include_num()?
                 }
                 ObjectType::Num => {
-                    rf.downcast::<GloomNum>().0.get() < other.assert_num_include_int()
+                    rf.downcast::<GloomNum>().0.get() < other.assert_num_include_int()?
                 }
-                _ => panic!("{:?} < {:?} ?", self, other),
+                _ => return Result::Err(self.unsupported("<", &other)),
             },
-            _ => panic!("{:?} < {:?} ?", self, other),
-        }
+            _ => return Result::Err(self.unsupported("<", &other)),
+        })
     }
 
     #[inline]
-    pub fn greater_equal(&self, other: Value) -> bool {
-        match self {
-            Value::Int(int) => *int >= other.assert_int_include_num(),
-            Value::Num(num) => *num >= other.assert_num_include_int(),
+    pub fn greater_equal(&self, other: Value) -> Result<bool, GloomError> {
+        Result::Ok(match self {
+            Value::Int(int) => *int >= other.assert_int_include_num()?,
+            Value::Num(num) => *num >= other.assert_num_include_int()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
-                    rf.downcast::<GloomInt>().0.get() >= other.assert_int_include_num()
+                    rf.downcast::<GloomInt>().0.get() >= other.assert_int_include_num()?
                 }
                 ObjectType::Num => {
-                    rf.downcast::<GloomNum>().0.get() >= other.assert_num_include_int()
+                    rf.downcast::<GloomNum>().0.get() >= other.assert_num_include_int()?
                 }
-                _ => panic!("{:?} >= {:?} ?", self, other),
+                _ => return Result::Err(self.unsupported(">=", &other)),
             },
-            _ => panic!("{:?} >= {:?} ?", self, other),
-        }
+            _ => return Result::Err(self.unsupported(">=", &other)),
+        })
     }
 
     #[inline]
-    pub fn less_equal(&self, other: Value) -> bool {
-        match self {
-            Value::Int(int) => *int <= other.assert_int_include_num(),
-            Value::Num(num) => *num <= other.assert_num_include_int(),
+    pub fn less_equal(&self, other: Value) -> Result<bool, GloomError> {
+        Result::Ok(match self {
+            Value::Int(int) => *int <= other.assert_int_include_num()?,
+            Value::Num(num) => *num <= other.assert_num_include_int()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
-                    rf.downcast::<GloomInt>().0.get() <= other.assert_int_include_num()
+                    rf.downcast::<GloomInt>().0.get() <= other.assert_int_include_num()?
                 }
                 ObjectType::Num => {
-                    rf.downcast::<GloomNum>().0.get() <= other.assert_num_include_int()
+                    rf.downcast::<GloomNum>().0.get() <= other.assert_num_include_int()?
                 }
-                _ => panic!("{:?} <= {:?} ?", self, other),
+                _ => return Result::Err(self.unsupported("<=", &other)),
             },
-            _ => panic!("{:?} <= {:?} ?", self, other),
-        }
+            _ => return Result::Err(self.unsupported("<=", &other)),
+        })
     }
 
     #[inline]
-    pub fn multiply(&mut self, other: Value) {
+    pub fn multiply(&mut self, other: Value) -> Result<(), GloomError> {
         match self {
-            Value::Int(int) => *int = *int * other.assert_int_include_num(),
-            Value::Num(num) => *num = *num * other.assert_num_include_int(),
+            Value::Int(int) => {
+                *int = checked(int.checked_mul(other.assert_int_include_num()?), "multiply")?
+            }
+            Value::Num(num) => *num = *num * other.assert_num_include_int()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
                     let int = rf.downcast::<GloomInt>();
-                    int.0.set(int.0.get() * other.assert_int_include_num());
+                    int.0.set(checked(
+                        int.0.get().checked_mul(other.assert_int_include_num()?),
+                        "multiply",
+                    )?);
                 }
                 ObjectType::Num => {
                     let num = rf.downcast::<GloomNum>();
-                    num.0.set(num.0.get() * other.assert_num_include_int());
+                    num.0.set(num.0.get() * other.assert_num_include_int()?);
                 }
-                _ => panic!("{:?} * {:?} ?", self, other),
+                _ => return Result::Err(self.unsupported("*", &other)),
             },
-            _ => panic!("{:?} * {:?} ?", self, other),
+            _ => return Result::Err(self.unsupported("*", &other)),
         }
+        Result::Ok(())
     }
 
     #[inline]
-    pub fn divide(&mut self, other: Value) {
+    pub fn divide(&mut self, other: Value) -> Result<(), GloomError> {
         match self {
-            Value::Int(int) => *int = *int / other.assert_int_include_num(),
-            Value::Num(num) => *num = *num / other.assert_num_include_int(),
+            Value::Int(int) => *int = checked_div(*int, other.assert_int_include_num()?)?,
+            Value::Num(num) => *num = *num / other.assert_num_include_int()?,
             Value::Ref(rf) => match rf.obj_type() {
                 ObjectType::Int => {
                     let int = rf.downcast::<GloomInt>();
-                    int.0.set(int.0.get() / other.assert_int_include_num());
+                    int.0
+                        .set(checked_div(int.0.get(), other.assert_int_include_num()?)?);
                 }
                 ObjectType::Num => {
                     let num = rf.downcast::<GloomNum>();
-                    num.0.set(num.0.get() / other.assert_num_include_int());
+                    num.0.set(num.0.get() / other.assert_num_include_int()?);
                 }
-                _ => panic!("{:?} / {:?} ?", self, other),
+                _ => return Result::Err(self.unsupported("/", &other)),
             },
-            _ => panic!("{:?} / {:?} ?", self, other),
+            _ => return Result::Err(self.unsupported("/", &other)),
+        }
+        Result::Ok(())
+    }
+
+    // 两个int的结果是int 否则是num the result of two ints is int, otherwise num
+    #[inline]
+    pub fn modulo(&mut self, other: Value) -> Result<(), GloomError> {
+        *self = match (self.as_int(), other.as_int()) {
+            (Some(left), Some(right)) => {
+                if right == 0 {
+                    return Result::Err(GloomError::new(String::from(
+                        "attempt to calculate the remainder with a divisor of zero",
+                    )));
+                }
+                Value::Int(left.wrapping_rem(right))
+            }
+            _ => Value::Num(self.assert_num_include_int()? % other.assert_num_include_int()?),
+        };
+        Result::Ok(())
+    }
+
+    // 整数乘方溢出时是运行时错误 overflow of int power is runtime error
+    #[inline]
+    pub fn power(&mut self, other: Value) -> Result<(), GloomError> {
+        *self = match (self.as_int(), other.as_int()) {
+            (Some(base), Some(exp)) => {
+                if exp < 0 {
+                    return Result::Err(GloomError::new(format!(
+                        "{} ** {}, negative exponent of int power",
+                        base, exp
+                    )));
+                }
+                // 底数为0 1 -1时结果只取决于指数的奇偶 the result only depends on the parity of exponent if base is 0 1 -1
+                let exp = if exp > u32::MAX as i64 && base.abs() <= 1 {
+                    (exp % 2 + 2) as u32
+                } else {
+                    exp.min(u32::MAX as i64) as u32
+                };
+                Value::Int(checked(base.checked_pow(exp), "power")?)
+            }
+            _ => Value::Num(
+                self.assert_num_include_int()?
+                    .powf(other.assert_num_include_int()?),
+            ),
+        };
+        Result::Ok(())
+    }
+
+    #[inline]
+    pub fn bit_not(&mut self) -> Result<(), GloomError> {
+        *self = Value::Int(!self.assert_int()?);
+        Result::Ok(())
+    }
+
+    #[inline]
+    pub fn bit_and(&self, other: Value) -> Result<Value, GloomError> {
+        Result::Ok(match self.as_int() {
+            Some(int) => Value::Int(int & other.assert_int()?),
+            None => Value::Bool(self.assert_bool()? & other.assert_bool()?),
+        })
+    }
+
+    #[inline]
+    pub fn bit_or(&self, other: Value) -> Result<Value, GloomError> {
+        Result::Ok(match self.as_int() {
+            Some(int) => Value::Int(int | other.assert_int()?),
+            None => Value::Bool(self.assert_bool()? | other.assert_bool()?),
+        })
+    }
+
+    #[inline]
+    pub fn bit_xor(&self, other: Value) -> Result<Value, GloomError> {
+        Result::Ok(match self.as_int() {
+            Some(int) => Value::Int(int ^ other.assert_int()?),
+            None => Value::Bool(self.assert_bool()? ^ other.assert_bool()?),
+        })
+    }
+
+    #[inline]
+    pub fn shift_left(&self, other: Value) -> Result<Value, GloomError> {
+        let int = self.assert_int()?;
+        Result::Ok(Value::Int(int << Self::shift_amount(int, "<<", other)?))
+    }
+
+    // 算术右移 arithmetic shift right
+    #[inline]
+    pub fn shift_right(&self, other: Value) -> Result<Value, GloomError> {
+        let int = self.assert_int()?;
+        Result::Ok(Value::Int(int >> Self::shift_amount(int, ">>", other)?))
+    }
+
+    #[inline]
+    fn shift_amount(int: i64, op: &str, other: Value) -> Result<i64, GloomError> {
+        let amount = other.assert_int()?;
+        if !(0..64).contains(&amount) {
+            return Result::Err(GloomError::new(format!(
+                "{} {} {}, shift amount out of range 0..64",
+                int, op, amount
+            )));
         }
+        Result::Ok(amount)
     }
 }
 
+// 整数的加减乘和取负溢出时是运行时错误 与JIT编译后的代码一致 乘方溢出同样是运行时错误
+// overflow of int add, subtract, multiply and negate is runtime error, same as the compiled code by JIT,
+// so is the overflow of int power
+#[inline]
+fn checked(result: Option<i64>, op: &str) -> Result<i64, GloomError> {
+    match result {
+        Some(int) => Result::Ok(int),
+        None => Result::Err(GloomError::new(format!("attempt to {} with overflow", op))),
+    }
+}
+
+#[inline]
+fn checked_div(left: i64, right: i64) -> Result<i64, GloomError> {
+    if right == 0 {
+        return Result::Err(GloomError::new(String::from("attempt to divide by zero")));
+    }
+    checked(left.checked_div(right), "divide")
+}
+
 #[derive(Debug)]
 pub struct GloomArgs {
     pub vec: Vec<Value>,
@@ -475,3 +627,99 @@ impl GloomArgs {
         GloomArgs { vec }
     }
 }
+
+// 宿主程序与脚本之间的值转换 value conversions between host program and script
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::Int(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Num(n)
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::Char(c)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(str: String) -> Self {
+        Value::Ref(GloomString::new(str))
+    }
+}
+
+impl From<&str> for Value {
+    fn from(str: &str) -> Self {
+        Value::Ref(GloomString::new(str.to_string()))
+    }
+}
+
+impl From<GloomObjRef> for Value {
+    fn from(rf: GloomObjRef) -> Self {
+        Value::Ref(rf)
+    }
+}
+
+// 类型不匹配时返回原值 return the original value if type mismatched
+impl TryFrom<Value> for i64 {
+    type Error = Value;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.as_int() {
+            Some(i) => Result::Ok(i),
+            None => Result::Err(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = Value;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.as_num() {
+            Some(n) => Result::Ok(n),
+            None => Result::Err(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for char {
+    type Error = Value;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.as_char() {
+            Some(c) => Result::Ok(c),
+            None => Result::Err(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Value;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.as_bool() {
+            Some(b) => Result::Ok(b),
+            None => Result::Err(value),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Value;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        if let Value::Ref(rf) = &value {
+            if let ObjectType::String = rf.obj_type() {
+                return Result::Ok(rf.downcast::<GloomString>().0.borrow().clone());
+            }
+        }
+        Result::Err(value)
+    }
+}
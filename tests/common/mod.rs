@@ -0,0 +1,44 @@
+// 各个集成测试共用的辅助函数 helpers shared by integration tests
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+// 打印对象时字段顺序不固定的脚本 scripts whose field order is not fixed when printing objects
+pub const UNORDERED: [&str; 3] = ["AfterTest.gs", "DynTest.gs", "NewTest.gs"];
+// 无法通过解析的脚本 scripts that can't be parsed
+pub const BROKEN: [&str; 1] = ["builtin-function.gs"];
+
+pub fn gloom(args: &[&str]) -> Output {
+    gloom_with_input(args, "")
+}
+
+pub fn gloom_with_input(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gloomscript"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // 进程可能不读取输入就退出 the process may exit without reading the input
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+// 每个测试使用独立的临时目录 every test uses its own temporary directory
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gloom-test-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
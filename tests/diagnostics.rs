@@ -0,0 +1,115 @@
+use serde_json::Value;
+
+mod common;
+
+use common::{gloom, stderr, temp_dir};
+
+const SCRIPT: &str = r#"let n = 1
+let s = match (n) {
+    _ => "any",
+    1 => "one"
+}
+let a int = "x"
+let b = undefinedVar + 1
+println(b)
+"#;
+
+// 警告在错误之前输出 失败的let不会引起后续的错误
+// warnings are output before errors, a failed let doesn't cause later errors
+const RENDERED: &str = r#"warning[W0101]: unreachable pattern 1 after '_'
+ --> {file}:4:5
+  |
+4 |     1 => "one"
+  |     ^^^^^^^^^^
+  |
+  = note: in script body
+
+error[E0105]: mismatched declared type of variable 'a', expect int, found String
+ --> {file}:6:5
+  |
+6 | let a int = "x"
+  |     ^
+  |
+  = note: in script body
+
+error[E0120]: undefined variable undefinedVar
+ --> {file}:7:9
+  |
+7 | let b = undefinedVar + 1
+  |         ^^^^^^^^^^^^
+  |
+  = note: in script body
+  = help: declare the variable by 'let' before using it
+
+error: aborting due to 2 previous errors
+"#;
+
+fn write_script(name: &str, src: &str) -> (std::path::PathBuf, String) {
+    let dir = temp_dir(name);
+    let file = dir.join("script.gs");
+    std::fs::write(&file, src).unwrap();
+    let file = file.to_str().unwrap().to_string();
+    (dir, file)
+}
+
+#[test]
+fn rendered() {
+    let (dir, file) = write_script("rendered", SCRIPT);
+    let output = gloom(&["check", file.as_str()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), RENDERED.replace("{file}", file.as_str()));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// 每行一个json对象 其中rendered与人类可读的格式相同
+// one json object per line, the rendered field is the same as the human readable format
+#[test]
+fn json() {
+    let (dir, file) = write_script("json", SCRIPT);
+    let output = gloom(&["check", "--error-format=json", file.as_str()]);
+    assert_eq!(output.status.code(), Some(1));
+    let diagnostics: Vec<Value> = stderr(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let codes: Vec<&str> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(codes, vec!["W0101", "E0105", "E0120"]);
+    let undefined = &diagnostics[2];
+    assert_eq!(undefined["level"], "error");
+    assert_eq!(undefined["file"], file.as_str());
+    assert_eq!(undefined["line"], 7);
+    assert_eq!(undefined["column"], 9);
+    assert_eq!(undefined["message"], "undefined variable undefinedVar");
+    assert_eq!(
+        undefined["help"],
+        "declare the variable by 'let' before using it"
+    );
+    let start = SCRIPT.find("undefinedVar").unwrap();
+    assert_eq!(undefined["span"]["start"], start);
+    assert_eq!(undefined["span"]["end"], start + "undefinedVar".len());
+    let rendered = RENDERED.replace("{file}", file.as_str());
+    let rendered: Vec<&str> = rendered.split("\n\n").collect();
+    for (diagnostic, rendered) in diagnostics.iter().zip(rendered.iter()) {
+        assert_eq!(
+            diagnostic["rendered"].as_str().unwrap(),
+            format!("{}\n", rendered)
+        );
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn parse_error() {
+    let (dir, file) = write_script("parse-error", "let = 3\n");
+    let output = gloom(&["check", file.as_str()]);
+    assert_eq!(output.status.code(), Some(1));
+    let expect = format!(
+        "error[E0001]: expect identifier in fact found token '='\n --> {}:1:5\n  |\n1 | let = 3\n  |     ^\n\nerror: aborting due to previous error\n",
+        file
+    );
+    assert_eq!(stderr(&output), expect);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,172 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use gloomscript::builtin::host::HostObject;
+use gloomscript::engine::{Engine, EngineError};
+use gloomscript::obj::func::{Param, ReturnType};
+use gloomscript::obj::types::{DataType, RefType};
+use gloomscript::vm::error::GloomError;
+use gloomscript::vm::value::Value;
+
+struct Counter {
+    count: i64,
+}
+
+fn int_param(name: &str) -> Param {
+    Param::new(Rc::new(name.to_string()), DataType::Ref(RefType::Int))
+}
+
+// 注册宿主函数和宿主类 register host function and host class
+fn engine() -> Engine {
+    let mut engine = Engine::new(false);
+    engine
+        .register_fn(
+            "hostAdd",
+            vec![int_param("a"), int_param("b")],
+            ReturnType::Have(DataType::Ref(RefType::Int)),
+            |_, args| {
+                Result::Ok(Value::Int(
+                    args.vec[0].assert_int()? + args.vec[1].assert_int()?,
+                ))
+            },
+        )
+        .unwrap();
+    engine
+        .register_fn("hostFail", Vec::new(), ReturnType::Void, |_, _| {
+            Result::Err(GloomError::new(String::from("failed in host")))
+        })
+        .unwrap();
+    let counter = engine.register_class("Counter").unwrap();
+    let class = counter.clone();
+    engine
+        .register_method(
+            &counter,
+            "new",
+            Vec::new(),
+            ReturnType::Have(counter.data_type()),
+            false,
+            move |_, _| Result::Ok(Value::Ref(HostObject::new(&class, Counter { count: 0 }))),
+        )
+        .unwrap();
+    engine
+        .register_method(
+            &counter,
+            "incr",
+            vec![int_param("step")],
+            ReturnType::Have(DataType::Ref(RefType::Int)),
+            true,
+            |_, args| {
+                let obj = args.vec[0].as_ref().downcast::<HostObject>();
+                let mut counter = obj.borrow_mut::<Counter>();
+                counter.count += args.vec[1].assert_int()?;
+                Result::Ok(Value::Int(counter.count))
+            },
+        )
+        .unwrap();
+    engine
+}
+
+const SCRIPT: &str = r#"
+func count(int n) int {
+    let counter = Counter.new()
+    let result = 0
+    for i in (0,n) {
+        result = counter.incr(hostAdd(i, 1))
+    }
+    result
+}
+func greet(String name) String {
+    name.toUpper()
+}
+func catchHost() String {
+    let message = "unreachable"
+    try {
+        hostFail()
+    } catch err {
+        message = err.message()
+    }
+    message
+}
+func check(int n) int {
+    if n < 0 {
+        throw "n can't be negative"
+    }
+    n
+}
+"#;
+
+#[test]
+fn call_script_func() {
+    let mut engine = engine();
+    engine.compile_str(SCRIPT.to_string(), "engine.gs").unwrap();
+    let count = engine.call("count", vec![Value::from(10)]).unwrap();
+    assert_eq!(i64::try_from(count).unwrap(), 55);
+    let greeting = engine.call("greet", vec![Value::from("Rust")]).unwrap();
+    assert_eq!(String::try_from(greeting).unwrap(), "RUST");
+    // 宿主函数返回的错误可以在脚本中捕获 the error returned by host function can be caught in script
+    let message = engine.call("catchHost", Vec::new()).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "failed in host");
+}
+
+#[test]
+fn runtime_error() {
+    let mut engine = engine();
+    engine.compile_str(SCRIPT.to_string(), "engine.gs").unwrap();
+    match engine.call("check", vec![Value::from(-1)]) {
+        Err(EngineError::Runtime(error)) => {
+            assert_eq!(error.msg, "n can't be negative");
+            assert!(error
+                .traceback()
+                .contains("File \"engine.gs\", line 24, in check"));
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn check_args() {
+    let mut engine = engine();
+    assert!(matches!(
+        engine.call("check", vec![Value::from(1)]),
+        Err(EngineError::NotCompiled)
+    ));
+    engine.compile_str(SCRIPT.to_string(), "engine.gs").unwrap();
+    assert!(matches!(
+        engine.call("missing", Vec::new()),
+        Err(EngineError::FuncNotFound(_))
+    ));
+    assert!(matches!(
+        engine.call("check", Vec::new()),
+        Err(EngineError::MismatchedArgsNum {
+            expect: 1,
+            found: 0,
+            ..
+        })
+    ));
+    assert!(matches!(
+        engine.call("check", vec![Value::from("1")]),
+        Err(EngineError::MismatchedArgType { index: 0, .. })
+    ));
+}
+
+// 编译失败后可以再次编译 编译成功后不能再注册或编译
+// could compile again after failure, can't register or compile after success
+#[test]
+fn compile_again() {
+    let mut engine = engine();
+    match engine.compile_str(String::from("let x int = \"x\""), "broken.gs") {
+        Err(EngineError::Analysis(diagnostics)) => assert_eq!(diagnostics.len(), 1),
+        _ => panic!(),
+    }
+    engine.compile_str(SCRIPT.to_string(), "engine.gs").unwrap();
+    let count = engine.call("count", vec![Value::from(3)]).unwrap();
+    assert_eq!(i64::try_from(count).unwrap(), 6);
+    assert!(matches!(
+        engine.register_class("Late"),
+        Err(EngineError::RegisterAfterCompiled)
+    ));
+    assert!(matches!(
+        engine.compile_str(SCRIPT.to_string(), "engine.gs"),
+        Err(EngineError::AlreadyCompiled)
+    ));
+}
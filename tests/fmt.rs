@@ -0,0 +1,66 @@
+use std::path::Path;
+
+mod common;
+
+use common::{gloom, stderr, stdout, temp_dir, BROKEN, UNORDERED};
+
+fn scripts() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir("gloom")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| !BROKEN.contains(&name.as_str()))
+        .collect();
+    names.sort();
+    names
+}
+
+fn format(path: &Path) -> String {
+    let output = gloom(&["fmt", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    std::fs::read_to_string(path).unwrap()
+}
+
+// 格式化已格式化的脚本不会再改变它 且格式化不改变脚本的输出
+// formatting a formatted script doesn't change it again, and formatting doesn't change the output of script
+#[test]
+fn idempotent() {
+    let dir = temp_dir("fmt");
+    for name in scripts() {
+        let path = dir.join(name.as_str());
+        std::fs::copy(Path::new("gloom").join(name.as_str()), &path).unwrap();
+        let once = format(&path);
+        let twice = format(&path);
+        assert_eq!(once, twice, "{}", name);
+        let output = gloom(&["fmt", "--check", path.to_str().unwrap()]);
+        assert!(output.status.success(), "{}", name);
+        if UNORDERED.contains(&name.as_str()) {
+            continue;
+        }
+        let expect = gloom(&["run", format!("gloom/{}", name).as_str()]);
+        let found = gloom(&["run", path.to_str().unwrap()]);
+        // 调用栈中的路径不同 the paths in traceback are different
+        let prefix = format!("{}/", dir.to_str().unwrap());
+        assert_eq!(
+            stdout(&expect),
+            stdout(&found).replace(prefix.as_str(), "gloom/"),
+            "{}",
+            name
+        );
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// check模式列出未格式化的文件 但不修改它们 check mode lists the unformatted files, but doesn't modify them
+#[test]
+fn check() {
+    let dir = temp_dir("fmt-check");
+    let path = dir.join("unformatted.gs");
+    let src = "let  a = 1\nprintln(a)\n";
+    std::fs::write(&path, src).unwrap();
+    let output = gloom(&["fmt", "--check", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("unformatted.gs is not formatted"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), src);
+    assert_eq!(format(&path), "let a = 1\nprintln(a)\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
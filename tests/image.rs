@@ -0,0 +1,73 @@
+use gloomscript::bytecode::code::ByteCode;
+use gloomscript::bytecode::image::{self, ImageError};
+use gloomscript::obj::func::FuncBody;
+
+mod common;
+
+use common::{gloom, stderr, stdout, temp_dir, BROKEN, UNORDERED};
+
+// 运行编译后的镜像与运行源码的输出应当相同 running the compiled image should print the same as running the source
+#[test]
+fn round_trip() {
+    let dir = temp_dir("round-trip");
+    let mut entries: Vec<_> = std::fs::read_dir("gloom")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if UNORDERED.contains(&name) || BROKEN.contains(&name) {
+            continue;
+        }
+        let source = path.to_str().unwrap();
+        let compiled = dir.join(name).with_extension(image::EXTENSION);
+        let compiled = compiled.to_str().unwrap();
+        let output = gloom(&["compile", source, "-o", compiled]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let expect = gloom(&["run", source]);
+        let found = gloom(&["run", compiled]);
+        assert_eq!(stdout(&expect), stdout(&found), "{}", name);
+        assert_eq!(stderr(&expect), stderr(&found), "{}", name);
+        assert_eq!(expect.status.code(), found.status.code(), "{}", name);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// 损坏的下标在加载时被拒绝 corrupted index is rejected when loading
+#[test]
+fn invalid_index() {
+    let dir = temp_dir("invalid-index");
+    let compiled = dir.join("StringTest.gsc");
+    let compiled = compiled.to_str().unwrap();
+    let output = gloom(&["compile", "gloom/StringTest.gs", "-o", compiled]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let bytes = std::fs::read(compiled).unwrap();
+    let (status, pool, statics) = image::read_image(&bytes).unwrap();
+    {
+        let mut script_body = status.script_bodies[0].inner_mut();
+        let bytecodes = match &mut script_body.func.body {
+            FuncBody::ByteCodes(bytecodes) => bytecodes,
+            _ => panic!(),
+        };
+        let code = bytecodes
+            .iter_mut()
+            .find(|code| matches!(code, ByteCode::LoadConstString(_)))
+            .unwrap();
+        *code = ByteCode::LoadConstString(pool.str.len() as u16);
+    }
+    let bytes = image::write_image(&status, &pool, &statics);
+    match image::read_image(&bytes) {
+        Err(ImageError::InvalidIndex { what, index }) => {
+            assert_eq!(what, "string");
+            assert_eq!(index as usize, pool.str.len());
+        }
+        _ => panic!("the corrupted image is loaded"),
+    }
+    // 通过命令行加载时报告错误而不是崩溃 report the error rather than crashing when loaded by command line
+    std::fs::write(compiled, bytes).unwrap();
+    let output = gloom(&["run", compiled]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("invalid string index"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
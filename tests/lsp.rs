@@ -0,0 +1,109 @@
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{gloom_with_input, stdout};
+
+const URI: &str = "file:///tmp/gloom-lsp/main.gs";
+const TEXT: &str =
+    "func double(int n) int {\n    n * 2\n}\nlet x = double(2)\nprintln(y)\nlet s = \"a\"\n";
+
+fn frame(message: Value) -> String {
+    let content = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+}
+
+// 按 Content-Length 拆分服务器发送的消息 split the messages sent by server by Content-Length
+fn messages(mut output: &str) -> Vec<Value> {
+    let mut messages = Vec::new();
+    while let Some(start) = output.find("\r\n\r\n") {
+        let length: usize = output["Content-Length: ".len()..start].parse().unwrap();
+        let content = &output[start + 4..start + 4 + length];
+        messages.push(serde_json::from_str(content).unwrap());
+        output = &output[start + 4 + length..];
+    }
+    messages
+}
+
+fn request(id: i64, method: &str, params: Value) -> String {
+    frame(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+}
+
+fn notify(method: &str, params: Value) -> String {
+    frame(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+}
+
+fn position(line: u32, character: u32) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+fn response(messages: &[Value], id: i64) -> &Value {
+    messages.iter().find(|message| message["id"] == id).unwrap()
+}
+
+#[test]
+fn session() {
+    let input = [
+        request(1, "initialize", json!({})),
+        notify("initialized", json!({})),
+        notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "gloom", "version": 1, "text": TEXT } }),
+        ),
+        request(2, "textDocument/hover", position(3, 9)),
+        request(3, "textDocument/definition", position(3, 9)),
+        // 解析失败时使用上一次的分析结果补全 complete by the last analysis when failed to parse
+        notify(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": format!("{}s.\n", TEXT) }] }),
+        ),
+        request(4, "textDocument/completion", position(6, 2)),
+        request(5, "unknown/method", json!({})),
+        request(6, "shutdown", Value::Null),
+        notify("exit", Value::Null),
+    ]
+    .concat();
+    let output = gloom_with_input(&["lsp"], input.as_str());
+    assert_eq!(output.status.code(), Some(0));
+    let messages = messages(stdout(&output).as_str());
+
+    let capabilities = &response(&messages, 1)["result"]["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(capabilities["definitionProvider"], true);
+
+    let published: Vec<&Value> = messages
+        .iter()
+        .filter(|message| message["method"] == "textDocument/publishDiagnostics")
+        .collect();
+    assert_eq!(published.len(), 2);
+    let diagnostics = published[0]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "E0120");
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 4, "character": 8 }, "end": { "line": 4, "character": 9 } })
+    );
+
+    let hover = &response(&messages, 2)["result"];
+    assert_eq!(
+        hover["contents"]["value"],
+        "```gloom\ndouble : Func<[int],int>\n```"
+    );
+
+    let definition = &response(&messages, 3)["result"];
+    assert_eq!(definition["uri"], URI);
+    assert_eq!(
+        definition["range"],
+        json!({ "start": { "line": 0, "character": 5 }, "end": { "line": 0, "character": 11 } })
+    );
+
+    let completion = response(&messages, 4)["result"].as_array().unwrap();
+    assert!(completion
+        .iter()
+        .any(|item| item["label"] == "toUpper"
+            && item["detail"] == "func toUpper(String self) String"));
+
+    assert_eq!(response(&messages, 5)["error"]["code"], -32601);
+    assert_eq!(response(&messages, 6)["result"], Value::Null);
+}
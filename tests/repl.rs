@@ -0,0 +1,27 @@
+mod common;
+
+use common::{gloom_with_input, stderr, stdout};
+
+// 表达式的值被打印 声明在之后的输入中仍然可用
+// the value of expression is printed, declarations are still available in later inputs
+#[test]
+fn keep_declarations() {
+    let input = "let a = 1 + 2\na\nfunc double(int n) int {\n    n * 2\n}\ndouble(a)\n";
+    let output = gloom_with_input(&["repl"], input);
+    assert!(output.status.success());
+    let lines: Vec<String> = stdout(&output).lines().skip(1).map(String::from).collect();
+    assert_eq!(lines, vec!["3", "6"]);
+}
+
+// 编译错误和运行时错误不会结束会话 compile errors and runtime errors don't end the session
+#[test]
+fn recover_from_errors() {
+    let input = "let a = 1\nprintln(b)\na / 0\nlet b = a + 1\nb\nexit\nb\n";
+    let output = gloom_with_input(&["repl"], input);
+    assert!(output.status.success());
+    let lines: Vec<String> = stdout(&output).lines().skip(1).map(String::from).collect();
+    assert_eq!(lines, vec!["2"]);
+    let errors = stderr(&output);
+    assert!(errors.contains("error[E0120]: undefined variable b"));
+    assert!(errors.contains("runtime error : attempt to divide by zero"));
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::{gloom, stdout, UNORDERED};
+
+// 以 /* */ 结尾的脚本 其中是期望的输出
+// the scripts end with /* */, which contains the expected output
+#[test]
+fn expected_output() {
+    let mut entries: Vec<_> = std::fs::read_dir("gloom")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let script = std::fs::read_to_string(&path).unwrap();
+        let script = script.trim_end();
+        if UNORDERED.contains(&name) || !script.ends_with("*/") {
+            continue;
+        }
+        let expect = &script[script.rfind("/*").unwrap() + 2..script.len() - 2];
+        // 与命令行中的相对路径一致 same as the relative path in command line
+        let output = gloom(&["run", format!("gloom/{}", name).as_str()]);
+        let found = stdout(&output);
+        let expect: Vec<&str> = expect.trim().lines().map(str::trim_end).collect();
+        let found: Vec<&str> = found.trim().lines().map(str::trim_end).collect();
+        assert_eq!(expect, found, "{}", name);
+    }
+}